@@ -0,0 +1,60 @@
+//! In-memory serial backend.
+//!
+//! `BufferBackend` is a cheap handle to shared buffers: keep a clone in the test to
+//! feed input and inspect output while the ACIA owns the other one.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use crate::backends::SerialBackend;
+
+/// Contents of the buffers shared by the clones of a `BufferBackend`
+#[derive(Debug, Default)]
+struct Buffers {
+    /// Bytes waiting to be received by the ACIA
+    input: VecDeque<u8>,
+    /// Bytes transmitted by the ACIA
+    output: Vec<u8>,
+}
+
+/// Serial backend reading from and writing to memory
+#[derive(Debug, Clone, Default)]
+pub struct BufferBackend {
+    buffers: Rc<RefCell<Buffers>>,
+}
+
+impl BufferBackend {
+    /// Create a backend with empty buffers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue bytes to be received by the ACIA
+    ///
+    /// # Arguments
+    /// * `data` - The bytes, received one per frame
+    pub fn push_input(&self, data: &[u8]) {
+        self.buffers.borrow_mut().input.extend(data);
+    }
+
+    /// Number of queued bytes the ACIA has not received yet
+    pub fn pending_input(&self) -> usize {
+        self.buffers.borrow().input.len()
+    }
+
+    /// Take the bytes transmitted by the ACIA so far
+    pub fn take_output(&self) -> Vec<u8> {
+        std::mem::take(&mut self.buffers.borrow_mut().output)
+    }
+}
+
+impl SerialBackend for BufferBackend {
+    fn receive(&mut self) -> Option<u8> {
+        self.buffers.borrow_mut().input.pop_front()
+    }
+
+    fn transmit(&mut self, data: u8) {
+        self.buffers.borrow_mut().output.push(data);
+    }
+}
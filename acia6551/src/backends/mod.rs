@@ -0,0 +1,27 @@
+//! Host ends of the serial line of an ACIA.
+//!
+//! A backend receives the bytes transmitted by the ACIA and supplies the bytes it
+//! receives. The ACIA polls `SerialBackend::receive` once per frame, so backends must
+//! not block.
+
+/// In-memory buffers, for tests
+pub mod buffer;
+/// Linux pseudo-terminal, for terminal programs
+#[cfg(target_os = "linux")]
+pub mod pty;
+/// Standard input and output of the emulator
+pub mod stdio;
+/// Localhost TCP socket, for telnet and netcat
+pub mod tcp;
+
+/// Other end of the serial line of an ACIA
+pub trait SerialBackend {
+    /// Take the next byte sent by the host
+    ///
+    /// # Returns
+    /// * `Some(u8)` containing the next byte
+    /// * `None` if no byte is available yet
+    fn receive(&mut self) -> Option<u8>;
+    /// Deliver a byte transmitted by the ACIA to the host
+    fn transmit(&mut self, data: u8);
+}
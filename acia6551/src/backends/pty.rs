@@ -0,0 +1,110 @@
+//! Serial backend behind a Linux pseudo-terminal.
+//!
+//! The emulator owns the master side. Terminal programs open the slave side, e.g.
+//! `screen /dev/pts/3` or `minicom -D /dev/pts/3`, see `PtyBackend::path`.
+
+use std::ffi::CStr;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::fd::{AsRawFd, FromRawFd};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+use crate::backends::SerialBackend;
+
+/// Length of the buffer receiving the path of the slave side
+const PATH_LENGTH: usize = 128;
+
+/// Serial backend using a pseudo-terminal
+#[derive(Debug)]
+pub struct PtyBackend {
+    /// Master side, non-blocking
+    master: File,
+    /// Slave side, kept open so the master does not fail between clients
+    _slave: File,
+    /// Path of the slave side
+    path: PathBuf,
+}
+
+/// Convert the result of a libc call to an `io::Result`
+fn check(result: libc::c_int) -> io::Result<libc::c_int> {
+    if result < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(result)
+    }
+}
+
+impl PtyBackend {
+    /// Create a new pseudo-terminal in raw mode.
+    ///
+    /// # Returns
+    /// * `Ok(PtyBackend)` whose slave side can be opened by a terminal program
+    /// * `Err(io::Error)` if the pseudo-terminal cannot be created
+    ///
+    /// # Errors
+    /// * If no pseudo-terminal is available or it cannot be configured
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let backend = PtyBackend::open()?;
+    /// println!("screen {}", backend.path().display());
+    /// ```
+    pub fn open() -> io::Result<Self> {
+        // SAFETY: plain libc calls on a file descriptor owned by this function; the
+        // descriptor is moved into a `File` right after it is opened.
+        unsafe {
+            let fd = check(libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY))?;
+            let master = File::from_raw_fd(fd);
+            check(libc::grantpt(fd))?;
+            check(libc::unlockpt(fd))?;
+            let mut name = [0 as libc::c_char; PATH_LENGTH];
+            let result = libc::ptsname_r(fd, name.as_mut_ptr(), name.len());
+            if result != 0 {
+                return Err(io::Error::from_raw_os_error(result));
+            }
+            let path = PathBuf::from(CStr::from_ptr(name.as_ptr()).to_string_lossy().as_ref());
+
+            // Opening the slave must not make it the controlling terminal either
+            let slave = File::options()
+                .read(true)
+                .write(true)
+                .custom_flags(libc::O_NOCTTY)
+                .open(&path)?;
+            let mut termios = std::mem::zeroed::<libc::termios>();
+            check(libc::tcgetattr(slave.as_raw_fd(), &mut termios))?;
+            libc::cfmakeraw(&mut termios);
+            check(libc::tcsetattr(slave.as_raw_fd(), libc::TCSANOW, &termios))?;
+
+            let flags = check(libc::fcntl(fd, libc::F_GETFL))?;
+            check(libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK))?;
+
+            Ok(Self {
+                master,
+                _slave: slave,
+                path,
+            })
+        }
+    }
+
+    /// Path of the slave side, to be opened by a terminal program
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl SerialBackend for PtyBackend {
+    fn receive(&mut self) -> Option<u8> {
+        let mut byte = [0];
+        // Nothing to read returns `WouldBlock`, and `EIO` while no client has the slave open
+        match self.master.read(&mut byte) {
+            Ok(1) => Some(byte[0]),
+            _ => None,
+        }
+    }
+
+    fn transmit(&mut self, data: u8) {
+        // Output is dropped while the kernel buffer is full, like a line without flow control
+        let _ = self.master.write_all(&[data]);
+    }
+}
@@ -0,0 +1,63 @@
+//! Serial backend connected to the standard input and output of the emulator.
+
+use std::io::{self, Read, Write};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use crate::backends::SerialBackend;
+
+/// Serial backend using stdin and stdout
+///
+/// Standard input is read by a background thread, so that the ACIA never blocks.
+/// Input is only seen line by line unless the terminal is in raw mode.
+pub struct StdioBackend {
+    input: Receiver<u8>,
+    /// Translate line feeds typed by the user into the carriage returns monitors expect
+    translate_newlines: bool,
+}
+
+impl StdioBackend {
+    /// Create a backend and start reading standard input
+    pub fn new() -> Self {
+        let (sender, input) = mpsc::channel();
+        thread::spawn(move || {
+            for byte in io::stdin().lock().bytes() {
+                match byte {
+                    Ok(byte) if sender.send(byte).is_ok() => {}
+                    _ => break,
+                }
+            }
+        });
+        Self {
+            input,
+            translate_newlines: false,
+        }
+    }
+
+    /// Receive line feeds as carriage returns
+    pub fn with_newline_translation(mut self) -> Self {
+        self.translate_newlines = true;
+        self
+    }
+}
+
+impl Default for StdioBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SerialBackend for StdioBackend {
+    fn receive(&mut self) -> Option<u8> {
+        match self.input.try_recv().ok()? {
+            b'\n' if self.translate_newlines => Some(b'\r'),
+            byte => Some(byte),
+        }
+    }
+
+    fn transmit(&mut self, data: u8) {
+        let mut stdout = io::stdout().lock();
+        // A closed stdout loses the output, like an unplugged terminal
+        let _ = stdout.write_all(&[data]).and_then(|_| stdout.flush());
+    }
+}
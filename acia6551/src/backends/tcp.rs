@@ -0,0 +1,109 @@
+//! Serial backend listening on a localhost TCP socket.
+//!
+//! Connect with `telnet localhost <port>` or `nc localhost <port>`. One client is served
+//! at a time; when it disconnects, the next one is accepted.
+
+use std::collections::VecDeque;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+
+use crate::backends::SerialBackend;
+
+/// Number of bytes read from the socket at once
+const READ_CHUNK: usize = 256;
+
+/// Serial backend using a TCP connection on 127.0.0.1
+#[derive(Debug)]
+pub struct TcpBackend {
+    listener: TcpListener,
+    /// Connected client
+    stream: Option<TcpStream>,
+    /// Bytes read from the client but not received by the ACIA yet
+    input: VecDeque<u8>,
+}
+
+impl TcpBackend {
+    /// Listen on a localhost port.
+    ///
+    /// # Arguments
+    /// * `port` - The port, or 0 to let the system choose one
+    ///
+    /// # Returns
+    /// * `Ok(TcpBackend)` listening for a client
+    /// * `Err(io::Error)` if the port cannot be bound
+    ///
+    /// # Errors
+    /// * If the port is in use or cannot be bound
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let backend = TcpBackend::bind(6551)?;
+    /// println!("telnet localhost {}", backend.local_addr()?.port());
+    /// ```
+    pub fn bind(port: u16) -> io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            stream: None,
+            input: VecDeque::new(),
+        })
+    }
+
+    /// Address the backend listens on
+    ///
+    /// # Errors
+    /// * If the address of the socket cannot be queried
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Check whether a client is connected
+    pub fn is_connected(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// Accept a waiting client, if none is connected
+    fn accept(&mut self) {
+        if self.stream.is_none()
+            && let Ok((stream, _)) = self.listener.accept()
+            && stream.set_nonblocking(true).is_ok()
+        {
+            let _ = stream.set_nodelay(true);
+            self.stream = Some(stream);
+        }
+    }
+
+    /// Read what the client has sent so far
+    fn fill_input(&mut self) {
+        let Some(stream) = &mut self.stream else {
+            return;
+        };
+        let mut buffer = [0; READ_CHUNK];
+        match stream.read(&mut buffer) {
+            Ok(0) => self.stream = None,
+            Ok(count) => self.input.extend(&buffer[..count]),
+            Err(error) if error.kind() == ErrorKind::WouldBlock => {}
+            Err(_) => self.stream = None,
+        }
+    }
+}
+
+impl SerialBackend for TcpBackend {
+    fn receive(&mut self) -> Option<u8> {
+        if self.input.is_empty() {
+            self.accept();
+            self.fill_input();
+        }
+        self.input.pop_front()
+    }
+
+    fn transmit(&mut self, data: u8) {
+        self.accept();
+        if let Some(stream) = &mut self.stream
+            && stream.write_all(&[data]).is_err()
+        {
+            self.stream = None;
+        }
+    }
+}
@@ -0,0 +1,378 @@
+//! MOS 6551 Asynchronous Communications Interface Adapter (ACIA).
+//!
+//! Register map, selected by address lines RS0-RS1 and mirrored every 4 bytes:
+//! - `$0`: Transmit data (write) / Receive data (read)
+//! - `$1`: Status (read) / Programmed reset (write)
+//! - `$2`: Command (DTR, receiver interrupt, transmitter control, echo, parity)
+//! - `$3`: Control (baud rate, word length, stop bits)
+//!
+//! Characters take the time of a whole frame at the selected baud rate to be sent or
+//! received, counted in CPU cycles through `tick`. The other end of the serial line is
+//! a `SerialBackend`, see the `backends` module.
+
+/// Host ends of the serial line
+pub mod backends;
+
+use bus::errors::BusError;
+use bus::trait_bus_device::BusDevice;
+
+use crate::backends::SerialBackend;
+
+/// Number of registers exposed on the bus
+pub const ACIA_REGISTER_COUNT: u16 = 4;
+/// Default CPU clock in Hz, used to convert baud rates to cycles
+pub const DEFAULT_CPU_CLOCK: u32 = 1_000_000;
+
+const REGISTER_DATA: u16 = 0x0;
+const REGISTER_STATUS: u16 = 0x1;
+const REGISTER_COMMAND: u16 = 0x2;
+
+/// Status: receiver overrun
+pub const STATUS_OVERRUN: u8 = 0x04;
+/// Status: receiver data register full
+pub const STATUS_RDRF: u8 = 0x08;
+/// Status: transmitter data register empty
+pub const STATUS_TDRE: u8 = 0x10;
+/// Status: data carrier detect is not asserted
+pub const STATUS_DCD: u8 = 0x20;
+/// Status: data set ready is not asserted
+pub const STATUS_DSR: u8 = 0x40;
+/// Status: an interrupt has occurred
+pub const STATUS_IRQ: u8 = 0x80;
+
+const COMMAND_DTR: u8 = 0x01;
+const COMMAND_RECEIVER_IRQ_DISABLE: u8 = 0x02;
+const COMMAND_TRANSMITTER_CONTROL: u8 = 0x0C;
+const COMMAND_TRANSMITTER_IRQ: u8 = 0x04;
+const COMMAND_ECHO: u8 = 0x10;
+const COMMAND_PARITY_ENABLE: u8 = 0x20;
+/// Command bits cleared by a programmed reset
+const COMMAND_RESET_MASK: u8 = 0x1F;
+/// Command register after a hardware reset
+const COMMAND_POWER_ON: u8 = COMMAND_RECEIVER_IRQ_DISABLE;
+
+const CONTROL_STOP_BITS: u8 = 0x80;
+
+/// Baud rates selected by bits 0-3 of the control register
+///
+/// Rate 0 selects the external 16x clock, taken to be a 1.8432 MHz crystal.
+const BAUD_RATES: [u32; 16] = [
+    115_200, 50, 75, 110, 135, 150, 300, 600, 1200, 1800, 2400, 3600, 4800, 7200, 9600, 19_200,
+];
+
+/// Represents a MOS 6551 ACIA.
+pub struct Acia {
+    /// Start address of the register window
+    start_address: u16,
+    /// Other end of the serial line
+    backend: Box<dyn SerialBackend>,
+    /// CPU clock in Hz
+    cpu_clock: u32,
+    /// Emulate the W65C51N, whose TDRE bit is stuck at 1
+    w65c51_bug: bool,
+    command: u8,
+    control: u8,
+    /// Status bits other than TDRE, DCD and DSR
+    status: u8,
+    /// Received byte waiting to be read
+    receive_data: u8,
+    /// Byte written, waiting for the transmit shift register
+    transmit_data: Option<u8>,
+    /// Byte being shifted out
+    transmit_shift: Option<u8>,
+    /// Ticks until the byte being shifted out is sent
+    transmit_ticks: u64,
+    /// Ticks until the receiver checks for the next byte
+    receive_ticks: u64,
+    /// Carrier detect is asserted
+    dcd: bool,
+    /// Data set ready is asserted
+    dsr: bool,
+}
+
+impl Acia {
+    /// Create a new ACIA with its registers at the specified start address.
+    ///
+    /// # Arguments
+    /// * `start_address` - Start address of the register window
+    /// * `backend` - Other end of the serial line
+    ///
+    /// # Returns
+    /// * A new Acia instance in its hardware reset state
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let acia = Acia::new(0x5000, Box::new(StdioBackend::new()));
+    /// bus.register_device(0x5000, 0x5003, Box::new(acia))?;
+    /// ```
+    pub fn new(start_address: u16, backend: Box<dyn SerialBackend>) -> Self {
+        Self {
+            start_address,
+            backend,
+            cpu_clock: DEFAULT_CPU_CLOCK,
+            w65c51_bug: false,
+            command: COMMAND_POWER_ON,
+            control: 0,
+            status: 0,
+            receive_data: 0,
+            transmit_data: None,
+            transmit_shift: None,
+            transmit_ticks: 0,
+            receive_ticks: 0,
+            dcd: true,
+            dsr: true,
+        }
+    }
+
+    /// Use a different CPU clock to convert baud rates to cycles.
+    ///
+    /// # Arguments
+    /// * `hz` - CPU clock in Hz
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let acia = Acia::new(0x5000, backend).with_cpu_clock(1_843_200);
+    /// ```
+    pub fn with_cpu_clock(mut self, hz: u32) -> Self {
+        self.cpu_clock = hz.max(1);
+        self
+    }
+
+    /// Emulate the transmitter bug of the WDC W65C51N.
+    ///
+    /// The TDRE status bit always reads 1 and no transmit interrupts are raised, so
+    /// firmware must wait a character time between writes. A byte written while another
+    /// is being sent replaces it, and the interrupted byte is lost.
+    pub fn with_w65c51_bug(mut self) -> Self {
+        self.w65c51_bug = true;
+        self
+    }
+
+    /// Pull the RES pin low, as at power-on
+    pub fn reset(&mut self) {
+        self.command = COMMAND_POWER_ON;
+        self.control = 0;
+        self.status = 0;
+        self.transmit_data = None;
+        self.transmit_shift = None;
+        self.receive_ticks = 0;
+    }
+
+    /// Drive the DCD input, a change raises an interrupt
+    ///
+    /// # Arguments
+    /// * `asserted` - Carrier detected
+    pub fn set_dcd(&mut self, asserted: bool) {
+        if asserted != self.dcd {
+            self.dcd = asserted;
+            self.raise_irq();
+        }
+    }
+
+    /// Drive the DSR input, a change raises an interrupt
+    ///
+    /// # Arguments
+    /// * `asserted` - Data set ready
+    pub fn set_dsr(&mut self, asserted: bool) {
+        if asserted != self.dsr {
+            self.dsr = asserted;
+            self.raise_irq();
+        }
+    }
+
+    /// Level of the DTR output, asserted when command bit 0 is set
+    pub fn dtr(&self) -> bool {
+        self.command & COMMAND_DTR != 0
+    }
+
+    /// Level of the RTS output, asserted unless transmitter control is 00
+    pub fn rts(&self) -> bool {
+        self.command & COMMAND_TRANSMITTER_CONTROL != 0
+    }
+
+    /// The backend at the other end of the serial line
+    pub fn backend(&self) -> &dyn SerialBackend {
+        self.backend.as_ref()
+    }
+
+    /// Mutable access to the backend at the other end of the serial line
+    pub fn backend_mut(&mut self) -> &mut dyn SerialBackend {
+        self.backend.as_mut()
+    }
+
+    /// Number of CPU cycles taken by a frame of start, data, parity and stop bits
+    pub fn frame_ticks(&self) -> u64 {
+        let data_bits = 8 - ((self.control >> 5) & 0x03) as u64;
+        let parity_bits = (self.command & COMMAND_PARITY_ENABLE != 0) as u64;
+        let stop_bits = if self.control & CONTROL_STOP_BITS != 0 {
+            2
+        } else {
+            1
+        };
+        let bits = 1 + data_bits + parity_bits + stop_bits;
+        let baud = BAUD_RATES[(self.control & 0x0F) as usize] as u64;
+        (bits * self.cpu_clock as u64).div_ceil(baud)
+    }
+
+    /// The receiver and interrupts are enabled by DTR
+    fn is_enabled(&self) -> bool {
+        self.command & COMMAND_DTR != 0
+    }
+
+    /// Transmit interrupts are enabled by transmitter control 01
+    fn transmit_irq_enabled(&self) -> bool {
+        self.command & COMMAND_TRANSMITTER_CONTROL == COMMAND_TRANSMITTER_IRQ
+    }
+
+    /// The transmitter requests an interrupt while its data register is empty
+    fn transmit_irq(&self) -> bool {
+        !self.w65c51_bug
+            && self.is_enabled()
+            && self.transmit_irq_enabled()
+            && self.transmit_data.is_none()
+    }
+
+    /// Set the interrupt status bit, if interrupts are enabled
+    fn raise_irq(&mut self) {
+        if self.is_enabled() {
+            self.status |= STATUS_IRQ;
+        }
+    }
+
+    /// The transmit data register is empty
+    fn transmit_empty(&self) -> bool {
+        self.w65c51_bug || self.transmit_data.is_none()
+    }
+
+    /// Status register as read by the CPU
+    fn read_status(&self) -> u8 {
+        let mut status = self.status;
+        if self.transmit_irq() {
+            status |= STATUS_IRQ;
+        }
+        if self.transmit_empty() {
+            status |= STATUS_TDRE;
+        }
+        if !self.dcd {
+            status |= STATUS_DCD;
+        }
+        if !self.dsr {
+            status |= STATUS_DSR;
+        }
+        status
+    }
+
+    /// Move the transmit data register into the idle shift register
+    fn load_shift_register(&mut self) {
+        if self.transmit_shift.is_none()
+            && let Some(data) = self.transmit_data.take()
+        {
+            self.transmit_shift = Some(data);
+            self.transmit_ticks = self.frame_ticks();
+        }
+    }
+
+    /// Write to the transmit data register
+    fn write_data(&mut self, data: u8) {
+        if self.w65c51_bug {
+            self.transmit_shift = Some(data);
+            self.transmit_ticks = self.frame_ticks();
+        } else {
+            self.transmit_data = Some(data);
+            self.load_shift_register();
+        }
+    }
+
+    /// Store a byte received from the backend
+    fn receive(&mut self, data: u8) {
+        if self.status & STATUS_RDRF != 0 {
+            // The new byte is lost, the unread one is kept
+            self.status |= STATUS_OVERRUN;
+        } else {
+            self.receive_data = data;
+            self.status |= STATUS_RDRF;
+        }
+        if self.command & COMMAND_RECEIVER_IRQ_DISABLE == 0 {
+            self.raise_irq();
+        }
+        if self.command & (COMMAND_ECHO | COMMAND_TRANSMITTER_CONTROL) == COMMAND_ECHO {
+            self.backend.transmit(data);
+        }
+    }
+}
+
+impl BusDevice for Acia {
+    fn read(&mut self, address: u16) -> Result<u8, BusError> {
+        match address.wrapping_sub(self.start_address) & 0x03 {
+            REGISTER_DATA => {
+                self.status &= !(STATUS_RDRF | STATUS_OVERRUN);
+                Ok(self.receive_data)
+            }
+            REGISTER_STATUS => {
+                let status = self.read_status();
+                self.status &= !STATUS_IRQ;
+                Ok(status)
+            }
+            REGISTER_COMMAND => Ok(self.command),
+            // $3: control
+            _ => Ok(self.control),
+        }
+    }
+
+    fn write(&mut self, address: u16, data: u8) -> Result<(), BusError> {
+        match address.wrapping_sub(self.start_address) & 0x03 {
+            REGISTER_DATA => self.write_data(data),
+            REGISTER_STATUS => {
+                // Programmed reset
+                self.command &= !COMMAND_RESET_MASK;
+                self.status &= !STATUS_OVERRUN;
+            }
+            REGISTER_COMMAND => self.command = data,
+            // $3: control
+            _ => self.control = data,
+        }
+        Ok(())
+    }
+
+    fn tick(&mut self) {
+        if self.transmit_shift.is_some() {
+            self.transmit_ticks = self.transmit_ticks.saturating_sub(1);
+            if self.transmit_ticks == 0
+                && let Some(data) = self.transmit_shift.take()
+            {
+                self.backend.transmit(data);
+                self.load_shift_register();
+            }
+        }
+
+        // The receiver samples the line once per frame
+        self.receive_ticks = self.receive_ticks.saturating_sub(1);
+        if self.receive_ticks == 0 {
+            self.receive_ticks = self.frame_ticks();
+            if self.is_enabled()
+                && let Some(data) = self.backend.receive()
+            {
+                self.receive(data);
+            }
+        }
+    }
+
+    fn check_irq(&self) -> bool {
+        self.status & STATUS_IRQ != 0 || self.transmit_irq()
+    }
+
+    fn check_nmi(&self) -> bool {
+        // The ACIA does not generate NMIs
+        false
+    }
+
+    fn check_rdy(&self) -> bool {
+        // The ACIA does not insert wait states
+        false
+    }
+
+    fn check_so(&self) -> bool {
+        // The ACIA does not drive the SO line
+        false
+    }
+}
@@ -0,0 +1,261 @@
+//! Unit tests for the 6551 ACIA implementation
+//!
+//! This module tests the registers, transmit and receive timing, interrupts,
+//! the W65C51 transmitter bug and the serial backends.
+
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+use acia6551::backends::SerialBackend;
+use acia6551::backends::buffer::BufferBackend;
+use acia6551::backends::tcp::TcpBackend;
+use acia6551::{Acia, STATUS_DCD, STATUS_IRQ, STATUS_OVERRUN, STATUS_RDRF, STATUS_TDRE};
+use bus::trait_bus_device::BusDevice;
+
+const BASE: u16 = 0x5000;
+const DATA: u16 = BASE;
+const STATUS: u16 = BASE + 1;
+const COMMAND: u16 = BASE + 2;
+const CONTROL: u16 = BASE + 3;
+
+/// 19200 baud, 8 data bits, 1 stop bit: 10 bits of 1_000_000 / 19200 cycles
+const FRAME_19200: u64 = 521;
+
+/// Create an ACIA at 19200 8N1 with DTR set and the receiver interrupt enabled
+fn create_acia() -> (Acia, BufferBackend) {
+    let backend = BufferBackend::new();
+    let mut acia = Acia::new(BASE, Box::new(backend.clone()));
+    acia.write(CONTROL, 0x1F).unwrap();
+    acia.write(COMMAND, 0x09).unwrap();
+    (acia, backend)
+}
+
+/// Tick the ACIA a number of times
+fn run(acia: &mut Acia, ticks: u64) {
+    for _ in 0..ticks {
+        acia.tick();
+    }
+}
+
+// Test registers
+#[test]
+fn test_power_on_state() {
+    let mut acia = Acia::new(BASE, Box::new(BufferBackend::new()));
+    assert_eq!(acia.read(COMMAND).unwrap(), 0x02);
+    assert_eq!(acia.read(CONTROL).unwrap(), 0x00);
+    assert_eq!(acia.read(STATUS).unwrap(), STATUS_TDRE);
+    assert!(!acia.dtr());
+    assert!(!acia.rts());
+}
+
+#[test]
+fn test_registers_are_mirrored() {
+    let (mut acia, _) = create_acia();
+    assert_eq!(acia.read(CONTROL + 4).unwrap(), 0x1F);
+    acia.write(COMMAND + 0x0C, 0x0B).unwrap();
+    assert_eq!(acia.read(COMMAND).unwrap(), 0x0B);
+}
+
+#[test]
+fn test_programmed_reset() {
+    let (mut acia, _) = create_acia();
+    acia.write(COMMAND, 0xEB).unwrap();
+    acia.write(STATUS, 0x00).unwrap();
+    assert_eq!(acia.read(COMMAND).unwrap(), 0xE0);
+    assert_eq!(acia.read(CONTROL).unwrap(), 0x1F);
+}
+
+#[test]
+fn test_frame_ticks() {
+    let (mut acia, _) = create_acia();
+    assert_eq!(acia.frame_ticks(), FRAME_19200);
+    // 300 baud, 7 data bits, parity, 2 stop bits: 11 bits
+    acia.write(CONTROL, 0xA6).unwrap();
+    acia.write(COMMAND, 0x29).unwrap();
+    assert_eq!(acia.frame_ticks(), 36_667);
+}
+
+// Test transmitter
+#[test]
+fn test_transmit_timing() {
+    let (mut acia, backend) = create_acia();
+    acia.write(DATA, b'A').unwrap();
+    // The byte moves straight into the shift register
+    assert_eq!(acia.read(STATUS).unwrap() & STATUS_TDRE, STATUS_TDRE);
+    acia.write(DATA, b'B').unwrap();
+    assert_eq!(acia.read(STATUS).unwrap() & STATUS_TDRE, 0);
+
+    run(&mut acia, FRAME_19200 - 1);
+    assert!(backend.take_output().is_empty());
+    acia.tick();
+    assert_eq!(backend.take_output(), b"A");
+    assert_eq!(acia.read(STATUS).unwrap() & STATUS_TDRE, STATUS_TDRE);
+    run(&mut acia, FRAME_19200);
+    assert_eq!(backend.take_output(), b"B");
+}
+
+#[test]
+fn test_transmit_interrupt() {
+    let (mut acia, _) = create_acia();
+    assert!(!acia.check_irq());
+    // Transmitter control 01 enables the interrupt while TDRE is set
+    acia.write(COMMAND, 0x05 | 0x02).unwrap();
+    assert!(acia.check_irq());
+    assert!(acia.rts());
+    acia.write(DATA, 0x00).unwrap();
+    acia.write(DATA, 0x01).unwrap();
+    assert!(!acia.check_irq());
+    run(&mut acia, FRAME_19200);
+    assert!(acia.check_irq());
+    assert_eq!(
+        acia.read(STATUS).unwrap() & (STATUS_IRQ | STATUS_TDRE),
+        STATUS_IRQ | STATUS_TDRE
+    );
+}
+
+#[test]
+fn test_w65c51_bug() {
+    let backend = BufferBackend::new();
+    let mut acia = Acia::new(BASE, Box::new(backend.clone())).with_w65c51_bug();
+    acia.write(CONTROL, 0x1F).unwrap();
+    acia.write(COMMAND, 0x05).unwrap();
+    acia.write(DATA, b'A').unwrap();
+    assert_eq!(acia.read(STATUS).unwrap() & STATUS_TDRE, STATUS_TDRE);
+    assert!(!acia.check_irq());
+
+    // Writing during transmission replaces the byte being sent
+    run(&mut acia, 100);
+    acia.write(DATA, b'B').unwrap();
+    run(&mut acia, FRAME_19200);
+    assert_eq!(backend.take_output(), b"B");
+}
+
+// Test receiver
+#[test]
+fn test_receive() {
+    let (mut acia, backend) = create_acia();
+    backend.push_input(b"hi");
+    acia.tick();
+    assert!(acia.check_irq());
+    let status = acia.read(STATUS).unwrap();
+    assert_eq!(
+        status & (STATUS_IRQ | STATUS_RDRF),
+        STATUS_IRQ | STATUS_RDRF
+    );
+    assert!(!acia.check_irq());
+    assert_eq!(acia.read(DATA).unwrap(), b'h');
+    assert_eq!(acia.read(STATUS).unwrap() & STATUS_RDRF, 0);
+
+    // The next byte arrives a frame later
+    run(&mut acia, FRAME_19200 - 1);
+    assert_eq!(acia.read(STATUS).unwrap() & STATUS_RDRF, 0);
+    acia.tick();
+    assert_eq!(acia.read(DATA).unwrap(), b'i');
+}
+
+#[test]
+fn test_receive_overrun() {
+    let (mut acia, backend) = create_acia();
+    backend.push_input(b"ab");
+    run(&mut acia, FRAME_19200 + 1);
+    assert_eq!(acia.read(STATUS).unwrap() & STATUS_OVERRUN, STATUS_OVERRUN);
+    assert_eq!(acia.read(DATA).unwrap(), b'a');
+    assert_eq!(acia.read(STATUS).unwrap() & STATUS_OVERRUN, 0);
+}
+
+#[test]
+fn test_receiver_disabled_without_dtr() {
+    let backend = BufferBackend::new();
+    let mut acia = Acia::new(BASE, Box::new(backend.clone()));
+    backend.push_input(b"x");
+    run(&mut acia, 10);
+    assert_eq!(backend.pending_input(), 1);
+    assert_eq!(acia.read(STATUS).unwrap() & STATUS_RDRF, 0);
+}
+
+#[test]
+fn test_receiver_interrupt_disabled() {
+    let (mut acia, backend) = create_acia();
+    acia.write(COMMAND, 0x0B).unwrap();
+    backend.push_input(b"x");
+    acia.tick();
+    assert!(!acia.check_irq());
+    assert_eq!(acia.read(STATUS).unwrap() & STATUS_RDRF, STATUS_RDRF);
+}
+
+#[test]
+fn test_echo_mode() {
+    let (mut acia, backend) = create_acia();
+    acia.write(COMMAND, 0x13).unwrap();
+    backend.push_input(b"e");
+    acia.tick();
+    assert_eq!(backend.take_output(), b"e");
+    assert_eq!(acia.read(DATA).unwrap(), b'e');
+}
+
+// Test modem lines
+#[test]
+fn test_carrier_detect() {
+    let (mut acia, _) = create_acia();
+    acia.set_dcd(false);
+    assert!(acia.check_irq());
+    assert_eq!(
+        acia.read(STATUS).unwrap() & (STATUS_IRQ | STATUS_DCD),
+        STATUS_IRQ | STATUS_DCD
+    );
+}
+
+// Test backends
+#[test]
+fn test_tcp_backend() {
+    let mut backend = TcpBackend::bind(0).unwrap();
+    let address = backend.local_addr().unwrap();
+    let mut client = TcpStream::connect(address).unwrap();
+    client.write_all(b"ok").unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let mut received = Vec::new();
+    while received.len() < 2 && Instant::now() < deadline {
+        received.extend(backend.receive());
+    }
+    assert_eq!(received, b"ok");
+    assert!(backend.is_connected());
+
+    backend.transmit(b'!');
+    let mut byte = [0];
+    client
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    client.read_exact(&mut byte).unwrap();
+    assert_eq!(&byte, b"!");
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_pty_backend() {
+    use acia6551::backends::pty::PtyBackend;
+
+    let Ok(mut backend) = PtyBackend::open() else {
+        // No pseudo-terminals in this environment
+        return;
+    };
+    let mut terminal = std::fs::File::options()
+        .read(true)
+        .write(true)
+        .open(backend.path())
+        .unwrap();
+    terminal.write_all(b"\r").unwrap();
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let mut received = None;
+    while received.is_none() && Instant::now() < deadline {
+        received = backend.receive();
+    }
+    assert_eq!(received, Some(b'\r'));
+
+    backend.transmit(b'>');
+    let mut byte = [0];
+    terminal.read_exact(&mut byte).unwrap();
+    assert_eq!(&byte, b">");
+}
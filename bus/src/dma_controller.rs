@@ -0,0 +1,202 @@
+//! Generic DMA controller that copies memory while the CPU is halted through RDY.
+//!
+//! Register map, relative to the start address of the device:
+//! - `$0`/`$1`: Source address (low/high)
+//! - `$2`/`$3`: Destination address (low/high)
+//! - `$4`/`$5`: Transfer length in bytes (low/high)
+//! - `$6`: Control (write `$01` to start) / Status (bit 7 set while busy)
+//!
+//! Every byte takes two bus cycles, one read and one write, just like the DMA units
+//! found in 6502 based consoles.
+
+use crate::errors::BusError;
+use crate::trait_bus_device::{BusDevice, DmaRequest};
+
+const REGISTER_SOURCE_LOW: u16 = 0x0;
+const REGISTER_SOURCE_HIGH: u16 = 0x1;
+const REGISTER_DESTINATION_LOW: u16 = 0x2;
+const REGISTER_DESTINATION_HIGH: u16 = 0x3;
+const REGISTER_LENGTH_LOW: u16 = 0x4;
+const REGISTER_LENGTH_HIGH: u16 = 0x5;
+const REGISTER_CONTROL: u16 = 0x6;
+
+/// Number of registers exposed on the bus
+pub const DMA_CONTROLLER_REGISTER_COUNT: u16 = 7;
+
+const CONTROL_START: u8 = 0x01;
+const STATUS_BUSY: u8 = 0x80;
+
+/// Represents a memory-to-memory DMA controller.
+#[derive(Debug)]
+pub struct DmaController {
+    /// Start address of the register window
+    start_address: u16,
+    /// Programmed source address
+    source: u16,
+    /// Programmed destination address
+    destination: u16,
+    /// Programmed transfer length
+    length: u16,
+    /// Source address of the next byte of the running transfer
+    current_source: u16,
+    /// Destination address of the next byte of the running transfer
+    current_destination: u16,
+    /// Bytes left in the running transfer
+    remaining: u16,
+    /// Byte read by the first half of a transfer cycle, waiting to be written
+    latch: Option<u8>,
+    /// Total bus cycles used for transfers
+    dma_cycles: u64,
+}
+
+impl DmaController {
+    /// Create a new DMA controller with its registers at the specified start address.
+    ///
+    /// # Arguments
+    /// * `start_address` - Start address of the register window
+    ///
+    /// # Returns
+    /// * A new DmaController instance
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let dma = DmaController::new(0xDF00);
+    /// bus.register_device(0xDF00, 0xDF06, Box::new(dma))?;
+    /// ```
+    pub fn new(start_address: u16) -> Self {
+        Self {
+            start_address,
+            source: 0,
+            destination: 0,
+            length: 0,
+            current_source: 0,
+            current_destination: 0,
+            remaining: 0,
+            latch: None,
+            dma_cycles: 0,
+        }
+    }
+
+    /// Start a transfer from the host side, as if the registers had been programmed by the CPU.
+    ///
+    /// # Arguments
+    /// * `source` - Address of the first byte to copy
+    /// * `destination` - Address the first byte is copied to
+    /// * `length` - Number of bytes to copy
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// dma.start(0x0200, 0x2004, 256);
+    /// ```
+    pub fn start(&mut self, source: u16, destination: u16, length: u16) {
+        self.source = source;
+        self.destination = destination;
+        self.length = length;
+        self.begin_transfer();
+    }
+
+    /// Check whether a transfer is in progress.
+    ///
+    /// # Returns
+    /// * `true` while the controller holds RDY low
+    pub fn is_busy(&self) -> bool {
+        self.remaining > 0
+    }
+
+    /// Get the total number of bus cycles used for transfers.
+    ///
+    /// # Returns
+    /// * The number of cycles stolen from the CPU so far
+    pub fn dma_cycles(&self) -> u64 {
+        self.dma_cycles
+    }
+
+    fn begin_transfer(&mut self) {
+        self.current_source = self.source;
+        self.current_destination = self.destination;
+        self.remaining = self.length;
+        self.latch = None;
+    }
+}
+
+impl BusDevice for DmaController {
+    fn read(&mut self, address: u16) -> Result<u8, BusError> {
+        match address.wrapping_sub(self.start_address) {
+            REGISTER_SOURCE_LOW => Ok(self.source as u8),
+            REGISTER_SOURCE_HIGH => Ok((self.source >> 8) as u8),
+            REGISTER_DESTINATION_LOW => Ok(self.destination as u8),
+            REGISTER_DESTINATION_HIGH => Ok((self.destination >> 8) as u8),
+            REGISTER_LENGTH_LOW => Ok(self.length as u8),
+            REGISTER_LENGTH_HIGH => Ok((self.length >> 8) as u8),
+            REGISTER_CONTROL => Ok(if self.is_busy() { STATUS_BUSY } else { 0 }),
+            _ => Err(BusError::AddressOutOfRange(address)),
+        }
+    }
+
+    fn write(&mut self, address: u16, data: u8) -> Result<(), BusError> {
+        match address.wrapping_sub(self.start_address) {
+            REGISTER_SOURCE_LOW => self.source = (self.source & 0xFF00) | data as u16,
+            REGISTER_SOURCE_HIGH => self.source = (self.source & 0x00FF) | (data as u16) << 8,
+            REGISTER_DESTINATION_LOW => {
+                self.destination = (self.destination & 0xFF00) | data as u16
+            }
+            REGISTER_DESTINATION_HIGH => {
+                self.destination = (self.destination & 0x00FF) | (data as u16) << 8
+            }
+            REGISTER_LENGTH_LOW => self.length = (self.length & 0xFF00) | data as u16,
+            REGISTER_LENGTH_HIGH => self.length = (self.length & 0x00FF) | (data as u16) << 8,
+            REGISTER_CONTROL => {
+                if data & CONTROL_START != 0 && !self.is_busy() {
+                    self.begin_transfer();
+                }
+            }
+            _ => return Err(BusError::AddressOutOfRange(address)),
+        }
+        Ok(())
+    }
+
+    fn tick(&mut self) {
+        // Transfers are driven by the bus through dma_request
+    }
+
+    fn check_irq(&self) -> bool {
+        // The DMA controller does not generate IRQs
+        false
+    }
+
+    fn check_nmi(&self) -> bool {
+        // The DMA controller does not generate NMIs
+        false
+    }
+
+    fn check_rdy(&self) -> bool {
+        self.is_busy()
+    }
+
+    fn check_so(&self) -> bool {
+        // The DMA controller does not drive the SO line
+        false
+    }
+
+    fn dma_request(&mut self) -> Option<DmaRequest> {
+        if !self.is_busy() {
+            return None;
+        }
+        self.dma_cycles += 1;
+        match self.latch.take() {
+            None => Some(DmaRequest::Read(self.current_source)),
+            Some(data) => {
+                let request = DmaRequest::Write(self.current_destination, data);
+                self.current_source = self.current_source.wrapping_add(1);
+                self.current_destination = self.current_destination.wrapping_add(1);
+                self.remaining -= 1;
+                Some(request)
+            }
+        }
+    }
+
+    fn dma_response(&mut self, data: Option<u8>) {
+        // Reads from unmapped addresses return an open bus, approximated as 0xFF
+        self.latch = Some(data.unwrap_or(0xFF));
+    }
+}
@@ -1,5 +1,7 @@
 use std::fmt;
 
+use crate::DeviceId;
+
 #[derive(Debug)]
 /// Errors related to bus operations
 pub enum BusError {
@@ -11,6 +13,8 @@ pub enum BusError {
     WriteOnly(u16),
     /// No device found at the specified address
     DeviceNotFound(u16),
+    /// No device registered under the specified id
+    UnknownDevice(DeviceId),
     /// Invalid data encountered
     InvalidData,
     /// Other unspecified bus error
@@ -21,13 +25,20 @@ impl fmt::Display for BusError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             BusError::AddressOutOfRange(addr) => write!(f, "Address out of range: 0x{:04X}", addr),
-            BusError::ReadOnly(addr) => write!(f, "Attempted write to read-only address: 0x{:04X}", addr),
-            BusError::WriteOnly(addr) => write!(f, "Attempted read from write-only address: 0x{:04X}", addr),
-            BusError::DeviceNotFound(addr) => write!(f, "No device found at address: 0x{:04X}", addr),
+            BusError::ReadOnly(addr) => {
+                write!(f, "Attempted write to read-only address: 0x{:04X}", addr)
+            }
+            BusError::WriteOnly(addr) => {
+                write!(f, "Attempted read from write-only address: 0x{:04X}", addr)
+            }
+            BusError::DeviceNotFound(addr) => {
+                write!(f, "No device found at address: 0x{:04X}", addr)
+            }
+            BusError::UnknownDevice(id) => write!(f, "No device registered with id: {}", id),
             BusError::InvalidData => write!(f, "Invalid data encountered"),
             BusError::Other(msg) => write!(f, "Other bus error: {}", msg),
         }
     }
 }
 
-impl std::error::Error for BusError {}
\ No newline at end of file
+impl std::error::Error for BusError {}
@@ -2,23 +2,105 @@
 //! This module provides a `BusController` struct that implements the `BusDevice` trait,
 //! allowing it to manage multiple devices and route memory accesses appropriately.
 
+/// Generic DMA controller device
+pub mod dma_controller;
 /// Errors related to bus operations
 pub mod errors;
+/// Device timing modes used by the bus scheduler
+pub mod timing_mode;
 /// Trait defining the interface for bus devices
 pub mod trait_bus_device;
 
-use crate::{errors::BusError, trait_bus_device::BusDevice};
+use std::any::Any;
+use std::fmt;
+
+use crate::{
+    errors::BusError,
+    timing_mode::TimingMode,
+    trait_bus_device::{BusDevice, DmaRequest},
+};
+
+/// Handle identifying a device registered with a `BusController`
+///
+/// Ids are handed out by `BusController::register_device` and are never reused,
+/// so a stale id cannot silently refer to a different device after a removal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DeviceId(usize);
+
+impl fmt::Display for DeviceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
 
 struct DeviceEntry {
+    id: DeviceId,
+    /// Inclusive address ranges decoded by the device, the registered range first
+    ranges: Vec<(u16, u16)>,
+    device: Box<dyn BusDevice>,
+    /// Device ticks produced per `clock_divider` bus ticks
+    clock_multiplier: u32,
+    /// Bus ticks needed to produce `clock_multiplier` device ticks
+    clock_divider: u32,
+    /// Fractional clock progress carried over between bus ticks
+    clock_accumulator: u32,
+    /// How clock ticks are delivered to the device
+    timing_mode: TimingMode,
+    /// Device ticks owed to a scheduled device since it was last synchronised
+    pending_ticks: u64,
+    /// Device ticks after the last synchronisation at which the next event fires
+    next_event: Option<u64>,
+}
+
+impl DeviceEntry {
+    /// Check whether an address falls in one of the ranges decoded by the device
+    fn decodes(&self, address: u16) -> bool {
+        self.ranges
+            .iter()
+            .any(|&(start, end)| address >= start && address <= end)
+    }
+
+    /// Deliver all pending ticks to the device and query its next event
+    fn synchronize(&mut self) {
+        if self.pending_ticks > 0 {
+            self.device.advance(self.pending_ticks);
+            self.pending_ticks = 0;
+        }
+        self.next_event = match self.timing_mode {
+            TimingMode::EveryCycle => None,
+            TimingMode::Scheduled => self.device.ticks_until_event(),
+        };
+    }
+}
+
+/// Address range that repeats another range of the memory map
+struct MirrorEntry {
     start: u16,
     end: u16,
-    device: Box<dyn BusDevice>,
+    /// First address of the mirrored range
+    target_start: u16,
+    /// Number of addresses in the mirrored range
+    target_length: u32,
+}
+
+impl MirrorEntry {
+    /// Translate an address inside the mirror to the address it repeats
+    fn translate(&self, address: u16) -> u16 {
+        let offset = (address - self.start) as u32 % self.target_length;
+        (self.target_start as u32 + offset) as u16
+    }
 }
 
 /// BusController manages multiple memory-mapped devices and routes read/write operations
 /// to the appropriate device based on the address.
 pub struct BusController {
     devices: Vec<DeviceEntry>,
+    mirrors: Vec<MirrorEntry>,
+    next_id: usize,
+    /// Master clock, counting bus ticks since creation
+    clock: u64,
+    /// Whether the CPU is halted by RDY and the bus is free for DMA
+    cpu_halted: bool,
 }
 
 impl BusController {
@@ -34,6 +116,10 @@ impl BusController {
     pub fn new() -> Self {
         Self {
             devices: Vec::new(),
+            mirrors: Vec::new(),
+            next_id: 0,
+            clock: 0,
+            cpu_halted: false,
         }
     }
 
@@ -45,7 +131,7 @@ impl BusController {
     /// * `device` - The device to register
     ///
     /// # Returns
-    /// * `Ok(())` if the device was registered successfully
+    /// * `Ok(DeviceId)` identifying the registered device
     /// * `Err(BusError)` if the device overlaps with an existing device
     ///
     /// # Errors
@@ -55,34 +141,448 @@ impl BusController {
     /// ``` ignore
     /// let mut bus = BusController::new();
     /// let device = Box::new(MyDevice::new());
-    /// bus.register_device(0x2000, 0x2FFF, device).unwrap();
+    /// let id = bus.register_device(0x2000, 0x2FFF, device).unwrap();
     /// ```
     pub fn register_device(
         &mut self,
         start: u16,
         end: u16,
         device: Box<dyn BusDevice>,
+    ) -> Result<DeviceId, BusError> {
+        self.check_free_range(start, end)?;
+
+        let id = DeviceId(self.next_id);
+        self.next_id += 1;
+        self.devices.push(DeviceEntry {
+            id,
+            ranges: vec![(start, end)],
+            device,
+            clock_multiplier: 1,
+            clock_divider: 1,
+            clock_accumulator: 0,
+            timing_mode: TimingMode::EveryCycle,
+            pending_ticks: 0,
+            next_event: None,
+        });
+        Ok(id)
+    }
+
+    /// Map an additional address range to a registered device
+    ///
+    /// Some chips have more than one chip select, such as the 6532 RIOT whose RAM
+    /// and I/O registers are decoded separately. The device receives the unmodified
+    /// address for accesses in any of its ranges and tells them apart itself.
+    ///
+    /// # Arguments
+    /// * `id` - Id returned when the device was registered
+    /// * `start` - Start address of the additional range
+    /// * `end` - End address of the additional range
+    ///
+    /// # Returns
+    /// * `Ok(())` if the range was mapped
+    /// * `Err(BusError)` if the device is unknown or the range is not free
+    ///
+    /// # Errors
+    /// * `BusError::UnknownDevice` if the id does not refer to a registered device
+    /// * If the range overlaps with an existing device or mirror
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// // RIOT RAM at $0080-$00FF and I/O registers at $0280-$029F
+    /// let id = bus.register_device(0x0080, 0x00FF, Box::new(riot))?;
+    /// bus.register_device_range(id, 0x0280, 0x029F)?;
+    /// ```
+    pub fn register_device_range(
+        &mut self,
+        id: DeviceId,
+        start: u16,
+        end: u16,
     ) -> Result<(), BusError> {
-        // Check if the device overlaps with any existing devices
-        for device_entry in &self.devices {
-            if (start >= device_entry.start && start <= device_entry.end)
-                || (end >= device_entry.start && end <= device_entry.end)
-            {
+        self.check_free_range(start, end)?;
+        let entry = self
+            .devices
+            .iter_mut()
+            .find(|entry| entry.id == id)
+            .ok_or(BusError::UnknownDevice(id))?;
+        entry.ranges.push((start, end));
+        Ok(())
+    }
+
+    /// Register a mirror, an address range that repeats another range of the memory map
+    ///
+    /// Accesses to the mirror are forwarded to `target_start + (address - start) % length`,
+    /// where `length` is the size of the target range. A mirror larger than its target
+    /// repeats the target as many times as needed, like the partially decoded address
+    /// lines of real hardware.
+    ///
+    /// # Arguments
+    /// * `start` - Start address of the mirror
+    /// * `end` - End address of the mirror
+    /// * `target_start` - Start address of the mirrored range
+    /// * `target_end` - End address of the mirrored range
+    ///
+    /// # Returns
+    /// * `Ok(())` if the mirror was registered
+    /// * `Err(BusError)` if the mirror overlaps with an existing device or mirror
+    ///
+    /// # Errors
+    /// * If `start` is greater than `end` or `target_start` is greater than `target_end`
+    /// * If the mirror address range overlaps with an existing device or mirror
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// // 2K of RAM repeated four times over $0000-$1FFF
+    /// bus.register_device(0x0000, 0x07FF, Box::new(ram))?;
+    /// bus.register_mirror(0x0800, 0x1FFF, 0x0000, 0x07FF)?;
+    /// ```
+    pub fn register_mirror(
+        &mut self,
+        start: u16,
+        end: u16,
+        target_start: u16,
+        target_end: u16,
+    ) -> Result<(), BusError> {
+        if target_start > target_end {
+            return Err(BusError::Other(format!(
+                "Invalid mirror target range 0x{:04X}-0x{:04X}",
+                target_start, target_end
+            )));
+        }
+        self.check_free_range(start, end)?;
+
+        self.mirrors.push(MirrorEntry {
+            start,
+            end,
+            target_start,
+            target_length: (target_end - target_start) as u32 + 1,
+        });
+        Ok(())
+    }
+
+    /// Check whether an address is decoded by a device, directly or through a mirror
+    ///
+    /// Unlike `read`, this has no side effects on the devices.
+    ///
+    /// # Arguments
+    /// * `address` - Memory address to check
+    ///
+    /// # Returns
+    /// * `true` if a read or write to the address reaches a device
+    /// * `false` otherwise
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// if !bus.is_mapped(0xFFFC) {
+    ///     // No reset vector
+    /// }
+    /// ```
+    pub fn is_mapped(&self, address: u16) -> bool {
+        let address = self.resolve_mirror(address);
+        self.devices.iter().any(|entry| entry.decodes(address))
+    }
+
+    /// Check that an address range is valid and not used by a device or mirror yet
+    fn check_free_range(&self, start: u16, end: u16) -> Result<(), BusError> {
+        if start > end {
+            return Err(BusError::Other(format!(
+                "Invalid address range 0x{:04X}-0x{:04X}",
+                start, end
+            )));
+        }
+        let used_ranges = self
+            .devices
+            .iter()
+            .flat_map(|entry| entry.ranges.iter().copied())
+            .chain(self.mirrors.iter().map(|mirror| (mirror.start, mirror.end)));
+        for (used_start, used_end) in used_ranges {
+            if start <= used_end && end >= used_start {
                 return Err(BusError::Other(format!(
                     "Device address range 0x{:04X}-0x{:04X} overlaps with existing device range 0x{:04X}-0x{:04X}",
-                    start, end, device_entry.start, device_entry.end
+                    start, end, used_start, used_end
                 )));
             }
         }
+        Ok(())
+    }
+
+    /// Translate an address inside a mirror to the address it repeats
+    fn resolve_mirror(&self, address: u16) -> u16 {
+        self.mirrors
+            .iter()
+            .find(|mirror| address >= mirror.start && address <= mirror.end)
+            .map_or(address, |mirror| mirror.translate(address))
+    }
 
-        self.devices.push(DeviceEntry { start, end, device });
+    /// Get a reference to a registered device, downcast to its concrete type
+    ///
+    /// A device in `TimingMode::Scheduled` is returned in the state of its last
+    /// synchronisation; use `device_mut` or `synchronize` to catch it up first.
+    ///
+    /// # Arguments
+    /// * `id` - Id returned when the device was registered
+    ///
+    /// # Returns
+    /// * `Some(&T)` if a device with this id exists and is of type `T`
+    /// * `None` otherwise
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let id = bus.register_device(0x0000, 0x07FF, Box::new(ram))?;
+    /// let ram = bus.device::<Ram>(id).unwrap();
+    /// let dump = ram.export(0, 0x100);
+    /// ```
+    pub fn device<T: BusDevice>(&self, id: DeviceId) -> Option<&T> {
+        let entry = self.devices.iter().find(|entry| entry.id == id)?;
+        (entry.device.as_ref() as &dyn Any).downcast_ref::<T>()
+    }
+
+    /// Get a mutable reference to a registered device, downcast to its concrete type
+    ///
+    /// A device in `TimingMode::Scheduled` is caught up to the master clock first.
+    ///
+    /// # Arguments
+    /// * `id` - Id returned when the device was registered
+    ///
+    /// # Returns
+    /// * `Some(&mut T)` if a device with this id exists and is of type `T`
+    /// * `None` otherwise
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let id = bus.register_device(0x0000, 0x07FF, Box::new(ram))?;
+    /// bus.device_mut::<Ram>(id).unwrap().import(&program, 0x0200)?;
+    /// ```
+    pub fn device_mut<T: BusDevice>(&mut self, id: DeviceId) -> Option<&mut T> {
+        let entry = self.devices.iter_mut().find(|entry| entry.id == id)?;
+        entry.synchronize();
+        (entry.device.as_mut() as &mut dyn Any).downcast_mut::<T>()
+    }
+
+    /// Remove a device from the memory map
+    ///
+    /// The address range previously occupied by the device becomes free for new registrations.
+    ///
+    /// # Arguments
+    /// * `id` - Id returned when the device was registered
+    ///
+    /// # Returns
+    /// * `Ok(Box<dyn BusDevice>)` containing the removed device
+    /// * `Err(BusError)` if no device is registered under this id
+    ///
+    /// # Errors
+    /// * `BusError::UnknownDevice` if the id does not refer to a registered device
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let cartridge = bus.remove_device(id)?;
+    /// ```
+    pub fn remove_device(&mut self, id: DeviceId) -> Result<Box<dyn BusDevice>, BusError> {
+        let index = self
+            .devices
+            .iter()
+            .position(|entry| entry.id == id)
+            .ok_or(BusError::UnknownDevice(id))?;
+        let mut entry = self.devices.remove(index);
+        entry.synchronize();
+        Ok(entry.device)
+    }
+
+    /// Replace a registered device with another one mapped at the same address ranges
+    ///
+    /// The id stays valid and refers to the new device afterwards, which makes
+    /// swapping cartridges or ROM images a single call.
+    ///
+    /// # Arguments
+    /// * `id` - Id returned when the original device was registered
+    /// * `device` - The device to put in its place
+    ///
+    /// # Returns
+    /// * `Ok(Box<dyn BusDevice>)` containing the device that was replaced
+    /// * `Err(BusError)` if no device is registered under this id
+    ///
+    /// # Errors
+    /// * `BusError::UnknownDevice` if the id does not refer to a registered device
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let old_cartridge = bus.replace_device(id, Box::new(new_cartridge))?;
+    /// ```
+    pub fn replace_device(
+        &mut self,
+        id: DeviceId,
+        device: Box<dyn BusDevice>,
+    ) -> Result<Box<dyn BusDevice>, BusError> {
+        let entry = self
+            .devices
+            .iter_mut()
+            .find(|entry| entry.id == id)
+            .ok_or(BusError::UnknownDevice(id))?;
+        entry.synchronize();
+        let old_device = std::mem::replace(&mut entry.device, device);
+        entry.synchronize();
+        Ok(old_device)
+    }
+
+    /// Set the clock ratio between the bus and a registered device
+    ///
+    /// Every bus tick the device is ticked `multiplier / divider` times on average.
+    /// Fractional ratios are carried over between bus ticks so the device clock
+    /// never drifts from the CPU clock. Devices are registered at a 1:1 ratio.
+    ///
+    /// # Arguments
+    /// * `id` - Id returned when the device was registered
+    /// * `multiplier` - Device ticks per `divider` bus ticks
+    /// * `divider` - Bus ticks per `multiplier` device ticks
+    ///
+    /// # Returns
+    /// * `Ok(())` if the clock ratio was set
+    /// * `Err(BusError)` if the device is unknown or the ratio is invalid
+    ///
+    /// # Errors
+    /// * `BusError::UnknownDevice` if the id does not refer to a registered device
+    /// * `BusError::Other` if `multiplier` or `divider` is zero
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// // PPU runs at three times the CPU clock
+    /// bus.set_clock_ratio(ppu_id, 3, 1)?;
+    /// // Timer runs at a sixteenth of the CPU clock
+    /// bus.set_clock_ratio(timer_id, 1, 16)?;
+    /// ```
+    pub fn set_clock_ratio(
+        &mut self,
+        id: DeviceId,
+        multiplier: u32,
+        divider: u32,
+    ) -> Result<(), BusError> {
+        if multiplier == 0 || divider == 0 {
+            return Err(BusError::Other(format!(
+                "Invalid clock ratio {}:{}, multiplier and divider must be non-zero",
+                multiplier, divider
+            )));
+        }
+        let entry = self
+            .devices
+            .iter_mut()
+            .find(|entry| entry.id == id)
+            .ok_or(BusError::UnknownDevice(id))?;
+        entry.clock_multiplier = multiplier;
+        entry.clock_divider = divider;
+        entry.clock_accumulator = 0;
         Ok(())
     }
+
+    /// Set how clock ticks are delivered to a registered device
+    ///
+    /// Devices are registered in `TimingMode::EveryCycle`. In `TimingMode::Scheduled`
+    /// the device is only caught up when it is read or written, when the event reported
+    /// by `BusDevice::ticks_until_event` is due, or on `synchronize`. This avoids calling
+    /// into idle devices on every cycle.
+    ///
+    /// # Arguments
+    /// * `id` - Id returned when the device was registered
+    /// * `timing_mode` - The timing mode to use for the device
+    ///
+    /// # Returns
+    /// * `Ok(())` if the timing mode was set
+    /// * `Err(BusError)` if no device is registered under this id
+    ///
+    /// # Errors
+    /// * `BusError::UnknownDevice` if the id does not refer to a registered device
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// bus.set_timing_mode(timer_id, TimingMode::Scheduled)?;
+    /// ```
+    pub fn set_timing_mode(
+        &mut self,
+        id: DeviceId,
+        timing_mode: TimingMode,
+    ) -> Result<(), BusError> {
+        let entry = self
+            .devices
+            .iter_mut()
+            .find(|entry| entry.id == id)
+            .ok_or(BusError::UnknownDevice(id))?;
+        entry.synchronize();
+        entry.timing_mode = timing_mode;
+        entry.synchronize();
+        Ok(())
+    }
+
+    /// Catch up every scheduled device to the master clock
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// bus.synchronize();
+    /// let timer = bus.device::<Timer>(timer_id).unwrap();
+    /// ```
+    pub fn synchronize(&mut self) {
+        for device_entry in &mut self.devices {
+            device_entry.synchronize();
+        }
+    }
+
+    /// Tell the bus whether the CPU is halted by RDY during the next tick
+    ///
+    /// DMA requests from devices are only served while the CPU is halted,
+    /// so the CPU and a bus master never access the bus in the same cycle.
+    ///
+    /// # Arguments
+    /// * `halted` - `true` if the CPU is not using the bus this cycle
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// bus.set_cpu_halted(true);
+    /// bus.tick(); // DMA transfer happens here
+    /// ```
+    pub fn set_cpu_halted(&mut self, halted: bool) {
+        self.cpu_halted = halted;
+    }
+
+    /// Serve at most one DMA request from the registered devices
+    fn serve_dma(&mut self) {
+        for index in 0..self.devices.len() {
+            let Some(request) = self.devices[index].device.dma_request() else {
+                continue;
+            };
+            match request {
+                DmaRequest::Read(address) => {
+                    let data = self.read(address).ok();
+                    self.devices[index].device.dma_response(data);
+                }
+                DmaRequest::Write(address, data) => {
+                    // A failed DMA write is dropped, just like a write to an unmapped address
+                    let _ = self.write(address, data);
+                }
+            }
+            return;
+        }
+    }
+
+    /// Get the master clock, the number of bus ticks since the bus was created
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let elapsed = bus.clock();
+    /// ```
+    pub fn clock(&self) -> u64 {
+        self.clock
+    }
+}
+
+impl Default for BusController {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl BusDevice for BusController {
     /// Handle memory reads by forwarding to the correct device
     ///
+    /// Addresses inside a mirror are translated first, see `register_mirror`.
+    /// A device in `TimingMode::Scheduled` is caught up to the master clock before the read.
+    ///
     /// # Arguments
     /// * `address` - Memory address to read from
     ///
@@ -92,10 +592,14 @@ impl BusDevice for BusController {
     /// # Errors
     /// * If the memory access is out of range
     /// * If the device read fails
-    fn read(&self, address: u16) -> Result<u8, BusError> {
-        for device_entry in &self.devices {
-            if address >= device_entry.start && address <= device_entry.end {
-                return device_entry.device.read(address);
+    fn read(&mut self, address: u16) -> Result<u8, BusError> {
+        let address = self.resolve_mirror(address);
+        for device_entry in &mut self.devices {
+            if device_entry.decodes(address) {
+                device_entry.synchronize();
+                let result = device_entry.device.read(address);
+                device_entry.synchronize();
+                return result;
             }
         }
         Err(BusError::AddressOutOfRange(address))
@@ -103,6 +607,9 @@ impl BusDevice for BusController {
 
     /// Handle memory writes by forwarding to the correct device
     ///
+    /// Addresses inside a mirror are translated first, see `register_mirror`.
+    /// A device in `TimingMode::Scheduled` is caught up to the master clock before the write.
+    ///
     /// # Arguments
     /// * `address` - Memory address to write to
     /// * `data` - Byte value to write
@@ -111,23 +618,89 @@ impl BusDevice for BusController {
     /// * If the memory access is out of range
     /// * If the device write fails
     fn write(&mut self, address: u16, data: u8) -> Result<(), BusError> {
+        let address = self.resolve_mirror(address);
+        for device_entry in &mut self.devices {
+            if device_entry.decodes(address) {
+                device_entry.synchronize();
+                let result = device_entry.device.write(address, data);
+                device_entry.synchronize();
+                return result;
+            }
+        }
+        Err(BusError::AddressOutOfRange(address))
+    }
+
+    /// Handle loader stores by forwarding to the correct device
+    ///
+    /// Addresses inside a mirror are translated first, see `register_mirror`.
+    ///
+    /// # Arguments
+    /// * `address` - Memory address to store to
+    /// * `data` - Byte value to store
+    ///
+    /// # Errors
+    /// * If the memory access is out of range
+    /// * If the device store fails
+    fn load(&mut self, address: u16, data: u8) -> Result<(), BusError> {
+        let address = self.resolve_mirror(address);
         for device_entry in &mut self.devices {
-            if address >= device_entry.start && address <= device_entry.end {
-                return device_entry.device.write(address, data);
+            if device_entry.decodes(address) {
+                device_entry.synchronize();
+                let result = device_entry.device.load(address, data);
+                device_entry.synchronize();
+                return result;
             }
         }
         Err(BusError::AddressOutOfRange(address))
     }
 
+    /// Forward an opcode fetch signalled by the CPU's SYNC output to all devices
+    ///
+    /// # Arguments
+    /// * `address` - Address of the opcode being fetched
+    fn sync(&mut self, address: u16) {
+        for device_entry in &mut self.devices {
+            device_entry.device.sync(address);
+        }
+    }
+
     /// Perform a clock tick for all devices
     ///
+    /// Each device is ticked according to its clock ratio, see `set_clock_ratio`.
+    /// Devices in `TimingMode::Scheduled` only accumulate pending ticks until
+    /// their next event is due. While the CPU is halted, one DMA request is served.
+    ///
     /// # Examples
     /// ``` ignore
     /// bus.tick();
     /// ```
     fn tick(&mut self) {
+        self.clock = self.clock.wrapping_add(1);
         for device_entry in &mut self.devices {
-            device_entry.device.tick();
+            device_entry.clock_accumulator += device_entry.clock_multiplier;
+            let due_ticks = device_entry.clock_accumulator / device_entry.clock_divider;
+            device_entry.clock_accumulator %= device_entry.clock_divider;
+
+            match device_entry.timing_mode {
+                TimingMode::EveryCycle => {
+                    for _ in 0..due_ticks {
+                        device_entry.device.tick();
+                    }
+                }
+                TimingMode::Scheduled => {
+                    device_entry.pending_ticks += due_ticks as u64;
+                    if device_entry
+                        .next_event
+                        .is_some_and(|event| device_entry.pending_ticks >= event)
+                    {
+                        device_entry.synchronize();
+                    }
+                }
+            }
+        }
+
+        if self.cpu_halted {
+            self.serve_dma();
         }
     }
 
@@ -172,4 +745,44 @@ impl BusDevice for BusController {
         }
         false
     }
+
+    /// Check the state of the RDY line
+    ///
+    /// # Returns
+    /// * `true` if any device is pulling RDY low
+    /// * `false` otherwise
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// if bus.check_rdy() {
+    ///   // Halt the CPU
+    /// }
+    /// ```
+    fn check_rdy(&self) -> bool {
+        for device_entry in &self.devices {
+            if device_entry.device.check_rdy() {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Check the state of the SO line
+    ///
+    /// # Returns
+    /// * `true` if any device is pulling SO low
+    /// * `false` otherwise
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let so_asserted = bus.check_so();
+    /// ```
+    fn check_so(&self) -> bool {
+        for device_entry in &self.devices {
+            if device_entry.device.check_so() {
+                return true;
+            }
+        }
+        false
+    }
 }
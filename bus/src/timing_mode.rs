@@ -0,0 +1,15 @@
+//! Defines how the bus delivers clock ticks to a device.
+
+/// Timing mode of a registered device
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimingMode {
+    /// The device is ticked on every bus tick (default)
+    #[default]
+    EveryCycle,
+    /// The device is only caught up when it is accessed, when its next event is due,
+    /// or when the host explicitly synchronises it
+    ///
+    /// Devices using this mode should override `BusDevice::advance` and
+    /// `BusDevice::ticks_until_event` so that they can skip over idle periods.
+    Scheduled,
+}
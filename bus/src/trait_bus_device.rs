@@ -1,11 +1,19 @@
 //! Trait defining the interface for bus devices
 
+use std::any::Any;
+
 use crate::errors::BusError;
 
 /// This module provides the `BusDevice` trait which must be implemented by any device
 /// that wants to be connected to the `BusController`.
-pub trait BusDevice {
+///
+/// `Any` is a supertrait so that a registered device can be downcast back to its concrete
+/// type through `BusController::device` and `BusController::device_mut`.
+pub trait BusDevice: Any {
     /// Read a byte from the device at the specified address
+    ///
+    /// Reads take `&mut self` because they may have side effects on the device, such as
+    /// catching up its clock or clearing status flags.
     /// # Arguments
     /// * `address` - The address to read from
     /// # Returns
@@ -13,15 +21,98 @@ pub trait BusDevice {
     /// * `Err(String)` if the read fails
     /// # Errors
     /// * If the address is out of range for the device
-    fn read(&self, address: u16) -> Result<u8, BusError>;
+    fn read(&mut self, address: u16) -> Result<u8, BusError>;
     /// Write a byte to the device at the specified address
     fn write(&mut self, address: u16, data: u8) -> Result<(), BusError>;
 
+    /// Store a byte at the specified address on behalf of a program loader
+    ///
+    /// Unlike `write`, this bypasses write protection so that images can be placed
+    /// into ROM through the bus. The default forwards to `write`.
+    /// # Arguments
+    /// * `address` - The address to store the byte at
+    /// * `data` - The byte to store
+    /// # Errors
+    /// * If the address is out of range for the device
+    fn load(&mut self, address: u16, data: u8) -> Result<(), BusError> {
+        self.write(address, data)
+    }
+
+    /// Observe an opcode fetch, signalled by the SYNC output of the CPU
+    ///
+    /// Called before the opcode at `address` is read, so a device can attribute the
+    /// following accesses to the instruction at that address. The default ignores it.
+    /// # Arguments
+    /// * `address` - Address of the opcode being fetched
+    fn sync(&mut self, _address: u16) {}
+
     /// Perform a clock tick for the device
     fn tick(&mut self);
 
+    /// Advance the device by several clock ticks at once
+    ///
+    /// Used by the bus to catch up devices in `TimingMode::Scheduled`. The default
+    /// calls `tick` repeatedly; event-driven devices should override it to jump
+    /// straight to the resulting state.
+    fn advance(&mut self, ticks: u64) {
+        for _ in 0..ticks {
+            self.tick();
+        }
+    }
+
+    /// Number of device clock ticks until the device's next observable event
+    ///
+    /// An event is anything the rest of the system must see without accessing the device,
+    /// such as a timer underflow raising an IRQ. Only consulted for devices in
+    /// `TimingMode::Scheduled`.
+    ///
+    /// # Returns
+    /// * `Some(ticks)` if an event is pending
+    /// * `None` if the device is idle until it is accessed (default)
+    fn ticks_until_event(&self) -> Option<u64> {
+        None
+    }
+
     /// Check the state of the IRQ line
     fn check_irq(&self) -> bool;
     /// Check the state of the NMI line
     fn check_nmi(&self) -> bool;
+    /// Check the state of the RDY line
+    ///
+    /// Returns `true` while the device pulls RDY low to halt the CPU, e.g. during DMA
+    /// or to insert wait states.
+    fn check_rdy(&self) -> bool;
+    /// Check the state of the SO (Set Overflow) line
+    ///
+    /// Returns `true` while the device pulls SO low. The CPU sets the overflow flag
+    /// on the falling edge, i.e. when this changes from `false` to `true`.
+    fn check_so(&self) -> bool;
+
+    /// Bus access the device wants to perform as bus master
+    ///
+    /// Only polled by the `BusController` while the CPU is halted by RDY. At most one
+    /// request is served per bus tick. The result of a `DmaRequest::Read` is delivered
+    /// through `dma_response`.
+    ///
+    /// # Returns
+    /// * `Some(DmaRequest)` if the device wants the bus this tick
+    /// * `None` otherwise (default)
+    fn dma_request(&mut self) -> Option<DmaRequest> {
+        None
+    }
+
+    /// Receive the data of a completed `DmaRequest::Read`
+    ///
+    /// # Arguments
+    /// * `data` - The byte read from the bus, or `None` if the read failed
+    fn dma_response(&mut self, _data: Option<u8>) {}
+}
+
+/// A single bus access performed by a device acting as bus master
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DmaRequest {
+    /// Read a byte from the address
+    Read(u16),
+    /// Write a byte to the address
+    Write(u16, u8),
 }
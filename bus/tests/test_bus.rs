@@ -0,0 +1,777 @@
+//! Unit tests for the bus controller
+//!
+//! This module contains tests for the BusController implementation,
+//! testing device registration, address routing, mirrors, typed device handles
+//! and device removal/replacement.
+
+use bus::BusController;
+use bus::dma_controller::DmaController;
+use bus::errors::BusError;
+use bus::timing_mode::TimingMode;
+use bus::trait_bus_device::BusDevice;
+
+/// Minimal memory-like device used to exercise the bus
+struct TestDevice {
+    start_address: u16,
+    memory: Vec<u8>,
+}
+
+impl TestDevice {
+    fn new(start_address: u16, size: usize) -> Self {
+        Self {
+            start_address,
+            memory: vec![0; size],
+        }
+    }
+}
+
+impl BusDevice for TestDevice {
+    fn read(&mut self, address: u16) -> Result<u8, BusError> {
+        let offset = address.wrapping_sub(self.start_address) as usize;
+        self.memory
+            .get(offset)
+            .copied()
+            .ok_or(BusError::AddressOutOfRange(address))
+    }
+
+    fn write(&mut self, address: u16, data: u8) -> Result<(), BusError> {
+        let offset = address.wrapping_sub(self.start_address) as usize;
+        match self.memory.get_mut(offset) {
+            Some(byte) => {
+                *byte = data;
+                Ok(())
+            }
+            None => Err(BusError::AddressOutOfRange(address)),
+        }
+    }
+
+    fn tick(&mut self) {}
+
+    fn check_irq(&self) -> bool {
+        false
+    }
+
+    fn check_nmi(&self) -> bool {
+        false
+    }
+
+    fn check_rdy(&self) -> bool {
+        false
+    }
+
+    fn check_so(&self) -> bool {
+        false
+    }
+}
+
+/// A second device type, used to check that downcasting rejects the wrong type
+struct OtherDevice;
+
+impl BusDevice for OtherDevice {
+    fn read(&mut self, _address: u16) -> Result<u8, BusError> {
+        Ok(0xEA)
+    }
+
+    fn write(&mut self, address: u16, _data: u8) -> Result<(), BusError> {
+        Err(BusError::ReadOnly(address))
+    }
+
+    fn tick(&mut self) {}
+
+    fn check_irq(&self) -> bool {
+        false
+    }
+
+    fn check_nmi(&self) -> bool {
+        false
+    }
+
+    fn check_rdy(&self) -> bool {
+        false
+    }
+
+    fn check_so(&self) -> bool {
+        false
+    }
+}
+
+// Test device registration
+#[test]
+fn test_register_device_returns_distinct_ids() {
+    let mut bus = BusController::new();
+    let first = bus
+        .register_device(0x0000, 0x00FF, Box::new(TestDevice::new(0x0000, 0x100)))
+        .unwrap();
+    let second = bus
+        .register_device(0x0100, 0x01FF, Box::new(TestDevice::new(0x0100, 0x100)))
+        .unwrap();
+
+    assert_ne!(first, second);
+}
+
+#[test]
+fn test_register_overlapping_device_fails() {
+    let mut bus = BusController::new();
+    bus.register_device(0x0000, 0x00FF, Box::new(TestDevice::new(0x0000, 0x100)))
+        .unwrap();
+
+    let result = bus.register_device(0x0080, 0x017F, Box::new(OtherDevice));
+    assert!(matches!(result, Err(BusError::Other(_))));
+}
+
+#[test]
+fn test_register_enclosing_device_fails() {
+    let mut bus = BusController::new();
+    bus.register_device(0x0080, 0x00FF, Box::new(TestDevice::new(0x0080, 0x80)))
+        .unwrap();
+
+    let result = bus.register_device(0x0000, 0x01FF, Box::new(OtherDevice));
+    assert!(matches!(result, Err(BusError::Other(_))));
+}
+
+// Test mirrors
+#[test]
+fn test_mirror_repeats_target_range() {
+    let mut bus = BusController::new();
+    bus.register_device(0x0000, 0x07FF, Box::new(TestDevice::new(0x0000, 0x800)))
+        .unwrap();
+    bus.register_mirror(0x0800, 0x1FFF, 0x0000, 0x07FF).unwrap();
+
+    bus.write(0x0012, 0x34).unwrap();
+    assert_eq!(bus.read(0x0812).unwrap(), 0x34);
+    assert_eq!(bus.read(0x1812).unwrap(), 0x34);
+
+    bus.write(0x1FFF, 0x56).unwrap();
+    assert_eq!(bus.read(0x07FF).unwrap(), 0x56);
+}
+
+#[test]
+fn test_mirror_overlapping_device_fails() {
+    let mut bus = BusController::new();
+    bus.register_device(0x0000, 0x07FF, Box::new(TestDevice::new(0x0000, 0x800)))
+        .unwrap();
+
+    let result = bus.register_mirror(0x0400, 0x0FFF, 0x0000, 0x07FF);
+    assert!(matches!(result, Err(BusError::Other(_))));
+}
+
+#[test]
+fn test_device_overlapping_mirror_fails() {
+    let mut bus = BusController::new();
+    bus.register_mirror(0x0800, 0x1FFF, 0x0000, 0x07FF).unwrap();
+
+    let result = bus.register_device(0x1000, 0x10FF, Box::new(OtherDevice));
+    assert!(matches!(result, Err(BusError::Other(_))));
+}
+
+#[test]
+fn test_is_mapped() {
+    let mut bus = BusController::new();
+    bus.register_device(0x0000, 0x07FF, Box::new(TestDevice::new(0x0000, 0x800)))
+        .unwrap();
+    bus.register_mirror(0x0800, 0x0FFF, 0x0000, 0x07FF).unwrap();
+
+    assert!(bus.is_mapped(0x0000));
+    assert!(bus.is_mapped(0x0C00));
+    assert!(!bus.is_mapped(0x1000));
+}
+
+// Test devices with several address ranges
+#[test]
+fn test_device_range_routes_to_same_device() {
+    let mut bus = BusController::new();
+    let id = bus
+        .register_device(0x0080, 0x00FF, Box::new(OtherDevice))
+        .unwrap();
+    bus.register_device_range(id, 0x0280, 0x029F).unwrap();
+
+    assert_eq!(bus.read(0x0080).unwrap(), 0xEA);
+    assert_eq!(bus.read(0x0290).unwrap(), 0xEA);
+    assert!(matches!(
+        bus.write(0x029F, 0x00),
+        Err(BusError::ReadOnly(0x029F))
+    ));
+    assert!(bus.is_mapped(0x0280));
+    assert!(!bus.is_mapped(0x02A0));
+}
+
+#[test]
+fn test_device_range_overlapping_fails() {
+    let mut bus = BusController::new();
+    bus.register_device(0x0200, 0x02FF, Box::new(TestDevice::new(0x0200, 0x100)))
+        .unwrap();
+    let id = bus
+        .register_device(0x0080, 0x00FF, Box::new(OtherDevice))
+        .unwrap();
+
+    let result = bus.register_device_range(id, 0x0280, 0x029F);
+    assert!(matches!(result, Err(BusError::Other(_))));
+    let result = bus.register_device(0x0290, 0x0290, Box::new(OtherDevice));
+    assert!(matches!(result, Err(BusError::Other(_))));
+}
+
+#[test]
+fn test_device_range_unknown_device() {
+    let mut bus = BusController::new();
+    let id = bus
+        .register_device(0x0080, 0x00FF, Box::new(OtherDevice))
+        .unwrap();
+    bus.remove_device(id).unwrap();
+
+    let result = bus.register_device_range(id, 0x0280, 0x029F);
+    assert!(matches!(result, Err(BusError::UnknownDevice(_))));
+}
+
+#[test]
+fn test_remove_device_frees_all_ranges() {
+    let mut bus = BusController::new();
+    let id = bus
+        .register_device(0x0080, 0x00FF, Box::new(OtherDevice))
+        .unwrap();
+    bus.register_device_range(id, 0x0280, 0x029F).unwrap();
+    bus.remove_device(id).unwrap();
+
+    assert!(!bus.is_mapped(0x0290));
+    assert!(
+        bus.register_device(0x0200, 0x02FF, Box::new(OtherDevice))
+            .is_ok()
+    );
+}
+
+// Test typed device access
+#[test]
+fn test_device_downcast() {
+    let mut bus = BusController::new();
+    let id = bus
+        .register_device(0x0000, 0x00FF, Box::new(TestDevice::new(0x0000, 0x100)))
+        .unwrap();
+
+    bus.write(0x0010, 0x42).unwrap();
+
+    let device = bus.device::<TestDevice>(id).unwrap();
+    assert_eq!(device.memory[0x10], 0x42);
+}
+
+#[test]
+fn test_device_downcast_wrong_type() {
+    let mut bus = BusController::new();
+    let id = bus
+        .register_device(0x0000, 0x00FF, Box::new(TestDevice::new(0x0000, 0x100)))
+        .unwrap();
+
+    assert!(bus.device::<OtherDevice>(id).is_none());
+    assert!(bus.device_mut::<OtherDevice>(id).is_none());
+}
+
+#[test]
+fn test_device_mut_changes_are_visible_on_bus() {
+    let mut bus = BusController::new();
+    let id = bus
+        .register_device(0x2000, 0x20FF, Box::new(TestDevice::new(0x2000, 0x100)))
+        .unwrap();
+
+    bus.device_mut::<TestDevice>(id).unwrap().memory[0x05] = 0x99;
+
+    assert_eq!(bus.read(0x2005).unwrap(), 0x99);
+}
+
+// Test device removal and replacement
+#[test]
+fn test_remove_device() {
+    let mut bus = BusController::new();
+    let id = bus
+        .register_device(0x0000, 0x00FF, Box::new(TestDevice::new(0x0000, 0x100)))
+        .unwrap();
+
+    assert!(bus.remove_device(id).is_ok());
+    assert!(bus.device::<TestDevice>(id).is_none());
+    assert!(matches!(
+        bus.read(0x0000),
+        Err(BusError::AddressOutOfRange(0x0000))
+    ));
+
+    // The freed range can be registered again
+    assert!(
+        bus.register_device(0x0000, 0x00FF, Box::new(OtherDevice))
+            .is_ok()
+    );
+}
+
+#[test]
+fn test_remove_unknown_device() {
+    let mut bus = BusController::new();
+    let id = bus
+        .register_device(0x0000, 0x00FF, Box::new(OtherDevice))
+        .unwrap();
+    bus.remove_device(id).unwrap();
+
+    assert!(matches!(
+        bus.remove_device(id),
+        Err(BusError::UnknownDevice(unknown)) if unknown == id
+    ));
+}
+
+#[test]
+fn test_ids_are_not_reused_after_removal() {
+    let mut bus = BusController::new();
+    let first = bus
+        .register_device(0x0000, 0x00FF, Box::new(OtherDevice))
+        .unwrap();
+    bus.remove_device(first).unwrap();
+
+    let second = bus
+        .register_device(0x0000, 0x00FF, Box::new(OtherDevice))
+        .unwrap();
+    assert_ne!(first, second);
+}
+
+#[test]
+fn test_replace_device() {
+    let mut bus = BusController::new();
+    let id = bus
+        .register_device(0x8000, 0x80FF, Box::new(TestDevice::new(0x8000, 0x100)))
+        .unwrap();
+    bus.write(0x8000, 0x11).unwrap();
+
+    let mut replacement = TestDevice::new(0x8000, 0x100);
+    replacement.memory[0] = 0x22;
+    let mut old = bus.replace_device(id, Box::new(replacement)).unwrap();
+
+    assert_eq!(old.read(0x8000).unwrap(), 0x11);
+    assert_eq!(bus.read(0x8000).unwrap(), 0x22);
+    assert_eq!(bus.device::<TestDevice>(id).unwrap().memory[0], 0x22);
+}
+
+#[test]
+fn test_replace_device_with_different_type() {
+    let mut bus = BusController::new();
+    let id = bus
+        .register_device(0x8000, 0x80FF, Box::new(TestDevice::new(0x8000, 0x100)))
+        .unwrap();
+
+    bus.replace_device(id, Box::new(OtherDevice)).unwrap();
+
+    assert!(bus.device::<TestDevice>(id).is_none());
+    assert!(bus.device::<OtherDevice>(id).is_some());
+    assert_eq!(bus.read(0x8042).unwrap(), 0xEA);
+}
+
+#[test]
+fn test_replace_unknown_device() {
+    let mut bus = BusController::new();
+    let id = bus
+        .register_device(0x0000, 0x00FF, Box::new(OtherDevice))
+        .unwrap();
+    bus.remove_device(id).unwrap();
+
+    assert!(matches!(
+        bus.replace_device(id, Box::new(OtherDevice)),
+        Err(BusError::UnknownDevice(_))
+    ));
+}
+
+/// Device that only counts how often it was ticked
+struct TickCounter {
+    ticks: u64,
+}
+
+impl BusDevice for TickCounter {
+    fn read(&mut self, _address: u16) -> Result<u8, BusError> {
+        Ok(self.ticks as u8)
+    }
+
+    fn write(&mut self, address: u16, _data: u8) -> Result<(), BusError> {
+        Err(BusError::ReadOnly(address))
+    }
+
+    fn tick(&mut self) {
+        self.ticks += 1;
+    }
+
+    fn check_irq(&self) -> bool {
+        false
+    }
+
+    fn check_nmi(&self) -> bool {
+        false
+    }
+
+    fn check_rdy(&self) -> bool {
+        false
+    }
+
+    fn check_so(&self) -> bool {
+        false
+    }
+}
+
+// Test device clock ratios
+#[test]
+fn test_tick_default_ratio() {
+    let mut bus = BusController::new();
+    let id = bus
+        .register_device(0x0000, 0x0000, Box::new(TickCounter { ticks: 0 }))
+        .unwrap();
+
+    for _ in 0..10 {
+        bus.tick();
+    }
+
+    assert_eq!(bus.device::<TickCounter>(id).unwrap().ticks, 10);
+}
+
+#[test]
+fn test_tick_multiplier() {
+    let mut bus = BusController::new();
+    let id = bus
+        .register_device(0x0000, 0x0000, Box::new(TickCounter { ticks: 0 }))
+        .unwrap();
+    bus.set_clock_ratio(id, 3, 1).unwrap();
+
+    for _ in 0..10 {
+        bus.tick();
+    }
+
+    assert_eq!(bus.device::<TickCounter>(id).unwrap().ticks, 30);
+}
+
+#[test]
+fn test_tick_divider() {
+    let mut bus = BusController::new();
+    let id = bus
+        .register_device(0x0000, 0x0000, Box::new(TickCounter { ticks: 0 }))
+        .unwrap();
+    bus.set_clock_ratio(id, 1, 4).unwrap();
+
+    for _ in 0..3 {
+        bus.tick();
+    }
+    assert_eq!(bus.device::<TickCounter>(id).unwrap().ticks, 0);
+
+    bus.tick();
+    assert_eq!(bus.device::<TickCounter>(id).unwrap().ticks, 1);
+}
+
+#[test]
+fn test_tick_fractional_ratio_does_not_drift() {
+    let mut bus = BusController::new();
+    let id = bus
+        .register_device(0x0000, 0x0000, Box::new(TickCounter { ticks: 0 }))
+        .unwrap();
+    // PAL PPU runs at 3.2 times the CPU clock
+    bus.set_clock_ratio(id, 16, 5).unwrap();
+
+    for _ in 0..1000 {
+        bus.tick();
+    }
+
+    assert_eq!(bus.device::<TickCounter>(id).unwrap().ticks, 3200);
+}
+
+#[test]
+fn test_tick_ratios_are_per_device() {
+    let mut bus = BusController::new();
+    let fast = bus
+        .register_device(0x0000, 0x0000, Box::new(TickCounter { ticks: 0 }))
+        .unwrap();
+    let slow = bus
+        .register_device(0x0001, 0x0001, Box::new(TickCounter { ticks: 0 }))
+        .unwrap();
+    bus.set_clock_ratio(fast, 2, 1).unwrap();
+    bus.set_clock_ratio(slow, 1, 2).unwrap();
+
+    for _ in 0..8 {
+        bus.tick();
+    }
+
+    assert_eq!(bus.device::<TickCounter>(fast).unwrap().ticks, 16);
+    assert_eq!(bus.device::<TickCounter>(slow).unwrap().ticks, 4);
+}
+
+#[test]
+fn test_set_clock_ratio_invalid() {
+    let mut bus = BusController::new();
+    let id = bus
+        .register_device(0x0000, 0x0000, Box::new(TickCounter { ticks: 0 }))
+        .unwrap();
+
+    assert!(matches!(
+        bus.set_clock_ratio(id, 0, 1),
+        Err(BusError::Other(_))
+    ));
+    assert!(matches!(
+        bus.set_clock_ratio(id, 1, 0),
+        Err(BusError::Other(_))
+    ));
+
+    bus.remove_device(id).unwrap();
+    assert!(matches!(
+        bus.set_clock_ratio(id, 1, 1),
+        Err(BusError::UnknownDevice(_))
+    ));
+}
+
+/// Countdown timer that raises an IRQ on underflow and supports lazy catch-up
+struct TestTimer {
+    counter: u64,
+    irq: bool,
+    elapsed: u64,
+    advance_calls: u64,
+}
+
+impl TestTimer {
+    fn new(counter: u64) -> Self {
+        Self {
+            counter,
+            irq: false,
+            elapsed: 0,
+            advance_calls: 0,
+        }
+    }
+}
+
+impl BusDevice for TestTimer {
+    fn read(&mut self, _address: u16) -> Result<u8, BusError> {
+        Ok(self.counter as u8)
+    }
+
+    fn write(&mut self, _address: u16, data: u8) -> Result<(), BusError> {
+        self.counter = data as u64;
+        self.irq = false;
+        Ok(())
+    }
+
+    fn tick(&mut self) {
+        self.advance(1);
+    }
+
+    fn advance(&mut self, ticks: u64) {
+        self.advance_calls += 1;
+        self.elapsed += ticks;
+        if self.counter > 0 {
+            if ticks >= self.counter {
+                self.irq = true;
+            }
+            self.counter = self.counter.saturating_sub(ticks);
+        }
+    }
+
+    fn ticks_until_event(&self) -> Option<u64> {
+        if self.counter > 0 {
+            Some(self.counter)
+        } else {
+            None
+        }
+    }
+
+    fn check_irq(&self) -> bool {
+        self.irq
+    }
+
+    fn check_nmi(&self) -> bool {
+        false
+    }
+
+    fn check_rdy(&self) -> bool {
+        false
+    }
+
+    fn check_so(&self) -> bool {
+        false
+    }
+}
+
+// Test event scheduled timing
+#[test]
+fn test_scheduled_device_fires_event_on_time() {
+    let mut bus = BusController::new();
+    let id = bus
+        .register_device(0xD000, 0xD000, Box::new(TestTimer::new(100)))
+        .unwrap();
+    bus.set_timing_mode(id, TimingMode::Scheduled).unwrap();
+
+    for _ in 0..99 {
+        bus.tick();
+    }
+    assert!(!bus.check_irq());
+
+    bus.tick();
+    assert!(bus.check_irq());
+
+    let timer = bus.device::<TestTimer>(id).unwrap();
+    assert_eq!(timer.elapsed, 100);
+    assert_eq!(timer.advance_calls, 1);
+}
+
+#[test]
+fn test_scheduled_device_caught_up_on_read() {
+    let mut bus = BusController::new();
+    let id = bus
+        .register_device(0xD000, 0xD000, Box::new(TestTimer::new(200)))
+        .unwrap();
+    bus.set_timing_mode(id, TimingMode::Scheduled).unwrap();
+
+    for _ in 0..50 {
+        bus.tick();
+    }
+
+    assert_eq!(bus.read(0xD000).unwrap(), 150);
+    assert_eq!(bus.device::<TestTimer>(id).unwrap().advance_calls, 1);
+}
+
+#[test]
+fn test_scheduled_device_rescheduled_on_write() {
+    let mut bus = BusController::new();
+    let id = bus
+        .register_device(0xD000, 0xD000, Box::new(TestTimer::new(0)))
+        .unwrap();
+    bus.set_timing_mode(id, TimingMode::Scheduled).unwrap();
+
+    for _ in 0..10 {
+        bus.tick();
+    }
+    bus.write(0xD000, 5).unwrap();
+
+    for _ in 0..4 {
+        bus.tick();
+    }
+    assert!(!bus.check_irq());
+    bus.tick();
+    assert!(bus.check_irq());
+    assert_eq!(bus.device::<TestTimer>(id).unwrap().elapsed, 15);
+}
+
+#[test]
+fn test_scheduled_device_caught_up_on_device_mut() {
+    let mut bus = BusController::new();
+    let id = bus
+        .register_device(0xD000, 0xD000, Box::new(TestTimer::new(0)))
+        .unwrap();
+    bus.set_timing_mode(id, TimingMode::Scheduled).unwrap();
+
+    for _ in 0..25 {
+        bus.tick();
+    }
+
+    // Shared access shows the state of the last synchronisation
+    assert_eq!(bus.device::<TestTimer>(id).unwrap().elapsed, 0);
+    assert_eq!(bus.device_mut::<TestTimer>(id).unwrap().elapsed, 25);
+}
+
+#[test]
+fn test_synchronize_catches_up_all_devices() {
+    let mut bus = BusController::new();
+    let first = bus
+        .register_device(0xD000, 0xD000, Box::new(TestTimer::new(0)))
+        .unwrap();
+    let second = bus
+        .register_device(0xD001, 0xD001, Box::new(TestTimer::new(0)))
+        .unwrap();
+    bus.set_timing_mode(first, TimingMode::Scheduled).unwrap();
+    bus.set_timing_mode(second, TimingMode::Scheduled).unwrap();
+    bus.set_clock_ratio(second, 1, 2).unwrap();
+
+    for _ in 0..40 {
+        bus.tick();
+    }
+    bus.synchronize();
+
+    assert_eq!(bus.clock(), 40);
+    assert_eq!(bus.device::<TestTimer>(first).unwrap().elapsed, 40);
+    assert_eq!(bus.device::<TestTimer>(second).unwrap().elapsed, 20);
+}
+
+#[test]
+fn test_every_cycle_device_ticked_each_cycle() {
+    let mut bus = BusController::new();
+    let id = bus
+        .register_device(0xD000, 0xD000, Box::new(TestTimer::new(0)))
+        .unwrap();
+
+    for _ in 0..10 {
+        bus.tick();
+    }
+
+    let timer = bus.device::<TestTimer>(id).unwrap();
+    assert_eq!(timer.elapsed, 10);
+    assert_eq!(timer.advance_calls, 10);
+}
+
+// Test the DMA controller
+fn create_dma_bus() -> (BusController, bus::DeviceId) {
+    let mut bus = BusController::new();
+    bus.register_device(0x0000, 0x0FFF, Box::new(TestDevice::new(0x0000, 0x1000)))
+        .unwrap();
+    let id = bus
+        .register_device(0xDF00, 0xDF06, Box::new(DmaController::new(0xDF00)))
+        .unwrap();
+    (bus, id)
+}
+
+#[test]
+fn test_dma_controller_registers() {
+    let (mut bus, _) = create_dma_bus();
+
+    bus.write(0xDF00, 0x34).unwrap();
+    bus.write(0xDF01, 0x12).unwrap();
+    bus.write(0xDF04, 0x10).unwrap();
+
+    assert_eq!(bus.read(0xDF00).unwrap(), 0x34);
+    assert_eq!(bus.read(0xDF01).unwrap(), 0x12);
+    assert_eq!(bus.read(0xDF04).unwrap(), 0x10);
+    assert_eq!(bus.read(0xDF06).unwrap(), 0x00);
+}
+
+#[test]
+fn test_dma_controller_copies_while_cpu_halted() {
+    let (mut bus, id) = create_dma_bus();
+    for (offset, byte) in [0xDE, 0xAD, 0xBE, 0xEF].iter().enumerate() {
+        bus.write(0x0100 + offset as u16, *byte).unwrap();
+    }
+
+    // Source 0x0100, destination 0x0200, length 4, start
+    bus.write(0xDF01, 0x01).unwrap();
+    bus.write(0xDF03, 0x02).unwrap();
+    bus.write(0xDF04, 0x04).unwrap();
+    bus.write(0xDF06, 0x01).unwrap();
+    assert!(bus.check_rdy());
+    assert_eq!(bus.read(0xDF06).unwrap(), 0x80);
+
+    bus.set_cpu_halted(true);
+    let mut cycles = 0;
+    while bus.check_rdy() {
+        bus.tick();
+        cycles += 1;
+    }
+
+    assert_eq!(cycles, 8);
+    assert_eq!(bus.device::<DmaController>(id).unwrap().dma_cycles(), 8);
+    for (offset, byte) in [0xDE, 0xAD, 0xBE, 0xEF].iter().enumerate() {
+        assert_eq!(bus.read(0x0200 + offset as u16).unwrap(), *byte);
+    }
+}
+
+#[test]
+fn test_dma_controller_waits_for_cpu_halt() {
+    let (mut bus, id) = create_dma_bus();
+    bus.device_mut::<DmaController>(id)
+        .unwrap()
+        .start(0x0100, 0x0200, 1);
+
+    bus.set_cpu_halted(false);
+    for _ in 0..10 {
+        bus.tick();
+    }
+
+    assert!(bus.check_rdy());
+    assert_eq!(bus.device::<DmaController>(id).unwrap().dma_cycles(), 0);
+}
+
+#[test]
+fn test_dma_controller_zero_length() {
+    let (mut bus, _) = create_dma_bus();
+
+    bus.write(0xDF06, 0x01).unwrap();
+
+    assert!(!bus.check_rdy());
+}
@@ -0,0 +1,772 @@
+//! MOS 6526 Complex Interface Adapter (CIA).
+//!
+//! Register map, selected by address lines RS0-RS3 and mirrored every 16 bytes:
+//! - `$0`/`$1`: PRA/PRB, port A/B data
+//! - `$2`/`$3`: DDRA/DDRB, data direction of port A/B (1 = output)
+//! - `$4`/`$5`: timer A low/high, reads the counter and writes the latch
+//! - `$6`/`$7`: timer B low/high, reads the counter and writes the latch
+//! - `$8`-`$B`: time-of-day tenths, seconds, minutes and hours in BCD
+//! - `$C`: SDR, serial data register
+//! - `$D`: ICR, interrupt flags on read (cleared by the read), mask on write
+//! - `$E`/`$F`: CRA/CRB, control registers for timer A/B, serial port and TOD
+//!
+//! The CIA is clocked by Φ2, one `tick` per CPU cycle. The TOD pin is derived from
+//! the CPU clock, see `Cia::with_tod_frequency`. External pins are driven and
+//! observed through the host pin methods, e.g. `Cia::set_port_a_input` and `Cia::sp`.
+
+use bus::errors::BusError;
+use bus::trait_bus_device::BusDevice;
+
+/// Number of registers exposed on the bus
+pub const CIA_REGISTER_COUNT: u16 = 16;
+/// CPU clock assumed when converting the TOD pin frequency to ticks, in Hz
+pub const DEFAULT_CPU_CLOCK: u32 = 1_000_000;
+/// Frequency of the TOD pin unless configured otherwise, in Hz
+pub const DEFAULT_TOD_FREQUENCY: u32 = 60;
+
+const REGISTER_PRA: u16 = 0x0;
+const REGISTER_PRB: u16 = 0x1;
+const REGISTER_DDRA: u16 = 0x2;
+const REGISTER_DDRB: u16 = 0x3;
+const REGISTER_TA_L: u16 = 0x4;
+const REGISTER_TA_H: u16 = 0x5;
+const REGISTER_TB_L: u16 = 0x6;
+const REGISTER_TB_H: u16 = 0x7;
+const REGISTER_TOD_TENTHS: u16 = 0x8;
+const REGISTER_TOD_SECONDS: u16 = 0x9;
+const REGISTER_TOD_MINUTES: u16 = 0xA;
+const REGISTER_TOD_HOURS: u16 = 0xB;
+const REGISTER_SDR: u16 = 0xC;
+const REGISTER_ICR: u16 = 0xD;
+const REGISTER_CRA: u16 = 0xE;
+
+/// Interrupt flag: timer A underflowed
+pub const INTERRUPT_TIMER_A: u8 = 0x01;
+/// Interrupt flag: timer B underflowed
+pub const INTERRUPT_TIMER_B: u8 = 0x02;
+/// Interrupt flag: the TOD clock reached the alarm time
+pub const INTERRUPT_ALARM: u8 = 0x04;
+/// Interrupt flag: eight bits were shifted through the serial port
+pub const INTERRUPT_SERIAL: u8 = 0x08;
+/// Interrupt flag: falling edge on the FLAG pin
+pub const INTERRUPT_FLAG: u8 = 0x10;
+/// ICR bit 7: any unmasked interrupt is active on read, set rather than clear on write
+const INTERRUPT_ANY: u8 = 0x80;
+
+/// Control register bits shared by CRA and CRB
+const CONTROL_START: u8 = 0x01;
+const CONTROL_PB_ON: u8 = 0x02;
+const CONTROL_TOGGLE: u8 = 0x04;
+const CONTROL_ONE_SHOT: u8 = 0x08;
+const CONTROL_LOAD: u8 = 0x10;
+/// CRA: timer A counts CNT rising edges rather than Φ2
+const CRA_COUNT_CNT: u8 = 0x20;
+/// CRA: the serial port shifts out rather than in
+const CRA_SERIAL_OUT: u8 = 0x40;
+/// CRA: the TOD pin runs at 50 Hz rather than 60 Hz
+const CRA_TOD_50HZ: u8 = 0x80;
+/// CRB: writes to the TOD registers set the alarm
+const CRB_ALARM: u8 = 0x80;
+
+/// Input of timer B, from bits 5-6 of CRB
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TimerBInput {
+    /// Count Φ2 cycles
+    Phi2,
+    /// Count CNT rising edges
+    Cnt,
+    /// Count timer A underflows
+    TimerA,
+    /// Count timer A underflows while CNT is high
+    TimerAGated,
+}
+
+impl TimerBInput {
+    /// Decode bits 5-6 of CRB
+    fn from_crb(crb: u8) -> TimerBInput {
+        match (crb >> 5) & 0x03 {
+            0 => TimerBInput::Phi2,
+            1 => TimerBInput::Cnt,
+            2 => TimerBInput::TimerA,
+            _ => TimerBInput::TimerAGated,
+        }
+    }
+}
+
+/// One of the two interval timers
+#[derive(Debug)]
+struct Timer {
+    counter: u16,
+    latch: u16,
+    /// Control register, without the load strobe
+    control: u8,
+    /// Timer output in toggle mode, inverted on every underflow
+    toggle: bool,
+    /// Timer output in pulse mode, high for the cycle after an underflow
+    pulse: bool,
+}
+
+impl Timer {
+    fn new() -> Self {
+        Self {
+            counter: 0xFFFF,
+            latch: 0xFFFF,
+            control: 0,
+            toggle: false,
+            pulse: false,
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        self.control & CONTROL_START != 0
+    }
+
+    /// Count one input event, returning whether the timer underflowed
+    fn count(&mut self) -> bool {
+        if !self.is_running() {
+            return false;
+        }
+        if self.counter > 0 {
+            self.counter -= 1;
+            return false;
+        }
+        self.counter = self.latch;
+        self.pulse = true;
+        self.toggle = !self.toggle;
+        if self.control & CONTROL_ONE_SHOT != 0 {
+            self.control &= !CONTROL_START;
+        }
+        true
+    }
+
+    /// Write the low byte of the latch
+    fn write_low(&mut self, data: u8) {
+        self.latch = (self.latch & 0xFF00) | data as u16;
+    }
+
+    /// Write the high byte of the latch, loading the counter if the timer is stopped
+    fn write_high(&mut self, data: u8) {
+        self.latch = (self.latch & 0x00FF) | (data as u16) << 8;
+        if !self.is_running() {
+            self.counter = self.latch;
+        }
+    }
+
+    /// Write the control register, handling the load strobe
+    fn write_control(&mut self, data: u8) {
+        if data & CONTROL_START != 0 && !self.is_running() {
+            // Starting the timer sets the toggle output high
+            self.toggle = true;
+        }
+        if data & CONTROL_LOAD != 0 {
+            self.counter = self.latch;
+        }
+        self.control = data & !CONTROL_LOAD;
+    }
+
+    /// Level of the timer output on PB6/PB7, if enabled
+    fn output(&self) -> Option<bool> {
+        if self.control & CONTROL_PB_ON == 0 {
+            None
+        } else if self.control & CONTROL_TOGGLE != 0 {
+            Some(self.toggle)
+        } else {
+            Some(self.pulse)
+        }
+    }
+}
+
+/// Time-of-day clock or alarm time, in BCD
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TimeOfDay {
+    tenths: u8,
+    seconds: u8,
+    minutes: u8,
+    /// Hours 1-12, bit 7 set for PM
+    hours: u8,
+}
+
+impl TimeOfDay {
+    /// Register value of one of the four TOD registers
+    fn register(&self, register: u16) -> u8 {
+        match register {
+            REGISTER_TOD_TENTHS => self.tenths,
+            REGISTER_TOD_SECONDS => self.seconds,
+            REGISTER_TOD_MINUTES => self.minutes,
+            _ => self.hours,
+        }
+    }
+
+    /// Set one of the four TOD registers, keeping only the implemented bits
+    fn set_register(&mut self, register: u16, data: u8) {
+        match register {
+            REGISTER_TOD_TENTHS => self.tenths = data & 0x0F,
+            REGISTER_TOD_SECONDS => self.seconds = data & 0x7F,
+            REGISTER_TOD_MINUTES => self.minutes = data & 0x7F,
+            _ => self.hours = data & 0x9F,
+        }
+    }
+
+    /// Advance the clock by a tenth of a second
+    fn increment(&mut self) {
+        self.tenths = bcd_increment(self.tenths);
+        if self.tenths < 0x10 {
+            return;
+        }
+        self.tenths = 0;
+        self.seconds = bcd_increment(self.seconds);
+        if self.seconds < 0x60 {
+            return;
+        }
+        self.seconds = 0;
+        self.minutes = bcd_increment(self.minutes);
+        if self.minutes < 0x60 {
+            return;
+        }
+        self.minutes = 0;
+        let pm = self.hours & 0x80;
+        self.hours = match self.hours & 0x1F {
+            // 11 to 12 switches between AM and PM
+            0x11 => 0x12 | (pm ^ 0x80),
+            0x12 => 0x01 | pm,
+            hours => bcd_increment(hours) | pm,
+        };
+    }
+}
+
+/// Increment a BCD value, returning `$10` after `$09` and `$60` after `$59`
+fn bcd_increment(value: u8) -> u8 {
+    if value & 0x0F >= 0x09 {
+        (value & 0xF0) + 0x10
+    } else {
+        value + 1
+    }
+}
+
+/// Represents a MOS 6526 CIA.
+#[derive(Debug)]
+pub struct Cia {
+    /// Start address of the register window
+    start_address: u16,
+    pra: u8,
+    prb: u8,
+    ddra: u8,
+    ddrb: u8,
+    /// Levels driven onto port A by external hardware
+    port_a_input: u8,
+    /// Levels driven onto port B by external hardware
+    port_b_input: u8,
+    timer_a: Timer,
+    timer_b: Timer,
+    /// CRA bits that do not belong to timer A
+    cra_high: u8,
+    /// CRB bits that do not belong to timer B
+    crb_high: u8,
+    tod: TimeOfDay,
+    alarm: TimeOfDay,
+    /// Copy of the clock returned while the registers are latched by an hours read
+    tod_latch: Option<TimeOfDay>,
+    /// The clock is stopped by an hours write until the tenths are written
+    tod_stopped: bool,
+    /// Ticks between two pulses on the TOD pin
+    tod_pulse_ticks: u64,
+    /// Ticks until the next pulse on the TOD pin
+    tod_pulse_timer: u64,
+    /// TOD pin pulses since the tenths last advanced
+    tod_divider: u8,
+    sdr: u8,
+    /// Serial shift register
+    shift: u8,
+    /// Bits left to shift before the serial interrupt
+    shift_bits: u8,
+    /// Byte written to SDR while another one is shifted out
+    shift_pending: Option<u8>,
+    /// Interrupt flags
+    icr: u8,
+    /// Interrupt mask
+    mask: u8,
+    /// Levels of the serial and FLAG lines, as driven externally or by the CIA
+    cnt_input: bool,
+    sp_input: bool,
+    flag: bool,
+    /// CNT output while shifting out
+    cnt_output: bool,
+    /// SP output while shifting out
+    sp_output: bool,
+}
+
+impl Cia {
+    /// Create a new CIA with its registers at the specified start address.
+    ///
+    /// # Arguments
+    /// * `start_address` - Start address of the register window
+    ///
+    /// # Returns
+    /// * A new Cia instance in its reset state, with the TOD clock at 1:00:00.0 AM
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let cia = Cia::new(0xDC00);
+    /// bus.register_device(0xDC00, 0xDC0F, Box::new(cia))?;
+    /// ```
+    pub fn new(start_address: u16) -> Self {
+        let tod_pulse_ticks = (DEFAULT_CPU_CLOCK / DEFAULT_TOD_FREQUENCY) as u64;
+        Self {
+            start_address,
+            pra: 0,
+            prb: 0,
+            ddra: 0,
+            ddrb: 0,
+            // Undriven inputs are pulled high
+            port_a_input: 0xFF,
+            port_b_input: 0xFF,
+            timer_a: Timer::new(),
+            timer_b: Timer::new(),
+            cra_high: 0,
+            crb_high: 0,
+            tod: TimeOfDay {
+                tenths: 0,
+                seconds: 0,
+                minutes: 0,
+                hours: 0x01,
+            },
+            alarm: TimeOfDay {
+                tenths: 0,
+                seconds: 0,
+                minutes: 0,
+                hours: 0,
+            },
+            tod_latch: None,
+            tod_stopped: false,
+            tod_pulse_ticks,
+            tod_pulse_timer: tod_pulse_ticks,
+            tod_divider: 0,
+            sdr: 0,
+            shift: 0,
+            shift_bits: 0,
+            shift_pending: None,
+            icr: 0,
+            mask: 0,
+            cnt_input: true,
+            sp_input: true,
+            flag: true,
+            cnt_output: true,
+            sp_output: true,
+        }
+    }
+
+    /// Set the frequency of the TOD pin relative to the CPU clock.
+    ///
+    /// CRA bit 7 must match the pin: 60 Hz mains needs it clear, 50 Hz mains set.
+    ///
+    /// # Arguments
+    /// * `cpu_clock` - Frequency of Φ2 in Hz
+    /// * `tod_frequency` - Frequency of the TOD pin in Hz
+    ///
+    /// # Returns
+    /// * The Cia with the new TOD pin frequency
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// // PAL C64
+    /// let cia = Cia::new(0xDC00).with_tod_frequency(985_248, 50);
+    /// ```
+    pub fn with_tod_frequency(mut self, cpu_clock: u32, tod_frequency: u32) -> Self {
+        self.tod_pulse_ticks = (cpu_clock / tod_frequency.max(1)).max(1) as u64;
+        self.tod_pulse_timer = self.tod_pulse_ticks;
+        self
+    }
+
+    /// Pull the RES pin low.
+    ///
+    /// Clears the port, data direction, control and serial registers, stops the timers
+    /// and masks all interrupts. The timer latches are set to `$FFFF`.
+    pub fn reset(&mut self) {
+        self.pra = 0;
+        self.prb = 0;
+        self.ddra = 0;
+        self.ddrb = 0;
+        self.timer_a = Timer::new();
+        self.timer_b = Timer::new();
+        self.cra_high = 0;
+        self.crb_high = 0;
+        self.tod_latch = None;
+        self.tod_stopped = false;
+        self.sdr = 0;
+        self.shift_bits = 0;
+        self.shift_pending = None;
+        self.icr = 0;
+        self.mask = 0;
+        self.cnt_output = true;
+        self.sp_output = true;
+    }
+
+    /// Drive the port A pins from external hardware.
+    ///
+    /// Only pins configured as inputs in DDRA are affected.
+    ///
+    /// # Arguments
+    /// * `value` - Levels of PA0-PA7
+    pub fn set_port_a_input(&mut self, value: u8) {
+        self.port_a_input = value;
+    }
+
+    /// Drive the port B pins from external hardware.
+    ///
+    /// Only pins configured as inputs in DDRB are affected.
+    ///
+    /// # Arguments
+    /// * `value` - Levels of PB0-PB7
+    pub fn set_port_b_input(&mut self, value: u8) {
+        self.port_b_input = value;
+    }
+
+    /// Levels of the port A pins: outputs from PRA, inputs from external hardware
+    pub fn port_a_output(&self) -> u8 {
+        (self.pra & self.ddra) | (self.port_a_input & !self.ddra)
+    }
+
+    /// Levels of the port B pins: outputs from PRB or the timers on PB6/PB7,
+    /// inputs from external hardware
+    pub fn port_b_output(&self) -> u8 {
+        let mut value = (self.prb & self.ddrb) | (self.port_b_input & !self.ddrb);
+        if let Some(level) = self.timer_a.output() {
+            value = (value & !0x40) | if level { 0x40 } else { 0 };
+        }
+        if let Some(level) = self.timer_b.output() {
+            value = (value & !0x80) | if level { 0x80 } else { 0 };
+        }
+        value
+    }
+
+    /// Drive the CNT pin, when the serial port is not shifting out.
+    ///
+    /// Rising edges are counted by the timers in CNT mode and shift a bit in from SP
+    /// when the serial port is an input.
+    ///
+    /// # Arguments
+    /// * `level` - New level of the pin
+    pub fn set_cnt(&mut self, level: bool) {
+        let rising = level && !self.cnt_input;
+        self.cnt_input = level;
+        if !rising {
+            return;
+        }
+        if self.cra_high & CRA_COUNT_CNT != 0 && self.timer_a.count() {
+            self.timer_a_underflow();
+        }
+        if TimerBInput::from_crb(self.crb_high) == TimerBInput::Cnt && self.timer_b.count() {
+            self.icr |= INTERRUPT_TIMER_B;
+        }
+        if self.cra_high & CRA_SERIAL_OUT == 0 {
+            self.shift = self.shift << 1 | self.sp_input as u8;
+            self.shift_bits += 1;
+            if self.shift_bits == 8 {
+                self.sdr = self.shift;
+                self.shift_bits = 0;
+                self.icr |= INTERRUPT_SERIAL;
+            }
+        }
+    }
+
+    /// Drive the SP pin, when the serial port is an input.
+    ///
+    /// # Arguments
+    /// * `level` - New level of the pin
+    pub fn set_sp(&mut self, level: bool) {
+        self.sp_input = level;
+    }
+
+    /// Drive the FLAG input, a falling edge sets the FLAG interrupt flag.
+    ///
+    /// # Arguments
+    /// * `level` - New level of the pin
+    pub fn set_flag(&mut self, level: bool) {
+        if self.flag && !level {
+            self.icr |= INTERRUPT_FLAG;
+        }
+        self.flag = level;
+    }
+
+    /// Level of the CNT pin, which carries the shift clock while shifting out
+    pub fn cnt(&self) -> bool {
+        if self.cra_high & CRA_SERIAL_OUT != 0 {
+            self.cnt_output
+        } else {
+            self.cnt_input
+        }
+    }
+
+    /// Level of the SP pin, which carries the shift data while shifting out
+    pub fn sp(&self) -> bool {
+        if self.cra_high & CRA_SERIAL_OUT != 0 {
+            self.sp_output
+        } else {
+            self.sp_input
+        }
+    }
+
+    /// Handle an underflow of timer A: interrupt, serial clock and timer B cascade
+    fn timer_a_underflow(&mut self) {
+        self.icr |= INTERRUPT_TIMER_A;
+        if self.cra_high & CRA_SERIAL_OUT != 0 && self.shift_bits > 0 {
+            self.serial_clock_edge();
+        }
+        let counts = match TimerBInput::from_crb(self.crb_high) {
+            TimerBInput::TimerA => true,
+            TimerBInput::TimerAGated => self.cnt(),
+            _ => false,
+        };
+        if counts && self.timer_b.count() {
+            self.icr |= INTERRUPT_TIMER_B;
+        }
+    }
+
+    /// Toggle CNT while shifting out, shifting a bit out on the falling edge
+    fn serial_clock_edge(&mut self) {
+        self.cnt_output = !self.cnt_output;
+        if !self.cnt_output {
+            self.sp_output = self.shift & 0x80 != 0;
+            self.shift <<= 1;
+            return;
+        }
+        self.shift_bits -= 1;
+        if self.shift_bits == 0 {
+            self.icr |= INTERRUPT_SERIAL;
+            if let Some(data) = self.shift_pending.take() {
+                self.shift = data;
+                self.shift_bits = 8;
+            }
+        }
+    }
+
+    /// Advance the TOD clock by one pulse of the TOD pin
+    fn tod_pulse(&mut self) {
+        self.tod_divider += 1;
+        let divider = if self.cra_high & CRA_TOD_50HZ != 0 {
+            5
+        } else {
+            6
+        };
+        if self.tod_divider < divider {
+            return;
+        }
+        self.tod_divider = 0;
+        if !self.tod_stopped {
+            self.tod.increment();
+            self.check_alarm();
+        }
+    }
+
+    /// Timer A counts Φ2 cycles
+    fn timer_a_counts_phi2(&self) -> bool {
+        self.timer_a.is_running() && self.cra_high & CRA_COUNT_CNT == 0
+    }
+
+    /// Timer B counts Φ2 cycles
+    fn timer_b_counts_phi2(&self) -> bool {
+        self.timer_b.is_running() && TimerBInput::from_crb(self.crb_high) == TimerBInput::Phi2
+    }
+
+    /// Number of ticks during which the timers and the TOD pin only count down
+    fn quiet_ticks(&self) -> u64 {
+        let mut quiet = self.tod_pulse_timer - 1;
+        if self.timer_a_counts_phi2() {
+            quiet = quiet.min(self.timer_a.counter as u64);
+        }
+        if self.timer_b_counts_phi2() {
+            quiet = quiet.min(self.timer_b.counter as u64);
+        }
+        quiet
+    }
+
+    /// Count down by a number of ticks no larger than `quiet_ticks`
+    fn skip_quiet_ticks(&mut self, ticks: u64) {
+        if ticks == 0 {
+            return;
+        }
+        self.timer_a.pulse = false;
+        self.timer_b.pulse = false;
+        // Bounded by the 16-bit counters of running timers
+        if self.timer_a_counts_phi2() {
+            self.timer_a.counter -= ticks as u16;
+        }
+        if self.timer_b_counts_phi2() {
+            self.timer_b.counter -= ticks as u16;
+        }
+        self.tod_pulse_timer -= ticks;
+    }
+
+    /// Set the alarm flag if the clock advanced to the alarm time
+    fn check_alarm(&mut self) {
+        if self.tod == self.alarm {
+            self.icr |= INTERRUPT_ALARM;
+        }
+    }
+
+    /// Read one of the TOD registers, handling the hours latch
+    fn read_tod(&mut self, register: u16) -> u8 {
+        if register == REGISTER_TOD_HOURS && self.tod_latch.is_none() {
+            self.tod_latch = Some(self.tod);
+        }
+        let time = self.tod_latch.unwrap_or(self.tod);
+        if register == REGISTER_TOD_TENTHS {
+            self.tod_latch = None;
+        }
+        time.register(register)
+    }
+
+    /// Write one of the TOD registers, or the alarm if CRB bit 7 is set
+    fn write_tod(&mut self, register: u16, data: u8) {
+        if self.crb_high & CRB_ALARM != 0 {
+            self.alarm.set_register(register, data);
+        } else {
+            self.tod.set_register(register, data);
+            // Writing the hours stops the clock until the tenths are written
+            match register {
+                REGISTER_TOD_HOURS => self.tod_stopped = true,
+                REGISTER_TOD_TENTHS => self.tod_stopped = false,
+                _ => {}
+            }
+        }
+    }
+}
+
+impl BusDevice for Cia {
+    fn read(&mut self, address: u16) -> Result<u8, BusError> {
+        let value = match address.wrapping_sub(self.start_address) & 0x0F {
+            REGISTER_PRA => self.port_a_output(),
+            REGISTER_PRB => self.port_b_output(),
+            REGISTER_DDRA => self.ddra,
+            REGISTER_DDRB => self.ddrb,
+            REGISTER_TA_L => self.timer_a.counter as u8,
+            REGISTER_TA_H => (self.timer_a.counter >> 8) as u8,
+            REGISTER_TB_L => self.timer_b.counter as u8,
+            REGISTER_TB_H => (self.timer_b.counter >> 8) as u8,
+            register @ REGISTER_TOD_TENTHS..=REGISTER_TOD_HOURS => self.read_tod(register),
+            REGISTER_SDR => self.sdr,
+            REGISTER_ICR => {
+                // Reading the flags clears them
+                let mut value = self.icr;
+                if self.icr & self.mask != 0 {
+                    value |= INTERRUPT_ANY;
+                }
+                self.icr = 0;
+                value
+            }
+            REGISTER_CRA => self.timer_a.control | self.cra_high,
+            // $F: CRB
+            _ => self.timer_b.control | self.crb_high,
+        };
+        Ok(value)
+    }
+
+    fn write(&mut self, address: u16, data: u8) -> Result<(), BusError> {
+        match address.wrapping_sub(self.start_address) & 0x0F {
+            REGISTER_PRA => self.pra = data,
+            REGISTER_PRB => self.prb = data,
+            REGISTER_DDRA => self.ddra = data,
+            REGISTER_DDRB => self.ddrb = data,
+            REGISTER_TA_L => self.timer_a.write_low(data),
+            REGISTER_TA_H => self.timer_a.write_high(data),
+            REGISTER_TB_L => self.timer_b.write_low(data),
+            REGISTER_TB_H => self.timer_b.write_high(data),
+            register @ REGISTER_TOD_TENTHS..=REGISTER_TOD_HOURS => self.write_tod(register, data),
+            REGISTER_SDR => {
+                self.sdr = data;
+                if self.cra_high & CRA_SERIAL_OUT != 0 {
+                    if self.shift_bits == 0 {
+                        self.shift = data;
+                        self.shift_bits = 8;
+                    } else {
+                        self.shift_pending = Some(data);
+                    }
+                }
+            }
+            REGISTER_ICR => {
+                if data & INTERRUPT_ANY != 0 {
+                    self.mask |= data & 0x1F;
+                } else {
+                    self.mask &= !data;
+                }
+            }
+            REGISTER_CRA => {
+                if (data ^ self.cra_high) & CRA_SERIAL_OUT != 0 {
+                    // Switching the serial direction aborts a transfer
+                    self.shift_bits = 0;
+                    self.shift_pending = None;
+                    self.cnt_output = true;
+                }
+                self.timer_a.write_control(data & 0x1F);
+                self.cra_high = data & 0xE0;
+            }
+            // $F: CRB
+            _ => {
+                self.timer_b.write_control(data & 0x1F);
+                self.crb_high = data & 0xE0;
+            }
+        }
+        Ok(())
+    }
+
+    fn tick(&mut self) {
+        self.timer_a.pulse = false;
+        self.timer_b.pulse = false;
+        if self.cra_high & CRA_COUNT_CNT == 0 && self.timer_a.count() {
+            self.timer_a_underflow();
+        }
+        if TimerBInput::from_crb(self.crb_high) == TimerBInput::Phi2 && self.timer_b.count() {
+            self.icr |= INTERRUPT_TIMER_B;
+        }
+
+        self.tod_pulse_timer -= 1;
+        if self.tod_pulse_timer == 0 {
+            self.tod_pulse_timer = self.tod_pulse_ticks;
+            self.tod_pulse();
+        }
+    }
+
+    fn advance(&mut self, ticks: u64) {
+        let mut remaining = ticks;
+        while remaining > 0 {
+            let quiet = self.quiet_ticks().min(remaining);
+            self.skip_quiet_ticks(quiet);
+            remaining -= quiet;
+            if remaining > 0 {
+                self.tick();
+                remaining -= 1;
+            }
+        }
+    }
+
+    fn ticks_until_event(&self) -> Option<u64> {
+        // Timer B counting timer A underflows is covered by the timer A event
+        let mut ticks = self.tod_pulse_timer;
+        if self.timer_a_counts_phi2() {
+            ticks = ticks.min(self.timer_a.counter as u64 + 1);
+        }
+        if self.timer_b_counts_phi2() {
+            ticks = ticks.min(self.timer_b.counter as u64 + 1);
+        }
+        Some(ticks)
+    }
+
+    fn check_irq(&self) -> bool {
+        self.icr & self.mask != 0
+    }
+
+    fn check_nmi(&self) -> bool {
+        // The IRQ output may be wired to NMI, but the CIA itself only has one output
+        false
+    }
+
+    fn check_rdy(&self) -> bool {
+        // The CIA does not insert wait states
+        false
+    }
+
+    fn check_so(&self) -> bool {
+        // The CIA does not drive the SO line
+        false
+    }
+}
@@ -0,0 +1,432 @@
+//! Unit tests for the 6526 CIA implementation
+//!
+//! This module tests the ports, the timers in continuous, one-shot and cascade modes,
+//! the time-of-day clock and alarm, the serial port and interrupt masking.
+
+use bus::BusController;
+use bus::timing_mode::TimingMode;
+use bus::trait_bus_device::BusDevice;
+use cia6526::{
+    Cia, INTERRUPT_ALARM, INTERRUPT_FLAG, INTERRUPT_SERIAL, INTERRUPT_TIMER_A, INTERRUPT_TIMER_B,
+};
+
+const BASE: u16 = 0xDC00;
+const PRA: u16 = BASE;
+const PRB: u16 = BASE + 0x1;
+const DDRA: u16 = BASE + 0x2;
+const DDRB: u16 = BASE + 0x3;
+const TA_L: u16 = BASE + 0x4;
+const TB_L: u16 = BASE + 0x6;
+const TOD_TENTHS: u16 = BASE + 0x8;
+const TOD_SECONDS: u16 = BASE + 0x9;
+const TOD_MINUTES: u16 = BASE + 0xA;
+const TOD_HOURS: u16 = BASE + 0xB;
+const SDR: u16 = BASE + 0xC;
+const ICR: u16 = BASE + 0xD;
+const CRA: u16 = BASE + 0xE;
+const CRB: u16 = BASE + 0xF;
+
+/// Tick the CIA a number of times
+fn run(cia: &mut Cia, ticks: u64) {
+    for _ in 0..ticks {
+        cia.tick();
+    }
+}
+
+/// Load a timer latch through its low and high registers
+fn set_latch(cia: &mut Cia, low: u16, value: u16) {
+    cia.write(low, value as u8).unwrap();
+    cia.write(low + 1, (value >> 8) as u8).unwrap();
+}
+
+/// Read a timer counter through its low and high registers
+fn counter(cia: &mut Cia, low: u16) -> u16 {
+    let low_byte = cia.read(low).unwrap() as u16;
+    (cia.read(low + 1).unwrap() as u16) << 8 | low_byte
+}
+
+// Test ports
+#[test]
+fn test_ports() {
+    let mut cia = Cia::new(BASE);
+    assert_eq!(cia.read(PRA).unwrap(), 0xFF);
+    cia.write(DDRA, 0xF0).unwrap();
+    cia.write(PRA, 0x5A).unwrap();
+    cia.set_port_a_input(0x03);
+    assert_eq!(cia.read(PRA).unwrap(), 0x53);
+    assert_eq!(cia.port_a_output(), 0x53);
+
+    cia.write(DDRB, 0xFF).unwrap();
+    cia.write(PRB, 0x81).unwrap();
+    assert_eq!(cia.port_b_output(), 0x81);
+    assert_eq!(cia.read(DDRB + 0x10).unwrap(), 0xFF);
+}
+
+// Test timers
+#[test]
+fn test_timer_a_continuous() {
+    let mut cia = Cia::new(BASE);
+    set_latch(&mut cia, TA_L, 10);
+    assert_eq!(counter(&mut cia, TA_L), 10);
+    cia.write(CRA, 0x01).unwrap();
+
+    run(&mut cia, 10);
+    assert_eq!(counter(&mut cia, TA_L), 0);
+    assert_eq!(cia.read(ICR).unwrap(), 0);
+    cia.tick();
+    assert_eq!(cia.read(ICR).unwrap(), INTERRUPT_TIMER_A);
+    assert_eq!(counter(&mut cia, TA_L), 10);
+
+    // The timer keeps running with a period of latch + 1 cycles
+    run(&mut cia, 11);
+    assert_eq!(cia.read(ICR).unwrap(), INTERRUPT_TIMER_A);
+    assert_eq!(cia.read(CRA).unwrap() & 0x01, 0x01);
+}
+
+#[test]
+fn test_timer_b_one_shot() {
+    let mut cia = Cia::new(BASE);
+    set_latch(&mut cia, TB_L, 4);
+    cia.write(CRB, 0x09).unwrap();
+    run(&mut cia, 5);
+    assert_eq!(cia.read(ICR).unwrap(), INTERRUPT_TIMER_B);
+    assert_eq!(cia.read(CRB).unwrap() & 0x01, 0x00);
+    assert_eq!(counter(&mut cia, TB_L), 4);
+    run(&mut cia, 10);
+    assert_eq!(cia.read(ICR).unwrap(), 0);
+}
+
+#[test]
+fn test_timer_force_load() {
+    let mut cia = Cia::new(BASE);
+    set_latch(&mut cia, TA_L, 100);
+    cia.write(CRA, 0x01).unwrap();
+    run(&mut cia, 50);
+    // Writing the latch of a running timer does not load the counter
+    set_latch(&mut cia, TA_L, 200);
+    assert_eq!(counter(&mut cia, TA_L), 50);
+    cia.write(CRA, 0x11).unwrap();
+    assert_eq!(counter(&mut cia, TA_L), 200);
+    // The load strobe is not stored
+    assert_eq!(cia.read(CRA).unwrap(), 0x01);
+}
+
+#[test]
+fn test_timer_cascade() {
+    let mut cia = Cia::new(BASE);
+    set_latch(&mut cia, TA_L, 9);
+    set_latch(&mut cia, TB_L, 2);
+    // Timer B counts timer A underflows
+    cia.write(CRB, 0x41).unwrap();
+    cia.write(CRA, 0x01).unwrap();
+    run(&mut cia, 20);
+    assert_eq!(counter(&mut cia, TB_L), 0);
+    assert_eq!(cia.read(ICR).unwrap(), INTERRUPT_TIMER_A);
+    run(&mut cia, 10);
+    assert_eq!(
+        cia.read(ICR).unwrap(),
+        INTERRUPT_TIMER_A | INTERRUPT_TIMER_B
+    );
+}
+
+#[test]
+fn test_timer_counts_cnt() {
+    let mut cia = Cia::new(BASE);
+    set_latch(&mut cia, TA_L, 1);
+    cia.write(CRA, 0x21).unwrap();
+    run(&mut cia, 100);
+    assert_eq!(counter(&mut cia, TA_L), 1);
+    for _ in 0..2 {
+        cia.set_cnt(false);
+        cia.set_cnt(true);
+    }
+    assert_eq!(
+        cia.read(ICR).unwrap() & INTERRUPT_TIMER_A,
+        INTERRUPT_TIMER_A
+    );
+}
+
+#[test]
+fn test_timer_output_on_port_b() {
+    let mut cia = Cia::new(BASE);
+    set_latch(&mut cia, TA_L, 2);
+    // Toggle mode on PB6
+    cia.write(CRA, 0x07).unwrap();
+    assert_eq!(cia.port_b_output() & 0x40, 0x40);
+    run(&mut cia, 3);
+    assert_eq!(cia.port_b_output() & 0x40, 0x00);
+    run(&mut cia, 3);
+    assert_eq!(cia.port_b_output() & 0x40, 0x40);
+
+    // Pulse mode on PB7
+    set_latch(&mut cia, TB_L, 2);
+    cia.write(CRB, 0x03).unwrap();
+    run(&mut cia, 2);
+    assert_eq!(cia.port_b_output() & 0x80, 0x00);
+    cia.tick();
+    assert_eq!(cia.read(PRB).unwrap() & 0x80, 0x80);
+    cia.tick();
+    assert_eq!(cia.port_b_output() & 0x80, 0x00);
+}
+
+// Test interrupts
+#[test]
+fn test_interrupt_mask() {
+    let mut cia = Cia::new(BASE);
+    set_latch(&mut cia, TA_L, 0);
+    cia.write(CRA, 0x09).unwrap();
+    cia.tick();
+    assert!(!cia.check_irq());
+
+    cia.write(ICR, 0x81).unwrap();
+    assert!(cia.check_irq());
+    assert_eq!(cia.read(ICR).unwrap(), 0x80 | INTERRUPT_TIMER_A);
+    // Reading ICR clears it
+    assert!(!cia.check_irq());
+    assert_eq!(cia.read(ICR).unwrap(), 0);
+
+    cia.write(ICR, 0x01).unwrap();
+    cia.write(CRA, 0x09).unwrap();
+    cia.tick();
+    assert!(!cia.check_irq());
+}
+
+#[test]
+fn test_flag_pin() {
+    let mut cia = Cia::new(BASE);
+    cia.write(ICR, 0x90).unwrap();
+    cia.set_flag(true);
+    assert!(!cia.check_irq());
+    cia.set_flag(false);
+    assert!(cia.check_irq());
+    assert_eq!(cia.read(ICR).unwrap(), 0x80 | INTERRUPT_FLAG);
+}
+
+// Test time-of-day clock
+#[test]
+fn test_tod_counts_tenths() {
+    // 1 kHz CPU clock with a 60 Hz TOD pin: 16 ticks per pulse, 6 pulses per tenth
+    let mut cia = Cia::new(BASE).with_tod_frequency(1_000, 60);
+    assert_eq!(cia.read(TOD_HOURS).unwrap(), 0x01);
+    assert_eq!(cia.read(TOD_TENTHS).unwrap(), 0x00);
+    run(&mut cia, 16 * 6 - 1);
+    assert_eq!(cia.read(TOD_TENTHS).unwrap(), 0x00);
+    cia.tick();
+    assert_eq!(cia.read(TOD_TENTHS).unwrap(), 0x01);
+    run(&mut cia, 16 * 6 * 9);
+    assert_eq!(cia.read(TOD_SECONDS).unwrap(), 0x01);
+    assert_eq!(cia.read(TOD_TENTHS).unwrap(), 0x00);
+}
+
+#[test]
+fn test_tod_50hz() {
+    let mut cia = Cia::new(BASE).with_tod_frequency(1_000, 50);
+    cia.write(CRA, 0x80).unwrap();
+    run(&mut cia, 20 * 5);
+    assert_eq!(cia.read(TOD_TENTHS).unwrap(), 0x01);
+}
+
+#[test]
+fn test_tod_hours_rollover() {
+    let mut cia = Cia::new(BASE).with_tod_frequency(1_000, 60);
+    // 11:59:59.9 AM
+    cia.write(TOD_HOURS, 0x11).unwrap();
+    cia.write(TOD_MINUTES, 0x59).unwrap();
+    cia.write(TOD_SECONDS, 0x59).unwrap();
+    cia.write(TOD_TENTHS, 0x09).unwrap();
+    run(&mut cia, 16 * 6);
+    assert_eq!(cia.read(TOD_HOURS).unwrap(), 0x92);
+    assert_eq!(cia.read(TOD_MINUTES).unwrap(), 0x00);
+    assert_eq!(cia.read(TOD_SECONDS).unwrap(), 0x00);
+    assert_eq!(cia.read(TOD_TENTHS).unwrap(), 0x00);
+
+    // 12:59:59.9 PM
+    cia.write(TOD_HOURS, 0x92).unwrap();
+    cia.write(TOD_MINUTES, 0x59).unwrap();
+    cia.write(TOD_SECONDS, 0x59).unwrap();
+    cia.write(TOD_TENTHS, 0x09).unwrap();
+    run(&mut cia, 16 * 6);
+    assert_eq!(cia.read(TOD_HOURS).unwrap(), 0x81);
+    cia.read(TOD_TENTHS).unwrap();
+}
+
+#[test]
+fn test_tod_write_stops_clock() {
+    let mut cia = Cia::new(BASE).with_tod_frequency(1_000, 60);
+    cia.write(TOD_HOURS, 0x05).unwrap();
+    run(&mut cia, 16 * 6 * 3);
+    assert_eq!(cia.read(TOD_TENTHS).unwrap(), 0x00);
+    cia.write(TOD_TENTHS, 0x00).unwrap();
+    run(&mut cia, 16 * 6 * 3);
+    assert_eq!(cia.read(TOD_TENTHS).unwrap(), 0x03);
+}
+
+#[test]
+fn test_tod_read_latch() {
+    let mut cia = Cia::new(BASE).with_tod_frequency(1_000, 60);
+    assert_eq!(cia.read(TOD_HOURS).unwrap(), 0x01);
+    run(&mut cia, 16 * 6 * 25);
+    // The registers hold the time of the hours read until the tenths are read
+    assert_eq!(cia.read(TOD_SECONDS).unwrap(), 0x00);
+    assert_eq!(cia.read(TOD_TENTHS).unwrap(), 0x00);
+    assert_eq!(cia.read(TOD_SECONDS).unwrap(), 0x02);
+    assert_eq!(cia.read(TOD_TENTHS).unwrap(), 0x05);
+}
+
+#[test]
+fn test_tod_alarm() {
+    let mut cia = Cia::new(BASE).with_tod_frequency(1_000, 60);
+    cia.write(ICR, 0x84).unwrap();
+    // Set the alarm to 1:00:00.3 AM
+    cia.write(CRB, 0x80).unwrap();
+    cia.write(TOD_HOURS, 0x01).unwrap();
+    cia.write(TOD_MINUTES, 0x00).unwrap();
+    cia.write(TOD_SECONDS, 0x00).unwrap();
+    cia.write(TOD_TENTHS, 0x03).unwrap();
+    cia.write(CRB, 0x00).unwrap();
+    // The alarm registers are write only, the clock is unchanged
+    assert_eq!(cia.read(TOD_TENTHS).unwrap(), 0x00);
+
+    run(&mut cia, 16 * 6 * 2);
+    assert!(!cia.check_irq());
+    run(&mut cia, 16 * 6);
+    assert!(cia.check_irq());
+    assert_eq!(cia.read(ICR).unwrap(), 0x80 | INTERRUPT_ALARM);
+}
+
+// Test scheduled timing
+/// CIA with both timers and the time-of-day clock running at different rates
+fn busy_cia() -> Cia {
+    let mut cia = Cia::new(BASE).with_tod_frequency(1_000, 60);
+    set_latch(&mut cia, TA_L, 0x123);
+    set_latch(&mut cia, TB_L, 0x45);
+    // Timer A toggles PB6, timer B counts cycles in one-shot mode on PB7
+    cia.write(CRA, 0x07).unwrap();
+    cia.write(CRB, 0x0B).unwrap();
+    cia
+}
+
+#[test]
+fn test_advance_matches_ticks() {
+    for ticks in [0, 1, 0x45, 0x46, 1_000, 0x20000] {
+        let mut ticked = busy_cia();
+        let mut advanced = busy_cia();
+        run(&mut ticked, ticks);
+        advanced.advance(ticks);
+
+        assert_eq!(advanced.ticks_until_event(), ticked.ticks_until_event());
+        assert_eq!(advanced.port_b_output(), ticked.port_b_output());
+        assert_eq!(counter(&mut advanced, TA_L), counter(&mut ticked, TA_L));
+        assert_eq!(counter(&mut advanced, TB_L), counter(&mut ticked, TB_L));
+        for register in [TOD_HOURS, TOD_MINUTES, TOD_SECONDS, TOD_TENTHS, ICR, CRB] {
+            assert_eq!(
+                advanced.read(register).unwrap(),
+                ticked.read(register).unwrap()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_ticks_until_event() {
+    // The next TOD pulse is always pending
+    let mut cia = Cia::new(BASE).with_tod_frequency(1_000, 60);
+    assert_eq!(cia.ticks_until_event(), Some(16));
+    run(&mut cia, 5);
+    assert_eq!(cia.ticks_until_event(), Some(11));
+
+    set_latch(&mut cia, TA_L, 3);
+    cia.write(CRA, 0x01).unwrap();
+    assert_eq!(cia.ticks_until_event(), Some(4));
+    // Timer B counting timer A underflows adds no event of its own
+    set_latch(&mut cia, TB_L, 0);
+    cia.write(CRB, 0x41).unwrap();
+    assert_eq!(cia.ticks_until_event(), Some(4));
+}
+
+#[test]
+fn test_scheduled_timer_and_alarm_raise_irq() {
+    let mut bus = BusController::new();
+    let cia = Cia::new(BASE).with_tod_frequency(1_000, 60);
+    let id = bus.register_device(BASE, CRB, Box::new(cia)).unwrap();
+    bus.set_timing_mode(id, TimingMode::Scheduled).unwrap();
+    bus.write(ICR, 0x80 | INTERRUPT_TIMER_A | INTERRUPT_ALARM)
+        .unwrap();
+    bus.write(TA_L, 0xE7).unwrap();
+    bus.write(TA_L + 1, 0x03).unwrap();
+    bus.write(CRA, 0x09).unwrap();
+
+    // Timer A underflows after latch + 1 cycles without the CPU touching the CIA
+    for _ in 0..999 {
+        bus.tick();
+    }
+    assert!(!bus.check_irq());
+    bus.tick();
+    assert!(bus.check_irq());
+    assert_eq!(bus.read(ICR).unwrap(), 0x80 | INTERRUPT_TIMER_A);
+
+    // Set the alarm to 1:00:02.0 AM, 20 tenths of 96 cycles after the start
+    bus.write(CRB, 0x80).unwrap();
+    bus.write(TOD_HOURS, 0x01).unwrap();
+    bus.write(TOD_MINUTES, 0x00).unwrap();
+    bus.write(TOD_SECONDS, 0x02).unwrap();
+    bus.write(TOD_TENTHS, 0x00).unwrap();
+    bus.write(CRB, 0x00).unwrap();
+    for _ in 1_000..16 * 6 * 20 - 1 {
+        bus.tick();
+    }
+    assert!(!bus.check_irq());
+    bus.tick();
+    assert!(bus.check_irq());
+    assert_eq!(bus.read(ICR).unwrap(), 0x80 | INTERRUPT_ALARM);
+}
+
+// Test serial port
+#[test]
+fn test_serial_output() {
+    let mut cia = Cia::new(BASE);
+    set_latch(&mut cia, TA_L, 0);
+    cia.write(CRA, 0x41).unwrap();
+    cia.write(SDR, 0xA5).unwrap();
+
+    // Two timer A underflows per bit, data changes on the falling CNT edge
+    let mut received = 0u8;
+    for _ in 0..8 {
+        cia.tick();
+        assert!(!cia.cnt());
+        cia.tick();
+        assert!(cia.cnt());
+        received = received << 1 | cia.sp() as u8;
+    }
+    assert_eq!(received, 0xA5);
+    assert_eq!(cia.read(ICR).unwrap() & INTERRUPT_SERIAL, INTERRUPT_SERIAL);
+    run(&mut cia, 4);
+    assert!(cia.cnt());
+}
+
+#[test]
+fn test_serial_input() {
+    let mut cia = Cia::new(BASE);
+    for bit in [true, false, false, true, true, false, true, false] {
+        cia.set_sp(bit);
+        cia.set_cnt(false);
+        cia.set_cnt(true);
+    }
+    assert_eq!(cia.read(SDR).unwrap(), 0x9A);
+    assert_eq!(cia.read(ICR).unwrap(), INTERRUPT_SERIAL);
+}
+
+#[test]
+fn test_reset() {
+    let mut cia = Cia::new(BASE);
+    cia.write(DDRA, 0xFF).unwrap();
+    cia.write(ICR, 0x9F).unwrap();
+    set_latch(&mut cia, TA_L, 0x1234);
+    cia.write(CRA, 0x01).unwrap();
+    cia.reset();
+    assert_eq!(cia.read(DDRA).unwrap(), 0x00);
+    assert_eq!(cia.read(CRA).unwrap(), 0x00);
+    assert_eq!(counter(&mut cia, TA_L), 0xFFFF);
+    cia.set_flag(false);
+    assert!(!cia.check_irq());
+}
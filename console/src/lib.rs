@@ -0,0 +1,141 @@
+//! Minimal memory-mapped console for getchar/putchar programs.
+//!
+//! Register map, relative to the start address of the device:
+//! - `$0`: Status (read) / Control (write)
+//! - `$1`: Input data (read) / Output data (write)
+//!
+//! There is no baud rate or framing: a byte written to the data register reaches the
+//! host at once, and a byte sent by the host can be read as soon as the backend has
+//! it. Both ends are a `SerialBackend` of the `acia6551` crate, so the stdio and
+//! buffer backends serve the console as well as the ACIA.
+
+use acia6551::backends::SerialBackend;
+use bus::errors::BusError;
+use bus::trait_bus_device::BusDevice;
+
+/// Host ends of the console, shared with the ACIA
+pub use acia6551::backends;
+
+/// Number of registers exposed on the bus
+pub const CONSOLE_REGISTER_COUNT: u16 = 2;
+
+const REGISTER_STATUS: u16 = 0x0;
+
+/// Status: a byte of input is waiting to be read
+pub const STATUS_INPUT_READY: u8 = 0x80;
+/// Status: the console accepts output, always set
+pub const STATUS_OUTPUT_READY: u8 = 0x40;
+/// Status and control: assert IRQ while input is waiting
+pub const CONTROL_IRQ_ENABLE: u8 = 0x01;
+
+/// Represents a memory-mapped console.
+pub struct Console {
+    /// Start address of the register window
+    start_address: u16,
+    /// Host end of the console
+    backend: Box<dyn SerialBackend>,
+    /// Byte taken from the backend, waiting to be read
+    input: Option<u8>,
+    irq_enabled: bool,
+}
+
+impl Console {
+    /// Create a new console with its registers at the specified start address.
+    ///
+    /// # Arguments
+    /// * `start_address` - Start address of the register window
+    /// * `backend` - Host end of the console
+    ///
+    /// # Returns
+    /// * A new Console instance with the input interrupt disabled
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let backend = StdioBackend::new().with_newline_translation();
+    /// let console = Console::new(0xF000, Box::new(backend));
+    /// bus.register_device(0xF000, 0xF001, Box::new(console))?;
+    /// ```
+    pub fn new(start_address: u16, backend: Box<dyn SerialBackend>) -> Self {
+        Self {
+            start_address,
+            backend,
+            input: None,
+            irq_enabled: false,
+        }
+    }
+
+    /// The backend at the host end of the console
+    pub fn backend(&self) -> &dyn SerialBackend {
+        self.backend.as_ref()
+    }
+
+    /// Mutable access to the backend at the host end of the console
+    pub fn backend_mut(&mut self) -> &mut dyn SerialBackend {
+        self.backend.as_mut()
+    }
+
+    /// Take the next byte from the backend unless one is already waiting
+    fn poll_input(&mut self) {
+        if self.input.is_none() {
+            self.input = self.backend.receive();
+        }
+    }
+
+    /// Value of the status register
+    fn status(&self) -> u8 {
+        let mut status = STATUS_OUTPUT_READY;
+        if self.input.is_some() {
+            status |= STATUS_INPUT_READY;
+        }
+        if self.irq_enabled {
+            status |= CONTROL_IRQ_ENABLE;
+        }
+        status
+    }
+}
+
+impl BusDevice for Console {
+    fn read(&mut self, address: u16) -> Result<u8, BusError> {
+        self.poll_input();
+        match address.wrapping_sub(self.start_address) & 0x01 {
+            REGISTER_STATUS => Ok(self.status()),
+            // $1: Input data, $00 when no input is waiting
+            _ => Ok(self.input.take().unwrap_or(0)),
+        }
+    }
+
+    fn write(&mut self, address: u16, data: u8) -> Result<(), BusError> {
+        match address.wrapping_sub(self.start_address) & 0x01 {
+            REGISTER_STATUS => self.irq_enabled = data & CONTROL_IRQ_ENABLE != 0,
+            // $1: Output data
+            _ => self.backend.transmit(data),
+        }
+        Ok(())
+    }
+
+    fn tick(&mut self) {
+        // Input only has to be looked for ahead of a read when it raises an interrupt
+        if self.irq_enabled {
+            self.poll_input();
+        }
+    }
+
+    fn check_irq(&self) -> bool {
+        self.irq_enabled && self.input.is_some()
+    }
+
+    fn check_nmi(&self) -> bool {
+        // The console does not generate NMIs
+        false
+    }
+
+    fn check_rdy(&self) -> bool {
+        // The console does not insert wait states
+        false
+    }
+
+    fn check_so(&self) -> bool {
+        // The console does not drive the SO line
+        false
+    }
+}
@@ -0,0 +1,117 @@
+//! Unit tests for the console implementation
+//!
+//! This module tests the status, data and control registers, the input interrupt
+//! and a getchar/putchar program running on the CPU.
+
+use bus::BusController;
+use bus::trait_bus_device::BusDevice;
+use console::backends::buffer::BufferBackend;
+use console::{CONTROL_IRQ_ENABLE, Console, STATUS_INPUT_READY, STATUS_OUTPUT_READY};
+use cpu6502::cpu::Cpu;
+use ram::{Ram, ram_size::RamSize};
+
+const STATUS: u16 = 0xF000;
+const DATA: u16 = 0xF001;
+
+/// Create a console with a buffer backend kept by the test
+fn create_console() -> (Console, BufferBackend) {
+    let backend = BufferBackend::new();
+    (Console::new(STATUS, Box::new(backend.clone())), backend)
+}
+
+// Test registers
+#[test]
+fn test_output() {
+    let (mut console, backend) = create_console();
+    assert_eq!(console.read(STATUS).unwrap(), STATUS_OUTPUT_READY);
+    console.write(DATA, b'O').unwrap();
+    console.write(DATA, b'K').unwrap();
+    assert_eq!(backend.take_output(), b"OK");
+}
+
+#[test]
+fn test_input() {
+    let (mut console, backend) = create_console();
+    backend.push_input(b"ab");
+    assert_eq!(
+        console.read(STATUS).unwrap(),
+        STATUS_INPUT_READY | STATUS_OUTPUT_READY
+    );
+    assert_eq!(console.read(DATA).unwrap(), b'a');
+    assert_eq!(console.read(DATA).unwrap(), b'b');
+    assert_eq!(console.read(STATUS).unwrap(), STATUS_OUTPUT_READY);
+    // Reading without input returns zero
+    assert_eq!(console.read(DATA).unwrap(), 0x00);
+    assert_eq!(backend.pending_input(), 0);
+}
+
+#[test]
+fn test_registers_mirrored() {
+    let (mut console, backend) = create_console();
+    console.write(DATA + 2, b'x').unwrap();
+    assert_eq!(backend.take_output(), b"x");
+    assert_eq!(console.read(STATUS + 4).unwrap(), STATUS_OUTPUT_READY);
+}
+
+// Test interrupts
+#[test]
+fn test_input_interrupt() {
+    let (mut console, backend) = create_console();
+    backend.push_input(b"k");
+    console.tick();
+    // Input does not interrupt until enabled
+    assert!(!console.check_irq());
+    console.write(STATUS, CONTROL_IRQ_ENABLE).unwrap();
+    assert_eq!(
+        console.read(STATUS).unwrap() & CONTROL_IRQ_ENABLE,
+        CONTROL_IRQ_ENABLE
+    );
+    console.tick();
+    assert!(console.check_irq());
+    assert_eq!(console.read(DATA).unwrap(), b'k');
+    console.tick();
+    assert!(!console.check_irq());
+}
+
+// Test programs
+#[test]
+fn test_echo_program() {
+    let mut bus = BusController::new();
+    bus.register_device(
+        0x0000,
+        0x7FFF,
+        Box::new(Ram::new(RamSize::_32K, 0x0000).unwrap()),
+    )
+    .unwrap();
+    let backend = BufferBackend::new();
+    bus.register_device(
+        STATUS,
+        DATA,
+        Box::new(Console::new(STATUS, Box::new(backend.clone()))),
+    )
+    .unwrap();
+    let mut cpu = Cpu::new(bus);
+
+    // Echo input in upper case until a carriage return
+    let program = [
+        0xAD, 0x00, 0xF0, // GETCHAR: LDA STATUS
+        0x10, 0xFB, // BPL GETCHAR
+        0xAD, 0x01, 0xF0, // LDA DATA
+        0xC9, 0x61, // CMP #'a'
+        0x90, 0x02, // BCC PUTCHAR
+        0x29, 0xDF, // AND #$DF
+        0x8D, 0x01, 0xF0, // PUTCHAR: STA DATA
+        0xC9, 0x0D, // CMP #CR
+        0xD0, 0xEB, // BNE GETCHAR
+        0x60, // RTS
+    ];
+    for (offset, byte) in program.iter().enumerate() {
+        cpu.bus_mut().write(0x0200 + offset as u16, *byte).unwrap();
+    }
+    backend.push_input(b"Hello\rignored");
+
+    cpu.call_subroutine(0x0200, 10_000).unwrap();
+
+    assert_eq!(backend.take_output(), b"HELLO\r");
+    assert_eq!(backend.pending_input(), 7);
+}
@@ -1,7 +1,9 @@
+use crate::cpu_variant::CpuVariant;
 use crate::errors::CpuError;
 use crate::flags::Flags;
 use crate::opcodes::{
     instruction_variants::{DEFAULT_INSTRUCTION_VARIANT, InstructionVariant},
+    is_write_step,
     microcode::{MicrocodeStep, OperationResult},
     variant_by_opcode,
 };
@@ -31,6 +33,10 @@ pub struct Cpu {
     pub(crate) page_boundary_cross_penalty: u8,
     /// Total CPU cycles executed
     pub(crate) cycles: u64,
+    /// CPU variant being emulated
+    pub(crate) variant: CpuVariant,
+    /// Last sampled state of the SO line, `true` while pulled low
+    pub(crate) so_line: bool,
 }
 
 impl Cpu {
@@ -58,9 +64,137 @@ impl Cpu {
             temp_data: 0,
             page_boundary_cross_penalty: 0,
             cycles: 0,
+            variant: CpuVariant::default(),
+            so_line: false,
         }
     }
 
+    /// Select the CPU variant to emulate
+    ///
+    /// # Arguments
+    /// * `variant` - The CPU variant
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let mut cpu = Cpu::new(bus);
+    /// cpu.set_variant(CpuVariant::Cmos);
+    /// ```
+    pub fn set_variant(&mut self, variant: CpuVariant) {
+        self.variant = variant;
+    }
+
+    /// Get the CPU variant being emulated
+    pub fn variant(&self) -> CpuVariant {
+        self.variant
+    }
+
+    /// Get the total number of CPU cycles executed since the last reset
+    ///
+    /// Cycles spent halted by RDY are included.
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    /// Get a reference to the BusController the CPU is connected to
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let ram = cpu.bus().device::<Ram>(ram_id).unwrap();
+    /// ```
+    pub fn bus(&self) -> &BusController {
+        &self.bus
+    }
+
+    /// Get a mutable reference to the BusController the CPU is connected to
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// cpu.bus_mut().device_mut::<Ram>(ram_id).unwrap().import(&program, 0x0200)?;
+    /// ```
+    pub fn bus_mut(&mut self) -> &mut BusController {
+        &mut self.bus
+    }
+
+    /// Get the current CPU registers
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let pc = cpu.registers().program_counter;
+    /// ```
+    pub fn registers(&self) -> &Registers {
+        &self.registers
+    }
+
+    /// Set the program counter, abandoning the instruction in progress
+    ///
+    /// The next call to `step` fetches an opcode from the new address.
+    ///
+    /// # Arguments
+    /// * `address` - Address of the next instruction to execute
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// cpu.reset()?;
+    /// cpu.set_program_counter(entry_point);
+    /// ```
+    pub fn set_program_counter(&mut self, address: u16) {
+        self.registers.program_counter = address;
+        self.current_instruction = DEFAULT_INSTRUCTION_VARIANT;
+        self.current_microcode_iter = [].iter();
+        self.page_boundary_cross_penalty = 0;
+    }
+
+    /// Run a subroutine to completion, as if it had been called with JSR
+    ///
+    /// The current program counter is pushed as the return address, so execution
+    /// continues where it left off once the subroutine returns with RTS.
+    ///
+    /// # Arguments
+    /// * `address` - Address of the subroutine
+    /// * `max_cycles` - Number of cycles after which the subroutine is abandoned
+    ///
+    /// # Returns
+    /// * `Ok(())` once the subroutine has returned
+    /// * `Err(CpuError)` if execution fails or the subroutine does not return in time
+    ///
+    /// # Errors
+    /// * Any error returned by `step`
+    /// * `CpuError::Other` if the subroutine has not returned after `max_cycles` cycles
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// // Run an initialisation routine before starting the program
+    /// cpu.call_subroutine(0x2000, 1_000_000)?;
+    /// ```
+    pub fn call_subroutine(&mut self, address: u16, max_cycles: u64) -> Result<(), CpuError> {
+        let return_address = self.registers.program_counter;
+        let stack_pointer = self.registers.stack_pointer;
+
+        // JSR pushes the address of its last byte, RTS adds one when returning
+        let [high, low] = return_address.wrapping_sub(1).to_be_bytes();
+        self.push_stack_data(high)?;
+        self.push_stack_ptr()?;
+        self.push_stack_data(low)?;
+        self.push_stack_ptr()?;
+        self.set_program_counter(address);
+
+        for _ in 0..max_cycles {
+            self.step()?;
+            let instruction_complete =
+                self.current_microcode_iter.len() == 0 && self.page_boundary_cross_penalty == 0;
+            if instruction_complete
+                && self.registers.program_counter == return_address
+                && self.registers.stack_pointer == stack_pointer
+            {
+                return Ok(());
+            }
+        }
+        Err(CpuError::Other(format!(
+            "Subroutine at 0x{:04X} did not return within {} cycles",
+            address, max_cycles
+        )))
+    }
+
     /// Reset the CPU to its initial state
     ///
     /// This sets the registers to their default values and initializes the program counter
@@ -223,7 +357,16 @@ impl Cpu {
     /// Execute a single CPU step (cycle)
     ///
     /// This function handles fetching the next instruction, managing cycles,
-    /// and processing the current instruction.
+    /// and processing the current instruction. The bus is ticked once for every
+    /// completed cycle, including page boundary penalty cycles, so device clocks
+    /// stay locked to the instruction stream.
+    ///
+    /// While a device pulls RDY low the CPU stalls instead of executing the cycle.
+    /// The NMOS 6502 only stalls on read cycles, the CMOS 65C02 on any cycle.
+    /// Stalled cycles are counted in `cycles` and the bus is free for DMA.
+    ///
+    /// The SO line is sampled at the end of every cycle, a falling edge sets the
+    /// overflow flag for the following cycle.
     ///
     /// # Returns
     /// * `Ok(())` if the step was successful
@@ -233,9 +376,17 @@ impl Cpu {
     /// * `CpuError::UnknownInstruction` if the fetched opcode does not correspond to an instruction
     /// * `CpuError::BusError` if there is an error reading from or writing to the bus
     pub fn step(&mut self) -> Result<(), CpuError> {
+        let halted =
+            self.bus.check_rdy() && (self.variant == CpuVariant::Cmos || !self.is_write_cycle());
+        self.bus.set_cpu_halted(halted);
+        if halted {
+            self.end_cycle();
+            return Ok(());
+        }
+
         if self.page_boundary_cross_penalty > 0 {
             self.page_boundary_cross_penalty -= 1;
-            self.cycles = self.cycles.wrapping_add(1);
+            self.end_cycle();
             return Ok(());
         }
 
@@ -251,6 +402,7 @@ impl Cpu {
                 }
             },
             None => {
+                self.bus.sync(self.registers.program_counter);
                 let opcode = self.fetch_operand()?;
                 match variant_by_opcode(opcode) {
                     Some(variant) => {
@@ -263,11 +415,43 @@ impl Cpu {
             }
         }
 
-        self.cycles = self.cycles.wrapping_add(1);
+        self.end_cycle();
 
         Ok(())
     }
 
+    /// Complete the current cycle
+    ///
+    /// Counts the cycle, ticks the bus and samples the SO line.
+    fn end_cycle(&mut self) {
+        self.cycles = self.cycles.wrapping_add(1);
+        self.bus.tick();
+
+        let so_line = self.bus.check_so();
+        if so_line && !self.so_line {
+            self.flags.overflow = true;
+        }
+        self.so_line = so_line;
+    }
+
+    /// Determine whether the next cycle executed by `step` writes to the bus
+    ///
+    /// # Returns
+    /// * `true` if the next cycle is a write cycle, `false` if it is a read cycle
+    pub(crate) fn is_write_cycle(&self) -> bool {
+        if self.page_boundary_cross_penalty > 0 {
+            return false;
+        }
+        let sequence_length = self.current_instruction.microcode_sequence.len();
+        let remaining = self.current_microcode_iter.len();
+        remaining > 0
+            && is_write_step(
+                &self.current_instruction.instruction,
+                sequence_length - remaining,
+                sequence_length,
+            )
+    }
+
     /// Update Zero and Negative flags based on the provided value
     ///
     /// # Arguments
@@ -310,3 +494,430 @@ impl Cpu {
         (start_address & 0xFF00) != (end_address & 0xFF00)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_cpu_builder::CpuBuilder;
+    use bus::dma_controller::DmaController;
+    use bus::errors::BusError;
+    use ram::{Ram, ram_size::RamSize};
+
+    /// Device that only counts how often it was ticked
+    struct TickCounter {
+        ticks: u64,
+    }
+
+    impl BusDevice for TickCounter {
+        fn read(&mut self, _address: u16) -> Result<u8, BusError> {
+            Ok(0x00)
+        }
+
+        fn write(&mut self, address: u16, _data: u8) -> Result<(), BusError> {
+            Err(BusError::ReadOnly(address))
+        }
+
+        fn tick(&mut self) {
+            self.ticks += 1;
+        }
+
+        fn check_irq(&self) -> bool {
+            false
+        }
+
+        fn check_nmi(&self) -> bool {
+            false
+        }
+
+        fn check_rdy(&self) -> bool {
+            false
+        }
+
+        fn check_so(&self) -> bool {
+            false
+        }
+    }
+
+    /// Create a CPU with RAM holding the given program at 0x0200 and a tick counter at 0xD000
+    fn create_test_cpu_with_program(program: &[u8]) -> (Cpu, bus::DeviceId) {
+        let mut ram = Ram::new(RamSize::_32K, 0x0000).expect("Failed to create RAM");
+        ram.import(program, 0x0200)
+            .expect("Failed to import program");
+        let mut cpu = CpuBuilder::new()
+            .with_bus_device(ram, 0x0000, 0x7FFF)
+            .expect("Failed to add RAM")
+            .with_program_counter(0x0200)
+            .build()
+            .expect("Failed to build CPU");
+        let counter_id = cpu
+            .bus_mut()
+            .register_device(0xD000, 0xD000, Box::new(TickCounter { ticks: 0 }))
+            .expect("Failed to add tick counter");
+        (cpu, counter_id)
+    }
+
+    /// Step the CPU until the given number of instructions have completed
+    fn run_instructions(cpu: &mut Cpu, count: usize) {
+        for _ in 0..count {
+            cpu.step().expect("Failed to step CPU");
+            while cpu.current_microcode_iter.len() > 0 || cpu.page_boundary_cross_penalty > 0 {
+                cpu.step().expect("Failed to step CPU");
+            }
+        }
+    }
+
+    /// Device that holds RDY low while `hold` is set
+    struct RdyHold {
+        hold: bool,
+    }
+
+    impl BusDevice for RdyHold {
+        fn read(&mut self, _address: u16) -> Result<u8, BusError> {
+            Ok(0x00)
+        }
+
+        fn write(&mut self, address: u16, _data: u8) -> Result<(), BusError> {
+            Err(BusError::ReadOnly(address))
+        }
+
+        fn tick(&mut self) {}
+
+        fn check_irq(&self) -> bool {
+            false
+        }
+
+        fn check_nmi(&self) -> bool {
+            false
+        }
+
+        fn check_rdy(&self) -> bool {
+            self.hold
+        }
+
+        fn check_so(&self) -> bool {
+            false
+        }
+    }
+
+    /// Device that pulls SO low while `asserted` is set
+    struct SoPin {
+        asserted: bool,
+    }
+
+    impl BusDevice for SoPin {
+        fn read(&mut self, _address: u16) -> Result<u8, BusError> {
+            Ok(0x00)
+        }
+
+        fn write(&mut self, address: u16, _data: u8) -> Result<(), BusError> {
+            Err(BusError::ReadOnly(address))
+        }
+
+        fn tick(&mut self) {}
+
+        fn check_irq(&self) -> bool {
+            false
+        }
+
+        fn check_nmi(&self) -> bool {
+            false
+        }
+
+        fn check_rdy(&self) -> bool {
+            false
+        }
+
+        fn check_so(&self) -> bool {
+            self.asserted
+        }
+    }
+
+    #[test]
+    fn test_step_ticks_bus_once_per_cycle() {
+        // LDA #$42; LDA $10
+        let (mut cpu, counter_id) = create_test_cpu_with_program(&[0xA9, 0x42, 0xA5, 0x10]);
+
+        run_instructions(&mut cpu, 2);
+
+        assert_eq!(cpu.cycles, 5);
+        let counter = cpu
+            .bus()
+            .device::<TickCounter>(counter_id)
+            .expect("Missing counter");
+        assert_eq!(counter.ticks, 5);
+    }
+
+    #[test]
+    fn test_step_ticks_bus_on_page_boundary_penalty() {
+        // LDX #$01; LDA $20FF,X
+        let (mut cpu, counter_id) = create_test_cpu_with_program(&[0xA2, 0x01, 0xBD, 0xFF, 0x20]);
+
+        run_instructions(&mut cpu, 2);
+
+        assert_eq!(cpu.cycles, 7);
+        let counter = cpu
+            .bus()
+            .device::<TickCounter>(counter_id)
+            .expect("Missing counter");
+        assert_eq!(counter.ticks, 7);
+    }
+
+    #[test]
+    fn test_step_ticks_bus_with_clock_ratio() {
+        // NOP
+        let (mut cpu, counter_id) = create_test_cpu_with_program(&[0xEA]);
+        cpu.bus_mut()
+            .set_clock_ratio(counter_id, 3, 1)
+            .expect("Failed to set clock ratio");
+
+        run_instructions(&mut cpu, 1);
+
+        let counter = cpu
+            .bus()
+            .device::<TickCounter>(counter_id)
+            .expect("Missing counter");
+        assert_eq!(counter.ticks, cpu.cycles * 3);
+    }
+
+    /// Create a CPU running `LDA #$42; STA $10; NOP` with an RDY holding device at 0xD100
+    fn create_test_cpu_with_rdy_hold(variant: CpuVariant) -> (Cpu, bus::DeviceId) {
+        let (mut cpu, _) = create_test_cpu_with_program(&[0xA9, 0x42, 0x85, 0x10, 0xEA]);
+        cpu.set_variant(variant);
+        let hold_id = cpu
+            .bus_mut()
+            .register_device(0xD100, 0xD100, Box::new(RdyHold { hold: false }))
+            .expect("Failed to add RDY device");
+        // Run LDA and STA up to, but not including, the store cycle
+        for _ in 0..4 {
+            cpu.step().expect("Failed to step CPU");
+        }
+        (cpu, hold_id)
+    }
+
+    #[test]
+    fn test_rdy_nmos_does_not_halt_on_write_cycle() {
+        let (mut cpu, hold_id) = create_test_cpu_with_rdy_hold(CpuVariant::Nmos);
+        cpu.bus_mut()
+            .device_mut::<RdyHold>(hold_id)
+            .expect("Missing RDY device")
+            .hold = true;
+
+        // The store cycle completes despite RDY
+        cpu.step().expect("Failed to step CPU");
+        assert_eq!(cpu.bus_mut().read(0x0010).expect("Failed to read"), 0x42);
+
+        // The following opcode fetch is a read and halts
+        let pc = cpu.registers.program_counter;
+        for _ in 0..3 {
+            cpu.step().expect("Failed to step CPU");
+        }
+        assert_eq!(cpu.registers.program_counter, pc);
+        assert_eq!(cpu.cycles, 8);
+
+        cpu.bus_mut()
+            .device_mut::<RdyHold>(hold_id)
+            .expect("Missing RDY device")
+            .hold = false;
+        cpu.step().expect("Failed to step CPU");
+        assert_eq!(cpu.registers.program_counter, pc + 1);
+    }
+
+    #[test]
+    fn test_rdy_cmos_halts_on_write_cycle() {
+        let (mut cpu, hold_id) = create_test_cpu_with_rdy_hold(CpuVariant::Cmos);
+        cpu.bus_mut()
+            .device_mut::<RdyHold>(hold_id)
+            .expect("Missing RDY device")
+            .hold = true;
+
+        cpu.step().expect("Failed to step CPU");
+        assert_eq!(cpu.bus_mut().read(0x0010).expect("Failed to read"), 0x00);
+
+        cpu.bus_mut()
+            .device_mut::<RdyHold>(hold_id)
+            .expect("Missing RDY device")
+            .hold = false;
+        cpu.step().expect("Failed to step CPU");
+        assert_eq!(cpu.bus_mut().read(0x0010).expect("Failed to read"), 0x42);
+        assert_eq!(cpu.cycles, 6);
+    }
+
+    #[test]
+    fn test_rdy_dma_controller_steals_cycles() {
+        // LDA #$01; STA $D206; NOP
+        let (mut cpu, _) = create_test_cpu_with_program(&[0xA9, 0x01, 0x8D, 0x06, 0xD2, 0xEA]);
+        cpu.bus_mut()
+            .register_device(0xD200, 0xD206, Box::new(DmaController::new(0xD200)))
+            .expect("Failed to add DMA controller");
+        for (offset, byte) in [0x11, 0x22, 0x33, 0x44].iter().enumerate() {
+            cpu.bus_mut()
+                .write(0x0300 + offset as u16, *byte)
+                .expect("Failed to write");
+        }
+        cpu.bus_mut().write(0xD204, 0x04).expect("Failed to write");
+        cpu.bus_mut().write(0xD201, 0x03).expect("Failed to write");
+        cpu.bus_mut().write(0xD203, 0x04).expect("Failed to write");
+
+        // Halted cycles do not advance the program, so run until NOP has completed
+        while cpu.registers.program_counter != 0x0206 || cpu.current_microcode_iter.len() > 0 {
+            cpu.step().expect("Failed to step CPU");
+        }
+
+        // LDA (2) + STA (4) + 4 bytes at 2 cycles each + NOP (2)
+        assert_eq!(cpu.cycles, 16);
+        for (offset, byte) in [0x11, 0x22, 0x33, 0x44].iter().enumerate() {
+            assert_eq!(
+                cpu.bus_mut()
+                    .read(0x0400 + offset as u16)
+                    .expect("Failed to read"),
+                *byte
+            );
+        }
+    }
+
+    /// Create a CPU running `BVC *` with an SO driving device at 0xD100
+    fn create_test_cpu_with_so_pin() -> (Cpu, bus::DeviceId) {
+        let (mut cpu, _) = create_test_cpu_with_program(&[0x50, 0xFE, 0xEA]);
+        cpu.flags.overflow = false;
+        let so_id = cpu
+            .bus_mut()
+            .register_device(0xD100, 0xD100, Box::new(SoPin { asserted: false }))
+            .expect("Failed to add SO device");
+        (cpu, so_id)
+    }
+
+    fn set_so(cpu: &mut Cpu, so_id: bus::DeviceId, asserted: bool) {
+        cpu.bus_mut()
+            .device_mut::<SoPin>(so_id)
+            .expect("Missing SO device")
+            .asserted = asserted;
+    }
+
+    #[test]
+    fn test_so_falling_edge_exits_bvc_loop() {
+        let (mut cpu, so_id) = create_test_cpu_with_so_pin();
+
+        for _ in 0..30 {
+            cpu.step().expect("Failed to step CPU");
+        }
+        assert!(!cpu.flags.overflow);
+        assert!(cpu.registers.program_counter <= 0x0202);
+
+        set_so(&mut cpu, so_id, true);
+        cpu.step().expect("Failed to step CPU");
+        assert!(cpu.flags.overflow);
+
+        for _ in 0..6 {
+            cpu.step().expect("Failed to step CPU");
+        }
+        assert!(cpu.registers.program_counter > 0x0202);
+    }
+
+    #[test]
+    fn test_so_held_low_only_sets_overflow_once() {
+        let (mut cpu, so_id) = create_test_cpu_with_so_pin();
+
+        set_so(&mut cpu, so_id, true);
+        cpu.step().expect("Failed to step CPU");
+        assert!(cpu.flags.overflow);
+
+        // Holding the line low is not another edge
+        cpu.flags.overflow = false;
+        cpu.step().expect("Failed to step CPU");
+        assert!(!cpu.flags.overflow);
+
+        // Releasing the line does not set the flag either
+        set_so(&mut cpu, so_id, false);
+        cpu.step().expect("Failed to step CPU");
+        assert!(!cpu.flags.overflow);
+
+        set_so(&mut cpu, so_id, true);
+        cpu.step().expect("Failed to step CPU");
+        assert!(cpu.flags.overflow);
+    }
+
+    #[test]
+    fn test_so_sampled_while_halted_by_rdy() {
+        let (mut cpu, so_id) = create_test_cpu_with_so_pin();
+        cpu.bus_mut()
+            .register_device(0xD101, 0xD101, Box::new(RdyHold { hold: true }))
+            .expect("Failed to add RDY device");
+
+        set_so(&mut cpu, so_id, true);
+        cpu.step().expect("Failed to step CPU");
+
+        assert!(cpu.flags.overflow);
+        assert_eq!(cpu.registers.program_counter, 0x0200);
+    }
+
+    #[test]
+    fn test_set_program_counter_abandons_instruction() {
+        // LDA $1234 at 0x0200, LDA #$42 at 0x0210
+        let mut program = vec![0xAD, 0x34, 0x12];
+        program.resize(0x10, 0xEA);
+        program.extend_from_slice(&[0xA9, 0x42]);
+        let (mut cpu, _) = create_test_cpu_with_program(&program);
+
+        cpu.step().expect("Failed to step CPU");
+        cpu.set_program_counter(0x0210);
+        run_instructions(&mut cpu, 1);
+
+        assert_eq!(cpu.registers().accumulator, 0x42);
+        assert_eq!(cpu.registers().program_counter, 0x0212);
+    }
+
+    #[test]
+    fn test_call_subroutine_returns_to_program_counter() {
+        // LDA #$42; STA $10; RTS at 0x0200
+        let (mut cpu, _) = create_test_cpu_with_program(&[0xA9, 0x42, 0x85, 0x10, 0x60]);
+        cpu.set_program_counter(0x1234);
+        let stack_pointer = cpu.registers().stack_pointer;
+
+        cpu.call_subroutine(0x0200, 100)
+            .expect("Subroutine did not return");
+
+        assert_eq!(cpu.registers().program_counter, 0x1234);
+        assert_eq!(cpu.registers().stack_pointer, stack_pointer);
+        assert_eq!(cpu.bus_mut().read(0x0010).expect("Read failed"), 0x42);
+    }
+
+    #[test]
+    fn test_sync_attributes_reads_to_instruction() {
+        // LDA #$01; LDA $10 at 0x0200
+        let mut ram = Ram::new(RamSize::_32K, 0x0000)
+            .expect("Failed to create RAM")
+            .with_uninitialised_read_tracking();
+        ram.import(&[0xA9, 0x01, 0xA5, 0x10], 0x0200)
+            .expect("Failed to import program");
+        let mut bus = BusController::new();
+        let ram_id = bus
+            .register_device(0x0000, 0x7FFF, Box::new(ram))
+            .expect("Failed to add RAM");
+        let mut cpu = Cpu::new(bus);
+        cpu.set_program_counter(0x0200);
+
+        run_instructions(&mut cpu, 2);
+
+        let ram = cpu
+            .bus_mut()
+            .device::<Ram>(ram_id)
+            .expect("RAM has the wrong type");
+        assert_eq!(
+            ram.uninitialised_reads(),
+            &[ram::UninitialisedRead {
+                address: 0x0010,
+                program_counter: Some(0x0202),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_call_subroutine_cycle_limit() {
+        // JMP $0200
+        let (mut cpu, _) = create_test_cpu_with_program(&[0x4C, 0x00, 0x02]);
+
+        let result = cpu.call_subroutine(0x0200, 100);
+
+        assert!(matches!(result, Err(CpuError::Other(_))));
+    }
+}
@@ -0,0 +1,11 @@
+//! CPU variants with differing hardware behaviour
+
+/// 6502 family member being emulated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CpuVariant {
+    /// Original NMOS 6502, RDY only halts the CPU on read cycles
+    #[default]
+    Nmos,
+    /// CMOS 65C02, RDY halts the CPU on any cycle
+    Cmos,
+}
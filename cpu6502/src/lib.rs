@@ -4,6 +4,8 @@
 mod alu;
 /// 6502 CPU implementation
 pub mod cpu;
+/// 6502 CPU variants
+pub mod cpu_variant;
 /// Errors related to CPU operations
 pub mod errors;
 /// 6502 Flags
@@ -68,7 +68,7 @@ mod tests {
 
     /// Create a CPU with basic RAM setup for testing
     fn create_test_cpu() -> Cpu {
-        let ram = Ram::new(RamSize::_32K, 0x0000);
+        let ram = Ram::new(RamSize::_32K, 0x0000).expect("Failed to create RAM");
         CpuBuilder::new()
             .with_bus_device(ram, 0x0000, 0x7FFF)
             .expect("Failed to add RAM")
@@ -78,8 +78,8 @@ mod tests {
 
     /// Create a CPU with memory pre-populated with test data
     fn create_test_cpu_with_data(data: &[u8], start_address: u16) -> Cpu {
-        let mut ram = Ram::new(RamSize::_32K, 0x0000);
-        ram.import(data, start_address)
+        let mut ram = Ram::new(RamSize::_32K, 0x0000).expect("Failed to create RAM");
+        ram.import(data, start_address as usize)
             .expect("Failed to import data");
         CpuBuilder::new()
             .with_bus_device(ram, 0x0000, 0x7FFF)
@@ -161,7 +161,7 @@ mod tests {
 
     #[test]
     fn test_accumulator_and_temp_address_data_bus_error() {
-        let ram = Ram::new(RamSize::_16K, 0x0000); // Only 16K (0x0000-0x3FFF)
+        let ram = Ram::new(RamSize::_16K, 0x0000).expect("Failed to create RAM"); // Only 16K (0x0000-0x3FFF)
         let mut cpu = CpuBuilder::new()
             .with_bus_device(ram, 0x0000, 0x3FFF)
             .expect("Failed to add RAM")
@@ -583,7 +583,7 @@ mod tests {
     #[test]
     fn test_bus_error_propagation() {
         // Test that bus errors are properly propagated through the microcode functions
-        let ram = Ram::new(RamSize::_16K, 0x0000); // Only goes to 0x3FFF
+        let ram = Ram::new(RamSize::_16K, 0x0000).expect("Failed to create RAM"); // Only goes to 0x3FFF
         let mut cpu = CpuBuilder::new()
             .with_bus_device(ram, 0x0000, 0x3FFF)
             .expect("Failed to add RAM")
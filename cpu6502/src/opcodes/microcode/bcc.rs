@@ -34,7 +34,7 @@ mod unit_tests {
 
     /// Create a CPU with basic RAM setup for testing
     fn create_test_cpu() -> Cpu {
-        let ram = Ram::new(RamSize::_32K, 0x0000);
+        let ram = Ram::new(RamSize::_32K, 0x0000).expect("Failed to create RAM");
         CpuBuilder::new()
             .with_bus_device(ram, 0x0000, 0x7FFF)
             .expect("Failed to add RAM")
@@ -44,8 +44,8 @@ mod unit_tests {
 
     /// Create a CPU with memory pre-populated with test data
     fn create_test_cpu_with_data(data: &[u8], start_address: u16) -> Cpu {
-        let mut ram = Ram::new(RamSize::_32K, 0x0000);
-        ram.import(data, start_address)
+        let mut ram = Ram::new(RamSize::_32K, 0x0000).expect("Failed to create RAM");
+        ram.import(data, start_address as usize)
             .expect("Failed to import data");
         CpuBuilder::new()
             .with_bus_device(ram, 0x0000, 0x7FFF)
@@ -482,7 +482,7 @@ mod unit_tests {
     #[test]
     fn test_bcc_bus_error_propagation() {
         // Test with limited RAM that doesn't cover the PC address
-        let ram = Ram::new(RamSize::_16K, 0x0000); // Only covers 0x0000-0x3FFF
+        let ram = Ram::new(RamSize::_16K, 0x0000).expect("Failed to create RAM"); // Only covers 0x0000-0x3FFF
         let mut cpu = CpuBuilder::new()
             .with_bus_device(ram, 0x0000, 0x3FFF)
             .expect("Failed to add RAM")
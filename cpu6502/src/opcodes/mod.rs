@@ -3,6 +3,7 @@ pub(crate) mod instruction_variants;
 pub(crate) mod instructions;
 pub(crate) mod microcode;
 
+use addressing_modes::AddressingMode;
 use instruction_variants::{INSTRUCTION_VARIANTS, InstructionVariant};
 use instructions::Instruction;
 
@@ -29,6 +30,50 @@ pub(crate) fn variant_by_opcode(opcode: u8) -> Option<&'static InstructionVarian
         .find(|variant| variant.opcode == opcode)
 }
 
+/// Determine whether a microcode step of an instruction performs a bus write
+///
+/// Used to model RDY on the NMOS 6502, which is ignored during write cycles.
+///
+/// # Arguments
+/// * `instruction` - The instruction being executed
+/// * `step` - Index of the microcode step within the instruction's sequence
+/// * `sequence_length` - Number of microcode steps of the instruction
+///
+/// # Returns
+/// * `true` if the step writes to the bus, `false` otherwise
+///
+/// # Example
+/// ``` ignore
+/// let sta = Instruction::STA(AddressingMode::ZeroPage);
+/// assert!(is_write_step(&sta, 1, 2));
+/// ```
+pub(crate) fn is_write_step(
+    instruction: &Instruction,
+    step: usize,
+    sequence_length: usize,
+) -> bool {
+    match instruction {
+        // Stores write on their last step
+        Instruction::STA(_) | Instruction::STX(_) | Instruction::STY(_) => {
+            step + 1 == sequence_length
+        }
+        // Read-modify-write instructions write the unmodified and then the modified value
+        Instruction::ASL(mode)
+        | Instruction::LSR(mode)
+        | Instruction::ROL(mode)
+        | Instruction::ROR(mode)
+        | Instruction::INC(mode)
+        | Instruction::DEC(mode) => {
+            *mode != AddressingMode::Accumulator && step + 2 >= sequence_length
+        }
+        // Pushes
+        Instruction::PHA(_) | Instruction::PHP(_) => step == 0,
+        Instruction::JSR(_) => step == 2 || step == 3,
+        Instruction::BRK(_) => (1..=3).contains(&step),
+        _ => false,
+    }
+}
+
 /// Get Instruction Variant by Instruction
 ///
 /// # Arguments
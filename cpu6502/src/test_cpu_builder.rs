@@ -216,8 +216,8 @@ mod tests {
 
     #[test]
     fn test_cpu_builder_with_ram() {
-        let ram = Ram::new(RamSize::_2K, 0x0000);
-        let cpu = CpuBuilder::new()
+        let ram = Ram::new(RamSize::_2K, 0x0000).expect("Failed to create RAM");
+        let mut cpu = CpuBuilder::new()
             .with_bus_device(ram, 0x0000, 0x07FF)
             .expect("Failed to add RAM")
             .build()
@@ -231,10 +231,10 @@ mod tests {
 
     #[test]
     fn test_cpu_builder_with_memory_data() {
-        let mut ram = Ram::new(RamSize::_2K, 0x0000);
+        let mut ram = Ram::new(RamSize::_2K, 0x0000).expect("Failed to create RAM");
         ram.import(&[0x10, 0x20, 0x30, 0x40], 0x0200)
             .expect("Failed to import data");
-        let cpu = CpuBuilder::new()
+        let mut cpu = CpuBuilder::new()
             .with_bus_device(ram, 0x0000, 0x07FF)
             .expect("Failed to add RAM")
             .build()
@@ -249,8 +249,8 @@ mod tests {
 
     #[test]
     fn test_cpu_builder_complete_setup() {
-        let ram = Ram::new(RamSize::_32K, 0x0000);
-        let mut rom = Rom::new(RomSize::_32K, 0x8000);
+        let ram = Ram::new(RamSize::_32K, 0x0000).expect("Failed to create RAM");
+        let mut rom = Rom::new(RomSize::_32K, 0x8000).expect("Failed to create ROM");
 
         // Pre-populate ROM with reset vector at 0xFFFC and 0xFFFD
         // 0xFFFC = 0x8000 & 0xFF = 0x00 (low byte)
@@ -260,7 +260,7 @@ mod tests {
         rom.import(&reset_vector_data, rom_offset as usize)
             .expect("Failed to set reset vector in ROM");
 
-        let cpu = CpuBuilder::new()
+        let mut cpu = CpuBuilder::new()
             .with_bus_device(ram, 0x0000, 0x7FFF)
             .expect("Failed to add RAM")
             .with_bus_device(rom, 0x8000, 0xFFFF)
@@ -0,0 +1,357 @@
+//! AT28C256 parallel EEPROM for 6502-based systems.
+//!
+//! The EEPROM reads like a ROM, but accepts writes with the timing of the real chip:
+//! - A write opens a page load window. Further writes to the same 64-byte page restart
+//!   the window, which closes after the byte load cycle time (tBLC, 150µs).
+//! - When the window closes the page is programmed, which takes the write cycle time
+//!   (tWC, up to 10ms). Writes during the write cycle are ignored.
+//! - Until programming completes, reads return the last byte written with D7 inverted
+//!   (data polling) and D6 toggling on every read (toggle bit).
+//!
+//! Software data protection (SDP) is enabled by writing $AA to $5555, $55 to $2AAA and
+//! $A0 to $5555, and disabled by writing $AA, $55, $80, $AA, $55, $20 to the same
+//! addresses. The addresses are offsets into the chip, e.g. $D555 and $AAAA for a chip
+//! mapped at $8000. While SDP is enabled, only writes directly following the enable
+//! sequence are programmed.
+//!
+//! Timing is counted in bus ticks, the defaults assume a 1 MHz clock.
+
+use bus::errors::BusError;
+use bus::trait_bus_device::BusDevice;
+
+/// Size of the AT28C256 in bytes
+pub const EEPROM_SIZE: usize = 0x8000;
+/// Size of a page in bytes
+pub const PAGE_SIZE: usize = 64;
+/// Default byte load cycle time in ticks, 150µs at 1 MHz
+pub const DEFAULT_BYTE_LOAD_TICKS: u64 = 150;
+/// Default write cycle time in ticks, 10ms at 1 MHz
+pub const DEFAULT_WRITE_CYCLE_TICKS: u64 = 10_000;
+
+/// Command sequence enabling software data protection
+const ENABLE_SDP: [(usize, u8); 3] = [(0x5555, 0xAA), (0x2AAA, 0x55), (0x5555, 0xA0)];
+/// Command sequence disabling software data protection
+const DISABLE_SDP: [(usize, u8); 6] = [
+    (0x5555, 0xAA),
+    (0x2AAA, 0x55),
+    (0x5555, 0x80),
+    (0x5555, 0xAA),
+    (0x2AAA, 0x55),
+    (0x5555, 0x20),
+];
+
+/// Internal state of the write logic
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Reads return the memory contents
+    Idle,
+    /// Writes are collected into the page buffer
+    Loading,
+    /// The page buffer is being programmed
+    Writing,
+}
+
+/// Represents an AT28C256 32K parallel EEPROM.
+#[derive(Debug)]
+pub struct Eeprom {
+    /// EEPROM memory
+    memory: Vec<u8>,
+    /// Start address of the EEPROM
+    start_address: u16,
+    /// Byte load cycle time in ticks
+    byte_load_ticks: u64,
+    /// Write cycle time in ticks
+    write_cycle_ticks: u64,
+    state: State,
+    /// Ticks until the current page load window closes or the write cycle completes
+    timer: u64,
+    /// Offset of the page latched by the first byte of the page load
+    page: Option<usize>,
+    /// Bytes loaded into the page, indexed by offset within the page
+    page_buffer: [Option<u8>; PAGE_SIZE],
+    /// Last byte written, returned inverted on D7 while busy
+    last_data: u8,
+    /// State of the toggle bit D6 while busy
+    toggle: u8,
+    /// Software data protection is enabled
+    protected: bool,
+    /// The current page load follows a complete command sequence
+    unlocked: bool,
+    /// Writes matching a command sequence so far
+    command: Vec<(usize, u8)>,
+}
+
+impl Eeprom {
+    /// Create a new, erased EEPROM with software data protection disabled.
+    ///
+    /// # Arguments
+    /// * `start_address` - Start address of the EEPROM in memory, usually 0x8000
+    ///
+    /// # Returns
+    /// * `Ok(Eeprom)` containing the new EEPROM instance
+    /// * `Err(String)` if the EEPROM does not fit below $FFFF
+    ///
+    /// # Errors
+    /// * If the start address plus 32K exceeds the 64K address space
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let eeprom = Eeprom::new(0x8000)?;
+    /// ```
+    pub fn new(start_address: u16) -> Result<Self, String> {
+        if start_address as usize + EEPROM_SIZE > 0x10000 {
+            return Err(format!(
+                "EEPROM of {} bytes at 0x{:04X} extends past 0xFFFF",
+                EEPROM_SIZE, start_address
+            ));
+        }
+        Ok(Self {
+            memory: vec![0xFF; EEPROM_SIZE],
+            start_address,
+            byte_load_ticks: DEFAULT_BYTE_LOAD_TICKS,
+            write_cycle_ticks: DEFAULT_WRITE_CYCLE_TICKS,
+            state: State::Idle,
+            timer: 0,
+            page: None,
+            page_buffer: [None; PAGE_SIZE],
+            last_data: 0,
+            toggle: 0,
+            protected: false,
+            unlocked: false,
+            command: Vec::new(),
+        })
+    }
+
+    /// Use different timing, e.g. for a faster clock than 1 MHz.
+    ///
+    /// # Arguments
+    /// * `byte_load_ticks` - Ticks without a write after which a page load ends
+    /// * `write_cycle_ticks` - Ticks taken to program a page
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// // 150µs and 10ms at 2 MHz
+    /// let eeprom = Eeprom::new(0x8000)?.with_timing(300, 20_000);
+    /// ```
+    pub fn with_timing(mut self, byte_load_ticks: u64, write_cycle_ticks: u64) -> Self {
+        self.byte_load_ticks = byte_load_ticks.max(1);
+        self.write_cycle_ticks = write_cycle_ticks.max(1);
+        self
+    }
+
+    /// Start with software data protection enabled, as on chips programmed by some
+    /// device programmers.
+    pub fn with_data_protection(mut self) -> Self {
+        self.protected = true;
+        self
+    }
+
+    /// Size of the EEPROM in bytes
+    pub fn size(&self) -> usize {
+        self.memory.len()
+    }
+
+    /// Check whether software data protection is enabled
+    pub fn is_protected(&self) -> bool {
+        self.protected
+    }
+
+    /// Check whether a page load or write cycle is in progress
+    pub fn is_busy(&self) -> bool {
+        self.state != State::Idle
+    }
+
+    /// Import data into the EEPROM at the specified offset, bypassing the write timing.
+    ///
+    /// # Arguments
+    /// * `data` - Data to import into the EEPROM
+    /// * `offset` - Offset within the EEPROM to start importing data
+    ///
+    /// # Returns
+    /// * `Ok(())` if data was imported successfully
+    /// * `Err(String)` if the data exceeds the EEPROM size
+    ///
+    /// # Errors
+    /// * If the data length plus offset exceeds the EEPROM size
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let mut eeprom = Eeprom::new(0x8000)?;
+    /// eeprom.import(&std::fs::read("firmware.bin")?, 0)?;
+    /// ```
+    pub fn import(&mut self, data: &[u8], offset: usize) -> Result<(), String> {
+        if offset + data.len() > self.memory.len() {
+            return Err("Data exceeds EEPROM size".to_string());
+        }
+        self.memory[offset..offset + data.len()].copy_from_slice(data);
+        Ok(())
+    }
+
+    /// Export data from the EEPROM at the specified offset and length.
+    ///
+    /// # Arguments
+    /// * `offset` - Offset within the EEPROM to start exporting data
+    /// * `length` - Length of data to export
+    ///
+    /// # Returns
+    /// * A vector containing the exported data, as programmed so far
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let config = eeprom.export(0x7F00, 0x100);
+    /// ```
+    pub fn export(&self, offset: usize, length: usize) -> Vec<u8> {
+        let start = offset.min(self.memory.len());
+        let end = (offset + length).min(self.memory.len());
+        self.memory[start..end].to_vec()
+    }
+
+    /// Offset of an address within the EEPROM
+    fn offset(&self, address: u16) -> Result<usize, BusError> {
+        let offset = address.wrapping_sub(self.start_address) as usize;
+        if offset < self.memory.len() {
+            Ok(offset)
+        } else {
+            Err(BusError::AddressOutOfRange(address))
+        }
+    }
+
+    /// Handle a write, recognising the SDP command sequences
+    fn write_byte(&mut self, offset: usize, data: u8) {
+        if self.state == State::Writing {
+            return;
+        }
+        // Every write, including command writes, opens or extends the page load window
+        self.state = State::Loading;
+        self.timer = self.byte_load_ticks;
+
+        self.command.push((offset, data));
+        if self.command[..] == ENABLE_SDP[..] {
+            self.command.clear();
+            self.protected = true;
+            self.unlocked = true;
+            return;
+        }
+        if self.command[..] == DISABLE_SDP[..] {
+            self.command.clear();
+            self.protected = false;
+            self.unlocked = true;
+            return;
+        }
+        if is_command_prefix(&self.command) {
+            return;
+        }
+
+        // Not a command after all: the held back writes are ordinary data
+        self.command.pop();
+        self.flush_command();
+        self.command.push((offset, data));
+        if !is_command_prefix(&self.command) {
+            self.command.clear();
+            self.load_byte(offset, data);
+        }
+    }
+
+    /// Treat the writes held back as a possible command as data writes
+    fn flush_command(&mut self) {
+        for (offset, data) in std::mem::take(&mut self.command) {
+            self.load_byte(offset, data);
+        }
+    }
+
+    /// Store a byte into the page buffer, unless the write is blocked by SDP
+    fn load_byte(&mut self, offset: usize, data: u8) {
+        if self.protected && !self.unlocked {
+            return;
+        }
+        // The page address is latched by the first byte, later bytes only supply A0-A5
+        if self.page.is_none() {
+            self.page = Some(offset & !(PAGE_SIZE - 1));
+        }
+        self.page_buffer[offset & (PAGE_SIZE - 1)] = Some(data);
+        self.last_data = data;
+    }
+
+    /// Close the page load window and start programming the page
+    fn start_write_cycle(&mut self) {
+        self.flush_command();
+        if let Some(page) = self.page.take() {
+            for (index, data) in self.page_buffer.iter_mut().enumerate() {
+                if let Some(data) = data.take() {
+                    self.memory[page + index] = data;
+                }
+            }
+        }
+        self.unlocked = false;
+        self.state = State::Writing;
+        self.timer = self.write_cycle_ticks;
+    }
+}
+
+/// Check whether writes are the start of a command sequence
+fn is_command_prefix(writes: &[(usize, u8)]) -> bool {
+    ENABLE_SDP.starts_with(writes) || DISABLE_SDP.starts_with(writes)
+}
+
+impl BusDevice for Eeprom {
+    fn read(&mut self, address: u16) -> Result<u8, BusError> {
+        let offset = self.offset(address)?;
+        if self.state == State::Idle {
+            return Ok(self.memory[offset]);
+        }
+        // Data polling on D7 and toggle bit on D6 while a write is in progress
+        self.toggle ^= 0x40;
+        Ok((!self.last_data & 0x80) | self.toggle | (self.last_data & 0x3F))
+    }
+
+    fn write(&mut self, address: u16, data: u8) -> Result<(), BusError> {
+        let offset = self.offset(address)?;
+        self.write_byte(offset, data);
+        Ok(())
+    }
+
+    fn load(&mut self, address: u16, data: u8) -> Result<(), BusError> {
+        let offset = self.offset(address)?;
+        self.memory[offset] = data;
+        Ok(())
+    }
+
+    fn tick(&mut self) {
+        self.advance(1);
+    }
+
+    fn advance(&mut self, ticks: u64) {
+        let mut ticks = ticks;
+        while ticks > 0 && self.state != State::Idle {
+            let elapsed = ticks.min(self.timer);
+            self.timer -= elapsed;
+            ticks -= elapsed;
+            if self.timer == 0 {
+                match self.state {
+                    State::Loading => self.start_write_cycle(),
+                    _ => self.state = State::Idle,
+                }
+            }
+        }
+    }
+
+    fn check_irq(&self) -> bool {
+        // EEPROM does not generate IRQs
+        false
+    }
+
+    fn check_nmi(&self) -> bool {
+        // EEPROM does not generate NMIs
+        false
+    }
+
+    fn check_rdy(&self) -> bool {
+        // EEPROM does not insert wait states, firmware polls it instead
+        false
+    }
+
+    fn check_so(&self) -> bool {
+        // EEPROM does not drive the SO line
+        false
+    }
+}
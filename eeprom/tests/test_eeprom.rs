@@ -0,0 +1,204 @@
+//! Unit tests for the EEPROM implementation
+//!
+//! This module tests the AT28C256 emulation: creation, write timing, page writes,
+//! data polling and toggle bit status, and software data protection.
+
+use bus::errors::BusError;
+use bus::trait_bus_device::BusDevice;
+use eeprom::{DEFAULT_BYTE_LOAD_TICKS, DEFAULT_WRITE_CYCLE_TICKS, EEPROM_SIZE, Eeprom};
+
+/// Write the SDP enable sequence to an EEPROM mapped at $8000
+fn enable_sdp(eeprom: &mut Eeprom) {
+    eeprom.write(0xD555, 0xAA).unwrap();
+    eeprom.write(0xAAAA, 0x55).unwrap();
+    eeprom.write(0xD555, 0xA0).unwrap();
+}
+
+/// Write the SDP disable sequence to an EEPROM mapped at $8000
+fn disable_sdp(eeprom: &mut Eeprom) {
+    for (address, data) in [
+        (0xD555, 0xAA),
+        (0xAAAA, 0x55),
+        (0xD555, 0x80),
+        (0xD555, 0xAA),
+        (0xAAAA, 0x55),
+        (0xD555, 0x20),
+    ] {
+        eeprom.write(address, data).unwrap();
+    }
+}
+
+/// Let the page load window close and the write cycle complete
+fn finish_write(eeprom: &mut Eeprom) {
+    eeprom.advance(DEFAULT_BYTE_LOAD_TICKS + DEFAULT_WRITE_CYCLE_TICKS);
+}
+
+// Test EEPROM creation
+#[test]
+fn test_eeprom_creation() {
+    let mut eeprom = Eeprom::new(0x8000).unwrap();
+    assert_eq!(eeprom.size(), EEPROM_SIZE);
+    assert_eq!(eeprom.export(0, 4), vec![0xFF; 4]);
+    assert_eq!(eeprom.read(0xFFFF).unwrap(), 0xFF);
+    assert!(!eeprom.is_protected());
+    assert!(!eeprom.is_busy());
+
+    assert!(Eeprom::new(0x8001).is_err());
+    assert!(Eeprom::new(0x0000).is_ok());
+}
+
+#[test]
+fn test_eeprom_out_of_range() {
+    let mut eeprom = Eeprom::new(0x8000).unwrap();
+    assert!(matches!(
+        eeprom.read(0x7FFF),
+        Err(BusError::AddressOutOfRange(0x7FFF))
+    ));
+    assert!(matches!(
+        eeprom.write(0x7FFF, 0x00),
+        Err(BusError::AddressOutOfRange(0x7FFF))
+    ));
+}
+
+#[test]
+fn test_eeprom_import_and_load_bypass_timing() {
+    let mut eeprom = Eeprom::new(0x8000).unwrap();
+    eeprom.import(&[0x01, 0x02], 0x10).unwrap();
+    eeprom.load(0x8012, 0x03).unwrap();
+    assert!(!eeprom.is_busy());
+    assert_eq!(eeprom.export(0x10, 3), vec![0x01, 0x02, 0x03]);
+    assert!(eeprom.import(&[0x00; 2], EEPROM_SIZE - 1).is_err());
+}
+
+// Test write timing
+#[test]
+fn test_byte_write_timing() {
+    let mut eeprom = Eeprom::new(0x8000).unwrap();
+    eeprom.write(0x8100, 0x42).unwrap();
+    assert!(eeprom.is_busy());
+    assert_eq!(eeprom.export(0x100, 1), vec![0xFF]);
+
+    // The page is programmed once the load window closes, but stays busy for tWC
+    eeprom.advance(DEFAULT_BYTE_LOAD_TICKS);
+    assert_eq!(eeprom.export(0x100, 1), vec![0x42]);
+    assert!(eeprom.is_busy());
+
+    eeprom.advance(DEFAULT_WRITE_CYCLE_TICKS - 1);
+    assert!(eeprom.is_busy());
+    eeprom.tick();
+    assert!(!eeprom.is_busy());
+    assert_eq!(eeprom.read(0x8100).unwrap(), 0x42);
+}
+
+#[test]
+fn test_custom_timing() {
+    let mut eeprom = Eeprom::new(0x8000).unwrap().with_timing(2, 5);
+    eeprom.write(0x8000, 0x12).unwrap();
+    eeprom.advance(6);
+    assert!(eeprom.is_busy());
+    eeprom.tick();
+    assert!(!eeprom.is_busy());
+    assert_eq!(eeprom.read(0x8000).unwrap(), 0x12);
+}
+
+#[test]
+fn test_writes_ignored_during_write_cycle() {
+    let mut eeprom = Eeprom::new(0x8000).unwrap();
+    eeprom.write(0x8000, 0x11).unwrap();
+    eeprom.advance(DEFAULT_BYTE_LOAD_TICKS);
+    eeprom.write(0x8001, 0x22).unwrap();
+    finish_write(&mut eeprom);
+    assert_eq!(eeprom.export(0, 2), vec![0x11, 0xFF]);
+}
+
+// Test page writes
+#[test]
+fn test_page_write() {
+    let mut eeprom = Eeprom::new(0x8000).unwrap();
+    for index in 0..64u16 {
+        eeprom.write(0x8040 + index, index as u8).unwrap();
+        // Each write restarts the byte load window
+        eeprom.advance(DEFAULT_BYTE_LOAD_TICKS - 1);
+    }
+    assert_eq!(eeprom.export(0x40, 1), vec![0xFF]);
+    finish_write(&mut eeprom);
+    assert_eq!(eeprom.export(0x40, 64), (0..64).collect::<Vec<u8>>());
+}
+
+#[test]
+fn test_page_address_latched_by_first_byte() {
+    let mut eeprom = Eeprom::new(0x8000).unwrap();
+    eeprom.write(0x8040, 0x01).unwrap();
+    // A6-A14 of later bytes are ignored, so this lands at offset 0x41
+    eeprom.write(0x8081, 0x02).unwrap();
+    finish_write(&mut eeprom);
+    assert_eq!(eeprom.export(0x40, 2), vec![0x01, 0x02]);
+    assert_eq!(eeprom.export(0x81, 1), vec![0xFF]);
+}
+
+// Test status polling
+#[test]
+fn test_data_polling_and_toggle_bit() {
+    let mut eeprom = Eeprom::new(0x8000).unwrap();
+    eeprom.write(0x8000, 0x81).unwrap();
+
+    let first = eeprom.read(0x8000).unwrap();
+    let second = eeprom.read(0x8000).unwrap();
+    assert_eq!(first & 0x80, 0x00);
+    assert_eq!(second & 0x80, 0x00);
+    assert_ne!(first & 0x40, second & 0x40);
+
+    finish_write(&mut eeprom);
+    assert_eq!(eeprom.read(0x8000).unwrap(), 0x81);
+    assert_eq!(eeprom.read(0x8000).unwrap(), 0x81);
+}
+
+// Test software data protection
+#[test]
+fn test_protected_writes_are_ignored() {
+    let mut eeprom = Eeprom::new(0x8000).unwrap().with_data_protection();
+    assert!(eeprom.is_protected());
+    eeprom.write(0x8000, 0x12).unwrap();
+    finish_write(&mut eeprom);
+    assert_eq!(eeprom.read(0x8000).unwrap(), 0xFF);
+}
+
+#[test]
+fn test_enable_sequence_unlocks_one_page_write() {
+    let mut eeprom = Eeprom::new(0x8000).unwrap();
+    enable_sdp(&mut eeprom);
+    eeprom.write(0x8000, 0x12).unwrap();
+    eeprom.write(0x8001, 0x34).unwrap();
+    finish_write(&mut eeprom);
+    assert!(eeprom.is_protected());
+    assert_eq!(eeprom.export(0, 2), vec![0x12, 0x34]);
+    // The command bytes themselves are not programmed
+    assert_eq!(eeprom.export(0x5555, 1), vec![0xFF]);
+    assert_eq!(eeprom.export(0x2AAA, 1), vec![0xFF]);
+
+    eeprom.write(0x8000, 0x56).unwrap();
+    finish_write(&mut eeprom);
+    assert_eq!(eeprom.read(0x8000).unwrap(), 0x12);
+}
+
+#[test]
+fn test_disable_sequence() {
+    let mut eeprom = Eeprom::new(0x8000).unwrap().with_data_protection();
+    disable_sdp(&mut eeprom);
+    finish_write(&mut eeprom);
+    assert!(!eeprom.is_protected());
+
+    eeprom.write(0x8000, 0x56).unwrap();
+    finish_write(&mut eeprom);
+    assert_eq!(eeprom.read(0x8000).unwrap(), 0x56);
+}
+
+#[test]
+fn test_broken_sequence_is_data_when_unprotected() {
+    let mut eeprom = Eeprom::new(0x8000).unwrap();
+    eeprom.write(0xD555, 0xAA).unwrap();
+    eeprom.write(0xD556, 0x01).unwrap();
+    finish_write(&mut eeprom);
+    assert!(!eeprom.is_protected());
+    assert_eq!(eeprom.export(0x5555, 2), vec![0xAA, 0x01]);
+}
@@ -0,0 +1,458 @@
+//! SST39SF0x0 NOR flash for 6502-based systems.
+//!
+//! The flash reads like a ROM. Writes are interpreted by the JEDEC command state machine
+//! of the SST39SF010A, SST39SF020A and SST39SF040. Each command starts with $AA written
+//! to $5555 and $55 written to $2AAA, where only address lines A0-A14 are decoded:
+//! - $A0 to $5555, then the data to the target address: byte program
+//! - $80 to $5555, $AA to $5555, $55 to $2AAA, $30 to an address in the sector: sector erase
+//! - $80 to $5555, $AA to $5555, $55 to $2AAA, $10 to $5555: chip erase
+//! - $90 to $5555: software ID entry, reads return the manufacturer and device IDs
+//! - $F0 to $5555, or $F0 to any address: software ID exit
+//!
+//! Programming can only clear bits, erasing sets a whole sector or the chip to $FF.
+//! While an operation is in progress, reads return the data written with DQ7 inverted
+//! (data polling), so DQ7 reads 0 during an erase, and DQ6 toggles on every read
+//! (toggle bit).
+//!
+//! The chip is larger than the 6502 address space, so it is seen through a window.
+//! The upper address lines are selected with `Flash::set_bank`, e.g. by the latch of a
+//! bank-switched cartridge.
+//!
+//! Timing is counted in bus ticks, the defaults assume a 1 MHz clock.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use bus::errors::BusError;
+use bus::trait_bus_device::BusDevice;
+
+/// Size of an erasable sector in bytes
+pub const SECTOR_SIZE: usize = 0x1000;
+/// Manufacturer ID of SST
+pub const MANUFACTURER_ID: u8 = 0xBF;
+/// Default byte program time in ticks, 20µs at 1 MHz
+pub const DEFAULT_PROGRAM_TICKS: u64 = 20;
+/// Default sector erase time in ticks, 25ms at 1 MHz
+pub const DEFAULT_SECTOR_ERASE_TICKS: u64 = 25_000;
+/// Default chip erase time in ticks, 100ms at 1 MHz
+pub const DEFAULT_CHIP_ERASE_TICKS: u64 = 100_000;
+
+/// Address lines decoded for command cycles
+const COMMAND_ADDRESS_MASK: usize = 0x7FFF;
+/// First unlock cycle address
+const UNLOCK_ADDRESS_1: usize = 0x5555;
+/// Second unlock cycle address
+const UNLOCK_ADDRESS_2: usize = 0x2AAA;
+
+/// Supported flash chips
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlashChip {
+    /// SST39SF010A, 128KB
+    Sst39sf010,
+    /// SST39SF020A, 256KB
+    Sst39sf020,
+    /// SST39SF040, 512KB
+    Sst39sf040,
+}
+
+impl FlashChip {
+    /// Size of the chip in bytes
+    pub fn size(&self) -> usize {
+        match self {
+            FlashChip::Sst39sf010 => 0x20000,
+            FlashChip::Sst39sf020 => 0x40000,
+            FlashChip::Sst39sf040 => 0x80000,
+        }
+    }
+
+    /// Device ID returned in software ID mode
+    pub fn device_id(&self) -> u8 {
+        match self {
+            FlashChip::Sst39sf010 => 0xB5,
+            FlashChip::Sst39sf020 => 0xB6,
+            FlashChip::Sst39sf040 => 0xB7,
+        }
+    }
+}
+
+/// Progress through a command sequence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    /// Waiting for the first unlock cycle
+    Ready,
+    /// $AA written to $5555
+    Unlock1,
+    /// $55 written to $2AAA
+    Unlock2,
+    /// Byte program set up, the next write is programmed
+    Program,
+    /// Erase set up with $80
+    EraseSetup,
+    /// $AA written to $5555 after the erase setup
+    EraseUnlock1,
+    /// $55 written to $2AAA after the erase setup
+    EraseUnlock2,
+}
+
+/// Represents an SST39SF0x0 NOR flash.
+#[derive(Debug)]
+pub struct Flash {
+    chip: FlashChip,
+    /// Flash memory
+    memory: Vec<u8>,
+    /// Start address of the window in memory
+    start_address: u16,
+    /// Size of the window in bytes
+    window_size: usize,
+    /// Bank selected by the upper address lines
+    bank: usize,
+    /// Byte program time in ticks
+    program_ticks: u64,
+    /// Sector erase time in ticks
+    sector_erase_ticks: u64,
+    /// Chip erase time in ticks
+    chip_erase_ticks: u64,
+    command: Command,
+    /// Reads return the IDs instead of the memory
+    id_mode: bool,
+    /// Ticks until the operation in progress completes
+    busy_ticks: u64,
+    /// Data of the operation in progress, returned inverted on DQ7 while busy
+    last_data: u8,
+    /// State of the toggle bit DQ6 while busy
+    toggle: u8,
+    /// File the image is persisted to
+    image_file: Option<PathBuf>,
+    /// The memory was changed since the image was last saved
+    dirty: bool,
+}
+
+impl Flash {
+    /// Create a new, erased flash seen through a window of the 6502 address space.
+    ///
+    /// # Arguments
+    /// * `chip` - The flash chip
+    /// * `start_address` - Start address of the window in memory
+    /// * `window_size` - Size of the window in bytes, at most the size of the chip
+    ///
+    /// # Returns
+    /// * `Ok(Flash)` containing the new flash instance
+    /// * `Err(String)` if the window is invalid
+    ///
+    /// # Errors
+    /// * If the window size is zero or larger than the chip
+    /// * If the start address plus the window size exceeds the 64K address space
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// // 16K banks at $8000-$BFFF
+    /// let flash = Flash::new(FlashChip::Sst39sf040, 0x8000, 0x4000)?;
+    /// ```
+    pub fn new(chip: FlashChip, start_address: u16, window_size: usize) -> Result<Self, String> {
+        if window_size == 0 || window_size > chip.size() {
+            return Err(format!(
+                "Flash window of {} bytes does not fit a chip of {} bytes",
+                window_size,
+                chip.size()
+            ));
+        }
+        if start_address as usize + window_size > 0x10000 {
+            return Err(format!(
+                "Flash window of {} bytes at 0x{:04X} extends past 0xFFFF",
+                window_size, start_address
+            ));
+        }
+        Ok(Self {
+            chip,
+            memory: vec![0xFF; chip.size()],
+            start_address,
+            window_size,
+            bank: 0,
+            program_ticks: DEFAULT_PROGRAM_TICKS,
+            sector_erase_ticks: DEFAULT_SECTOR_ERASE_TICKS,
+            chip_erase_ticks: DEFAULT_CHIP_ERASE_TICKS,
+            command: Command::Ready,
+            id_mode: false,
+            busy_ticks: 0,
+            last_data: 0,
+            toggle: 0,
+            image_file: None,
+            dirty: false,
+        })
+    }
+
+    /// Use different timing, e.g. for a faster clock than 1 MHz.
+    ///
+    /// # Arguments
+    /// * `program_ticks` - Ticks taken to program a byte
+    /// * `sector_erase_ticks` - Ticks taken to erase a sector
+    /// * `chip_erase_ticks` - Ticks taken to erase the chip
+    pub fn with_timing(
+        mut self,
+        program_ticks: u64,
+        sector_erase_ticks: u64,
+        chip_erase_ticks: u64,
+    ) -> Self {
+        self.program_ticks = program_ticks.max(1);
+        self.sector_erase_ticks = sector_erase_ticks.max(1);
+        self.chip_erase_ticks = chip_erase_ticks.max(1);
+        self
+    }
+
+    /// Persist the image to a host file.
+    ///
+    /// The image is loaded from the file if it exists, and saved by `save` and when the
+    /// flash is dropped.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the image file
+    ///
+    /// # Returns
+    /// * `Ok(Flash)` with the image loaded
+    /// * `Err(String)` if the file cannot be read or has the wrong size
+    ///
+    /// # Errors
+    /// * If the file exists but cannot be read
+    /// * If the size of the file differs from the size of the chip
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let flash = Flash::new(FlashChip::Sst39sf010, 0x8000, 0x8000)?
+    ///     .with_image_file("flash.bin")?;
+    /// ```
+    pub fn with_image_file(mut self, path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        match fs::read(path) {
+            Ok(image) if image.len() == self.memory.len() => self.memory = image,
+            Ok(image) => {
+                return Err(format!(
+                    "Flash image {} has {} bytes, expected {}",
+                    path.display(),
+                    image.len(),
+                    self.memory.len()
+                ));
+            }
+            Err(error) if error.kind() == ErrorKind::NotFound => self.dirty = true,
+            Err(error) => {
+                return Err(format!(
+                    "Failed to read flash image {}: {}",
+                    path.display(),
+                    error
+                ));
+            }
+        }
+        self.image_file = Some(path.to_path_buf());
+        Ok(self)
+    }
+
+    /// Save the image to the host file, if one is set and the memory has changed.
+    ///
+    /// # Errors
+    /// * If the file cannot be written
+    pub fn save(&mut self) -> Result<(), String> {
+        if let Some(path) = &self.image_file
+            && self.dirty
+        {
+            fs::write(path, &self.memory).map_err(|error| {
+                format!("Failed to write flash image {}: {}", path.display(), error)
+            })?;
+            self.dirty = false;
+        }
+        Ok(())
+    }
+
+    /// The flash chip
+    pub fn chip(&self) -> FlashChip {
+        self.chip
+    }
+
+    /// Size of the flash in bytes
+    pub fn size(&self) -> usize {
+        self.memory.len()
+    }
+
+    /// Select the bank seen through the window, wrapping past the end of the chip
+    ///
+    /// # Arguments
+    /// * `bank` - The bank, in units of the window size
+    pub fn set_bank(&mut self, bank: usize) {
+        self.bank = bank % self.memory.len().div_ceil(self.window_size);
+    }
+
+    /// The bank seen through the window
+    pub fn bank(&self) -> usize {
+        self.bank
+    }
+
+    /// Check whether a program or erase operation is in progress
+    pub fn is_busy(&self) -> bool {
+        self.busy_ticks > 0
+    }
+
+    /// Import data into the flash at the specified offset, bypassing the command set.
+    ///
+    /// # Arguments
+    /// * `data` - Data to import into the flash
+    /// * `offset` - Offset within the chip to start importing data
+    ///
+    /// # Returns
+    /// * `Ok(())` if data was imported successfully
+    /// * `Err(String)` if the data exceeds the flash size
+    ///
+    /// # Errors
+    /// * If the data length plus offset exceeds the flash size
+    pub fn import(&mut self, data: &[u8], offset: usize) -> Result<(), String> {
+        if offset + data.len() > self.memory.len() {
+            return Err("Data exceeds flash size".to_string());
+        }
+        self.memory[offset..offset + data.len()].copy_from_slice(data);
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Export data from the flash at the specified offset and length.
+    ///
+    /// # Arguments
+    /// * `offset` - Offset within the chip to start exporting data
+    /// * `length` - Length of data to export
+    ///
+    /// # Returns
+    /// * A vector containing the exported data
+    pub fn export(&self, offset: usize, length: usize) -> Vec<u8> {
+        let start = offset.min(self.memory.len());
+        let end = (offset + length).min(self.memory.len());
+        self.memory[start..end].to_vec()
+    }
+
+    /// Offset within the chip of an address in the window
+    fn offset(&self, address: u16) -> Result<usize, BusError> {
+        let offset = address.wrapping_sub(self.start_address) as usize;
+        if offset < self.window_size {
+            Ok((self.bank * self.window_size + offset) % self.memory.len())
+        } else {
+            Err(BusError::AddressOutOfRange(address))
+        }
+    }
+
+    /// Advance the command state machine by a write cycle
+    fn write_command(&mut self, offset: usize, data: u8) {
+        let command_address = offset & COMMAND_ADDRESS_MASK;
+        self.command = match (self.command, command_address, data) {
+            (Command::Program, _, _) => {
+                self.program(offset, data);
+                Command::Ready
+            }
+            (Command::Ready, UNLOCK_ADDRESS_1, 0xAA) => Command::Unlock1,
+            (Command::Unlock1, UNLOCK_ADDRESS_2, 0x55) => Command::Unlock2,
+            (Command::Unlock2, UNLOCK_ADDRESS_1, 0xA0) => Command::Program,
+            (Command::Unlock2, UNLOCK_ADDRESS_1, 0x80) => Command::EraseSetup,
+            (Command::Unlock2, UNLOCK_ADDRESS_1, 0x90) => {
+                self.id_mode = true;
+                Command::Ready
+            }
+            (Command::EraseSetup, UNLOCK_ADDRESS_1, 0xAA) => Command::EraseUnlock1,
+            (Command::EraseUnlock1, UNLOCK_ADDRESS_2, 0x55) => Command::EraseUnlock2,
+            (Command::EraseUnlock2, UNLOCK_ADDRESS_1, 0x10) => {
+                self.erase(0, self.memory.len(), self.chip_erase_ticks);
+                Command::Ready
+            }
+            (Command::EraseUnlock2, _, 0x30) => {
+                let sector = offset & !(SECTOR_SIZE - 1);
+                self.erase(sector, SECTOR_SIZE, self.sector_erase_ticks);
+                Command::Ready
+            }
+            // $F0 exits software ID mode, either alone or after the unlock cycles
+            (_, _, 0xF0) => {
+                self.id_mode = false;
+                Command::Ready
+            }
+            _ => Command::Ready,
+        };
+    }
+
+    /// Program a byte, which can only clear bits
+    fn program(&mut self, offset: usize, data: u8) {
+        self.memory[offset] &= data;
+        self.dirty = true;
+        self.last_data = data;
+        self.busy_ticks = self.program_ticks;
+    }
+
+    /// Erase a range of the memory to $FF
+    fn erase(&mut self, offset: usize, length: usize, ticks: u64) {
+        self.memory[offset..offset + length].fill(0xFF);
+        self.dirty = true;
+        self.last_data = 0xFF;
+        self.busy_ticks = ticks;
+    }
+}
+
+impl Drop for Flash {
+    fn drop(&mut self) {
+        // Errors cannot be reported from drop, call `save` to handle them
+        let _ = self.save();
+    }
+}
+
+impl BusDevice for Flash {
+    fn read(&mut self, address: u16) -> Result<u8, BusError> {
+        let offset = self.offset(address)?;
+        if self.busy_ticks > 0 {
+            // Data polling on DQ7 and toggle bit on DQ6 while an operation is in progress
+            self.toggle ^= 0x40;
+            return Ok((!self.last_data & 0x80) | self.toggle | (self.last_data & 0x3F));
+        }
+        if self.id_mode {
+            return Ok(if offset & 0x01 == 0 {
+                MANUFACTURER_ID
+            } else {
+                self.chip.device_id()
+            });
+        }
+        Ok(self.memory[offset])
+    }
+
+    fn write(&mut self, address: u16, data: u8) -> Result<(), BusError> {
+        let offset = self.offset(address)?;
+        if self.busy_ticks == 0 {
+            self.write_command(offset, data);
+        }
+        Ok(())
+    }
+
+    fn load(&mut self, address: u16, data: u8) -> Result<(), BusError> {
+        let offset = self.offset(address)?;
+        self.memory[offset] = data;
+        self.dirty = true;
+        Ok(())
+    }
+
+    fn tick(&mut self) {
+        self.busy_ticks = self.busy_ticks.saturating_sub(1);
+    }
+
+    fn advance(&mut self, ticks: u64) {
+        self.busy_ticks = self.busy_ticks.saturating_sub(ticks);
+    }
+
+    fn check_irq(&self) -> bool {
+        // Flash does not generate IRQs
+        false
+    }
+
+    fn check_nmi(&self) -> bool {
+        // Flash does not generate NMIs
+        false
+    }
+
+    fn check_rdy(&self) -> bool {
+        // Flash does not insert wait states, firmware polls it instead
+        false
+    }
+
+    fn check_so(&self) -> bool {
+        // Flash does not drive the SO line
+        false
+    }
+}
@@ -0,0 +1,251 @@
+//! Unit tests for the flash implementation
+//!
+//! This module tests the SST39SF0x0 emulation: creation, banking, the JEDEC command
+//! set, status polling and image persistence.
+
+use std::fs;
+use std::path::PathBuf;
+
+use bus::errors::BusError;
+use bus::trait_bus_device::BusDevice;
+use flash::{
+    DEFAULT_CHIP_ERASE_TICKS, DEFAULT_PROGRAM_TICKS, DEFAULT_SECTOR_ERASE_TICKS, Flash, FlashChip,
+    MANUFACTURER_ID, SECTOR_SIZE,
+};
+
+/// Create a 128K flash seen through a 32K window at $8000
+fn create_flash() -> Flash {
+    Flash::new(FlashChip::Sst39sf010, 0x8000, 0x8000).unwrap()
+}
+
+/// Write a command sequence, with chip addresses relative to the window at $8000
+fn command(flash: &mut Flash, writes: &[(u16, u8)]) {
+    for (offset, data) in writes {
+        flash.write(0x8000 + offset, *data).unwrap();
+    }
+}
+
+/// Program a byte through the command set and wait for completion
+fn program(flash: &mut Flash, address: u16, data: u8) {
+    command(flash, &[(0x5555, 0xAA), (0x2AAA, 0x55), (0x5555, 0xA0)]);
+    flash.write(address, data).unwrap();
+    flash.advance(DEFAULT_PROGRAM_TICKS);
+}
+
+/// Write the erase setup and unlock cycles
+fn erase_setup(flash: &mut Flash) {
+    command(
+        flash,
+        &[
+            (0x5555, 0xAA),
+            (0x2AAA, 0x55),
+            (0x5555, 0x80),
+            (0x5555, 0xAA),
+            (0x2AAA, 0x55),
+        ],
+    );
+}
+
+/// Unique path for an image file in the temporary directory
+fn image_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("flash_test_{}_{}.bin", std::process::id(), name))
+}
+
+// Test flash creation
+#[test]
+fn test_flash_creation() {
+    let mut flash = create_flash();
+    assert_eq!(flash.size(), 0x20000);
+    assert_eq!(flash.chip(), FlashChip::Sst39sf010);
+    assert_eq!(flash.read(0x8000).unwrap(), 0xFF);
+    assert!(!flash.is_busy());
+
+    assert_eq!(FlashChip::Sst39sf020.size(), 0x40000);
+    assert_eq!(FlashChip::Sst39sf040.size(), 0x80000);
+    assert!(Flash::new(FlashChip::Sst39sf010, 0x8001, 0x8000).is_err());
+    assert!(Flash::new(FlashChip::Sst39sf010, 0x8000, 0).is_err());
+}
+
+#[test]
+fn test_flash_out_of_range() {
+    let mut flash = Flash::new(FlashChip::Sst39sf010, 0x8000, 0x4000).unwrap();
+    assert!(matches!(
+        flash.read(0xC000),
+        Err(BusError::AddressOutOfRange(0xC000))
+    ));
+}
+
+// Test banking
+#[test]
+fn test_bank_switching() {
+    let mut flash = Flash::new(FlashChip::Sst39sf010, 0x8000, 0x4000).unwrap();
+    flash.import(&[0x11], 0x0000).unwrap();
+    flash.import(&[0x22], 0x4000).unwrap();
+    flash.import(&[0x88], 0x1C000).unwrap();
+
+    assert_eq!(flash.read(0x8000).unwrap(), 0x11);
+    flash.set_bank(1);
+    assert_eq!(flash.read(0x8000).unwrap(), 0x22);
+    flash.set_bank(7);
+    assert_eq!(flash.read(0x8000).unwrap(), 0x88);
+    flash.set_bank(9);
+    assert_eq!(flash.bank(), 1);
+}
+
+#[test]
+fn test_load_bypasses_command_set() {
+    let mut flash = create_flash();
+    flash.set_bank(2);
+    flash.load(0x8010, 0x42).unwrap();
+    assert_eq!(flash.export(0x10010, 1), vec![0x42]);
+    assert!(!flash.is_busy());
+}
+
+// Test byte program
+#[test]
+fn test_plain_writes_are_ignored() {
+    let mut flash = create_flash();
+    flash.write(0x8000, 0x00).unwrap();
+    assert_eq!(flash.read(0x8000).unwrap(), 0xFF);
+    assert!(!flash.is_busy());
+}
+
+#[test]
+fn test_byte_program() {
+    let mut flash = create_flash();
+    program(&mut flash, 0x8123, 0x5A);
+    assert_eq!(flash.read(0x8123).unwrap(), 0x5A);
+    // Programming can only clear bits
+    program(&mut flash, 0x8123, 0xF0);
+    assert_eq!(flash.read(0x8123).unwrap(), 0x50);
+}
+
+#[test]
+fn test_program_status_polling() {
+    let mut flash = create_flash();
+    command(
+        &mut flash,
+        &[(0x5555, 0xAA), (0x2AAA, 0x55), (0x5555, 0xA0)],
+    );
+    flash.write(0x8000, 0x80).unwrap();
+    assert!(flash.is_busy());
+
+    let first = flash.read(0x8000).unwrap();
+    let second = flash.read(0x8000).unwrap();
+    assert_eq!(first & 0x80, 0x00);
+    assert_ne!(first & 0x40, second & 0x40);
+
+    flash.advance(DEFAULT_PROGRAM_TICKS - 1);
+    assert!(flash.is_busy());
+    flash.tick();
+    assert!(!flash.is_busy());
+    assert_eq!(flash.read(0x8000).unwrap(), 0x80);
+}
+
+#[test]
+fn test_broken_sequence_resets() {
+    let mut flash = create_flash();
+    command(
+        &mut flash,
+        &[
+            (0x5555, 0xAA),
+            (0x2AAB, 0x55),
+            (0x5555, 0xA0),
+            (0x0000, 0x00),
+        ],
+    );
+    assert_eq!(flash.read(0x8000).unwrap(), 0xFF);
+}
+
+// Test erase
+#[test]
+fn test_sector_erase() {
+    let mut flash = create_flash();
+    flash.import(&[0x00; 0x3000], 0).unwrap();
+    erase_setup(&mut flash);
+    flash.write(0x9234, 0x30).unwrap();
+
+    // DQ7 reads 0 until the erase completes
+    assert_eq!(flash.read(0x9234).unwrap() & 0x80, 0x00);
+    flash.advance(DEFAULT_SECTOR_ERASE_TICKS);
+    assert!(!flash.is_busy());
+    assert_eq!(flash.export(0x0FFF, 1), vec![0x00]);
+    assert_eq!(flash.export(0x1000, SECTOR_SIZE), vec![0xFF; SECTOR_SIZE]);
+    assert_eq!(flash.export(0x2000, 1), vec![0x00]);
+}
+
+#[test]
+fn test_chip_erase() {
+    let mut flash = create_flash();
+    flash.import(&[0x00; 0x20000], 0).unwrap();
+    erase_setup(&mut flash);
+    command(&mut flash, &[(0x5555, 0x10)]);
+    assert!(flash.is_busy());
+    flash.advance(DEFAULT_CHIP_ERASE_TICKS);
+    assert_eq!(flash.export(0, 0x20000), vec![0xFF; 0x20000]);
+}
+
+#[test]
+fn test_commands_ignored_while_busy() {
+    let mut flash = create_flash();
+    erase_setup(&mut flash);
+    command(&mut flash, &[(0x5555, 0x10)]);
+    program(&mut flash, 0x8000, 0x00);
+    flash.advance(DEFAULT_CHIP_ERASE_TICKS);
+    assert_eq!(flash.read(0x8000).unwrap(), 0xFF);
+}
+
+// Test software ID
+#[test]
+fn test_software_id() {
+    let mut flash = Flash::new(FlashChip::Sst39sf040, 0x8000, 0x8000).unwrap();
+    command(
+        &mut flash,
+        &[(0x5555, 0xAA), (0x2AAA, 0x55), (0x5555, 0x90)],
+    );
+    assert_eq!(flash.read(0x8000).unwrap(), MANUFACTURER_ID);
+    assert_eq!(flash.read(0x8001).unwrap(), 0xB7);
+
+    command(
+        &mut flash,
+        &[(0x5555, 0xAA), (0x2AAA, 0x55), (0x5555, 0xF0)],
+    );
+    assert_eq!(flash.read(0x8000).unwrap(), 0xFF);
+
+    // A single $F0 also exits
+    command(
+        &mut flash,
+        &[(0x5555, 0xAA), (0x2AAA, 0x55), (0x5555, 0x90)],
+    );
+    command(&mut flash, &[(0x1234, 0xF0)]);
+    assert_eq!(flash.read(0x8001).unwrap(), 0xFF);
+}
+
+// Test image persistence
+#[test]
+fn test_image_persistence() {
+    let path = image_path("persist");
+    let _ = fs::remove_file(&path);
+    {
+        let mut flash = create_flash().with_image_file(&path).unwrap();
+        program(&mut flash, 0x8000, 0x12);
+    }
+    assert_eq!(fs::read(&path).unwrap().len(), 0x20000);
+
+    let mut flash = create_flash().with_image_file(&path).unwrap();
+    assert_eq!(flash.read(0x8000).unwrap(), 0x12);
+    program(&mut flash, 0x8001, 0x34);
+    flash.save().unwrap();
+    assert_eq!(&fs::read(&path).unwrap()[..2], &[0x12, 0x34]);
+    drop(flash);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_image_size_mismatch() {
+    let path = image_path("mismatch");
+    fs::write(&path, [0u8; 0x100]).unwrap();
+    let result = create_flash().with_image_file(&path);
+    fs::remove_file(&path).unwrap();
+    assert!(result.is_err());
+}
@@ -0,0 +1,583 @@
+//! Hitachi HD44780 character LCD controller.
+//!
+//! The controller has two registers, selected by the RS line:
+//! - RS = 0: instruction register on write, busy flag (bit 7) and address counter on read
+//! - RS = 1: data register, reading or writing DDRAM or CGRAM at the address counter
+//!
+//! On the bus, address line A0 drives RS. When the controller sits behind port pins,
+//! for example a 6522 VIA, the host forwards the pin levels with `Lcd::set_pins`.
+//! In the 4-bit interface every byte is transferred as two nibbles on D7-D4, high
+//! nibble first.
+//!
+//! Instructions keep the controller busy for the execution times given in the
+//! datasheet for a 270 kHz oscillator, converted to ticks with the CPU clock.
+//! Instructions and data written while the controller is busy are ignored, as
+//! they would be by the real chip.
+
+use bus::errors::BusError;
+use bus::trait_bus_device::BusDevice;
+
+/// Number of registers exposed on the bus
+pub const LCD_REGISTER_COUNT: u16 = 2;
+/// CPU clock assumed when converting execution times to ticks, in Hz
+pub const DEFAULT_CPU_CLOCK: u32 = 1_000_000;
+
+/// Execution time of clear display and return home, in microseconds
+const LONG_INSTRUCTION_US: u64 = 1520;
+/// Execution time of every other instruction and of data accesses, in microseconds
+const INSTRUCTION_US: u64 = 37;
+
+const INSTRUCTION_CLEAR: u8 = 0x01;
+const INSTRUCTION_HOME: u8 = 0x02;
+const INSTRUCTION_ENTRY_MODE: u8 = 0x04;
+const INSTRUCTION_DISPLAY_CONTROL: u8 = 0x08;
+const INSTRUCTION_SHIFT: u8 = 0x10;
+const INSTRUCTION_FUNCTION_SET: u8 = 0x20;
+const INSTRUCTION_CGRAM_ADDRESS: u8 = 0x40;
+const INSTRUCTION_DDRAM_ADDRESS: u8 = 0x80;
+
+/// Busy flag in the status read
+const STATUS_BUSY: u8 = 0x80;
+
+/// Characters per line of DDRAM in the 2-line display mode
+const LINE_LENGTH: u8 = 40;
+/// Characters of DDRAM
+const DDRAM_SIZE: u8 = 80;
+/// Bytes of CGRAM, eight 5x8 glyphs
+const CGRAM_SIZE: u8 = 64;
+
+/// RAM addressed by the address counter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Ddram,
+    Cgram,
+}
+
+/// Represents an HD44780 controller with its LCD panel.
+#[derive(Debug)]
+pub struct Lcd {
+    /// Start address of the register window
+    start_address: u16,
+    columns: u8,
+    rows: u8,
+    /// Ticks per microsecond, scaled by 1000 to allow clocks below 1 MHz
+    ticks_per_ms: u64,
+    /// Display data RAM, indexed by DDRAM address
+    ddram: [u8; 0x80],
+    /// Character generator RAM, eight glyphs of eight rows
+    cgram: [u8; CGRAM_SIZE as usize],
+    address: u8,
+    target: Target,
+    /// Entry mode: the address counter increments rather than decrements
+    increment: bool,
+    /// Entry mode: the display shifts on every DDRAM write
+    entry_shift: bool,
+    display_on: bool,
+    cursor_on: bool,
+    blink_on: bool,
+    /// Function set: 8-bit rather than 4-bit interface
+    eight_bit: bool,
+    /// Function set: 2-line rather than 1-line display mode
+    two_lines: bool,
+    /// Function set: 5x10 rather than 5x8 font
+    large_font: bool,
+    /// Characters the display is shifted left by
+    shift: u8,
+    /// Ticks until the current instruction completes
+    busy_ticks: u64,
+    /// High nibble of a 4-bit write waiting for its low nibble
+    write_nibble: Option<u8>,
+    /// Low nibble of a 4-bit read waiting to be transferred
+    read_nibble: Option<u8>,
+    /// Level of the E pin in the pin interface
+    enable: bool,
+    /// Value driven onto D7-D0 during a read through the pin interface
+    pin_output: Option<u8>,
+}
+
+impl Lcd {
+    /// Create a new LCD with its registers at the specified start address.
+    ///
+    /// The controller starts in the state of its internal reset: 8-bit interface,
+    /// 1-line display mode, display off and DDRAM filled with spaces.
+    ///
+    /// # Arguments
+    /// * `start_address` - Start address of the register window
+    /// * `columns` - Visible characters per row
+    /// * `rows` - Visible rows, 1, 2 or 4
+    ///
+    /// # Returns
+    /// * `Ok(Lcd)` if the panel size is supported
+    /// * `Err(String)` otherwise
+    ///
+    /// # Errors
+    /// * If `rows` is not 1, 2 or 4
+    /// * If the rows need more than the 80 characters of DDRAM
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let lcd = Lcd::new(0x7000, 16, 2)?;
+    /// bus.register_device(0x7000, 0x7001, Box::new(lcd))?;
+    /// ```
+    pub fn new(start_address: u16, columns: u8, rows: u8) -> Result<Self, String> {
+        let max_columns = match rows {
+            1 | 2 => LINE_LENGTH,
+            4 => LINE_LENGTH / 2,
+            _ => return Err(format!("Unsupported LCD with {} rows", rows)),
+        };
+        if columns == 0 || columns > max_columns {
+            return Err(format!(
+                "Unsupported LCD with {} columns and {} rows",
+                columns, rows
+            ));
+        }
+        Ok(Self {
+            start_address,
+            columns,
+            rows,
+            ticks_per_ms: DEFAULT_CPU_CLOCK as u64 / 1000,
+            ddram: [b' '; 0x80],
+            cgram: [0; CGRAM_SIZE as usize],
+            address: 0,
+            target: Target::Ddram,
+            increment: true,
+            entry_shift: false,
+            display_on: false,
+            cursor_on: false,
+            blink_on: false,
+            eight_bit: true,
+            two_lines: false,
+            large_font: false,
+            shift: 0,
+            busy_ticks: 0,
+            write_nibble: None,
+            read_nibble: None,
+            enable: false,
+            pin_output: None,
+        })
+    }
+
+    /// Set the CPU clock used to convert execution times to ticks.
+    ///
+    /// # Arguments
+    /// * `hz` - Frequency of the clock driving `tick`, in Hz
+    ///
+    /// # Returns
+    /// * The Lcd with the new clock
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let lcd = Lcd::new(0x7000, 20, 4)?.with_cpu_clock(2_000_000);
+    /// ```
+    pub fn with_cpu_clock(mut self, hz: u32) -> Self {
+        self.ticks_per_ms = (hz as u64 / 1000).max(1);
+        self
+    }
+
+    /// Drive the RS, R/W, E and D7-D0 pins, for a controller behind port pins.
+    ///
+    /// A write is performed on the falling edge of E and a read on the rising edge,
+    /// after which the data is available from `data_pins` while E stays high.
+    /// In the 4-bit interface only D7-D4 are used.
+    ///
+    /// # Arguments
+    /// * `rs` - Register select, `true` for the data register
+    /// * `read` - Level of R/W, `true` for a read
+    /// * `enable` - Level of E
+    /// * `data` - Levels of D7-D0
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// // VIA port B on D7-D0, port A bits 5-7 on RS, R/W and E
+    /// let control = via.port_a_output();
+    /// lcd.set_pins(control & 0x20 != 0, control & 0x40 != 0, control & 0x80 != 0, via.port_b_output());
+    /// ```
+    pub fn set_pins(&mut self, rs: bool, read: bool, enable: bool, data: u8) {
+        let rising = enable && !self.enable;
+        let falling = !enable && self.enable;
+        self.enable = enable;
+        if falling {
+            self.pin_output = None;
+            if !read {
+                self.write_register(rs, data);
+            }
+        } else if rising && read {
+            self.pin_output = Some(self.read_register(rs));
+        }
+    }
+
+    /// Value the controller drives onto D7-D0 during a read through the pin interface
+    ///
+    /// # Returns
+    /// * `Some(value)` while E is high after the rising edge of a read
+    /// * `None` while the data pins are not driven
+    pub fn data_pins(&self) -> Option<u8> {
+        self.pin_output
+    }
+
+    /// Render the visible characters, one line per row.
+    ///
+    /// Characters `$20`-`$7D` are the ASCII characters of the A00 character ROM,
+    /// except `$5C` which is a yen sign. `$7E` and `$7F` are arrows. CGRAM glyphs
+    /// `$00`-`$07`, and their mirrors `$08`-`$0F`, are rendered as the control
+    /// characters `\u{0}`-`\u{7}`, see `glyph` for their bitmaps. Other codes are
+    /// rendered as `\u{FFFD}`. Rows are blank while the display is off, and rows
+    /// on the second line are blank in the 1-line display mode.
+    ///
+    /// # Returns
+    /// * The rows, each exactly as wide as the panel, separated by `\n`
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// assert_eq!(lcd.text(), "Hello, World!   \n                ");
+    /// ```
+    pub fn text(&self) -> String {
+        let rows: Vec<String> = (0..self.rows)
+            .map(|row| {
+                (0..self.columns)
+                    .map(|column| match self.visible_address(row, column) {
+                        Some(address) => character(self.ddram[address as usize]),
+                        None => ' ',
+                    })
+                    .collect()
+            })
+            .collect();
+        rows.join("\n")
+    }
+
+    /// Bitmap of a CGRAM glyph.
+    ///
+    /// # Arguments
+    /// * `code` - Character code `$00`-`$0F`, codes `$08`-`$0F` mirror `$00`-`$07`
+    ///
+    /// # Returns
+    /// * `Some(rows)` with the 5 pixels of each row in bits 4-0
+    /// * `None` if the code is not a CGRAM character
+    pub fn glyph(&self, code: u8) -> Option<[u8; 8]> {
+        if code > 0x0F {
+            return None;
+        }
+        let start = (code as usize & 0x07) * 8;
+        let mut rows = [0; 8];
+        rows.copy_from_slice(&self.cgram[start..start + 8]);
+        Some(rows)
+    }
+
+    /// Render a CGRAM glyph as text, `#` for lit pixels and `.` for dark ones.
+    ///
+    /// # Arguments
+    /// * `code` - Character code `$00`-`$0F`
+    ///
+    /// # Returns
+    /// * `Some(text)` with eight rows of five pixels separated by `\n`
+    /// * `None` if the code is not a CGRAM character
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// assert_eq!(lcd.glyph_text(0).unwrap().lines().next(), Some("..#.."));
+    /// ```
+    pub fn glyph_text(&self, code: u8) -> Option<String> {
+        let rows = self.glyph(code)?;
+        let lines: Vec<String> = rows
+            .iter()
+            .map(|row| {
+                (0..5)
+                    .rev()
+                    .map(|bit| if row >> bit & 1 != 0 { '#' } else { '.' })
+                    .collect()
+            })
+            .collect();
+        Some(lines.join("\n"))
+    }
+
+    /// Position of the cursor as (row, column), if it is on a visible character
+    pub fn cursor(&self) -> Option<(u8, u8)> {
+        if self.target != Target::Ddram {
+            return None;
+        }
+        (0..self.rows)
+            .flat_map(|row| (0..self.columns).map(move |column| (row, column)))
+            .find(|&(row, column)| self.visible_address(row, column) == Some(self.address))
+    }
+
+    /// Value of the address counter
+    pub fn address_counter(&self) -> u8 {
+        self.address
+    }
+
+    /// The display is switched on
+    pub fn is_display_on(&self) -> bool {
+        self.display_on
+    }
+
+    /// The cursor underline is switched on
+    pub fn is_cursor_on(&self) -> bool {
+        self.cursor_on
+    }
+
+    /// The cursor position blinks
+    pub fn is_blink_on(&self) -> bool {
+        self.blink_on
+    }
+
+    /// The controller uses the 8-bit interface
+    pub fn is_eight_bit(&self) -> bool {
+        self.eight_bit
+    }
+
+    /// The controller is in the 2-line display mode
+    pub fn is_two_lines(&self) -> bool {
+        self.two_lines
+    }
+
+    /// The controller is in the 5x10 font mode
+    pub fn is_large_font(&self) -> bool {
+        self.large_font
+    }
+
+    /// The controller is executing an instruction
+    pub fn is_busy(&self) -> bool {
+        self.busy_ticks > 0
+    }
+
+    /// DDRAM address shown at a row and column, if the row is lit
+    fn visible_address(&self, row: u8, column: u8) -> Option<u8> {
+        if !self.display_on {
+            return None;
+        }
+        // Rows 3 and 4 continue the two lines of DDRAM after the first rows
+        let start = match row {
+            0 => 0x00,
+            1 => 0x40,
+            2 => self.columns,
+            _ => 0x40 + self.columns,
+        };
+        if self.two_lines {
+            let position = (start & 0x3F) + column + self.shift;
+            Some((start & 0x40) + position % LINE_LENGTH)
+        } else if start < 0x40 {
+            Some((start + column + self.shift) % DDRAM_SIZE)
+        } else {
+            None
+        }
+    }
+
+    /// Move the address counter by one in the entry mode direction, or the opposite one
+    fn step_address(&mut self, forward: bool) {
+        self.address = match self.target {
+            Target::Cgram => {
+                let address = if forward {
+                    self.address.wrapping_add(1)
+                } else {
+                    self.address.wrapping_sub(1)
+                };
+                address & (CGRAM_SIZE - 1)
+            }
+            Target::Ddram => self.step_ddram_address(forward),
+        };
+    }
+
+    /// Next DDRAM address in either direction, wrapping between the lines
+    fn step_ddram_address(&self, forward: bool) -> u8 {
+        let (line_start, line_length) = if self.two_lines {
+            (self.address & 0x40, LINE_LENGTH)
+        } else {
+            (0, DDRAM_SIZE)
+        };
+        let offset = (self.address - line_start).min(line_length - 1);
+        match (forward, self.two_lines) {
+            (true, _) if offset + 1 < line_length => self.address + 1,
+            (false, _) if offset > 0 => self.address - 1,
+            // The second line follows the first one and wraps back to it
+            (true, true) => line_start ^ 0x40,
+            (false, true) => (line_start ^ 0x40) + line_length - 1,
+            (true, false) => 0,
+            (false, false) => line_length - 1,
+        }
+    }
+
+    /// Shift the display by one character, left or right
+    fn shift_display(&mut self, left: bool) {
+        let length = if self.two_lines {
+            LINE_LENGTH
+        } else {
+            DDRAM_SIZE
+        };
+        self.shift = if left {
+            (self.shift + 1) % length
+        } else {
+            (self.shift + length - 1) % length
+        };
+    }
+
+    /// Start executing an instruction, keeping the controller busy for its duration
+    fn set_busy(&mut self, microseconds: u64) {
+        self.busy_ticks = (microseconds * self.ticks_per_ms).div_ceil(1000);
+    }
+
+    /// Write an 8-bit value or 4-bit nibble to the instruction or data register
+    fn write_register(&mut self, rs: bool, data: u8) {
+        self.read_nibble = None;
+        if self.eight_bit {
+            self.write_byte(rs, data);
+            return;
+        }
+        match self.write_nibble.take() {
+            None => self.write_nibble = Some(data & 0xF0),
+            Some(high) => self.write_byte(rs, high | data >> 4),
+        }
+    }
+
+    /// Read an 8-bit value or 4-bit nibble from the status or data register
+    fn read_register(&mut self, rs: bool) -> u8 {
+        self.write_nibble = None;
+        if self.eight_bit {
+            return self.read_byte(rs);
+        }
+        match self.read_nibble.take() {
+            Some(low) => low,
+            None => {
+                let value = self.read_byte(rs);
+                self.read_nibble = Some(value << 4);
+                value & 0xF0
+            }
+        }
+    }
+
+    /// Execute a complete write to the instruction or data register
+    fn write_byte(&mut self, rs: bool, data: u8) {
+        if self.is_busy() {
+            return;
+        }
+        if rs {
+            self.write_data(data);
+        } else {
+            self.execute(data);
+        }
+    }
+
+    /// Execute a complete read from the status or data register
+    fn read_byte(&mut self, rs: bool) -> u8 {
+        if !rs {
+            let busy = if self.is_busy() { STATUS_BUSY } else { 0 };
+            return busy | (self.address & 0x7F);
+        }
+        let value = match self.target {
+            Target::Ddram => self.ddram[self.address as usize],
+            Target::Cgram => self.cgram[self.address as usize],
+        };
+        self.step_address(self.increment);
+        self.set_busy(INSTRUCTION_US);
+        value
+    }
+
+    /// Store a character in DDRAM or a glyph row in CGRAM
+    fn write_data(&mut self, data: u8) {
+        match self.target {
+            Target::Ddram => {
+                self.ddram[self.address as usize] = data;
+                if self.entry_shift {
+                    self.shift_display(self.increment);
+                }
+            }
+            Target::Cgram => self.cgram[self.address as usize] = data & 0x1F,
+        }
+        self.step_address(self.increment);
+        self.set_busy(INSTRUCTION_US);
+    }
+
+    /// Execute an instruction, decoded by its highest set bit
+    fn execute(&mut self, instruction: u8) {
+        if instruction & INSTRUCTION_DDRAM_ADDRESS != 0 {
+            self.target = Target::Ddram;
+            self.address = instruction & 0x7F;
+        } else if instruction & INSTRUCTION_CGRAM_ADDRESS != 0 {
+            self.target = Target::Cgram;
+            self.address = instruction & 0x3F;
+        } else if instruction & INSTRUCTION_FUNCTION_SET != 0 {
+            self.eight_bit = instruction & 0x10 != 0;
+            self.two_lines = instruction & 0x08 != 0;
+            self.large_font = instruction & 0x04 != 0;
+            self.write_nibble = None;
+            self.read_nibble = None;
+        } else if instruction & INSTRUCTION_SHIFT != 0 {
+            let right = instruction & 0x04 != 0;
+            if instruction & 0x08 != 0 {
+                self.shift_display(!right);
+            } else {
+                self.step_address(right);
+            }
+        } else if instruction & INSTRUCTION_DISPLAY_CONTROL != 0 {
+            self.display_on = instruction & 0x04 != 0;
+            self.cursor_on = instruction & 0x02 != 0;
+            self.blink_on = instruction & 0x01 != 0;
+        } else if instruction & INSTRUCTION_ENTRY_MODE != 0 {
+            self.increment = instruction & 0x02 != 0;
+            self.entry_shift = instruction & 0x01 != 0;
+        } else if instruction & INSTRUCTION_HOME != 0 {
+            self.target = Target::Ddram;
+            self.address = 0;
+            self.shift = 0;
+            self.set_busy(LONG_INSTRUCTION_US);
+            return;
+        } else if instruction & INSTRUCTION_CLEAR != 0 {
+            self.ddram = [b' '; 0x80];
+            self.target = Target::Ddram;
+            self.address = 0;
+            self.shift = 0;
+            self.increment = true;
+            self.set_busy(LONG_INSTRUCTION_US);
+            return;
+        }
+        self.set_busy(INSTRUCTION_US);
+    }
+}
+
+/// Character of the A00 character ROM shown for a character code
+fn character(code: u8) -> char {
+    match code {
+        0x00..=0x0F => char::from(code & 0x07),
+        0x5C => '¥',
+        0x7E => '→',
+        0x7F => '←',
+        0x20..=0x7D => char::from(code),
+        _ => '\u{FFFD}',
+    }
+}
+
+impl BusDevice for Lcd {
+    fn read(&mut self, address: u16) -> Result<u8, BusError> {
+        let rs = address.wrapping_sub(self.start_address) & 0x01 != 0;
+        Ok(self.read_register(rs))
+    }
+
+    fn write(&mut self, address: u16, data: u8) -> Result<(), BusError> {
+        let rs = address.wrapping_sub(self.start_address) & 0x01 != 0;
+        self.write_register(rs, data);
+        Ok(())
+    }
+
+    fn tick(&mut self) {
+        self.busy_ticks = self.busy_ticks.saturating_sub(1);
+    }
+
+    fn check_irq(&self) -> bool {
+        // The LCD controller has no interrupt output
+        false
+    }
+
+    fn check_nmi(&self) -> bool {
+        // The LCD controller has no interrupt output
+        false
+    }
+
+    fn check_rdy(&self) -> bool {
+        // The busy flag is polled by software, the LCD does not insert wait states
+        false
+    }
+
+    fn check_so(&self) -> bool {
+        // The LCD controller does not drive the SO line
+        false
+    }
+}
@@ -0,0 +1,319 @@
+//! Unit tests for the HD44780 LCD implementation
+//!
+//! This module tests the instruction set, the busy flag timing, the 4- and 8-bit
+//! interfaces on the bus and on port pins, and the text and glyph snapshots.
+
+use bus::BusController;
+use bus::trait_bus_device::BusDevice;
+use cpu6502::cpu::Cpu;
+use hd44780::Lcd;
+use ram::{Ram, ram_size::RamSize};
+
+/// Register addresses
+const INSTRUCTION: u16 = 0x7000;
+const DATA: u16 = 0x7001;
+
+/// Tick the LCD until the busy flag clears
+fn wait(lcd: &mut Lcd) {
+    while lcd.is_busy() {
+        lcd.tick();
+    }
+}
+
+/// Write instructions, waiting for each one to complete
+fn command(lcd: &mut Lcd, instructions: &[u8]) {
+    for instruction in instructions {
+        lcd.write(INSTRUCTION, *instruction).unwrap();
+        wait(lcd);
+    }
+}
+
+/// Write characters at the address counter, waiting for each one
+fn print(lcd: &mut Lcd, text: &[u8]) {
+    for byte in text {
+        lcd.write(DATA, *byte).unwrap();
+        wait(lcd);
+    }
+}
+
+/// Create an LCD initialised in 8-bit, 2-line mode with the display on
+fn lcd(columns: u8, rows: u8) -> Lcd {
+    let mut lcd = Lcd::new(INSTRUCTION, columns, rows).unwrap();
+    command(&mut lcd, &[0x38, 0x0C, 0x06, 0x01]);
+    lcd
+}
+
+// Test construction
+#[test]
+fn test_unsupported_sizes() {
+    assert!(Lcd::new(INSTRUCTION, 16, 3).is_err());
+    assert!(Lcd::new(INSTRUCTION, 24, 4).is_err());
+    assert!(Lcd::new(INSTRUCTION, 0, 2).is_err());
+    assert!(Lcd::new(INSTRUCTION, 40, 2).is_ok());
+}
+
+#[test]
+fn test_power_on_state() {
+    let mut lcd = Lcd::new(INSTRUCTION, 16, 2).unwrap();
+    assert!(lcd.is_eight_bit());
+    assert!(!lcd.is_two_lines());
+    assert!(!lcd.is_display_on());
+    print(&mut lcd, b"Hi");
+    // Nothing is shown until the display is switched on
+    assert_eq!(lcd.text(), format!("{:16}\n{:16}", "", ""));
+    command(&mut lcd, &[0x0C]);
+    // The second row stays blank in the 1-line display mode
+    assert_eq!(lcd.text(), format!("{:16}\n{:16}", "Hi", ""));
+}
+
+// Test instructions
+#[test]
+fn test_text_snapshot() {
+    let mut lcd = lcd(16, 2);
+    print(&mut lcd, b"Hello, World!");
+    command(&mut lcd, &[0xC0]);
+    print(&mut lcd, b"6502 \\o/ ~");
+    assert_eq!(lcd.text(), "Hello, World!   \n6502 ¥o/ →      ");
+    assert_eq!(lcd.address_counter(), 0x4A);
+    assert_eq!(lcd.cursor(), Some((1, 10)));
+}
+
+#[test]
+fn test_four_line_layout() {
+    let mut lcd = lcd(20, 4);
+    // DDRAM is filled in address order, the first line continues on row 3
+    print(&mut lcd, &[b'a'; 20]);
+    print(&mut lcd, &[b'c'; 20]);
+    print(&mut lcd, &[b'b'; 20]);
+    print(&mut lcd, &[b'd'; 20]);
+    let rows: Vec<String> = ["a", "b", "c", "d"].iter().map(|c| c.repeat(20)).collect();
+    assert_eq!(lcd.text(), rows.join("\n"));
+    // The address counter wraps from the end of the second line to the first
+    assert_eq!(lcd.address_counter(), 0x00);
+}
+
+#[test]
+fn test_entry_mode_decrement() {
+    let mut lcd = lcd(16, 2);
+    command(&mut lcd, &[0x04, 0x85]);
+    print(&mut lcd, b"abc");
+    assert_eq!(lcd.text().lines().next(), Some("   cba          "));
+    assert_eq!(lcd.address_counter(), 0x02);
+    // Decrementing past the first line wraps to the end of the second one
+    command(&mut lcd, &[0x80]);
+    print(&mut lcd, b"x");
+    assert_eq!(lcd.address_counter(), 0x67);
+}
+
+#[test]
+fn test_display_shift() {
+    let mut lcd = lcd(16, 2);
+    print(&mut lcd, b"0123456789");
+    command(&mut lcd, &[0x18, 0x18]);
+    assert_eq!(lcd.text().lines().next(), Some("23456789        "));
+    command(&mut lcd, &[0x1C, 0x1C, 0x1C]);
+    assert_eq!(lcd.text().lines().next(), Some(" 0123456789     "));
+
+    // Cursor shifts move the address counter without changing the display
+    command(&mut lcd, &[0x10, 0x10]);
+    assert_eq!(lcd.address_counter(), 0x08);
+    command(&mut lcd, &[0x14]);
+    assert_eq!(lcd.address_counter(), 0x09);
+
+    // Return home undoes the shift
+    command(&mut lcd, &[0x02]);
+    assert_eq!(lcd.text().lines().next(), Some("0123456789      "));
+    assert_eq!(lcd.address_counter(), 0x00);
+}
+
+#[test]
+fn test_shift_on_entry() {
+    let mut lcd = lcd(16, 2);
+    command(&mut lcd, &[0x07, 0x90]);
+    print(&mut lcd, b"ABC");
+    // The text scrolls in from the right edge
+    assert_eq!(lcd.text().lines().next(), Some("             ABC"));
+}
+
+#[test]
+fn test_display_control() {
+    let mut lcd = lcd(16, 2);
+    command(&mut lcd, &[0x0F]);
+    assert!(lcd.is_display_on());
+    assert!(lcd.is_cursor_on());
+    assert!(lcd.is_blink_on());
+    command(&mut lcd, &[0x08]);
+    assert!(!lcd.is_display_on());
+    assert!(!lcd.is_cursor_on());
+}
+
+#[test]
+fn test_custom_glyph() {
+    let mut lcd = lcd(16, 2);
+    let bell = [0x04, 0x0E, 0x0E, 0x0E, 0x1F, 0x00, 0x04, 0xFF];
+    command(&mut lcd, &[0x48]);
+    print(&mut lcd, &bell);
+    command(&mut lcd, &[0x80]);
+    print(&mut lcd, b"Ring \x01 \x09");
+    assert_eq!(lcd.text().lines().next(), Some("Ring \u{1} \u{1}        "));
+    assert_eq!(lcd.glyph(0x09), lcd.glyph(0x01));
+    assert_eq!(lcd.glyph(0x10), None);
+    assert_eq!(
+        lcd.glyph_text(1).unwrap(),
+        "..#..\n.###.\n.###.\n.###.\n#####\n.....\n..#..\n#####"
+    );
+}
+
+#[test]
+fn test_read_data_and_status() {
+    let mut lcd = lcd(16, 2);
+    print(&mut lcd, b"AB");
+    command(&mut lcd, &[0x80]);
+    assert_eq!(lcd.read(DATA).unwrap(), b'A');
+    wait(&mut lcd);
+    assert_eq!(lcd.read(DATA).unwrap(), b'B');
+    wait(&mut lcd);
+    assert_eq!(lcd.read(INSTRUCTION).unwrap(), 0x02);
+
+    // Reading CGRAM returns the five pixel bits
+    command(&mut lcd, &[0x40]);
+    print(&mut lcd, &[0xFF]);
+    command(&mut lcd, &[0x40]);
+    assert_eq!(lcd.read(DATA).unwrap(), 0x1F);
+}
+
+// Test busy flag timing
+#[test]
+fn test_busy_flag_timing() {
+    let mut lcd = lcd(16, 2);
+    lcd.write(INSTRUCTION, 0x01).unwrap();
+    assert_eq!(lcd.read(INSTRUCTION).unwrap(), 0x80);
+    for _ in 0..1519 {
+        lcd.tick();
+    }
+    assert!(lcd.is_busy());
+    lcd.tick();
+    assert_eq!(lcd.read(INSTRUCTION).unwrap(), 0x00);
+
+    lcd.write(DATA, b'X').unwrap();
+    for _ in 0..36 {
+        lcd.tick();
+    }
+    assert!(lcd.is_busy());
+    // Writes while busy are ignored
+    lcd.write(DATA, b'Y').unwrap();
+    lcd.tick();
+    assert!(!lcd.is_busy());
+    assert_eq!(lcd.text().lines().next(), Some("X               "));
+}
+
+#[test]
+fn test_cpu_clock() {
+    let mut lcd = Lcd::new(INSTRUCTION, 16, 2)
+        .unwrap()
+        .with_cpu_clock(2_000_000);
+    lcd.write(INSTRUCTION, 0x38).unwrap();
+    for _ in 0..73 {
+        lcd.tick();
+    }
+    assert!(lcd.is_busy());
+    lcd.tick();
+    assert!(!lcd.is_busy());
+}
+
+// Test the 4-bit interface
+#[test]
+fn test_four_bit_bus_interface() {
+    let mut lcd = Lcd::new(INSTRUCTION, 16, 2).unwrap();
+    // Initialisation by instruction, then switch to 4 bits
+    command(&mut lcd, &[0x30, 0x30, 0x30, 0x20]);
+    assert!(!lcd.is_eight_bit());
+    // Function set 2 lines, display on, clear, sent as nibbles on D7-D4
+    command(&mut lcd, &[0x20, 0x80, 0x00, 0xC0, 0x00, 0x10]);
+    assert!(lcd.is_two_lines());
+    assert!(lcd.is_display_on());
+    lcd.write(DATA, 0x40).unwrap();
+    lcd.write(DATA, 0x20).unwrap();
+    wait(&mut lcd);
+    assert_eq!(lcd.text().lines().next(), Some("B               "));
+
+    // Reads are transferred high nibble first
+    assert_eq!(lcd.read(INSTRUCTION).unwrap(), 0x00);
+    assert_eq!(lcd.read(INSTRUCTION).unwrap(), 0x10);
+}
+
+#[test]
+fn test_pin_interface() {
+    let mut lcd = Lcd::new(INSTRUCTION, 16, 2).unwrap();
+    let strobe = |lcd: &mut Lcd, rs: bool, data: u8| {
+        lcd.set_pins(rs, false, true, data);
+        lcd.set_pins(rs, false, false, data);
+        wait(lcd);
+    };
+    // The write happens on the falling edge of E, switching to 4 bits
+    strobe(&mut lcd, false, 0x28);
+    assert!(!lcd.is_eight_bit());
+    strobe(&mut lcd, false, 0x20);
+    strobe(&mut lcd, false, 0x80);
+    strobe(&mut lcd, false, 0x00);
+    strobe(&mut lcd, false, 0xC0);
+    strobe(&mut lcd, true, 0x30);
+    strobe(&mut lcd, true, 0x70);
+    assert_eq!(lcd.text().lines().next(), Some("7               "));
+
+    // A read drives the data pins while E is high
+    lcd.set_pins(false, true, true, 0xFF);
+    assert_eq!(lcd.data_pins(), Some(0x00));
+    lcd.set_pins(false, true, false, 0xFF);
+    assert_eq!(lcd.data_pins(), None);
+    lcd.set_pins(false, true, true, 0xFF);
+    assert_eq!(lcd.data_pins(), Some(0x10));
+}
+
+#[test]
+fn test_program_polls_busy_flag() {
+    let mut bus = BusController::new();
+    bus.register_device(
+        0x0000,
+        0x7FFF,
+        Box::new(Ram::new(RamSize::_32K, 0x0000).unwrap()),
+    )
+    .unwrap();
+    let lcd_id = bus
+        .register_device(0x8000, 0x8001, Box::new(Lcd::new(0x8000, 16, 2).unwrap()))
+        .unwrap();
+    let mut cpu = Cpu::new(bus);
+
+    // Absolute stores, an indexed store would first make a dummy read of the
+    // data register and move the address counter
+    let program = [
+        0xA2, 0x00, // LDX #0
+        0x20, 0x1B, 0x02, // NEXT: JSR WAIT
+        0xBD, 0x00, 0x03, // LDA $0300,X
+        0xF0, 0x10, // BEQ DONE
+        0x8D, 0x00, 0x80, // STA $8000
+        0x20, 0x1B, 0x02, // JSR WAIT
+        0xBD, 0x01, 0x03, // LDA $0301,X
+        0x8D, 0x01, 0x80, // STA $8001
+        0xE8, // INX
+        0xE8, // INX
+        0xD0, 0xE8, // BNE NEXT
+        0x60, // DONE: RTS
+        0xAD, 0x00, 0x80, // WAIT: LDA $8000
+        0x30, 0xFB, // BMI WAIT
+        0x60, // RTS
+    ];
+    for (offset, byte) in program.iter().enumerate() {
+        cpu.bus_mut().write(0x0200 + offset as u16, *byte).unwrap();
+    }
+    // Pairs of an instruction and a character, then a terminating zero
+    let script = [0x38, b'O', 0x0C, b'K', 0x01, b'!', 0x06, b'?', 0x00];
+    for (offset, byte) in script.iter().enumerate() {
+        cpu.bus_mut().write(0x0300 + offset as u16, *byte).unwrap();
+    }
+
+    cpu.call_subroutine(0x0200, 20_000).unwrap();
+
+    let lcd = cpu.bus_mut().device_mut::<Lcd>(lcd_id).unwrap();
+    assert_eq!(lcd.text(), format!("{:16}\n{:16}", "!?", ""));
+}
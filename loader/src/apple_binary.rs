@@ -0,0 +1,51 @@
+//! Apple DOS 3.3 binary file loader.
+//!
+//! A binary (`B`) file starts with a 2-byte little-endian load address and a 2-byte
+//! little-endian length, followed by the data. Bytes past the length are padding
+//! from the last disk sector and are ignored. `BRUN` starts the program at its load address.
+
+use bus::trait_bus_device::BusDevice;
+
+use crate::errors::LoaderError;
+use crate::{LoadInfo, read_word, store_block};
+
+/// Load an Apple DOS 3.3 binary file through the bus
+///
+/// # Arguments
+/// * `bus` - The bus, or a single device, to store the data into
+/// * `bytes` - Contents of the binary file, including its header
+///
+/// # Returns
+/// * `Ok(LoadInfo)` with the number of bytes stored and the load address as entry point
+/// * `Err(LoaderError)` if the file is truncated or a store fails
+///
+/// # Errors
+/// * `LoaderError::InvalidImage` if the header is missing, the data is shorter than the
+///   length in the header or the data does not fit below $FFFF
+/// * `LoaderError::BusError` if no device accepts a byte
+///
+/// # Examples
+/// ``` ignore
+/// let info = apple_binary::load(cpu.bus_mut(), &std::fs::read("HELLO.bin")?)?;
+/// cpu.set_program_counter(info.entry_point.unwrap());
+/// ```
+pub fn load(bus: &mut dyn BusDevice, bytes: &[u8]) -> Result<LoadInfo, LoaderError> {
+    let (Some(load_address), Some(length)) = (read_word(bytes, 0), read_word(bytes, 2)) else {
+        return Err(LoaderError::InvalidImage(
+            "binary file has no address/length header".to_string(),
+        ));
+    };
+    let data = bytes.get(4..4 + length as usize).ok_or_else(|| {
+        LoaderError::InvalidImage(format!(
+            "binary file declares {} bytes but holds {}",
+            length,
+            bytes.len() - 4
+        ))
+    })?;
+    store_block(bus, load_address, data)?;
+
+    Ok(LoadInfo {
+        entry_point: Some(load_address),
+        bytes_loaded: data.len(),
+    })
+}
@@ -0,0 +1,238 @@
+//! ELF loader for llvm-mos executables.
+//!
+//! llvm-mos links 6502 programs into 32-bit little-endian ELF files. The `PT_LOAD`
+//! program headers are stored at their physical (load) address, the ELF entry point is
+//! reported as entry point and the `.symtab` section is read into a `SymbolTable`.
+
+use bus::trait_bus_device::BusDevice;
+
+use crate::errors::LoaderError;
+use crate::symbols::SymbolTable;
+use crate::{LoadInfo, store_block};
+
+/// ELF magic number
+const ELF_MAGIC: &[u8] = b"\x7FELF";
+/// `EI_CLASS` value for 32-bit files
+const ELF_CLASS_32: u8 = 1;
+/// `EI_DATA` value for little-endian files
+const ELF_DATA_LSB: u8 = 1;
+/// `e_machine` value used by llvm-mos
+const EM_MOS: u16 = 6502;
+/// Program header type of loadable segments
+const PT_LOAD: u32 = 1;
+/// Section header type of symbol tables
+const SHT_SYMTAB: u32 = 2;
+/// Section index of undefined symbols
+const SHN_UNDEF: u16 = 0;
+/// Symbol binding of global symbols
+const STB_GLOBAL: u8 = 1;
+/// Symbol types that name an address: no type, object and function
+const ADDRESS_SYMBOL_TYPES: [u8; 3] = [0, 1, 2];
+/// Size of a program header entry
+const PROGRAM_HEADER_SIZE: usize = 32;
+/// Size of a section header entry
+const SECTION_HEADER_SIZE: usize = 40;
+/// Size of a symbol table entry
+const SYMBOL_SIZE: usize = 16;
+
+/// Load an llvm-mos ELF executable through the bus
+///
+/// Segments with a memory size larger than their file size are padded with zeroes.
+/// Symbols outside the 16-bit address space, such as banked addresses, are skipped.
+///
+/// # Arguments
+/// * `bus` - The bus, or a single device, to store the segments into
+/// * `bytes` - Contents of the ELF file
+///
+/// # Returns
+/// * `Ok((LoadInfo, SymbolTable))` with the entry point, the number of bytes stored
+///   and the symbols of the program
+/// * `Err(LoaderError)` if the file is invalid or a store fails
+///
+/// # Errors
+/// * `LoaderError::InvalidImage` if the file is not a 32-bit little-endian 6502 ELF file,
+///   a header or segment is truncated, or a segment lies outside the 16-bit address space
+/// * `LoaderError::BusError` if no device accepts a byte
+///
+/// # Examples
+/// ``` ignore
+/// let (info, symbols) = elf::load(cpu.bus_mut(), &std::fs::read("hello.elf")?)?;
+/// cpu.set_program_counter(info.entry_point.unwrap());
+/// let main = symbols.address_of("main");
+/// ```
+pub fn load(bus: &mut dyn BusDevice, bytes: &[u8]) -> Result<(LoadInfo, SymbolTable), LoaderError> {
+    if bytes.get(..4) != Some(ELF_MAGIC) {
+        return Err(invalid("not an ELF file"));
+    }
+    if bytes.get(4) != Some(&ELF_CLASS_32) || bytes.get(5) != Some(&ELF_DATA_LSB) {
+        return Err(invalid("not a 32-bit little-endian ELF file"));
+    }
+    let machine = half(bytes, 18)?;
+    if machine != EM_MOS {
+        return Err(invalid(&format!(
+            "machine type {} is not a 6502 (EM_MOS)",
+            machine
+        )));
+    }
+
+    let mut info = LoadInfo {
+        entry_point: match word(bytes, 24)? {
+            0 => None,
+            entry => Some(address_from(entry, "entry point")?),
+        },
+        bytes_loaded: 0,
+    };
+
+    let program_headers = table(
+        bytes,
+        word(bytes, 28)?,
+        half(bytes, 44)?,
+        PROGRAM_HEADER_SIZE,
+    )?;
+    for header in program_headers {
+        if word(header, 0)? != PT_LOAD {
+            continue;
+        }
+        let file_size = word(header, 16)? as usize;
+        let memory_size = word(header, 20)? as usize;
+        if memory_size == 0 {
+            continue;
+        }
+        let address = address_from(word(header, 12)?, "segment address")?;
+        // Check the memory size before it is used to allocate the padding
+        let segment_size = memory_size.max(file_size);
+        if (address as usize)
+            .checked_add(segment_size)
+            .is_none_or(|end| end > 0x10000)
+        {
+            return Err(invalid(&format!(
+                "segment of {} bytes at 0x{:04X} runs past 0xFFFF",
+                segment_size, address
+            )));
+        }
+        let data = slice(bytes, word(header, 4)?, file_size, "segment")?;
+        store_block(bus, address, data)?;
+        if memory_size > file_size {
+            let padding = vec![0; memory_size - file_size];
+            let padding_address = address_from(address as u32 + file_size as u32, "padding")?;
+            store_block(bus, padding_address, &padding)?;
+        }
+        info.bytes_loaded += segment_size;
+    }
+
+    let symbols = read_symbols(bytes)?;
+    Ok((info, symbols))
+}
+
+/// Read the address symbols of the `.symtab` section, if there is one
+fn read_symbols(bytes: &[u8]) -> Result<SymbolTable, LoaderError> {
+    let sections = table(
+        bytes,
+        word(bytes, 32)?,
+        half(bytes, 48)?,
+        SECTION_HEADER_SIZE,
+    )?;
+    let mut symbols = SymbolTable::new();
+    let Some(symtab) = sections
+        .iter()
+        .find(|section| word(section, 4).ok() == Some(SHT_SYMTAB))
+    else {
+        return Ok(symbols);
+    };
+    let strtab = sections
+        .get(word(symtab, 24)? as usize)
+        .ok_or_else(|| invalid("symbol table links to a missing string table"))?;
+    let strings = slice(
+        bytes,
+        word(strtab, 16)?,
+        word(strtab, 20)? as usize,
+        "string table",
+    )?;
+    let entries = slice(
+        bytes,
+        word(symtab, 16)?,
+        word(symtab, 20)? as usize,
+        "symbol table",
+    )?;
+
+    // Add global symbols first so they are preferred as labels over local ones
+    let mut entries: Vec<&[u8]> = entries.chunks_exact(SYMBOL_SIZE).collect();
+    entries.sort_by_key(|entry| entry[12] >> 4 != STB_GLOBAL);
+    for entry in entries {
+        let symbol_type = entry[12] & 0x0F;
+        if !ADDRESS_SYMBOL_TYPES.contains(&symbol_type) || half(entry, 14)? == SHN_UNDEF {
+            continue;
+        }
+        let Ok(address) = u16::try_from(word(entry, 4)?) else {
+            continue;
+        };
+        let name = string_at(strings, word(entry, 0)? as usize)?;
+        if !name.is_empty() {
+            symbols.insert(name, address);
+        }
+    }
+    Ok(symbols)
+}
+
+/// Split a table of fixed-size entries out of the file
+fn table(
+    bytes: &[u8],
+    offset: u32,
+    count: u16,
+    entry_size: usize,
+) -> Result<Vec<&[u8]>, LoaderError> {
+    let entries = slice(bytes, offset, count as usize * entry_size, "header table")?;
+    Ok(entries.chunks_exact(entry_size).collect())
+}
+
+/// Take a slice of the file, failing if it is truncated
+fn slice<'a>(
+    bytes: &'a [u8],
+    offset: u32,
+    length: usize,
+    what: &str,
+) -> Result<&'a [u8], LoaderError> {
+    let start = offset as usize;
+    bytes
+        .get(start..start + length)
+        .ok_or_else(|| invalid(&format!("{} at offset {} is truncated", what, offset)))
+}
+
+/// Read a NUL-terminated string from a string table
+fn string_at(strings: &[u8], offset: usize) -> Result<&str, LoaderError> {
+    let tail = strings
+        .get(offset..)
+        .ok_or_else(|| invalid("symbol name lies outside the string table"))?;
+    let end = tail
+        .iter()
+        .position(|&byte| byte == 0)
+        .unwrap_or(tail.len());
+    std::str::from_utf8(&tail[..end]).map_err(|_| invalid("symbol name is not valid UTF-8"))
+}
+
+/// Read a little-endian 16-bit field
+fn half(bytes: &[u8], offset: usize) -> Result<u16, LoaderError> {
+    let field = slice(bytes, offset as u32, 2, "field")?;
+    Ok(u16::from_le_bytes([field[0], field[1]]))
+}
+
+/// Read a little-endian 32-bit field
+fn word(bytes: &[u8], offset: usize) -> Result<u32, LoaderError> {
+    let field = slice(bytes, offset as u32, 4, "field")?;
+    Ok(u32::from_le_bytes([field[0], field[1], field[2], field[3]]))
+}
+
+/// Check that an address from the file lies in the 6502 address space
+fn address_from(address: u32, what: &str) -> Result<u16, LoaderError> {
+    u16::try_from(address).map_err(|_| {
+        invalid(&format!(
+            "{} 0x{:X} lies outside 0x0000-0xFFFF",
+            what, address
+        ))
+    })
+}
+
+/// Build an invalid image error
+fn invalid(message: &str) -> LoaderError {
+    LoaderError::InvalidImage(message.to_string())
+}
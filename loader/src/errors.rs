@@ -0,0 +1,75 @@
+use std::fmt;
+
+use bus::errors::BusError;
+use cpu6502::errors::CpuError;
+
+#[derive(Debug)]
+/// Errors related to loading and exporting images
+pub enum LoaderError {
+    /// A record is malformed or of an unsupported type
+    InvalidRecord {
+        /// Line of the record
+        line: usize,
+        /// Description of the error
+        message: String,
+    },
+    /// The checksum of a record does not match its contents
+    ChecksumMismatch {
+        /// Line of the record
+        line: usize,
+        /// Checksum computed from the record contents
+        expected: u8,
+        /// Checksum stored in the record
+        actual: u8,
+    },
+    /// A record addresses memory outside the 16-bit address space
+    AddressOutOfRange {
+        /// Line of the record
+        line: usize,
+        /// The offending address
+        address: u32,
+    },
+    /// The start of an address range lies after its end
+    InvalidRange {
+        /// Start address of the range
+        start: u16,
+        /// End address of the range
+        end: u16,
+    },
+    /// A binary image is truncated or has an invalid header
+    InvalidImage(String),
+    /// A Bus error occurred
+    BusError(BusError),
+    /// A CPU error occurred while running code during loading
+    CpuError(CpuError),
+}
+
+impl fmt::Display for LoaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoaderError::InvalidRecord { line, message } => {
+                write!(f, "line {}: Invalid record: {}", line, message)
+            }
+            LoaderError::ChecksumMismatch {
+                line,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "line {}: Checksum mismatch: expected 0x{:02X}, found 0x{:02X}",
+                line, expected, actual
+            ),
+            LoaderError::AddressOutOfRange { line, address } => {
+                write!(f, "line {}: Address out of range: 0x{:X}", line, address)
+            }
+            LoaderError::InvalidRange { start, end } => {
+                write!(f, "Invalid address range: 0x{:04X}-0x{:04X}", start, end)
+            }
+            LoaderError::InvalidImage(msg) => write!(f, "Invalid image: {}", msg),
+            LoaderError::BusError(err) => write!(f, "Bus error: {}", err),
+            LoaderError::CpuError(err) => write!(f, "CPU error: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for LoaderError {}
@@ -0,0 +1,218 @@
+//! Intel HEX loader and exporter.
+//!
+//! Supported record types:
+//! - `00`: Data
+//! - `01`: End of file
+//! - `02`: Extended segment address
+//! - `03`: Start segment address (CS:IP), reported as the entry point
+//! - `04`: Extended linear address
+//! - `05`: Start linear address, reported as the entry point
+//!
+//! Extended addresses are accepted as long as the resulting addresses fit in 16 bits.
+
+use std::fmt::Write;
+
+use bus::trait_bus_device::BusDevice;
+
+use crate::errors::LoaderError;
+use crate::{LoadInfo, address_in_range, decode_hex, read_range, store};
+
+const RECORD_DATA: u8 = 0x00;
+const RECORD_END_OF_FILE: u8 = 0x01;
+const RECORD_EXTENDED_SEGMENT_ADDRESS: u8 = 0x02;
+const RECORD_START_SEGMENT_ADDRESS: u8 = 0x03;
+const RECORD_EXTENDED_LINEAR_ADDRESS: u8 = 0x04;
+const RECORD_START_LINEAR_ADDRESS: u8 = 0x05;
+
+/// Number of data bytes per record written by `export`
+const BYTES_PER_RECORD: usize = 16;
+
+/// Load an Intel HEX image through the bus
+///
+/// Every record checksum is verified. Data records are stored by absolute address
+/// with `BusDevice::load`, so ROM can be filled as well as RAM.
+///
+/// # Arguments
+/// * `bus` - The bus, or a single device, to store the data into
+/// * `text` - Contents of the HEX file
+///
+/// # Returns
+/// * `Ok(LoadInfo)` with the number of bytes stored and the start address, if any
+/// * `Err(LoaderError)` if a record is invalid or a store fails
+///
+/// # Errors
+/// * `LoaderError::InvalidRecord` for malformed records or a missing end of file record
+/// * `LoaderError::ChecksumMismatch` if a record checksum is wrong
+/// * `LoaderError::AddressOutOfRange` if data lies above $FFFF
+/// * `LoaderError::BusError` if no device accepts a byte
+///
+/// # Examples
+/// ``` ignore
+/// let text = std::fs::read_to_string("monitor.hex")?;
+/// let info = intel_hex::load(&mut bus, &text)?;
+/// ```
+pub fn load(bus: &mut dyn BusDevice, text: &str) -> Result<LoadInfo, LoaderError> {
+    let mut info = LoadInfo::default();
+    let mut base_address: u32 = 0;
+    let mut line_count = 0;
+
+    for (index, raw_line) in text.lines().enumerate() {
+        let line = index + 1;
+        line_count = line;
+        let record = raw_line.trim();
+        if record.is_empty() {
+            continue;
+        }
+        let Some(digits) = record.strip_prefix(':') else {
+            return Err(LoaderError::InvalidRecord {
+                line,
+                message: "record does not start with ':'".to_string(),
+            });
+        };
+        let bytes = decode_hex(digits, line)?;
+        if bytes.len() < 5 || bytes.len() != bytes[0] as usize + 5 {
+            return Err(LoaderError::InvalidRecord {
+                line,
+                message: "record length does not match its byte count".to_string(),
+            });
+        }
+        let (contents, checksum) = bytes.split_at(bytes.len() - 1);
+        let expected = checksum_of(contents);
+        if expected != checksum[0] {
+            return Err(LoaderError::ChecksumMismatch {
+                line,
+                expected,
+                actual: checksum[0],
+            });
+        }
+
+        let address = u16::from_be_bytes([contents[1], contents[2]]) as u32;
+        let record_type = contents[3];
+        let data = &contents[4..];
+        match record_type {
+            RECORD_DATA => {
+                for (offset, &byte) in data.iter().enumerate() {
+                    store(bus, base_address + address + offset as u32, byte, line)?;
+                }
+                info.bytes_loaded += data.len();
+            }
+            RECORD_END_OF_FILE => return Ok(info),
+            RECORD_EXTENDED_SEGMENT_ADDRESS => {
+                base_address = (expect_u16(data, line)? as u32) << 4;
+            }
+            RECORD_START_SEGMENT_ADDRESS => {
+                let segment = expect_u32(data, line)?;
+                let entry_point = ((segment >> 16) << 4) + (segment & 0xFFFF);
+                info.entry_point = Some(address_in_range(entry_point, line)?);
+            }
+            RECORD_EXTENDED_LINEAR_ADDRESS => {
+                base_address = (expect_u16(data, line)? as u32) << 16;
+            }
+            RECORD_START_LINEAR_ADDRESS => {
+                let entry_point = expect_u32(data, line)?;
+                info.entry_point = Some(address_in_range(entry_point, line)?);
+            }
+            other => {
+                return Err(LoaderError::InvalidRecord {
+                    line,
+                    message: format!("unsupported record type 0x{:02X}", other),
+                });
+            }
+        }
+    }
+
+    Err(LoaderError::InvalidRecord {
+        line: line_count + 1,
+        message: "missing end of file record".to_string(),
+    })
+}
+
+/// Export a memory range through the bus as an Intel HEX image
+///
+/// Memory is read with `BusDevice::read`, so reading I/O registers may have side effects.
+///
+/// # Arguments
+/// * `bus` - The bus, or a single device, to read the data from
+/// * `start` - First address to export
+/// * `end` - Last address to export, inclusive
+/// * `entry_point` - Start address written as a start linear address record, if any
+///
+/// # Returns
+/// * `Ok(String)` containing the HEX records, one per line
+/// * `Err(LoaderError)` if the range is invalid or a read fails
+///
+/// # Errors
+/// * `LoaderError::InvalidRange` if `start` is greater than `end`
+/// * `LoaderError::BusError` if an address in the range cannot be read
+///
+/// # Examples
+/// ``` ignore
+/// let text = intel_hex::export(&mut bus, 0xE000, 0xFFFF, Some(0xE000))?;
+/// std::fs::write("dump.hex", text)?;
+/// ```
+pub fn export(
+    bus: &mut dyn BusDevice,
+    start: u16,
+    end: u16,
+    entry_point: Option<u16>,
+) -> Result<String, LoaderError> {
+    let data = read_range(bus, start, end)?;
+    let mut text = String::new();
+    for (index, chunk) in data.chunks(BYTES_PER_RECORD).enumerate() {
+        let address = start.wrapping_add((index * BYTES_PER_RECORD) as u16);
+        push_record(&mut text, address, RECORD_DATA, chunk);
+    }
+    if let Some(entry_point) = entry_point {
+        push_record(
+            &mut text,
+            0,
+            RECORD_START_LINEAR_ADDRESS,
+            &(entry_point as u32).to_be_bytes(),
+        );
+    }
+    push_record(&mut text, 0, RECORD_END_OF_FILE, &[]);
+    Ok(text)
+}
+
+/// Two's complement checksum of the record contents
+fn checksum_of(contents: &[u8]) -> u8 {
+    contents
+        .iter()
+        .fold(0u8, |sum, &byte| sum.wrapping_add(byte))
+        .wrapping_neg()
+}
+
+/// Append a record, with its byte count and checksum, to the output
+fn push_record(text: &mut String, address: u16, record_type: u8, data: &[u8]) {
+    let mut contents = vec![data.len() as u8];
+    contents.extend_from_slice(&address.to_be_bytes());
+    contents.push(record_type);
+    contents.extend_from_slice(data);
+    let checksum = checksum_of(&contents);
+
+    text.push(':');
+    for byte in contents.iter().chain(std::iter::once(&checksum)) {
+        let _ = write!(text, "{:02X}", byte);
+    }
+    text.push('\n');
+}
+
+fn expect_u16(data: &[u8], line: usize) -> Result<u16, LoaderError> {
+    match data {
+        [high, low] => Ok(u16::from_be_bytes([*high, *low])),
+        _ => Err(LoaderError::InvalidRecord {
+            line,
+            message: "expected 2 data bytes".to_string(),
+        }),
+    }
+}
+
+fn expect_u32(data: &[u8], line: usize) -> Result<u32, LoaderError> {
+    match data {
+        [b0, b1, b2, b3] => Ok(u32::from_be_bytes([*b0, *b1, *b2, *b3])),
+        _ => Err(LoaderError::InvalidRecord {
+            line,
+            message: "expected 4 data bytes".to_string(),
+        }),
+    }
+}
@@ -0,0 +1,119 @@
+//! Library for loading program images into bus-attached memory and dumping memory back out.
+//!
+//! Loaders store bytes by absolute address through `BusDevice::load`, so an image can
+//! span several RAM and ROM devices registered with a `BusController`.
+
+/// Apple DOS 3.3 binary file loader
+pub mod apple_binary;
+/// llvm-mos ELF loader
+pub mod elf;
+/// Errors related to loading and exporting images
+pub mod errors;
+/// Intel HEX loader and exporter
+pub mod intel_hex;
+/// Commodore PRG loader
+pub mod prg;
+/// Motorola S-record loader and exporter
+pub mod srec;
+/// Symbol tables for labelled disassembly and breakpoints by name
+pub mod symbols;
+/// Atari XEX loader
+pub mod xex;
+
+use bus::trait_bus_device::BusDevice;
+
+use crate::errors::LoaderError;
+
+/// Summary of a loaded image
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LoadInfo {
+    /// Address execution should start at, if the image specifies one
+    pub entry_point: Option<u16>,
+    /// Number of bytes stored through the bus
+    pub bytes_loaded: usize,
+}
+
+/// Decode a string of hexadecimal digit pairs
+///
+/// # Arguments
+/// * `digits` - The hexadecimal digits, two per byte
+/// * `line` - Line of the record, for error reporting
+///
+/// # Errors
+/// * `LoaderError::InvalidRecord` if the string has an odd length or a non-hex digit
+pub(crate) fn decode_hex(digits: &str, line: usize) -> Result<Vec<u8>, LoaderError> {
+    if !digits.len().is_multiple_of(2) {
+        return Err(LoaderError::InvalidRecord {
+            line,
+            message: "odd number of hex digits".to_string(),
+        });
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|index| {
+            digits
+                .get(index..index + 2)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(|| LoaderError::InvalidRecord {
+                    line,
+                    message: format!("invalid hex digits in record: {}", digits),
+                })
+        })
+        .collect()
+}
+
+/// Store a block of bytes from a binary image, rejecting blocks that run past $FFFF
+pub(crate) fn store_block(
+    bus: &mut dyn BusDevice,
+    address: u16,
+    data: &[u8],
+) -> Result<(), LoaderError> {
+    if address as usize + data.len() > 0x10000 {
+        return Err(LoaderError::InvalidImage(format!(
+            "{} bytes loaded at 0x{:04X} run past 0xFFFF",
+            data.len(),
+            address
+        )));
+    }
+    for (offset, &byte) in data.iter().enumerate() {
+        bus.load(address.wrapping_add(offset as u16), byte)
+            .map_err(LoaderError::BusError)?;
+    }
+    Ok(())
+}
+
+/// Read a little-endian word from a binary image
+pub(crate) fn read_word(bytes: &[u8], offset: usize) -> Option<u16> {
+    let word = bytes.get(offset..offset + 2)?;
+    Some(u16::from_le_bytes([word[0], word[1]]))
+}
+
+/// Check that an address from a record lies in the 6502 address space
+pub(crate) fn address_in_range(address: u32, line: usize) -> Result<u16, LoaderError> {
+    u16::try_from(address).map_err(|_| LoaderError::AddressOutOfRange { line, address })
+}
+
+/// Store a byte at an absolute address from a record
+pub(crate) fn store(
+    bus: &mut dyn BusDevice,
+    address: u32,
+    data: u8,
+    line: usize,
+) -> Result<(), LoaderError> {
+    bus.load(address_in_range(address, line)?, data)
+        .map_err(LoaderError::BusError)
+}
+
+/// Read an inclusive address range through the bus
+pub(crate) fn read_range(
+    bus: &mut dyn BusDevice,
+    start: u16,
+    end: u16,
+) -> Result<Vec<u8>, LoaderError> {
+    if start > end {
+        return Err(LoaderError::InvalidRange { start, end });
+    }
+    (start..=end)
+        .map(|address| bus.read(address).map_err(LoaderError::BusError))
+        .collect()
+}
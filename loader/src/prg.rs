@@ -0,0 +1,73 @@
+//! Commodore PRG loader.
+//!
+//! A PRG file is a 2-byte little-endian load address followed by the data.
+//! Programs loaded at the start of BASIC ($0801) usually begin with a one-line
+//! BASIC stub such as `10 SYS 2064`; the address of the SYS is reported as the entry point.
+
+use bus::trait_bus_device::BusDevice;
+
+use crate::errors::LoaderError;
+use crate::{LoadInfo, read_word, store_block};
+
+/// Start of the BASIC program area on the C64, VIC-20 (with expansion) and C128 in C64 mode
+const BASIC_START: u16 = 0x0801;
+/// BASIC token for the SYS keyword
+const TOKEN_SYS: u8 = 0x9E;
+
+/// Load a Commodore PRG file through the bus
+///
+/// The entry point is:
+/// - the address of a `SYS` in the first BASIC line for programs loaded at $0801,
+/// - `None` for other programs loaded at $0801, which are plain BASIC and need `RUN`,
+/// - the load address for everything else.
+///
+/// # Arguments
+/// * `bus` - The bus, or a single device, to store the data into
+/// * `bytes` - Contents of the PRG file
+///
+/// # Returns
+/// * `Ok(LoadInfo)` with the number of bytes stored and the entry point
+/// * `Err(LoaderError)` if the file is too short or a store fails
+///
+/// # Errors
+/// * `LoaderError::InvalidImage` if the file has no load address or does not fit below $FFFF
+/// * `LoaderError::BusError` if no device accepts a byte
+///
+/// # Examples
+/// ``` ignore
+/// let info = prg::load(cpu.bus_mut(), &std::fs::read("game.prg")?)?;
+/// if let Some(entry_point) = info.entry_point {
+///     cpu.set_program_counter(entry_point);
+/// }
+/// ```
+pub fn load(bus: &mut dyn BusDevice, bytes: &[u8]) -> Result<LoadInfo, LoaderError> {
+    let load_address = read_word(bytes, 0)
+        .ok_or_else(|| LoaderError::InvalidImage("PRG file has no load address".to_string()))?;
+    let data = &bytes[2..];
+    store_block(bus, load_address, data)?;
+
+    let entry_point = if load_address == BASIC_START {
+        sys_address(data)
+    } else {
+        Some(load_address)
+    };
+    Ok(LoadInfo {
+        entry_point,
+        bytes_loaded: data.len(),
+    })
+}
+
+/// Find the address of a `SYS` statement in the first line of a tokenised BASIC program
+fn sys_address(program: &[u8]) -> Option<u16> {
+    // Skip the link to the next line and the line number
+    let line = program.get(4..)?;
+    let line = &line[..line.iter().position(|&byte| byte == 0)?];
+    let sys = line.iter().position(|&byte| byte == TOKEN_SYS)?;
+    let digits: String = line[sys + 1..]
+        .iter()
+        .skip_while(|&&byte| byte == b' ' || byte == b'(')
+        .take_while(|byte| byte.is_ascii_digit())
+        .map(|&byte| byte as char)
+        .collect();
+    digits.parse().ok()
+}
@@ -0,0 +1,196 @@
+//! Motorola S-record loader and exporter.
+//!
+//! Supported record types:
+//! - `S0`: Header, ignored
+//! - `S1`/`S2`/`S3`: Data with a 16, 24 or 32-bit address
+//! - `S5`/`S6`: Record count, checked against the number of data records
+//! - `S7`/`S8`/`S9`: Termination with a 32, 24 or 16-bit entry point
+//!
+//! Data addresses must fit in 16 bits, so S2 and S3 records are only accepted
+//! when their upper address bytes are zero.
+
+use std::fmt::Write;
+
+use bus::trait_bus_device::BusDevice;
+
+use crate::errors::LoaderError;
+use crate::{LoadInfo, address_in_range, decode_hex, read_range, store};
+
+/// Number of data bytes per record written by `export`
+const BYTES_PER_RECORD: usize = 16;
+
+/// Load a Motorola S-record image through the bus
+///
+/// Every record checksum is verified. Data records are stored by absolute address
+/// with `BusDevice::load`, so ROM can be filled as well as RAM.
+///
+/// # Arguments
+/// * `bus` - The bus, or a single device, to store the data into
+/// * `text` - Contents of the S19/S28/S37 file
+///
+/// # Returns
+/// * `Ok(LoadInfo)` with the number of bytes stored and the entry point
+/// * `Err(LoaderError)` if a record is invalid or a store fails
+///
+/// # Errors
+/// * `LoaderError::InvalidRecord` for malformed records, a wrong record count
+///   or a missing termination record
+/// * `LoaderError::ChecksumMismatch` if a record checksum is wrong
+/// * `LoaderError::AddressOutOfRange` if data or the entry point lies above $FFFF
+/// * `LoaderError::BusError` if no device accepts a byte
+///
+/// # Examples
+/// ``` ignore
+/// let text = std::fs::read_to_string("firmware.s19")?;
+/// let info = srec::load(&mut bus, &text)?;
+/// ```
+pub fn load(bus: &mut dyn BusDevice, text: &str) -> Result<LoadInfo, LoaderError> {
+    let mut info = LoadInfo::default();
+    let mut data_records: u32 = 0;
+    let mut line_count = 0;
+
+    for (index, raw_line) in text.lines().enumerate() {
+        let line = index + 1;
+        line_count = line;
+        let record = raw_line.trim();
+        if record.is_empty() {
+            continue;
+        }
+        let mut chars = record.chars();
+        let (Some('S'), Some(record_type)) = (chars.next(), chars.next()) else {
+            return Err(LoaderError::InvalidRecord {
+                line,
+                message: "record does not start with 'S' and a type digit".to_string(),
+            });
+        };
+        let address_length = match record_type {
+            '0' | '1' | '5' | '9' => 2,
+            '2' | '6' | '8' => 3,
+            '3' | '7' => 4,
+            other => {
+                return Err(LoaderError::InvalidRecord {
+                    line,
+                    message: format!("unsupported record type S{}", other),
+                });
+            }
+        };
+
+        let bytes = decode_hex(chars.as_str(), line)?;
+        if bytes.len() < address_length + 2 || bytes.len() != bytes[0] as usize + 1 {
+            return Err(LoaderError::InvalidRecord {
+                line,
+                message: "record length does not match its byte count".to_string(),
+            });
+        }
+        let (contents, checksum) = bytes.split_at(bytes.len() - 1);
+        let expected = checksum_of(contents);
+        if expected != checksum[0] {
+            return Err(LoaderError::ChecksumMismatch {
+                line,
+                expected,
+                actual: checksum[0],
+            });
+        }
+
+        let address = contents[1..=address_length]
+            .iter()
+            .fold(0u32, |address, &byte| (address << 8) | byte as u32);
+        let data = &contents[address_length + 1..];
+        match record_type {
+            '1' | '2' | '3' => {
+                for (offset, &byte) in data.iter().enumerate() {
+                    store(bus, address + offset as u32, byte, line)?;
+                }
+                info.bytes_loaded += data.len();
+                data_records += 1;
+            }
+            '5' | '6' if address != data_records => {
+                return Err(LoaderError::InvalidRecord {
+                    line,
+                    message: format!(
+                        "record count {} does not match the {} data records read",
+                        address, data_records
+                    ),
+                });
+            }
+            '7' | '8' | '9' => {
+                info.entry_point = Some(address_in_range(address, line)?);
+                return Ok(info);
+            }
+            // S0 header and matching record counts
+            _ => {}
+        }
+    }
+
+    Err(LoaderError::InvalidRecord {
+        line: line_count + 1,
+        message: "missing termination record".to_string(),
+    })
+}
+
+/// Export a memory range through the bus as an S19 image
+///
+/// The image consists of an empty `S0` header, `S1` data records, an `S5` record count
+/// and an `S9` termination record holding the entry point, or $0000 if there is none.
+/// Memory is read with `BusDevice::read`, so reading I/O registers may have side effects.
+///
+/// # Arguments
+/// * `bus` - The bus, or a single device, to read the data from
+/// * `start` - First address to export
+/// * `end` - Last address to export, inclusive
+/// * `entry_point` - Entry point written to the termination record, if any
+///
+/// # Returns
+/// * `Ok(String)` containing the S-records, one per line
+/// * `Err(LoaderError)` if the range is invalid or a read fails
+///
+/// # Errors
+/// * `LoaderError::InvalidRange` if `start` is greater than `end`
+/// * `LoaderError::BusError` if an address in the range cannot be read
+///
+/// # Examples
+/// ``` ignore
+/// let text = srec::export(&mut bus, 0x0200, 0x02FF, Some(0x0200))?;
+/// std::fs::write("dump.s19", text)?;
+/// ```
+pub fn export(
+    bus: &mut dyn BusDevice,
+    start: u16,
+    end: u16,
+    entry_point: Option<u16>,
+) -> Result<String, LoaderError> {
+    let data = read_range(bus, start, end)?;
+    let mut text = String::new();
+    push_record(&mut text, '0', 0, &[]);
+    let mut record_count: u16 = 0;
+    for (index, chunk) in data.chunks(BYTES_PER_RECORD).enumerate() {
+        let address = start.wrapping_add((index * BYTES_PER_RECORD) as u16);
+        push_record(&mut text, '1', address, chunk);
+        record_count += 1;
+    }
+    push_record(&mut text, '5', record_count, &[]);
+    push_record(&mut text, '9', entry_point.unwrap_or(0), &[]);
+    Ok(text)
+}
+
+/// One's complement checksum of the record contents
+fn checksum_of(contents: &[u8]) -> u8 {
+    !contents
+        .iter()
+        .fold(0u8, |sum, &byte| sum.wrapping_add(byte))
+}
+
+/// Append a record with a 16-bit address, its byte count and checksum, to the output
+fn push_record(text: &mut String, record_type: char, address: u16, data: &[u8]) {
+    let mut contents = vec![(data.len() + 3) as u8];
+    contents.extend_from_slice(&address.to_be_bytes());
+    contents.extend_from_slice(data);
+    let checksum = checksum_of(&contents);
+
+    text.push('S');
+    text.push(record_type);
+    for byte in contents.iter().chain(std::iter::once(&checksum)) {
+        let _ = write!(text, "{:02X}", byte);
+    }
+    text.push('\n');
+}
@@ -0,0 +1,95 @@
+//! Symbol tables mapping names to addresses.
+//!
+//! Loaders that carry symbol information, such as the ELF loader, fill a `SymbolTable`
+//! that can be used to label disassembly and to set breakpoints by name.
+
+use std::collections::{BTreeMap, HashMap};
+
+/// Symbols of a loaded program, searchable by name and by address
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolTable {
+    by_name: HashMap<String, u16>,
+    by_address: BTreeMap<u16, String>,
+}
+
+impl SymbolTable {
+    /// Create an empty symbol table
+    pub fn new() -> SymbolTable {
+        SymbolTable::default()
+    }
+
+    /// Add a symbol
+    ///
+    /// A name that is already present is moved to the new address. When several
+    /// symbols share an address, the first one added is used as its label.
+    ///
+    /// # Arguments
+    /// * `name` - Name of the symbol
+    /// * `address` - Address of the symbol
+    pub fn insert(&mut self, name: &str, address: u16) {
+        if let Some(old_address) = self.by_name.insert(name.to_string(), address)
+            && self.by_address.get(&old_address).map(String::as_str) == Some(name)
+        {
+            self.by_address.remove(&old_address);
+        }
+        self.by_address
+            .entry(address)
+            .or_insert_with(|| name.to_string());
+    }
+
+    /// Look up the address of a symbol, e.g. to set a breakpoint by name
+    ///
+    /// # Arguments
+    /// * `name` - Name of the symbol
+    ///
+    /// # Returns
+    /// * `Some(address)` if the symbol exists
+    /// * `None` otherwise
+    pub fn address_of(&self, name: &str) -> Option<u16> {
+        self.by_name.get(name).copied()
+    }
+
+    /// Look up the label of an address
+    ///
+    /// # Arguments
+    /// * `address` - The address to label
+    ///
+    /// # Returns
+    /// * `Some(name)` if a symbol is defined at exactly this address
+    /// * `None` otherwise
+    pub fn name_at(&self, address: u16) -> Option<&str> {
+        self.by_address.get(&address).map(String::as_str)
+    }
+
+    /// Find the closest symbol at or below an address, for labels such as `main+3`
+    ///
+    /// # Arguments
+    /// * `address` - The address to label
+    ///
+    /// # Returns
+    /// * `Some((name, offset))` with the offset of the address from the symbol
+    /// * `None` if no symbol lies at or below the address
+    pub fn nearest(&self, address: u16) -> Option<(&str, u16)> {
+        self.by_address
+            .range(..=address)
+            .next_back()
+            .map(|(&symbol_address, name)| (name.as_str(), address - symbol_address))
+    }
+
+    /// Iterate over all symbols as `(name, address)` pairs, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u16)> {
+        self.by_name
+            .iter()
+            .map(|(name, &address)| (name.as_str(), address))
+    }
+
+    /// Number of symbols in the table
+    pub fn len(&self) -> usize {
+        self.by_name.len()
+    }
+
+    /// Check whether the table holds no symbols
+    pub fn is_empty(&self) -> bool {
+        self.by_name.is_empty()
+    }
+}
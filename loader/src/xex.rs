@@ -0,0 +1,116 @@
+//! Atari XEX loader.
+//!
+//! An XEX file is a sequence of segments, each with a 4-byte header holding the
+//! little-endian start and end address, followed by the data. The first segment
+//! is preceded by a $FFFF marker, which may also appear in front of later segments.
+//!
+//! Two vectors control execution, just like in Atari DOS:
+//! - INITAD ($02E2): when a segment sets it, the routine is called before loading continues
+//! - RUNAD ($02E0): the entry point once all segments are loaded
+
+use bus::trait_bus_device::BusDevice;
+use cpu6502::cpu::Cpu;
+
+use crate::errors::LoaderError;
+use crate::{LoadInfo, read_word, store_block};
+
+/// Marker in front of the first segment
+const SEGMENT_MARKER: u16 = 0xFFFF;
+/// Run address vector
+const RUNAD: u16 = 0x02E0;
+/// Init address vector
+const INITAD: u16 = 0x02E2;
+/// Cycles an INIT routine may run before loading is abandoned, about 5 seconds on an Atari
+pub const INIT_CYCLE_LIMIT: u64 = 10_000_000;
+
+/// Load an Atari XEX file through the bus of a CPU
+///
+/// INIT routines are run on the CPU as soon as the segment setting INITAD is loaded,
+/// with the program counter of the CPU as return address. If no segment sets RUNAD,
+/// the start address of the first segment is reported as the entry point.
+///
+/// # Arguments
+/// * `cpu` - The CPU whose bus the segments are stored into
+/// * `bytes` - Contents of the XEX file
+///
+/// # Returns
+/// * `Ok(LoadInfo)` with the number of bytes stored and the entry point
+/// * `Err(LoaderError)` if the file is invalid, a store fails or an INIT routine fails
+///
+/// # Errors
+/// * `LoaderError::InvalidImage` if the $FFFF marker is missing or a segment is truncated
+/// * `LoaderError::BusError` if no device accepts a byte
+/// * `LoaderError::CpuError` if an INIT routine fails or runs longer than `INIT_CYCLE_LIMIT`
+///
+/// # Examples
+/// ``` ignore
+/// let info = xex::load(&mut cpu, &std::fs::read("game.xex")?)?;
+/// cpu.set_program_counter(info.entry_point.unwrap());
+/// ```
+pub fn load(cpu: &mut Cpu, bytes: &[u8]) -> Result<LoadInfo, LoaderError> {
+    if read_word(bytes, 0) != Some(SEGMENT_MARKER) {
+        return Err(LoaderError::InvalidImage(
+            "XEX file does not start with 0xFFFF".to_string(),
+        ));
+    }
+
+    let mut info = LoadInfo::default();
+    let mut first_segment_start = None;
+    let mut run_address = None;
+    let mut offset = 2;
+    while offset < bytes.len() {
+        if read_word(bytes, offset) == Some(SEGMENT_MARKER) {
+            offset += 2;
+        }
+        let (Some(start), Some(end)) = (read_word(bytes, offset), read_word(bytes, offset + 2))
+        else {
+            return Err(LoaderError::InvalidImage(format!(
+                "truncated segment header at offset {}",
+                offset
+            )));
+        };
+        if end < start {
+            return Err(LoaderError::InvalidImage(format!(
+                "segment at offset {} ends at 0x{:04X} before its start 0x{:04X}",
+                offset, end, start
+            )));
+        }
+        let length = (end - start) as usize + 1;
+        let data = bytes.get(offset + 4..offset + 4 + length).ok_or_else(|| {
+            LoaderError::InvalidImage(format!(
+                "segment 0x{:04X}-0x{:04X} at offset {} is truncated",
+                start, end, offset
+            ))
+        })?;
+        offset += 4 + length;
+
+        store_block(cpu.bus_mut(), start, data)?;
+        info.bytes_loaded += length;
+        first_segment_start.get_or_insert(start);
+
+        if covers_vector(start, end, RUNAD) {
+            run_address = Some(read_vector(cpu, RUNAD)?);
+        }
+        if covers_vector(start, end, INITAD) {
+            let init_address = read_vector(cpu, INITAD)?;
+            cpu.call_subroutine(init_address, INIT_CYCLE_LIMIT)
+                .map_err(LoaderError::CpuError)?;
+        }
+    }
+
+    info.entry_point = run_address.or(first_segment_start);
+    Ok(info)
+}
+
+/// Check whether a segment writes to either byte of a vector
+fn covers_vector(start: u16, end: u16, vector: u16) -> bool {
+    start <= vector + 1 && end >= vector
+}
+
+/// Read a vector through the bus of the CPU
+fn read_vector(cpu: &mut Cpu, vector: u16) -> Result<u16, LoaderError> {
+    let bus = cpu.bus_mut();
+    let low = bus.read(vector).map_err(LoaderError::BusError)?;
+    let high = bus.read(vector + 1).map_err(LoaderError::BusError)?;
+    Ok(u16::from_le_bytes([low, high]))
+}
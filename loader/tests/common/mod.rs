@@ -0,0 +1,23 @@
+//! Fixtures shared by the loader tests
+
+use bus::BusController;
+use ram::{Ram, ram_size::RamSize};
+use rom::{Rom, rom_size::RomSize};
+
+/// Create a bus with 32K of RAM at $0000 and 32K of ROM at $8000
+pub fn create_bus() -> BusController {
+    let mut bus = BusController::new();
+    bus.register_device(
+        0x0000,
+        0x7FFF,
+        Box::new(Ram::new(RamSize::_32K, 0x0000).unwrap()),
+    )
+    .unwrap();
+    bus.register_device(
+        0x8000,
+        0xFFFF,
+        Box::new(Rom::new(RomSize::_32K, 0x8000).unwrap()),
+    )
+    .unwrap();
+    bus
+}
@@ -0,0 +1,54 @@
+//! Unit tests for the Apple DOS 3.3 binary file loader
+//!
+//! This module contains tests for loading binary files through the bus,
+//! ignoring sector padding and rejecting truncated files.
+
+mod common;
+
+use bus::trait_bus_device::BusDevice;
+use loader::apple_binary;
+use loader::errors::LoaderError;
+
+use common::create_bus;
+
+// Test loading
+#[test]
+fn test_load_binary() {
+    let mut bus = create_bus();
+
+    let info = apple_binary::load(&mut bus, &[0x00, 0x03, 0x03, 0x00, 0xA9, 0xC1, 0x60]).unwrap();
+
+    assert_eq!(info.entry_point, Some(0x0300));
+    assert_eq!(info.bytes_loaded, 3);
+    assert_eq!(bus.read(0x0300).unwrap(), 0xA9);
+    assert_eq!(bus.read(0x0302).unwrap(), 0x60);
+}
+
+#[test]
+fn test_padding_is_ignored() {
+    let mut bus = create_bus();
+
+    let info = apple_binary::load(&mut bus, &[0x00, 0x03, 0x01, 0x00, 0x60, 0xFF, 0xFF]).unwrap();
+
+    assert_eq!(info.bytes_loaded, 1);
+    assert_eq!(bus.read(0x0301).unwrap(), 0x00);
+}
+
+// Test error reporting
+#[test]
+fn test_missing_header() {
+    let mut bus = create_bus();
+
+    let result = apple_binary::load(&mut bus, &[0x00, 0x03, 0x01]);
+
+    assert!(matches!(result, Err(LoaderError::InvalidImage(_))));
+}
+
+#[test]
+fn test_truncated_data() {
+    let mut bus = create_bus();
+
+    let result = apple_binary::load(&mut bus, &[0x00, 0x03, 0x04, 0x00, 0xA9, 0xC1]);
+
+    assert!(matches!(result, Err(LoaderError::InvalidImage(_))));
+}
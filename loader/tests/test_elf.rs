@@ -0,0 +1,340 @@
+//! Unit tests for the llvm-mos ELF loader and symbol tables
+//!
+//! This module contains tests for loading ELF program headers through the bus,
+//! reading the entry point and `.symtab` symbols, and looking symbols up
+//! by name and by address.
+
+mod common;
+
+use bus::trait_bus_device::BusDevice;
+use loader::elf;
+use loader::errors::LoaderError;
+use loader::symbols::SymbolTable;
+
+use common::create_bus;
+
+/// A loadable segment: physical address, data and memory size
+struct Segment<'a> {
+    address: u32,
+    data: &'a [u8],
+    memory_size: u32,
+}
+
+/// A symbol: name, value, binding and type
+struct Symbol<'a> {
+    name: &'a str,
+    value: u32,
+    global: bool,
+    kind: u8,
+}
+
+/// Build a minimal 32-bit little-endian ELF file for the 6502
+///
+/// Layout: ELF header, program headers, segment data, string table,
+/// symbol table and finally the section headers (null, .symtab, .strtab).
+fn build_elf(machine: u16, entry: u32, segments: &[Segment], symbols: &[Symbol]) -> Vec<u8> {
+    let program_headers_offset = 52;
+    let mut data_offset = program_headers_offset + 32 * segments.len();
+
+    let mut program_headers = Vec::new();
+    let mut data = Vec::new();
+    for segment in segments {
+        for field in [
+            1,
+            data_offset as u32,
+            segment.address,
+            segment.address,
+            segment.data.len() as u32,
+            segment.memory_size,
+            5,
+            1,
+        ] {
+            program_headers.extend_from_slice(&field.to_le_bytes());
+        }
+        data.extend_from_slice(segment.data);
+        data_offset += segment.data.len();
+    }
+
+    let strtab_offset = data_offset;
+    let mut strtab = vec![0];
+    let mut symtab = vec![0; 16];
+    for symbol in symbols {
+        symtab.extend_from_slice(&(strtab.len() as u32).to_le_bytes());
+        symtab.extend_from_slice(&symbol.value.to_le_bytes());
+        symtab.extend_from_slice(&0u32.to_le_bytes());
+        symtab.push(((symbol.global as u8) << 4) | symbol.kind);
+        symtab.push(0);
+        symtab.extend_from_slice(&1u16.to_le_bytes());
+        strtab.extend_from_slice(symbol.name.as_bytes());
+        strtab.push(0);
+    }
+    let symtab_offset = strtab_offset + strtab.len();
+    let section_headers_offset = symtab_offset + symtab.len();
+
+    let mut bytes = vec![0x7F, b'E', b'L', b'F', 1, 1, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    bytes.extend_from_slice(&2u16.to_le_bytes());
+    bytes.extend_from_slice(&machine.to_le_bytes());
+    bytes.extend_from_slice(&1u32.to_le_bytes());
+    bytes.extend_from_slice(&entry.to_le_bytes());
+    bytes.extend_from_slice(&(program_headers_offset as u32).to_le_bytes());
+    bytes.extend_from_slice(&(section_headers_offset as u32).to_le_bytes());
+    bytes.extend_from_slice(&0u32.to_le_bytes());
+    for field in [52u16, 32, segments.len() as u16, 40, 3, 0] {
+        bytes.extend_from_slice(&field.to_le_bytes());
+    }
+    bytes.extend_from_slice(&program_headers);
+    bytes.extend_from_slice(&data);
+    bytes.extend_from_slice(&strtab);
+    bytes.extend_from_slice(&symtab);
+
+    let sections: [[u32; 10]; 3] = [
+        [0; 10],
+        [
+            0,
+            2,
+            0,
+            0,
+            symtab_offset as u32,
+            symtab.len() as u32,
+            2,
+            1,
+            4,
+            16,
+        ],
+        [
+            0,
+            3,
+            0,
+            0,
+            strtab_offset as u32,
+            strtab.len() as u32,
+            0,
+            0,
+            1,
+            0,
+        ],
+    ];
+    for section in sections {
+        for field in section {
+            bytes.extend_from_slice(&field.to_le_bytes());
+        }
+    }
+    bytes
+}
+
+/// A program with code in ROM, a vector and a BSS segment in RAM
+fn sample_elf() -> Vec<u8> {
+    build_elf(
+        6502,
+        0x8000,
+        &[
+            Segment {
+                address: 0x8000,
+                data: &[0xA9, 0x01, 0x85, 0x10, 0x4C, 0x04, 0x80],
+                memory_size: 7,
+            },
+            Segment {
+                address: 0xFFFC,
+                data: &[0x00, 0x80],
+                memory_size: 2,
+            },
+            Segment {
+                address: 0x0200,
+                data: &[],
+                memory_size: 4,
+            },
+        ],
+        &[
+            Symbol {
+                name: "_start",
+                value: 0x8000,
+                global: true,
+                kind: 2,
+            },
+            Symbol {
+                name: ".Lloop",
+                value: 0x8004,
+                global: false,
+                kind: 0,
+            },
+            Symbol {
+                name: "loop",
+                value: 0x8004,
+                global: true,
+                kind: 2,
+            },
+            Symbol {
+                name: "counter",
+                value: 0x0200,
+                global: true,
+                kind: 1,
+            },
+            Symbol {
+                name: "crt0.c",
+                value: 0,
+                global: false,
+                kind: 4,
+            },
+            Symbol {
+                name: "banked",
+                value: 0x0001_8000,
+                global: true,
+                kind: 2,
+            },
+        ],
+    )
+}
+
+// Test loading
+#[test]
+fn test_load_program_headers() {
+    let mut bus = create_bus();
+    bus.write(0x0201, 0xAA).unwrap();
+
+    let (info, _) = elf::load(&mut bus, &sample_elf()).unwrap();
+
+    assert_eq!(info.entry_point, Some(0x8000));
+    assert_eq!(info.bytes_loaded, 13);
+    assert_eq!(bus.read(0x8000).unwrap(), 0xA9);
+    assert_eq!(bus.read(0x8006).unwrap(), 0x80);
+    assert_eq!(bus.read(0xFFFD).unwrap(), 0x80);
+    assert_eq!(bus.read(0x0201).unwrap(), 0x00);
+}
+
+#[test]
+fn test_load_symbols() {
+    let mut bus = create_bus();
+
+    let (_, symbols) = elf::load(&mut bus, &sample_elf()).unwrap();
+
+    assert_eq!(symbols.len(), 4);
+    assert_eq!(symbols.address_of("_start"), Some(0x8000));
+    assert_eq!(symbols.address_of("counter"), Some(0x0200));
+    assert_eq!(symbols.address_of(".Lloop"), Some(0x8004));
+    assert_eq!(symbols.address_of("crt0.c"), None);
+    assert_eq!(symbols.address_of("banked"), None);
+}
+
+#[test]
+fn test_global_symbols_preferred_as_labels() {
+    let mut bus = create_bus();
+
+    let (_, symbols) = elf::load(&mut bus, &sample_elf()).unwrap();
+
+    assert_eq!(symbols.name_at(0x8004), Some("loop"));
+}
+
+#[test]
+fn test_file_without_symbols() {
+    let mut bus = create_bus();
+    let bytes = build_elf(
+        6502,
+        0,
+        &[Segment {
+            address: 0x1000,
+            data: &[0x60],
+            memory_size: 1,
+        }],
+        &[],
+    );
+
+    let (info, symbols) = elf::load(&mut bus, &bytes).unwrap();
+
+    assert_eq!(info.entry_point, None);
+    assert!(symbols.is_empty());
+}
+
+// Test error reporting
+#[test]
+fn test_not_an_elf_file() {
+    let mut bus = create_bus();
+
+    let result = elf::load(&mut bus, b"\x00\x80\xA9\x01");
+
+    assert!(matches!(result, Err(LoaderError::InvalidImage(_))));
+}
+
+#[test]
+fn test_wrong_machine() {
+    let mut bus = create_bus();
+    let bytes = build_elf(62, 0x8000, &[], &[]);
+
+    let result = elf::load(&mut bus, &bytes);
+
+    assert!(matches!(result, Err(LoaderError::InvalidImage(_))));
+}
+
+#[test]
+fn test_segment_outside_address_space() {
+    let mut bus = create_bus();
+    let bytes = build_elf(
+        6502,
+        0,
+        &[Segment {
+            address: 0x0001_0000,
+            data: &[0x60],
+            memory_size: 1,
+        }],
+        &[],
+    );
+
+    let result = elf::load(&mut bus, &bytes);
+
+    assert!(matches!(result, Err(LoaderError::InvalidImage(_))));
+}
+
+#[test]
+fn test_segment_memory_size_past_end_of_address_space() {
+    let mut bus = create_bus();
+    let bytes = build_elf(
+        6502,
+        0,
+        &[Segment {
+            address: 0x8000,
+            data: &[0x60],
+            memory_size: 0xFFFF_FFFF,
+        }],
+        &[],
+    );
+
+    let result = elf::load(&mut bus, &bytes);
+
+    assert!(matches!(result, Err(LoaderError::InvalidImage(_))));
+    // Nothing of the rejected segment is stored
+    assert_eq!(bus.read(0x8000).unwrap(), 0x00);
+}
+
+#[test]
+fn test_truncated_file() {
+    let mut bus = create_bus();
+    let mut bytes = sample_elf();
+    bytes.truncate(60);
+
+    let result = elf::load(&mut bus, &bytes);
+
+    assert!(matches!(result, Err(LoaderError::InvalidImage(_))));
+}
+
+// Test symbol tables
+#[test]
+fn test_symbol_table_nearest() {
+    let mut symbols = SymbolTable::new();
+    symbols.insert("main", 0x8000);
+    symbols.insert("irq", 0x8100);
+
+    assert_eq!(symbols.nearest(0x8003), Some(("main", 3)));
+    assert_eq!(symbols.nearest(0x8100), Some(("irq", 0)));
+    assert_eq!(symbols.nearest(0x7FFF), None);
+}
+
+#[test]
+fn test_symbol_table_redefinition_moves_label() {
+    let mut symbols = SymbolTable::new();
+    symbols.insert("main", 0x8000);
+    symbols.insert("main", 0x9000);
+
+    assert_eq!(symbols.address_of("main"), Some(0x9000));
+    assert_eq!(symbols.name_at(0x8000), None);
+    assert_eq!(symbols.name_at(0x9000), Some("main"));
+    assert_eq!(symbols.len(), 1);
+}
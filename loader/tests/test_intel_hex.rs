@@ -0,0 +1,191 @@
+//! Unit tests for the Intel HEX loader
+//!
+//! This module contains tests for loading Intel HEX images through the bus,
+//! testing checksums, extended and start address records, error lines
+//! and round trips through the exporter.
+
+mod common;
+
+use bus::BusController;
+use bus::errors::BusError;
+use bus::trait_bus_device::BusDevice;
+use loader::errors::LoaderError;
+use loader::intel_hex;
+use ram::{Ram, ram_size::RamSize};
+
+use common::create_bus;
+
+const SAMPLE: &str = "\
+:10010000214601360121470136007EFE09D2190140
+:100110002146017E17C20001FF5F16002148011928
+:10012000194E79234623965778239EDA3F01B2CAA7
+:100130003F0156702B5E712B722B732146013421C7
+:00000001FF
+";
+
+// Test loading
+#[test]
+fn test_load_data_records() {
+    let mut bus = create_bus();
+
+    let info = intel_hex::load(&mut bus, SAMPLE).unwrap();
+
+    assert_eq!(info.bytes_loaded, 64);
+    assert_eq!(info.entry_point, None);
+    assert_eq!(bus.read(0x0100).unwrap(), 0x21);
+    assert_eq!(bus.read(0x013F).unwrap(), 0x21);
+    assert_eq!(bus.read(0x0140).unwrap(), 0x00);
+}
+
+#[test]
+fn test_load_into_rom() {
+    let mut bus = create_bus();
+    let text = ":02FFFC0000E023\n:00000001FF\n";
+
+    intel_hex::load(&mut bus, text).unwrap();
+
+    assert_eq!(bus.read(0xFFFC).unwrap(), 0x00);
+    assert_eq!(bus.read(0xFFFD).unwrap(), 0xE0);
+    // The ROM is still read-only for the CPU
+    assert!(matches!(
+        bus.write(0xFFFC, 0x12),
+        Err(BusError::ReadOnly(0xFFFC))
+    ));
+}
+
+#[test]
+fn test_load_start_linear_address() {
+    let mut bus = create_bus();
+    let text = ":0400000500000200F5\n:00000001FF\n";
+
+    let info = intel_hex::load(&mut bus, text).unwrap();
+
+    assert_eq!(info.entry_point, Some(0x0200));
+}
+
+#[test]
+fn test_load_extended_segment_address() {
+    let mut bus = create_bus();
+    // Segment 0x0100 moves the record at 0x0000 to 0x1000
+    let text = ":020000020100FB\n:01000000AA55\n:00000001FF\n";
+
+    intel_hex::load(&mut bus, text).unwrap();
+
+    assert_eq!(bus.read(0x1000).unwrap(), 0xAA);
+}
+
+// Test error reporting
+#[test]
+fn test_checksum_mismatch() {
+    let mut bus = create_bus();
+    let text = ":00000001FF\n";
+    let corrupted = format!(":01000000AA56\n{}", text);
+
+    let result = intel_hex::load(&mut bus, &corrupted);
+
+    assert!(matches!(
+        result,
+        Err(LoaderError::ChecksumMismatch {
+            line: 1,
+            expected: 0x55,
+            actual: 0x56
+        })
+    ));
+}
+
+#[test]
+fn test_extended_linear_address_out_of_range() {
+    let mut bus = create_bus();
+    let text = ":01000000AA55\n:020000040001F9\n:01000000AA55\n:00000001FF\n";
+
+    let result = intel_hex::load(&mut bus, text);
+
+    assert!(matches!(
+        result,
+        Err(LoaderError::AddressOutOfRange {
+            line: 3,
+            address: 0x10000
+        })
+    ));
+}
+
+#[test]
+fn test_invalid_record_line() {
+    let mut bus = create_bus();
+    let text = ":01000000AA55\n\n01000000AA55\n";
+
+    let result = intel_hex::load(&mut bus, text);
+
+    assert!(matches!(
+        result,
+        Err(LoaderError::InvalidRecord { line: 3, .. })
+    ));
+}
+
+#[test]
+fn test_missing_end_of_file() {
+    let mut bus = create_bus();
+
+    let result = intel_hex::load(&mut bus, ":01000000AA55\n");
+
+    assert!(matches!(
+        result,
+        Err(LoaderError::InvalidRecord { line: 2, .. })
+    ));
+}
+
+#[test]
+fn test_load_unmapped_address() {
+    let mut bus = BusController::new();
+    bus.register_device(
+        0x0000,
+        0x07FF,
+        Box::new(Ram::new(RamSize::_2K, 0x0000).unwrap()),
+    )
+    .unwrap();
+
+    let result = intel_hex::load(&mut bus, SAMPLE);
+    assert!(result.is_ok());
+
+    let result = intel_hex::load(&mut bus, ":01100000AA45\n:00000001FF\n");
+    assert!(matches!(
+        result,
+        Err(LoaderError::BusError(BusError::AddressOutOfRange(0x1000)))
+    ));
+}
+
+// Test exporting
+#[test]
+fn test_export_records() {
+    let mut bus = create_bus();
+    bus.write(0x0000, 0xAA).unwrap();
+
+    let text = intel_hex::export(&mut bus, 0x0000, 0x0000, Some(0x0200)).unwrap();
+
+    assert_eq!(text, ":01000000AA55\n:0400000500000200F5\n:00000001FF\n");
+}
+
+#[test]
+fn test_export_round_trip() {
+    let mut bus = create_bus();
+    intel_hex::load(&mut bus, SAMPLE).unwrap();
+
+    let text = intel_hex::export(&mut bus, 0x0100, 0x013F, None).unwrap();
+
+    assert_eq!(text, SAMPLE);
+}
+
+#[test]
+fn test_export_invalid_range() {
+    let mut bus = create_bus();
+
+    let result = intel_hex::export(&mut bus, 0x0200, 0x01FF, None);
+
+    assert!(matches!(
+        result,
+        Err(LoaderError::InvalidRange {
+            start: 0x0200,
+            end: 0x01FF
+        })
+    ));
+}
@@ -0,0 +1,77 @@
+//! Unit tests for the Commodore PRG loader
+//!
+//! This module contains tests for loading PRG files through the bus
+//! and finding the entry point of machine code and BASIC programs.
+
+mod common;
+
+use bus::trait_bus_device::BusDevice;
+use loader::errors::LoaderError;
+use loader::prg;
+
+use common::create_bus;
+
+/// `10 SYS 2061` followed by INC $D020; RTS at $080D
+const BASIC_STUB: &[u8] = &[
+    0x01, 0x08, // load address
+    0x0B, 0x08, 0x0A, 0x00, 0x9E, b'2', b'0', b'6', b'1', 0x00, // 10 SYS2061
+    0x00, 0x00, // end of program
+    0xEE, 0x20, 0xD0, 0x60,
+];
+
+// Test loading
+#[test]
+fn test_load_machine_code() {
+    let mut bus = create_bus();
+
+    let info = prg::load(&mut bus, &[0x00, 0xC0, 0xA9, 0x01, 0x60]).unwrap();
+
+    assert_eq!(info.entry_point, Some(0xC000));
+    assert_eq!(info.bytes_loaded, 3);
+    assert_eq!(bus.read(0xC000).unwrap(), 0xA9);
+    assert_eq!(bus.read(0xC002).unwrap(), 0x60);
+}
+
+#[test]
+fn test_load_basic_stub_reports_sys_address() {
+    let mut bus = create_bus();
+
+    let info = prg::load(&mut bus, BASIC_STUB).unwrap();
+
+    assert_eq!(info.entry_point, Some(2061));
+    assert_eq!(bus.read(0x0801).unwrap(), 0x0B);
+    assert_eq!(bus.read(0x080D).unwrap(), 0xEE);
+}
+
+#[test]
+fn test_load_basic_without_sys() {
+    let mut bus = create_bus();
+    // 10 PRINT"HI"
+    let bytes = [
+        0x01, 0x08, 0x0C, 0x08, 0x0A, 0x00, 0x99, b'"', b'H', b'I', b'"', 0x00, 0x00, 0x00,
+    ];
+
+    let info = prg::load(&mut bus, &bytes).unwrap();
+
+    assert_eq!(info.entry_point, None);
+    assert_eq!(info.bytes_loaded, 12);
+}
+
+// Test error reporting
+#[test]
+fn test_missing_load_address() {
+    let mut bus = create_bus();
+
+    let result = prg::load(&mut bus, &[0x01]);
+
+    assert!(matches!(result, Err(LoaderError::InvalidImage(_))));
+}
+
+#[test]
+fn test_data_past_end_of_memory() {
+    let mut bus = create_bus();
+
+    let result = prg::load(&mut bus, &[0xFF, 0xFF, 0x01, 0x02]);
+
+    assert!(matches!(result, Err(LoaderError::InvalidImage(_))));
+}
@@ -0,0 +1,145 @@
+//! Unit tests for the Motorola S-record loader
+//!
+//! This module contains tests for loading S-record images through the bus,
+//! testing checksums, record counts, entry points, error lines
+//! and round trips through the exporter.
+
+mod common;
+
+use bus::trait_bus_device::BusDevice;
+use loader::errors::LoaderError;
+use loader::srec;
+
+use common::create_bus;
+
+const SAMPLE: &str = "\
+S00F000068656C6C6F202020202000003C
+S11F00007C0802A6900100049421FFF07C6C1B787C8C23783C6000003863000026
+S11F001C4BFFFFE5398000007D83637880010014382100107C0803A64E800020E9
+S111003848656C6C6F20776F726C642E0A0042
+S5030003F9
+S9030000FC
+";
+
+// Test loading
+#[test]
+fn test_load_data_records() {
+    let mut bus = create_bus();
+
+    let info = srec::load(&mut bus, SAMPLE).unwrap();
+
+    assert_eq!(info.bytes_loaded, 70);
+    assert_eq!(info.entry_point, Some(0x0000));
+    assert_eq!(bus.read(0x0000).unwrap(), 0x7C);
+    assert_eq!(bus.read(0x0038).unwrap(), 0x48);
+    assert_eq!(bus.read(0x0045).unwrap(), 0x00);
+}
+
+#[test]
+fn test_load_s2_record_into_rom() {
+    let mut bus = create_bus();
+    let text = "S20600FFFC00E01E\nS804000200F9\n";
+
+    let info = srec::load(&mut bus, text).unwrap();
+
+    assert_eq!(bus.read(0xFFFC).unwrap(), 0x00);
+    assert_eq!(bus.read(0xFFFD).unwrap(), 0xE0);
+    assert_eq!(info.entry_point, Some(0x0200));
+}
+
+// Test error reporting
+#[test]
+fn test_checksum_mismatch() {
+    let mut bus = create_bus();
+    let text = "S1040000AA50\nS9030000FC\n";
+
+    let result = srec::load(&mut bus, text);
+
+    assert!(matches!(
+        result,
+        Err(LoaderError::ChecksumMismatch {
+            line: 1,
+            expected: 0x51,
+            actual: 0x50
+        })
+    ));
+}
+
+#[test]
+fn test_record_count_mismatch() {
+    let mut bus = create_bus();
+    let text = "S1040000AA51\nS5030002FA\nS9030000FC\n";
+
+    let result = srec::load(&mut bus, text);
+
+    assert!(matches!(
+        result,
+        Err(LoaderError::InvalidRecord { line: 2, .. })
+    ));
+}
+
+#[test]
+fn test_s3_address_out_of_range() {
+    let mut bus = create_bus();
+    let text = "S1040000AA51\nS30600010000AA4E\nS9030000FC\n";
+
+    let result = srec::load(&mut bus, text);
+
+    assert!(matches!(
+        result,
+        Err(LoaderError::AddressOutOfRange {
+            line: 2,
+            address: 0x10000
+        })
+    ));
+}
+
+#[test]
+fn test_unsupported_record_type() {
+    let mut bus = create_bus();
+
+    let result = srec::load(&mut bus, "S4030000FC\n");
+
+    assert!(matches!(
+        result,
+        Err(LoaderError::InvalidRecord { line: 1, .. })
+    ));
+}
+
+#[test]
+fn test_missing_termination() {
+    let mut bus = create_bus();
+
+    let result = srec::load(&mut bus, "S1040000AA51\n");
+
+    assert!(matches!(
+        result,
+        Err(LoaderError::InvalidRecord { line: 2, .. })
+    ));
+}
+
+// Test exporting
+#[test]
+fn test_export_records() {
+    let mut bus = create_bus();
+    bus.write(0x0000, 0xAA).unwrap();
+
+    let text = srec::export(&mut bus, 0x0000, 0x0000, Some(0x0200)).unwrap();
+
+    assert_eq!(text, "S0030000FC\nS1040000AA51\nS5030001FB\nS9030200FA\n");
+}
+
+#[test]
+fn test_export_round_trip() {
+    let mut bus = create_bus();
+    srec::load(&mut bus, SAMPLE).unwrap();
+
+    let text = srec::export(&mut bus, 0x0000, 0x0045, Some(0x0000)).unwrap();
+    let mut copy = create_bus();
+    let info = srec::load(&mut copy, &text).unwrap();
+
+    assert_eq!(info.bytes_loaded, 70);
+    for address in 0x0000..=0x0045 {
+        assert_eq!(copy.read(address).unwrap(), bus.read(address).unwrap());
+    }
+}
@@ -0,0 +1,108 @@
+//! Unit tests for the Atari XEX loader
+//!
+//! This module contains tests for loading segmented XEX files through the bus
+//! of a CPU, the RUNAD entry point and running INIT routines during loading.
+
+use bus::BusController;
+use bus::trait_bus_device::BusDevice;
+use cpu6502::cpu::Cpu;
+use loader::errors::LoaderError;
+use loader::xex;
+use ram::{Ram, ram_size::RamSize};
+
+/// Create a CPU with 32K of RAM at $0000, stopped at $1000
+fn create_cpu() -> Cpu {
+    let mut bus = BusController::new();
+    bus.register_device(
+        0x0000,
+        0x7FFF,
+        Box::new(Ram::new(RamSize::_32K, 0x0000).unwrap()),
+    )
+    .unwrap();
+    let mut cpu = Cpu::new(bus);
+    cpu.set_program_counter(0x1000);
+    cpu
+}
+
+// Test loading
+#[test]
+fn test_load_segments() {
+    let mut cpu = create_cpu();
+    let bytes = [
+        0xFF, 0xFF, 0x00, 0x20, 0x01, 0x20, 0xA9, 0x01, // $2000-$2001
+        0x00, 0x30, 0x00, 0x30, 0x60, // $3000, no marker
+        0xFF, 0xFF, 0x00, 0x40, 0x00, 0x40, 0xEA, // $4000, with marker
+    ];
+
+    let info = xex::load(&mut cpu, &bytes).unwrap();
+
+    assert_eq!(info.bytes_loaded, 4);
+    assert_eq!(info.entry_point, Some(0x2000));
+    let bus = cpu.bus_mut();
+    assert_eq!(bus.read(0x2001).unwrap(), 0x01);
+    assert_eq!(bus.read(0x3000).unwrap(), 0x60);
+    assert_eq!(bus.read(0x4000).unwrap(), 0xEA);
+}
+
+#[test]
+fn test_runad_sets_entry_point() {
+    let mut cpu = create_cpu();
+    let bytes = [
+        0xFF, 0xFF, 0x00, 0x20, 0x00, 0x20, 0x60, // $2000
+        0xE0, 0x02, 0xE1, 0x02, 0x34, 0x12, // RUNAD = $1234
+    ];
+
+    let info = xex::load(&mut cpu, &bytes).unwrap();
+
+    assert_eq!(info.entry_point, Some(0x1234));
+}
+
+#[test]
+fn test_init_segment_runs_during_load() {
+    let mut cpu = create_cpu();
+    let bytes = [
+        0xFF, 0xFF, 0x00, 0x20, 0x04, 0x20, // $2000: LDA $80; STA $81; RTS
+        0xA5, 0x80, 0x85, 0x81, 0x60, //
+        0x80, 0x00, 0x80, 0x00, 0x42, // $80 = $42
+        0xE2, 0x02, 0xE3, 0x02, 0x00, 0x20, // INITAD = $2000
+        0x80, 0x00, 0x80, 0x00, 0x99, // $80 = $99, after INIT ran
+    ];
+
+    xex::load(&mut cpu, &bytes).unwrap();
+
+    assert_eq!(cpu.bus_mut().read(0x0081).unwrap(), 0x42);
+    assert_eq!(cpu.bus_mut().read(0x0080).unwrap(), 0x99);
+    assert_eq!(cpu.registers().program_counter, 0x1000);
+}
+
+// Test error reporting
+#[test]
+fn test_missing_header() {
+    let mut cpu = create_cpu();
+
+    let result = xex::load(&mut cpu, &[0x00, 0x20, 0x00, 0x20, 0x60]);
+
+    assert!(matches!(result, Err(LoaderError::InvalidImage(_))));
+}
+
+#[test]
+fn test_truncated_segment() {
+    let mut cpu = create_cpu();
+
+    let result = xex::load(&mut cpu, &[0xFF, 0xFF, 0x00, 0x20, 0x03, 0x20, 0xA9]);
+
+    assert!(matches!(result, Err(LoaderError::InvalidImage(_))));
+}
+
+#[test]
+fn test_init_routine_that_never_returns() {
+    let mut cpu = create_cpu();
+    let bytes = [
+        0xFF, 0xFF, 0x00, 0x20, 0x02, 0x20, 0x4C, 0x00, 0x20, // $2000: JMP $2000
+        0xE2, 0x02, 0xE3, 0x02, 0x00, 0x20, // INITAD = $2000
+    ];
+
+    let result = xex::load(&mut cpu, &bytes);
+
+    assert!(matches!(result, Err(LoaderError::CpuError(_))));
+}
@@ -0,0 +1,123 @@
+//! Machine description file format.
+//!
+//! A machine is described by a TOML document. Addresses and sizes may be written
+//! in hexadecimal (`0xC000`). Image paths are relative to the directory of the file.
+//!
+//! ``` toml
+//! [cpu]
+//! variant = "cmos"            # "nmos" (default) or "cmos"
+//! reset_vector = 0xC000       # optional, overrides the vector at $FFFC
+//!
+//! [[ram]]
+//! name = "main"
+//! start = 0x0000
+//! size = 0x1800               # any size that fits below $FFFF
+//! sparse = false              # RAM only: allocate pages on first write
+//!
+//! [[rom]]
+//! name = "monitor"
+//! start = 0xC000
+//! size = 0x4000
+//! image = "monitor.bin"
+//! image_offset = 0x10         # bytes to skip at the start of the file
+//! offset = 0x0000             # where the image is placed inside the region
+//!
+//! [[mirror]]
+//! start = 0x0800
+//! end = 0x1FFF
+//! target_start = 0x0000
+//! target_end = 0x07FF
+//!
+//! [[device]]
+//! type = "dma"
+//! name = "dma"
+//! start = 0xDF00
+//! clock_multiplier = 1
+//! clock_divider = 1
+//! ```
+
+use serde::Deserialize;
+use toml::Spanned;
+
+/// Root of a machine description
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MachineConfig {
+    /// CPU settings
+    pub cpu: Option<Spanned<CpuConfig>>,
+    /// RAM regions
+    #[serde(default)]
+    pub ram: Vec<Spanned<MemoryConfig>>,
+    /// ROM regions
+    #[serde(default)]
+    pub rom: Vec<Spanned<MemoryConfig>>,
+    /// Mirrored address ranges
+    #[serde(default)]
+    pub mirror: Vec<Spanned<MirrorConfig>>,
+    /// I/O devices
+    #[serde(default)]
+    pub device: Vec<Spanned<DeviceConfig>>,
+}
+
+/// CPU settings
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CpuConfig {
+    /// CPU variant, `"nmos"` or `"cmos"`
+    pub variant: Option<Spanned<String>>,
+    /// Start address used instead of the reset vector at $FFFC
+    pub reset_vector: Option<Spanned<u16>>,
+}
+
+/// RAM or ROM region
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MemoryConfig {
+    /// Name used to look up the device after loading
+    pub name: Option<String>,
+    /// Start address of the region
+    pub start: Spanned<u16>,
+    /// Size of the region in bytes
+    pub size: Spanned<u32>,
+    /// Image file loaded into the region
+    pub image: Option<Spanned<String>>,
+    /// Number of bytes skipped at the start of the image file
+    #[serde(default)]
+    pub image_offset: u64,
+    /// Offset inside the region at which the image is placed
+    pub offset: Option<Spanned<u32>>,
+    /// Allocate RAM only where it is written, for large test setups
+    #[serde(default)]
+    pub sparse: bool,
+}
+
+/// Address range that repeats another address range
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MirrorConfig {
+    /// Start address of the mirror
+    pub start: u16,
+    /// End address of the mirror
+    pub end: u16,
+    /// Start address of the mirrored range
+    pub target_start: u16,
+    /// End address of the mirrored range
+    pub target_end: u16,
+}
+
+/// I/O device
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DeviceConfig {
+    /// Device type, for example `"dma"`
+    #[serde(rename = "type")]
+    pub kind: Spanned<String>,
+    /// Name used to look up the device after loading
+    pub name: Option<String>,
+    /// Start address of the device registers
+    pub start: u16,
+    /// Device ticks per `clock_divider` CPU cycles
+    pub clock_multiplier: Option<u32>,
+    /// CPU cycles per `clock_multiplier` device ticks
+    pub clock_divider: Option<u32>,
+}
@@ -0,0 +1,88 @@
+use std::fmt;
+
+#[derive(Debug)]
+/// Errors related to loading a machine description
+///
+/// Every error found in the description carries the 1-based line it refers to.
+pub enum ConfigError {
+    /// The description file could not be read
+    Io(String),
+    /// The description is not valid TOML or does not match the expected format
+    Syntax {
+        /// Line of the error
+        line: usize,
+        /// Description of the error
+        message: String,
+    },
+    /// A value is out of range or not supported
+    InvalidValue {
+        /// Line of the invalid value
+        line: usize,
+        /// Description of the error
+        message: String,
+    },
+    /// Two address ranges overlap
+    Overlap {
+        /// Line of the region that could not be placed
+        line: usize,
+        /// Line of the region it overlaps with
+        other_line: usize,
+    },
+    /// An image file does not exist or cannot be read
+    MissingFile {
+        /// Line of the image path
+        line: usize,
+        /// Path of the image file
+        path: String,
+    },
+    /// The reset vector is unmapped or points to unmapped memory
+    BadVector {
+        /// Line of the setting or region responsible for the vector
+        line: usize,
+        /// Description of the error
+        message: String,
+    },
+}
+
+impl ConfigError {
+    /// Get the line of the description the error refers to
+    ///
+    /// # Returns
+    /// * `Some(line)` for errors found in the description
+    /// * `None` if the description could not be read at all
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            ConfigError::Io(_) => None,
+            ConfigError::Syntax { line, .. }
+            | ConfigError::InvalidValue { line, .. }
+            | ConfigError::Overlap { line, .. }
+            | ConfigError::MissingFile { line, .. }
+            | ConfigError::BadVector { line, .. } => Some(*line),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(msg) => write!(f, "Cannot read machine description: {}", msg),
+            ConfigError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+            ConfigError::InvalidValue { line, message } => {
+                write!(f, "line {}: Invalid value: {}", line, message)
+            }
+            ConfigError::Overlap { line, other_line } => write!(
+                f,
+                "line {}: Address range overlaps with the range at line {}",
+                line, other_line
+            ),
+            ConfigError::MissingFile { line, path } => {
+                write!(f, "line {}: Cannot read image file: {}", line, path)
+            }
+            ConfigError::BadVector { line, message } => {
+                write!(f, "line {}: Bad vector: {}", line, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
@@ -0,0 +1,519 @@
+//! Library for building complete 6502 machines from declarative descriptions.
+//!
+//! A machine description lists the RAM and ROM regions, mirrors and I/O devices of a
+//! board, see the `config` module for the file format. Loading a description produces
+//! a `Machine` holding a reset `Cpu` connected to a fully wired `BusController`.
+
+/// Machine description file format
+pub mod config;
+/// Errors related to loading machine descriptions
+pub mod errors;
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use bus::dma_controller::{DMA_CONTROLLER_REGISTER_COUNT, DmaController};
+use bus::trait_bus_device::BusDevice;
+use bus::{BusController, DeviceId};
+use cpu6502::cpu::Cpu;
+use cpu6502::cpu_variant::CpuVariant;
+use ram::Ram;
+use rom::Rom;
+use toml::Spanned;
+
+use crate::config::{CpuConfig, DeviceConfig, MachineConfig, MemoryConfig, MirrorConfig};
+use crate::errors::ConfigError;
+
+const RESET_VECTOR: u16 = 0xFFFC;
+
+/// A CPU and its bus, built from a machine description
+pub struct Machine {
+    /// CPU connected to the bus of the machine
+    cpu: Cpu,
+    /// Ids of the named devices
+    devices: HashMap<String, DeviceId>,
+}
+
+impl Machine {
+    /// Load a machine description from a TOML file
+    ///
+    /// Image paths in the description are relative to the directory of the file.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the description file
+    ///
+    /// # Returns
+    /// * `Ok(Machine)` with the CPU reset and ready to run
+    /// * `Err(ConfigError)` if the file cannot be read or the description is invalid
+    ///
+    /// # Errors
+    /// * `ConfigError::Io` if the file cannot be read
+    /// * Any error returned by `from_toml_str`
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let mut machine = Machine::from_file("boards/rev_b.toml")?;
+    /// machine.cpu_mut().step()?;
+    /// ```
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Machine, ConfigError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .map_err(|error| ConfigError::Io(format!("{}: {}", path.display(), error)))?;
+        let base_dir = path.parent().unwrap_or(Path::new(""));
+        Self::from_toml_str(&text, base_dir)
+    }
+
+    /// Build a machine from a TOML description
+    ///
+    /// # Arguments
+    /// * `text` - The machine description
+    /// * `base_dir` - Directory that relative image paths are resolved against
+    ///
+    /// # Returns
+    /// * `Ok(Machine)` with the CPU reset and ready to run
+    /// * `Err(ConfigError)` if the description is invalid
+    ///
+    /// # Errors
+    /// * `ConfigError::Syntax` if the text is not a valid description
+    /// * `ConfigError::InvalidValue` for unsupported sizes, regions past $FFFF or unknown devices
+    /// * `ConfigError::Overlap` if two address ranges overlap
+    /// * `ConfigError::MissingFile` if an image file cannot be read
+    /// * `ConfigError::BadVector` if the reset vector is unmapped or points to unmapped memory
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let machine = Machine::from_toml_str(
+    ///     "[[ram]]\nstart = 0x0000\nsize = 0x10000\n[cpu]\nreset_vector = 0x0200\n",
+    ///     Path::new("."),
+    /// )?;
+    /// ```
+    pub fn from_toml_str(text: &str, base_dir: &Path) -> Result<Machine, ConfigError> {
+        let config: MachineConfig = toml::from_str(text).map_err(|error| ConfigError::Syntax {
+            line: error.span().map_or(1, |span| line_of(text, span.start)),
+            message: error.message().to_string(),
+        })?;
+
+        let mut loader = Loader {
+            text,
+            base_dir,
+            bus: BusController::new(),
+            ranges: Vec::new(),
+            devices: HashMap::new(),
+        };
+        for region in &config.ram {
+            loader.add_ram(region)?;
+        }
+        for region in &config.rom {
+            loader.add_rom(region)?;
+        }
+        for mirror in &config.mirror {
+            loader.add_mirror(mirror)?;
+        }
+        for device in &config.device {
+            loader.add_device(device)?;
+        }
+        loader.build(config.cpu.as_ref())
+    }
+
+    /// Get a reference to the CPU
+    pub fn cpu(&self) -> &Cpu {
+        &self.cpu
+    }
+
+    /// Get a mutable reference to the CPU
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// machine.cpu_mut().step()?;
+    /// ```
+    pub fn cpu_mut(&mut self) -> &mut Cpu {
+        &mut self.cpu
+    }
+
+    /// Take the CPU out of the machine
+    pub fn into_cpu(self) -> Cpu {
+        self.cpu
+    }
+
+    /// Look up the id of a device by the name given in the description
+    ///
+    /// # Arguments
+    /// * `name` - Name of the region or device
+    ///
+    /// # Returns
+    /// * `Some(DeviceId)` if a region or device with this name exists
+    /// * `None` otherwise
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let id = machine.device_id("main").unwrap();
+    /// let ram = machine.cpu().bus().device::<Ram>(id).unwrap();
+    /// ```
+    pub fn device_id(&self, name: &str) -> Option<DeviceId> {
+        self.devices.get(name).copied()
+    }
+}
+
+/// Address range claimed by a region, mirror or device of the description
+struct ClaimedRange {
+    start: u16,
+    end: u16,
+    line: usize,
+}
+
+/// Image bytes to load into a memory region
+struct RegionImage {
+    data: Vec<u8>,
+    /// Offset inside the region
+    offset: usize,
+}
+
+/// State of a machine being built from a description
+struct Loader<'a> {
+    text: &'a str,
+    base_dir: &'a Path,
+    bus: BusController,
+    ranges: Vec<ClaimedRange>,
+    devices: HashMap<String, DeviceId>,
+}
+
+impl Loader<'_> {
+    /// Get the line of a span in the description
+    fn line<T>(&self, value: &Spanned<T>) -> usize {
+        line_of(self.text, value.span().start)
+    }
+
+    /// Reserve an address range, rejecting ranges past $FFFF and overlaps
+    fn claim(&mut self, start: u16, end: u32, line: usize) -> Result<u16, ConfigError> {
+        if end < start as u32 || end > 0xFFFF {
+            return Err(ConfigError::InvalidValue {
+                line,
+                message: format!("address range 0x{:04X}-0x{:X} is not valid", start, end),
+            });
+        }
+        let end = end as u16;
+        if let Some(other) = self
+            .ranges
+            .iter()
+            .find(|range| start <= range.end && end >= range.start)
+        {
+            return Err(ConfigError::Overlap {
+                line,
+                other_line: other.line,
+            });
+        }
+        self.ranges.push(ClaimedRange { start, end, line });
+        Ok(end)
+    }
+
+    /// Register a device claimed at `line` and remember its name
+    fn register(
+        &mut self,
+        start: u16,
+        end: u16,
+        device: Box<dyn BusDevice>,
+        name: Option<&String>,
+        line: usize,
+    ) -> Result<DeviceId, ConfigError> {
+        let id = self
+            .bus
+            .register_device(start, end, device)
+            .map_err(|error| ConfigError::InvalidValue {
+                line,
+                message: error.to_string(),
+            })?;
+        if let Some(name) = name
+            && self.devices.insert(name.clone(), id).is_some()
+        {
+            return Err(ConfigError::InvalidValue {
+                line,
+                message: format!("duplicate name `{}`", name),
+            });
+        }
+        Ok(id)
+    }
+
+    /// Claim the address range of a memory region and read its image, if any
+    ///
+    /// # Returns
+    /// * The end address of the region and its image
+    fn load_region(
+        &mut self,
+        region: &Spanned<MemoryConfig>,
+    ) -> Result<(u16, Option<RegionImage>), ConfigError> {
+        let config = region.get_ref();
+        let start = *config.start.get_ref();
+        let size = *config.size.get_ref();
+        if size == 0 {
+            return Err(ConfigError::InvalidValue {
+                line: self.line(&config.size),
+                message: "region size must not be zero".to_string(),
+            });
+        }
+        let end =
+            (start as u32)
+                .checked_add(size - 1)
+                .ok_or_else(|| ConfigError::InvalidValue {
+                    line: self.line(&config.size),
+                    message: format!("region size 0x{:X} is too large", size),
+                })?;
+        let end = self.claim(start, end, self.line(region))?;
+
+        let Some(image) = &config.image else {
+            return Ok((end, None));
+        };
+        let image_line = self.line(image);
+        let path = self.base_dir.join(image.get_ref());
+        let bytes = fs::read(&path).map_err(|_| ConfigError::MissingFile {
+            line: image_line,
+            path: path.display().to_string(),
+        })?;
+        let data = usize::try_from(config.image_offset)
+            .ok()
+            .and_then(|skip| bytes.get(skip..))
+            .ok_or_else(|| ConfigError::InvalidValue {
+                line: image_line,
+                message: format!(
+                    "image offset 0x{:X} is past the end of {}",
+                    config.image_offset,
+                    path.display()
+                ),
+            })?;
+        let offset = config.offset.as_ref().map_or(0, |offset| *offset.get_ref()) as usize;
+        if offset + data.len() > size as usize {
+            return Err(ConfigError::InvalidValue {
+                line: image_line,
+                message: format!(
+                    "image of {} bytes at offset 0x{:X} does not fit in a region of {} bytes",
+                    data.len(),
+                    offset,
+                    size
+                ),
+            });
+        }
+        Ok((
+            end,
+            Some(RegionImage {
+                data: data.to_vec(),
+                offset,
+            }),
+        ))
+    }
+
+    fn add_ram(&mut self, region: &Spanned<MemoryConfig>) -> Result<(), ConfigError> {
+        let config = region.get_ref();
+        let (end, image) = self.load_region(region)?;
+
+        let start = *config.start.get_ref();
+        let size = *config.size.get_ref() as usize;
+        let ram = if config.sparse {
+            Ram::sparse(size, start)
+        } else {
+            Ram::with_length(size, start)
+        };
+        let mut ram = ram.map_err(|message| ConfigError::InvalidValue {
+            line: self.line(&config.size),
+            message,
+        })?;
+        if let Some(image) = image {
+            ram.import(&image.data, image.offset)
+                .map_err(|message| ConfigError::InvalidValue {
+                    line: self.line(region),
+                    message,
+                })?;
+        }
+        self.register(
+            start,
+            end,
+            Box::new(ram),
+            config.name.as_ref(),
+            self.line(region),
+        )?;
+        Ok(())
+    }
+
+    fn add_rom(&mut self, region: &Spanned<MemoryConfig>) -> Result<(), ConfigError> {
+        let config = region.get_ref();
+        if config.sparse {
+            return Err(ConfigError::InvalidValue {
+                line: self.line(region),
+                message: "ROM regions cannot be sparse".to_string(),
+            });
+        }
+        let (end, image) = self.load_region(region)?;
+
+        let start = *config.start.get_ref();
+        let mut rom =
+            Rom::with_length(*config.size.get_ref() as usize, start).map_err(|message| {
+                ConfigError::InvalidValue {
+                    line: self.line(&config.size),
+                    message,
+                }
+            })?;
+        if let Some(image) = image {
+            rom.import(&image.data, image.offset)
+                .map_err(|message| ConfigError::InvalidValue {
+                    line: self.line(region),
+                    message,
+                })?;
+        }
+        self.register(
+            start,
+            end,
+            Box::new(rom),
+            config.name.as_ref(),
+            self.line(region),
+        )?;
+        Ok(())
+    }
+
+    fn add_mirror(&mut self, mirror: &Spanned<MirrorConfig>) -> Result<(), ConfigError> {
+        let line = self.line(mirror);
+        let config = mirror.get_ref();
+        if config.target_start > config.target_end {
+            return Err(ConfigError::InvalidValue {
+                line,
+                message: format!(
+                    "mirror target range 0x{:04X}-0x{:04X} is not valid",
+                    config.target_start, config.target_end
+                ),
+            });
+        }
+        self.claim(config.start, config.end as u32, line)?;
+        self.bus
+            .register_mirror(
+                config.start,
+                config.end,
+                config.target_start,
+                config.target_end,
+            )
+            .map_err(|error| ConfigError::InvalidValue {
+                line,
+                message: error.to_string(),
+            })
+    }
+
+    fn add_device(&mut self, device: &Spanned<DeviceConfig>) -> Result<(), ConfigError> {
+        let line = self.line(device);
+        let config = device.get_ref();
+        let (register_count, bus_device): (u16, Box<dyn BusDevice>) =
+            match config.kind.get_ref().as_str() {
+                "dma" => (
+                    DMA_CONTROLLER_REGISTER_COUNT,
+                    Box::new(DmaController::new(config.start)),
+                ),
+                other => {
+                    return Err(ConfigError::InvalidValue {
+                        line: self.line(&config.kind),
+                        message: format!("unknown device type `{}`", other),
+                    });
+                }
+            };
+        let end = self.claim(
+            config.start,
+            config.start as u32 + register_count as u32 - 1,
+            line,
+        )?;
+        let id = self.register(config.start, end, bus_device, config.name.as_ref(), line)?;
+
+        if config.clock_multiplier.is_some() || config.clock_divider.is_some() {
+            self.bus
+                .set_clock_ratio(
+                    id,
+                    config.clock_multiplier.unwrap_or(1),
+                    config.clock_divider.unwrap_or(1),
+                )
+                .map_err(|error| ConfigError::InvalidValue {
+                    line,
+                    message: error.to_string(),
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Create the CPU and point it at the reset vector
+    fn build(self, cpu_config: Option<&Spanned<CpuConfig>>) -> Result<Machine, ConfigError> {
+        let cpu_line = cpu_config.map_or(1, |config| self.line(config));
+        let variant = match cpu_config.and_then(|config| config.get_ref().variant.as_ref()) {
+            None => CpuVariant::default(),
+            Some(variant) => match variant.get_ref().as_str() {
+                "nmos" => CpuVariant::Nmos,
+                "cmos" => CpuVariant::Cmos,
+                other => {
+                    return Err(ConfigError::InvalidValue {
+                        line: self.line(variant),
+                        message: format!(
+                            "unknown CPU variant `{}`, expected `nmos` or `cmos`",
+                            other
+                        ),
+                    });
+                }
+            },
+        };
+        let reset_vector = cpu_config.and_then(|config| config.get_ref().reset_vector.as_ref());
+
+        // Find the line responsible for the vector before the bus moves into the CPU
+        let vector_line = match reset_vector {
+            Some(reset_vector) => self.line(reset_vector),
+            None => {
+                if !self.bus.is_mapped(RESET_VECTOR) || !self.bus.is_mapped(RESET_VECTOR + 1) {
+                    return Err(ConfigError::BadVector {
+                        line: cpu_line,
+                        message: format!(
+                            "nothing is mapped at the reset vector 0x{:04X}-0x{:04X}",
+                            RESET_VECTOR,
+                            RESET_VECTOR + 1
+                        ),
+                    });
+                }
+                self.range_line(RESET_VECTOR).unwrap_or(cpu_line)
+            }
+        };
+
+        // A new CPU is in its reset state apart from the program counter
+        let mut cpu = Cpu::new(self.bus);
+        cpu.set_variant(variant);
+        match reset_vector {
+            Some(reset_vector) => cpu.set_program_counter(*reset_vector.get_ref()),
+            None => cpu.reset().map_err(|error| ConfigError::BadVector {
+                line: vector_line,
+                message: error.to_string(),
+            })?,
+        }
+
+        let program_counter = cpu.registers().program_counter;
+        if !cpu.bus().is_mapped(program_counter) {
+            return Err(ConfigError::BadVector {
+                line: vector_line,
+                message: format!(
+                    "reset vector 0x{:04X} points to unmapped memory",
+                    program_counter
+                ),
+            });
+        }
+
+        Ok(Machine {
+            cpu,
+            devices: self.devices,
+        })
+    }
+
+    /// Get the line of the claimed range containing an address
+    fn range_line(&self, address: u16) -> Option<usize> {
+        self.ranges
+            .iter()
+            .find(|range| address >= range.start && address <= range.end)
+            .map(|range| range.line)
+    }
+}
+
+/// Convert a byte offset in the description to a 1-based line number
+fn line_of(text: &str, offset: usize) -> usize {
+    let offset = offset.min(text.len());
+    text.as_bytes()[..offset]
+        .iter()
+        .filter(|&&byte| byte == b'\n')
+        .count()
+        + 1
+}
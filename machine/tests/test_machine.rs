@@ -0,0 +1,315 @@
+//! Unit tests for machine descriptions
+//!
+//! This module contains tests for loading machine descriptions,
+//! testing region and image loading, mirrors, devices, the reset vector
+//! and the line numbers reported for invalid descriptions.
+
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+use bus::dma_controller::DmaController;
+use bus::trait_bus_device::BusDevice;
+use cpu6502::cpu_variant::CpuVariant;
+use machine::Machine;
+use machine::errors::ConfigError;
+use ram::Ram;
+
+/// Scratch directory of a test, removed when dropped
+struct ScratchDir(PathBuf);
+
+impl Deref for ScratchDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Create an empty scratch directory for a test
+fn scratch_dir(name: &str) -> ScratchDir {
+    let dir = std::env::temp_dir().join(format!("machine_test_{}_{}", std::process::id(), name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    ScratchDir(dir)
+}
+
+/// Write an 8K ROM image starting with `program` and with the reset vector at $E000
+fn write_rom_image(dir: &Path, program: &[u8]) {
+    let mut image = vec![0xEA; 0x2000];
+    image[..program.len()].copy_from_slice(program);
+    image[0x1FFC] = 0x00;
+    image[0x1FFD] = 0xE0;
+    fs::write(dir.join("rom.bin"), image).unwrap();
+}
+
+const BOARD: &str = r#"
+[cpu]
+variant = "cmos"
+
+[[ram]]
+name = "main"
+start = 0x0000
+size = 0x0800
+
+[[rom]]
+name = "firmware"
+start = 0xE000
+size = 0x2000
+image = "rom.bin"
+
+[[mirror]]
+start = 0x0800
+end = 0x1FFF
+target_start = 0x0000
+target_end = 0x07FF
+
+[[device]]
+type = "dma"
+name = "dma"
+start = 0xD000
+"#;
+
+// Test building machines
+#[test]
+fn test_load_board_from_file() {
+    let dir = scratch_dir("board");
+    // LDA #$42; STA $0810
+    write_rom_image(&dir, &[0xA9, 0x42, 0x8D, 0x10, 0x08]);
+    fs::write(dir.join("board.toml"), BOARD).unwrap();
+
+    let mut machine = Machine::from_file(dir.join("board.toml")).unwrap();
+    assert_eq!(machine.cpu().registers().program_counter, 0xE000);
+    assert_eq!(machine.cpu().variant(), CpuVariant::Cmos);
+
+    for _ in 0..6 {
+        machine.cpu_mut().step().unwrap();
+    }
+
+    // The write went through the mirror into RAM
+    let ram_id = machine.device_id("main").unwrap();
+    let ram = machine.cpu().bus().device::<Ram>(ram_id).unwrap();
+    assert_eq!(ram.export(0x0010, 1), vec![0x42]);
+}
+
+#[test]
+fn test_named_devices() {
+    let dir = scratch_dir("named");
+    write_rom_image(&dir, &[]);
+
+    let machine = Machine::from_toml_str(BOARD, &dir).unwrap();
+
+    let dma_id = machine.device_id("dma").unwrap();
+    let dma = machine.cpu().bus().device::<DmaController>(dma_id).unwrap();
+    assert!(!dma.is_busy());
+    assert!(machine.device_id("missing").is_none());
+}
+
+#[test]
+fn test_image_offsets() {
+    let dir = scratch_dir("offsets");
+    fs::write(dir.join("data.bin"), [0xFF, 0xFF, 0x11, 0x22]).unwrap();
+    let text = r#"
+[cpu]
+reset_vector = 0x0200
+
+[[ram]]
+name = "main"
+start = 0x0000
+size = 0x10000
+image = "data.bin"
+image_offset = 2
+offset = 0x0300
+"#;
+
+    let mut machine = Machine::from_toml_str(text, &dir).unwrap();
+
+    assert_eq!(machine.cpu().registers().program_counter, 0x0200);
+    let bus = machine.cpu_mut().bus_mut();
+    assert_eq!(bus.read(0x02FF).unwrap(), 0x00);
+    assert_eq!(bus.read(0x0300).unwrap(), 0x11);
+    assert_eq!(bus.read(0x0301).unwrap(), 0x22);
+}
+
+#[test]
+fn test_device_clock_ratio() {
+    let text = r#"
+[cpu]
+reset_vector = 0x0000
+
+[[ram]]
+start = 0x0000
+size = 0x0800
+
+[[device]]
+type = "dma"
+start = 0xD000
+clock_multiplier = 3
+"#;
+
+    assert!(Machine::from_toml_str(text, Path::new(".")).is_ok());
+}
+
+// Test error reporting
+#[test]
+fn test_syntax_error_line() {
+    let text = "[[ram]]\nstart = 0x0000\nsize = 0x0800\nspeed = 2\n";
+
+    let error = Machine::from_toml_str(text, Path::new(".")).err().unwrap();
+    assert!(matches!(error, ConfigError::Syntax { line: 4, .. }));
+}
+
+#[test]
+fn test_overlap_error_lines() {
+    let text = r#"[cpu]
+reset_vector = 0x0000
+
+[[ram]]
+start = 0x0000
+size = 0x2000
+
+[[rom]]
+start = 0x1000
+size = 0x0800
+"#;
+
+    let error = Machine::from_toml_str(text, Path::new(".")).err().unwrap();
+    assert!(matches!(
+        error,
+        ConfigError::Overlap {
+            line: 8,
+            other_line: 4
+        }
+    ));
+}
+
+#[test]
+fn test_mirror_overlap_error_line() {
+    let text = r#"[cpu]
+reset_vector = 0x0000
+
+[[ram]]
+start = 0x0000
+size = 0x0800
+
+[[mirror]]
+start = 0x0400
+end = 0x0FFF
+target_start = 0x0000
+target_end = 0x07FF
+"#;
+
+    let error = Machine::from_toml_str(text, Path::new(".")).err().unwrap();
+    assert_eq!(error.line(), Some(8));
+}
+
+#[test]
+fn test_missing_file_error_line() {
+    let dir = scratch_dir("missing");
+    let text = r#"[[rom]]
+start = 0xE000
+size = 0x2000
+image = "missing.bin"
+"#;
+
+    let error = Machine::from_toml_str(text, &dir).err().unwrap();
+    assert!(matches!(error, ConfigError::MissingFile { line: 4, .. }));
+}
+
+#[test]
+fn test_arbitrary_and_sparse_memory_sizes() {
+    let text = "[[ram]]\nstart = 0x0000\nsize = 0x1800\n\n[[ram]]\nstart = 0x1800\nsize = 0xE700\nsparse = true\n\n[[rom]]\nstart = 0xFF00\nsize = 0x100\n";
+
+    let mut machine = Machine::from_toml_str(text, Path::new(".")).unwrap();
+
+    let bus = machine.cpu_mut().bus_mut();
+    bus.write(0x17FF, 0x12).unwrap();
+    bus.write(0xFEFF, 0x34).unwrap();
+    assert_eq!(bus.read(0x17FF).unwrap(), 0x12);
+    assert_eq!(bus.read(0xFEFF).unwrap(), 0x34);
+}
+
+#[test]
+fn test_sparse_rom_error_line() {
+    let text = "[[ram]]\nstart = 0x0000\nsize = 0x0800\n\n[[rom]]\nstart = 0xF000\nsize = 0x1000\nsparse = true\n";
+
+    let error = Machine::from_toml_str(text, Path::new(".")).err().unwrap();
+    assert!(matches!(error, ConfigError::InvalidValue { line: 5, .. }));
+}
+
+#[test]
+fn test_region_past_end_of_address_space() {
+    let text = "[[rom]]\nstart = 0xC000\nsize = 0x8000\n";
+
+    let error = Machine::from_toml_str(text, Path::new(".")).err().unwrap();
+    assert!(matches!(error, ConfigError::InvalidValue { line: 1, .. }));
+}
+
+#[test]
+fn test_region_size_overflow() {
+    let text = "[[ram]]\nstart = 0x8000\nsize = 0xFFFFFFFF\n";
+
+    let error = Machine::from_toml_str(text, Path::new(".")).err().unwrap();
+    assert!(matches!(error, ConfigError::InvalidValue { line: 3, .. }));
+}
+
+#[test]
+fn test_device_params_rejected() {
+    let text = "[[device]]\ntype = \"dma\"\nstart = 0xDF00\nparams = { rate = 2 }\n";
+
+    let error = Machine::from_toml_str(text, Path::new(".")).err().unwrap();
+    assert!(matches!(error, ConfigError::Syntax { line: 4, .. }));
+}
+
+#[test]
+fn test_unknown_device_error_line() {
+    let text =
+        "[[ram]]\nstart = 0x0000\nsize = 0x0800\n\n[[device]]\ntype = \"uart\"\nstart = 0xD000\n";
+
+    let error = Machine::from_toml_str(text, Path::new(".")).err().unwrap();
+    assert!(matches!(error, ConfigError::InvalidValue { line: 6, .. }));
+}
+
+#[test]
+fn test_unmapped_reset_vector() {
+    let text = "[cpu]\nvariant = \"nmos\"\n\n[[ram]]\nstart = 0x0000\nsize = 0x0800\n";
+
+    let error = Machine::from_toml_str(text, Path::new(".")).err().unwrap();
+    assert!(matches!(error, ConfigError::BadVector { line: 1, .. }));
+}
+
+#[test]
+fn test_reset_vector_to_unmapped_memory() {
+    let dir = scratch_dir("bad_vector");
+    // Reset vector $E000 in a ROM mapped at $F000 points below the ROM
+    let mut image = vec![0xEA; 0x1000];
+    image[0x0FFC] = 0x00;
+    image[0x0FFD] = 0xE0;
+    fs::write(dir.join("rom.bin"), image).unwrap();
+    let text = r#"[[ram]]
+start = 0x0000
+size = 0x0800
+
+[[rom]]
+start = 0xF000
+size = 0x1000
+image = "rom.bin"
+"#;
+
+    let error = Machine::from_toml_str(text, &dir).err().unwrap();
+    assert!(matches!(error, ConfigError::BadVector { line: 5, .. }));
+}
+
+#[test]
+fn test_reset_vector_override_to_unmapped_memory() {
+    let text = "[[ram]]\nstart = 0x0000\nsize = 0x0800\n\n[cpu]\nreset_vector = 0x9000\n";
+
+    let error = Machine::from_toml_str(text, Path::new(".")).err().unwrap();
+    assert!(matches!(error, ConfigError::BadVector { line: 6, .. }));
+}
@@ -0,0 +1,165 @@
+//! Delta modulation channel of the APU.
+
+/// Output unit periods selected by the rate register, in CPU cycles (NTSC)
+const RATES: [u16; 16] = [
+    428, 380, 340, 320, 286, 254, 226, 214, 190, 160, 142, 128, 106, 84, 72, 54,
+];
+
+/// Halted cycles before the sample byte is read: halt, dummy and alignment cycles
+const FETCH_DELAY: u8 = 3;
+
+/// 1-bit delta modulation of a 7-bit output level, fed by sample bytes read by DMA
+#[derive(Debug, Clone)]
+pub(crate) struct Dmc {
+    irq_enabled: bool,
+    looping: bool,
+    period: u16,
+    timer: u16,
+    /// 7-bit output level
+    level: u8,
+    /// Programmed sample start address
+    sample_address: u16,
+    /// Programmed sample length in bytes
+    sample_length: u16,
+    /// Address of the next byte to fetch
+    current_address: u16,
+    bytes_remaining: u16,
+    /// Byte fetched by the memory reader, waiting for the output unit
+    buffer: Option<u8>,
+    shift_register: u8,
+    bits_remaining: u8,
+    /// No sample byte was available when the output cycle started
+    silence: bool,
+    /// Halted cycles spent waiting for the sample fetch
+    fetch_delay: u8,
+    pub(crate) irq: bool,
+}
+
+impl Dmc {
+    pub(crate) fn new() -> Self {
+        Self {
+            irq_enabled: false,
+            looping: false,
+            period: RATES[0],
+            timer: 0,
+            level: 0,
+            sample_address: 0xC000,
+            sample_length: 1,
+            current_address: 0xC000,
+            bytes_remaining: 0,
+            buffer: None,
+            shift_register: 0,
+            bits_remaining: 8,
+            silence: true,
+            fetch_delay: 0,
+            irq: false,
+        }
+    }
+
+    /// Write one of the four channel registers
+    pub(crate) fn write(&mut self, register: u16, value: u8) {
+        match register {
+            0 => {
+                self.irq_enabled = value & 0x80 != 0;
+                if !self.irq_enabled {
+                    self.irq = false;
+                }
+                self.looping = value & 0x40 != 0;
+                self.period = RATES[(value & 0x0F) as usize];
+            }
+            1 => self.level = value & 0x7F,
+            2 => self.sample_address = 0xC000 | (value as u16) << 6,
+            // 3: Sample length
+            _ => self.sample_length = (value as u16) << 4 | 1,
+        }
+    }
+
+    /// Enable or disable the channel through the status register
+    pub(crate) fn set_enabled(&mut self, enabled: bool) {
+        self.irq = false;
+        if !enabled {
+            self.bytes_remaining = 0;
+        } else if self.bytes_remaining == 0 {
+            self.restart();
+        }
+    }
+
+    fn restart(&mut self) {
+        self.current_address = self.sample_address;
+        self.bytes_remaining = self.sample_length;
+    }
+
+    /// Bytes of the sample not fetched yet, reported in the status register
+    pub(crate) fn is_active(&self) -> bool {
+        self.bytes_remaining > 0
+    }
+
+    /// The memory reader needs the bus, and holds RDY low until it got it
+    pub(crate) fn wants_fetch(&self) -> bool {
+        self.buffer.is_none() && self.bytes_remaining > 0
+    }
+
+    /// Address to read once the fetch delay has passed, polled while the CPU is halted
+    pub(crate) fn fetch_address(&mut self) -> Option<u16> {
+        if !self.wants_fetch() {
+            return None;
+        }
+        if self.fetch_delay < FETCH_DELAY {
+            self.fetch_delay += 1;
+            return None;
+        }
+        self.fetch_delay = 0;
+        Some(self.current_address)
+    }
+
+    /// Store a fetched sample byte and move to the next one
+    pub(crate) fn fetched(&mut self, data: u8) {
+        self.buffer = Some(data);
+        // The address wraps from $FFFF to $8000
+        self.current_address = self.current_address.checked_add(1).unwrap_or(0x8000);
+        self.bytes_remaining -= 1;
+        if self.bytes_remaining == 0 {
+            if self.looping {
+                self.restart();
+            } else if self.irq_enabled {
+                self.irq = true;
+            }
+        }
+    }
+
+    /// Advance the output unit by one CPU cycle
+    pub(crate) fn clock_timer(&mut self) {
+        if self.timer > 0 {
+            self.timer -= 1;
+            return;
+        }
+        self.timer = self.period - 1;
+
+        if !self.silence {
+            if self.shift_register & 0x01 != 0 {
+                if self.level <= 125 {
+                    self.level += 2;
+                }
+            } else if self.level >= 2 {
+                self.level -= 2;
+            }
+        }
+        self.shift_register >>= 1;
+        self.bits_remaining -= 1;
+        if self.bits_remaining == 0 {
+            self.bits_remaining = 8;
+            match self.buffer.take() {
+                Some(data) => {
+                    self.silence = false;
+                    self.shift_register = data;
+                }
+                None => self.silence = true,
+            }
+        }
+    }
+
+    /// Output level, 0-127
+    pub(crate) fn output(&self) -> u8 {
+        self.level
+    }
+}
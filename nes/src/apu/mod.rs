@@ -0,0 +1,410 @@
+//! Audio processing unit of the Ricoh 2A03.
+//!
+//! Register map on the CPU bus:
+//! - $4000-$4003: pulse 1 duty/envelope, sweep, timer low, length/timer high
+//! - $4004-$4007: pulse 2, same layout as pulse 1
+//! - $4008-$400B: triangle linear counter, unused, timer low, length/timer high
+//! - $400C-$400F: noise envelope, unused, mode/period, length
+//! - $4010-$4013: DMC flags/rate, direct load, sample address, sample length
+//! - $4015: channel enables on write, length counter and IRQ status on read
+//! - $4017: frame counter mode and IRQ inhibit (write only)
+//!
+//! $4014 (OAM DMA) and $4016 (controller port) are not part of the APU, so the APU is
+//! registered at $4000-$4013 with $4015 and $4017 added as extra ranges. The APU is
+//! clocked by the CPU clock, one `tick` per CPU cycle, with the NTSC frame counter and
+//! timer periods. The channels are mixed with the non-linear DAC formulas into 16-bit
+//! samples at the sample rate, see `Apu::with_sample_rate`.
+//!
+//! The DMC reads its samples from CPU memory: it holds RDY low, and once the CPU is
+//! halted takes the bus through `BusDevice::dma_request`, stealing four cycles per byte.
+
+mod dmc;
+mod noise;
+mod pulse;
+mod triangle;
+mod units;
+
+use bus::errors::BusError;
+use bus::trait_bus_device::{BusDevice, DmaRequest};
+
+use crate::apu::dmc::Dmc;
+use crate::apu::noise::Noise;
+use crate::apu::pulse::Pulse;
+use crate::apu::triangle::Triangle;
+
+/// First channel register of the APU
+pub const APU_START: u16 = 0x4000;
+/// Last channel register of the APU
+pub const APU_END: u16 = 0x4013;
+/// Status register
+pub const APU_STATUS: u16 = 0x4015;
+/// Frame counter register
+pub const APU_FRAME_COUNTER: u16 = 0x4017;
+/// CPU clock of the NTSC NES, in Hz
+pub const DEFAULT_CPU_CLOCK: u32 = 1_789_773;
+/// Sample rate of the rendered audio unless configured otherwise, in Hz
+pub const DEFAULT_SAMPLE_RATE: u32 = 44_100;
+
+/// Status: frame counter interrupt
+pub const STATUS_FRAME_IRQ: u8 = 0x40;
+/// Status: DMC interrupt
+pub const STATUS_DMC_IRQ: u8 = 0x80;
+
+/// Frame counter: 5-step sequence
+const FRAME_FIVE_STEP: u8 = 0x80;
+/// Frame counter: interrupt inhibit
+const FRAME_IRQ_INHIBIT: u8 = 0x40;
+
+/// CPU cycles into the sequence of the quarter frame steps
+const QUARTER_FRAME_1: u32 = 7457;
+const QUARTER_FRAME_2: u32 = 14913;
+const QUARTER_FRAME_3: u32 = 22371;
+/// Last step of the 4-step sequence, which raises the frame interrupt
+const FOUR_STEP_LAST: u32 = 29829;
+/// Last step of the 5-step sequence
+const FIVE_STEP_LAST: u32 = 37281;
+
+/// Represents the APU of a Ricoh 2A03.
+#[derive(Debug)]
+pub struct Apu {
+    pulse_1: Pulse,
+    pulse_2: Pulse,
+    triangle: Triangle,
+    noise: Noise,
+    dmc: Dmc,
+    /// CPU cycles into the frame counter sequence
+    frame_cycle: u32,
+    five_step: bool,
+    irq_inhibit: bool,
+    frame_irq: bool,
+    /// Pulse timers are clocked on every other CPU cycle
+    odd_cycle: bool,
+    /// CPU cycles stolen by DMC sample fetches
+    dmc_cycles: u64,
+    /// Frequency of the CPU clock in Hz
+    cpu_clock: u32,
+    sample_rate: u32,
+    /// Fraction of the next sample, in units of 1 / cpu_clock samples
+    sample_phase: u64,
+    /// Sum of the mixer outputs since the last sample
+    sample_sum: f64,
+    /// Cycles summed into `sample_sum`
+    sample_cycles: u32,
+    samples: Vec<i16>,
+}
+
+impl Apu {
+    /// Create a new APU in its power-on state.
+    ///
+    /// # Returns
+    /// * A new Apu instance with all channels disabled, rendering at the NTSC clock and
+    ///   default sample rate
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let id = bus.register_device(APU_START, APU_END, Box::new(Apu::new()))?;
+    /// bus.register_device_range(id, APU_STATUS, APU_STATUS)?;
+    /// bus.register_device_range(id, APU_FRAME_COUNTER, APU_FRAME_COUNTER)?;
+    /// ```
+    pub fn new() -> Self {
+        Self {
+            pulse_1: Pulse::new(true),
+            pulse_2: Pulse::new(false),
+            triangle: Triangle::default(),
+            noise: Noise::new(),
+            dmc: Dmc::new(),
+            frame_cycle: 0,
+            five_step: false,
+            irq_inhibit: false,
+            frame_irq: false,
+            odd_cycle: false,
+            dmc_cycles: 0,
+            cpu_clock: DEFAULT_CPU_CLOCK,
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            sample_phase: 0,
+            sample_sum: 0.0,
+            sample_cycles: 0,
+            samples: Vec::new(),
+        }
+    }
+
+    /// Set the CPU clock and the sample rate of the rendered audio.
+    ///
+    /// Only the sample timing changes, the frame counter keeps its NTSC periods.
+    ///
+    /// # Arguments
+    /// * `cpu_clock` - Frequency of the CPU clock in Hz
+    /// * `sample_rate` - Samples per second of the rendered audio
+    ///
+    /// # Returns
+    /// * The Apu with the new clock and sample rate
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let apu = Apu::new().with_sample_rate(1_789_773, 48_000);
+    /// ```
+    pub fn with_sample_rate(mut self, cpu_clock: u32, sample_rate: u32) -> Self {
+        self.cpu_clock = cpu_clock.max(1);
+        self.sample_rate = sample_rate.clamp(1, self.cpu_clock);
+        self
+    }
+
+    /// Reset the 2A03.
+    ///
+    /// Silences all channels, restarts the frame counter in 4-step mode and discards
+    /// unread samples.
+    pub fn reset(&mut self) {
+        let cpu_clock = self.cpu_clock;
+        let sample_rate = self.sample_rate;
+        *self = Self::new().with_sample_rate(cpu_clock, sample_rate);
+    }
+
+    /// Samples per second of the rendered audio
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Samples rendered and not taken yet
+    pub fn samples(&self) -> &[i16] {
+        &self.samples
+    }
+
+    /// Take the samples rendered so far
+    ///
+    /// # Returns
+    /// * Unsigned DAC output as 16-bit mono samples at the sample rate, 0 when all channel
+    ///   levels are 0
+    pub fn take_samples(&mut self) -> Vec<i16> {
+        std::mem::take(&mut self.samples)
+    }
+
+    /// Get the total number of CPU cycles stolen by DMC sample fetches.
+    ///
+    /// # Returns
+    /// * The number of cycles the CPU was halted for the DMC so far
+    pub fn dmc_cycles(&self) -> u64 {
+        self.dmc_cycles
+    }
+
+    /// Current timer period of a pulse channel, as changed by its sweep unit
+    ///
+    /// # Arguments
+    /// * `channel` - Pulse channel, 1 or 2
+    ///
+    /// # Returns
+    /// * `Some(period)` with the 11-bit timer period
+    /// * `None` if there is no such channel
+    pub fn pulse_period(&self, channel: u8) -> Option<u16> {
+        match channel {
+            1 => Some(self.pulse_1.period()),
+            2 => Some(self.pulse_2.period()),
+            _ => None,
+        }
+    }
+
+    /// Output of the non-linear DAC, from 0.0 for silence to just below 1.0
+    pub fn output(&self) -> f64 {
+        let pulse = (self.pulse_1.output() + self.pulse_2.output()) as f64;
+        let pulse_out = if pulse == 0.0 {
+            0.0
+        } else {
+            95.88 / (8128.0 / pulse + 100.0)
+        };
+        let tnd = self.triangle.output() as f64 / 8227.0
+            + self.noise.output() as f64 / 12241.0
+            + self.dmc.output() as f64 / 22638.0;
+        let tnd_out = if tnd == 0.0 {
+            0.0
+        } else {
+            159.79 / (1.0 / tnd + 100.0)
+        };
+        pulse_out + tnd_out
+    }
+
+    /// Value of the status register
+    fn status(&self) -> u8 {
+        let mut status = 0;
+        if self.pulse_1.length.is_active() {
+            status |= 0x01;
+        }
+        if self.pulse_2.length.is_active() {
+            status |= 0x02;
+        }
+        if self.triangle.length.is_active() {
+            status |= 0x04;
+        }
+        if self.noise.length.is_active() {
+            status |= 0x08;
+        }
+        if self.dmc.is_active() {
+            status |= 0x10;
+        }
+        if self.frame_irq {
+            status |= STATUS_FRAME_IRQ;
+        }
+        if self.dmc.irq {
+            status |= STATUS_DMC_IRQ;
+        }
+        status
+    }
+
+    /// Write the channel enables of the status register
+    fn set_enables(&mut self, value: u8) {
+        self.pulse_1.length.set_enabled(value & 0x01 != 0);
+        self.pulse_2.length.set_enabled(value & 0x02 != 0);
+        self.triangle.length.set_enabled(value & 0x04 != 0);
+        self.noise.length.set_enabled(value & 0x08 != 0);
+        self.dmc.set_enabled(value & 0x10 != 0);
+    }
+
+    /// Write the frame counter register, restarting the sequence
+    fn set_frame_counter(&mut self, value: u8) {
+        self.five_step = value & FRAME_FIVE_STEP != 0;
+        self.irq_inhibit = value & FRAME_IRQ_INHIBIT != 0;
+        if self.irq_inhibit {
+            self.frame_irq = false;
+        }
+        self.frame_cycle = 0;
+        // The 5-step mode clocks the units at once
+        if self.five_step {
+            self.clock_quarter_frame();
+            self.clock_half_frame();
+        }
+    }
+
+    /// Clock the envelopes and the triangle linear counter
+    fn clock_quarter_frame(&mut self) {
+        self.pulse_1.envelope.clock();
+        self.pulse_2.envelope.clock();
+        self.noise.envelope.clock();
+        self.triangle.clock_linear_counter();
+    }
+
+    /// Clock the length counters and the sweep units
+    fn clock_half_frame(&mut self) {
+        self.pulse_1.length.clock();
+        self.pulse_2.length.clock();
+        self.triangle.length.clock();
+        self.noise.length.clock();
+        self.pulse_1.clock_sweep();
+        self.pulse_2.clock_sweep();
+    }
+
+    /// Advance the frame counter sequence by one CPU cycle
+    fn clock_frame_counter(&mut self) {
+        self.frame_cycle += 1;
+        match (self.frame_cycle, self.five_step) {
+            (QUARTER_FRAME_1 | QUARTER_FRAME_3, _) => self.clock_quarter_frame(),
+            (QUARTER_FRAME_2, _) | (FIVE_STEP_LAST, true) => {
+                self.clock_quarter_frame();
+                self.clock_half_frame();
+            }
+            (FOUR_STEP_LAST, false) => {
+                self.clock_quarter_frame();
+                self.clock_half_frame();
+                if !self.irq_inhibit {
+                    self.frame_irq = true;
+                }
+            }
+            (cycle, false) if cycle > FOUR_STEP_LAST => self.frame_cycle = 0,
+            (cycle, true) if cycle > FIVE_STEP_LAST => self.frame_cycle = 0,
+            _ => {}
+        }
+    }
+
+    /// Average the mixer output into a sample once a sample period has passed
+    fn render(&mut self) {
+        self.sample_sum += self.output();
+        self.sample_cycles += 1;
+        self.sample_phase += self.sample_rate as u64;
+        if self.sample_phase < self.cpu_clock as u64 {
+            return;
+        }
+        self.sample_phase -= self.cpu_clock as u64;
+        let average = self.sample_sum / self.sample_cycles as f64;
+        self.samples
+            .push((average * 32767.0).round().clamp(0.0, 32767.0) as i16);
+        self.sample_sum = 0.0;
+        self.sample_cycles = 0;
+    }
+}
+
+impl Default for Apu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BusDevice for Apu {
+    fn read(&mut self, address: u16) -> Result<u8, BusError> {
+        match address {
+            APU_STATUS => {
+                let status = self.status();
+                self.frame_irq = false;
+                Ok(status)
+            }
+            // Write-only registers read back the open bus, approximated by the address high byte
+            APU_START..=APU_END | APU_FRAME_COUNTER => Ok((address >> 8) as u8),
+            _ => Err(BusError::AddressOutOfRange(address)),
+        }
+    }
+
+    fn write(&mut self, address: u16, data: u8) -> Result<(), BusError> {
+        let register = address & 0x03;
+        match address {
+            0x4000..=0x4003 => self.pulse_1.write(register, data),
+            0x4004..=0x4007 => self.pulse_2.write(register, data),
+            0x4008..=0x400B => self.triangle.write(register, data),
+            0x400C..=0x400F => self.noise.write(register, data),
+            0x4010..=APU_END => self.dmc.write(register, data),
+            APU_STATUS => self.set_enables(data),
+            APU_FRAME_COUNTER => self.set_frame_counter(data),
+            _ => return Err(BusError::AddressOutOfRange(address)),
+        }
+        Ok(())
+    }
+
+    fn tick(&mut self) {
+        self.clock_frame_counter();
+        if self.odd_cycle {
+            self.pulse_1.clock_timer();
+            self.pulse_2.clock_timer();
+        }
+        self.odd_cycle = !self.odd_cycle;
+        self.triangle.clock_timer();
+        self.noise.clock_timer();
+        self.dmc.clock_timer();
+        self.render();
+    }
+
+    fn check_irq(&self) -> bool {
+        self.frame_irq || self.dmc.irq
+    }
+
+    fn check_nmi(&self) -> bool {
+        // The APU only drives IRQ
+        false
+    }
+
+    fn check_rdy(&self) -> bool {
+        self.dmc.wants_fetch()
+    }
+
+    fn check_so(&self) -> bool {
+        // The APU does not drive the SO line
+        false
+    }
+
+    fn dma_request(&mut self) -> Option<DmaRequest> {
+        if !self.dmc.wants_fetch() {
+            return None;
+        }
+        self.dmc_cycles += 1;
+        self.dmc.fetch_address().map(DmaRequest::Read)
+    }
+
+    fn dma_response(&mut self, data: Option<u8>) {
+        // Reads from unmapped addresses return an open bus, approximated as 0xFF
+        self.dmc.fetched(data.unwrap_or(0xFF));
+    }
+}
@@ -0,0 +1,74 @@
+//! Noise channel of the APU.
+
+use crate::apu::units::{Envelope, LengthCounter};
+
+/// Timer periods selected by the period register, in CPU cycles (NTSC)
+const PERIODS: [u16; 16] = [
+    4, 8, 16, 32, 64, 96, 128, 160, 202, 254, 380, 508, 762, 1016, 2034, 4068,
+];
+
+/// Pseudo-random noise from a 15-bit linear feedback shift register
+#[derive(Debug, Clone)]
+pub(crate) struct Noise {
+    shift_register: u16,
+    /// Short mode, feedback from bit 6 instead of bit 1 for a 93-step sequence
+    short_mode: bool,
+    period: u16,
+    timer: u16,
+    pub(crate) envelope: Envelope,
+    pub(crate) length: LengthCounter,
+}
+
+impl Noise {
+    pub(crate) fn new() -> Self {
+        Self {
+            shift_register: 1,
+            short_mode: false,
+            period: PERIODS[0],
+            timer: 0,
+            envelope: Envelope::default(),
+            length: LengthCounter::default(),
+        }
+    }
+
+    /// Write one of the channel registers, 1 is unused
+    pub(crate) fn write(&mut self, register: u16, value: u8) {
+        match register {
+            0 => {
+                self.length.set_halted(value & 0x20 != 0);
+                self.envelope.set_control(value);
+            }
+            2 => {
+                self.short_mode = value & 0x80 != 0;
+                self.period = PERIODS[(value & 0x0F) as usize];
+            }
+            3 => {
+                self.length.load(value);
+                self.envelope.restart();
+            }
+            // 1: Unused
+            _ => {}
+        }
+    }
+
+    /// Advance the timer by one CPU cycle
+    pub(crate) fn clock_timer(&mut self) {
+        if self.timer > 0 {
+            self.timer -= 1;
+            return;
+        }
+        self.timer = self.period - 1;
+        let tap = if self.short_mode { 6 } else { 1 };
+        let feedback = (self.shift_register ^ (self.shift_register >> tap)) & 0x01;
+        self.shift_register = (self.shift_register >> 1) | feedback << 14;
+    }
+
+    /// Output level, 0-15
+    pub(crate) fn output(&self) -> u8 {
+        if self.shift_register & 0x01 != 0 || !self.length.is_active() {
+            0
+        } else {
+            self.envelope.volume()
+        }
+    }
+}
@@ -0,0 +1,133 @@
+//! Pulse channels of the APU.
+
+use crate::apu::units::{Envelope, LengthCounter};
+
+/// Waveforms selected by the duty bits, one bit per sequencer step
+const DUTY_SEQUENCES: [u8; 4] = [0b0100_0000, 0b0110_0000, 0b0111_1000, 0b1001_1111];
+
+/// Square wave with duty cycle, envelope, sweep and length counter
+#[derive(Debug, Clone)]
+pub(crate) struct Pulse {
+    /// Pulse 1 negates with one's complement, pulse 2 with two's complement
+    ones_complement: bool,
+    duty: u8,
+    /// Sequencer step, 0-7
+    step: u8,
+    /// 11-bit timer period, in APU cycles
+    period: u16,
+    timer: u16,
+    pub(crate) envelope: Envelope,
+    pub(crate) length: LengthCounter,
+    sweep_enabled: bool,
+    sweep_period: u8,
+    sweep_negate: bool,
+    sweep_shift: u8,
+    sweep_divider: u8,
+    sweep_reload: bool,
+}
+
+impl Pulse {
+    /// Create a pulse channel
+    ///
+    /// # Arguments
+    /// * `ones_complement` - `true` for pulse 1, whose sweep subtracts one more
+    pub(crate) fn new(ones_complement: bool) -> Self {
+        Self {
+            ones_complement,
+            duty: 0,
+            step: 0,
+            period: 0,
+            timer: 0,
+            envelope: Envelope::default(),
+            length: LengthCounter::default(),
+            sweep_enabled: false,
+            sweep_period: 0,
+            sweep_negate: false,
+            sweep_shift: 0,
+            sweep_divider: 0,
+            sweep_reload: false,
+        }
+    }
+
+    /// Write one of the four channel registers
+    pub(crate) fn write(&mut self, register: u16, value: u8) {
+        match register {
+            0 => {
+                self.duty = value >> 6;
+                self.length.set_halted(value & 0x20 != 0);
+                self.envelope.set_control(value);
+            }
+            1 => {
+                self.sweep_enabled = value & 0x80 != 0;
+                self.sweep_period = (value >> 4) & 0x07;
+                self.sweep_negate = value & 0x08 != 0;
+                self.sweep_shift = value & 0x07;
+                self.sweep_reload = true;
+            }
+            2 => self.period = (self.period & 0x700) | value as u16,
+            // 3: Length counter load and timer high bits
+            _ => {
+                self.period = (self.period & 0x0FF) | (value as u16 & 0x07) << 8;
+                self.length.load(value);
+                self.step = 0;
+                self.envelope.restart();
+            }
+        }
+    }
+
+    /// Advance the timer by one APU cycle, two CPU cycles
+    pub(crate) fn clock_timer(&mut self) {
+        if self.timer == 0 {
+            self.timer = self.period;
+            self.step = (self.step + 1) & 0x07;
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    /// Period the sweep unit would set, which may overflow 11 bits
+    fn sweep_target(&self) -> u16 {
+        let change = self.period >> self.sweep_shift;
+        if !self.sweep_negate {
+            self.period + change
+        } else if self.ones_complement {
+            self.period.saturating_sub(change + 1)
+        } else {
+            self.period.saturating_sub(change)
+        }
+    }
+
+    /// The sweep unit silences the channel, even while sweeping is disabled
+    fn is_muted(&self) -> bool {
+        self.period < 8 || self.sweep_target() > 0x7FF
+    }
+
+    /// Clock the sweep unit on a half frame
+    pub(crate) fn clock_sweep(&mut self) {
+        if self.sweep_divider == 0 && self.sweep_enabled && self.sweep_shift > 0 && !self.is_muted()
+        {
+            self.period = self.sweep_target();
+        }
+        if self.sweep_divider == 0 || self.sweep_reload {
+            self.sweep_divider = self.sweep_period;
+            self.sweep_reload = false;
+        } else {
+            self.sweep_divider -= 1;
+        }
+    }
+
+    /// Current 11-bit timer period
+    pub(crate) fn period(&self) -> u16 {
+        self.period
+    }
+
+    /// Output level, 0-15
+    pub(crate) fn output(&self) -> u8 {
+        let high = DUTY_SEQUENCES[self.duty as usize] & (0x80 >> self.step) != 0;
+        if !high || !self.length.is_active() || self.is_muted() {
+            0
+        } else {
+            self.envelope.volume()
+        }
+    }
+}
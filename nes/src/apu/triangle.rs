@@ -0,0 +1,75 @@
+//! Triangle channel of the APU.
+
+use crate::apu::units::LengthCounter;
+
+/// Triangle wave stepped by the timer while the linear and length counters run
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Triangle {
+    /// Sequencer step, 0-31
+    step: u8,
+    /// 11-bit timer period, in CPU cycles
+    period: u16,
+    timer: u16,
+    pub(crate) length: LengthCounter,
+    /// Halts the length counter and keeps reloading the linear counter
+    control: bool,
+    linear_reload_value: u8,
+    linear_counter: u8,
+    linear_reload: bool,
+}
+
+impl Triangle {
+    /// Write one of the channel registers, 1 is unused
+    pub(crate) fn write(&mut self, register: u16, value: u8) {
+        match register {
+            0 => {
+                self.control = value & 0x80 != 0;
+                self.length.set_halted(self.control);
+                self.linear_reload_value = value & 0x7F;
+            }
+            2 => self.period = (self.period & 0x700) | value as u16,
+            3 => {
+                self.period = (self.period & 0x0FF) | (value as u16 & 0x07) << 8;
+                self.length.load(value);
+                self.linear_reload = true;
+            }
+            // 1: Unused
+            _ => {}
+        }
+    }
+
+    /// Advance the timer by one CPU cycle
+    pub(crate) fn clock_timer(&mut self) {
+        if self.timer == 0 {
+            self.timer = self.period;
+            if self.length.is_active() && self.linear_counter > 0 {
+                self.step = (self.step + 1) & 0x1F;
+            }
+        } else {
+            self.timer -= 1;
+        }
+    }
+
+    /// Clock the linear counter on a quarter frame
+    pub(crate) fn clock_linear_counter(&mut self) {
+        if self.linear_reload {
+            self.linear_counter = self.linear_reload_value;
+        } else if self.linear_counter > 0 {
+            self.linear_counter -= 1;
+        }
+        if !self.control {
+            self.linear_reload = false;
+        }
+    }
+
+    /// Output level, 0-15
+    ///
+    /// The sequencer holds its step while silenced, so the output stays at its last level.
+    pub(crate) fn output(&self) -> u8 {
+        if self.step < 16 {
+            15 - self.step
+        } else {
+            self.step - 16
+        }
+    }
+}
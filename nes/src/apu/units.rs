@@ -0,0 +1,104 @@
+//! Envelope and length counter units shared by the APU channels.
+
+/// Length counter values selected by bits 3-7 of the length register
+const LENGTH_TABLE: [u8; 32] = [
+    10, 254, 20, 2, 40, 4, 80, 6, 160, 8, 60, 10, 14, 12, 26, 14, 12, 16, 24, 18, 48, 20, 96, 22,
+    192, 24, 72, 26, 16, 28, 32, 30,
+];
+
+/// Volume envelope of the pulse and noise channels, clocked by quarter frames
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Envelope {
+    /// Restart the envelope on the next quarter frame
+    start: bool,
+    /// Output `period` as a constant volume instead of the decay level
+    constant: bool,
+    /// Restart at 15 after reaching 0, shared with the length counter halt flag
+    looping: bool,
+    /// Constant volume or decay period
+    period: u8,
+    divider: u8,
+    decay: u8,
+}
+
+impl Envelope {
+    /// Write the `--LC VVVV` bits of the channel control register
+    pub(crate) fn set_control(&mut self, value: u8) {
+        self.looping = value & 0x20 != 0;
+        self.constant = value & 0x10 != 0;
+        self.period = value & 0x0F;
+    }
+
+    /// Restart the envelope, done by writes to the length register
+    pub(crate) fn restart(&mut self) {
+        self.start = true;
+    }
+
+    pub(crate) fn clock(&mut self) {
+        if self.start {
+            self.start = false;
+            self.decay = 15;
+            self.divider = self.period;
+        } else if self.divider > 0 {
+            self.divider -= 1;
+        } else {
+            self.divider = self.period;
+            if self.decay > 0 {
+                self.decay -= 1;
+            } else if self.looping {
+                self.decay = 15;
+            }
+        }
+    }
+
+    /// Current 4-bit volume
+    pub(crate) fn volume(&self) -> u8 {
+        if self.constant {
+            self.period
+        } else {
+            self.decay
+        }
+    }
+}
+
+/// Length counter silencing a channel after a programmed duration, clocked by half frames
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LengthCounter {
+    /// The channel is enabled in the status register
+    enabled: bool,
+    /// Counting is halted by the channel control register
+    halted: bool,
+    counter: u8,
+}
+
+impl LengthCounter {
+    /// Enable or disable the channel, disabling clears the counter
+    pub(crate) fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.counter = 0;
+        }
+    }
+
+    pub(crate) fn set_halted(&mut self, halted: bool) {
+        self.halted = halted;
+    }
+
+    /// Load the counter from bits 3-7 of a length register, if the channel is enabled
+    pub(crate) fn load(&mut self, value: u8) {
+        if self.enabled {
+            self.counter = LENGTH_TABLE[(value >> 3) as usize];
+        }
+    }
+
+    pub(crate) fn clock(&mut self) {
+        if !self.halted && self.counter > 0 {
+            self.counter -= 1;
+        }
+    }
+
+    /// The counter has not run out, reported in the status register
+    pub(crate) fn is_active(&self) -> bool {
+        self.counter > 0
+    }
+}
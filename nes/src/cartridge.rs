@@ -0,0 +1,199 @@
+//! Cartridge device mounted on the CPU bus.
+//!
+//! A `Cartridge` is a cheap handle to the shared cartridge state. One clone is registered
+//! with the `BusController` at $4020-$FFFF, another is kept by the PPU for CHR access.
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use bus::errors::BusError;
+use bus::trait_bus_device::BusDevice;
+
+use crate::errors::CartridgeError;
+use crate::header::{HEADER_SIZE, INesHeader, Mirroring, TRAINER_SIZE};
+use crate::mappers::{self, CartridgeMemory, Mapper};
+
+/// First CPU address decoded by the cartridge
+pub const CARTRIDGE_START: u16 = 0x4020;
+/// Last CPU address decoded by the cartridge
+pub const CARTRIDGE_END: u16 = 0xFFFF;
+/// Offset of the trainer in PRG RAM, which starts at $6000
+const TRAINER_OFFSET: usize = 0x1000;
+
+/// An NES cartridge: header, memories and mapper
+#[derive(Clone)]
+pub struct Cartridge {
+    header: INesHeader,
+    mapper: Rc<RefCell<Box<dyn Mapper>>>,
+}
+
+impl Cartridge {
+    /// Create a cartridge from an iNES or NES 2.0 image
+    ///
+    /// # Arguments
+    /// * `bytes` - Contents of the `.nes` file
+    ///
+    /// # Returns
+    /// * `Ok(Cartridge)` ready to be registered at $4020-$FFFF
+    /// * `Err(CartridgeError)` if the image is invalid or uses an unsupported mapper
+    ///
+    /// # Errors
+    /// * `CartridgeError::InvalidHeader` if the header is invalid
+    /// * `CartridgeError::Truncated` if the image is shorter than the header declares
+    /// * `CartridgeError::UnsupportedMapper` for mappers other than 0-4
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let cartridge = Cartridge::from_ines(&std::fs::read("game.nes")?)?;
+    /// bus.register_device(CARTRIDGE_START, CARTRIDGE_END, Box::new(cartridge.clone()))?;
+    /// ```
+    pub fn from_ines(bytes: &[u8]) -> Result<Cartridge, CartridgeError> {
+        let header = INesHeader::parse(bytes)?;
+        let expected = header.image_size();
+        if bytes.len() < expected {
+            return Err(CartridgeError::Truncated {
+                expected,
+                actual: bytes.len(),
+            });
+        }
+
+        let mut offset = HEADER_SIZE;
+        let trainer = if header.trainer {
+            offset += TRAINER_SIZE;
+            Some(&bytes[HEADER_SIZE..offset])
+        } else {
+            None
+        };
+        let prg_rom = bytes[offset..offset + header.prg_rom_size].to_vec();
+        offset += header.prg_rom_size;
+        let chr_rom = bytes[offset..offset + header.chr_rom_size].to_vec();
+
+        let prg_ram_size = if header.trainer {
+            header.prg_ram_size.max(TRAINER_OFFSET + TRAINER_SIZE)
+        } else {
+            header.prg_ram_size
+        };
+        let mut memory = CartridgeMemory::new(prg_rom, chr_rom, header.chr_ram_size, prg_ram_size);
+        if let Some(trainer) = trainer {
+            memory.prg_ram_mut()[TRAINER_OFFSET..TRAINER_OFFSET + TRAINER_SIZE]
+                .copy_from_slice(trainer);
+        }
+
+        let mapper = mappers::create(&header, memory)
+            .ok_or(CartridgeError::UnsupportedMapper(header.mapper))?;
+        Ok(Cartridge::new(header, mapper))
+    }
+
+    /// Create a cartridge from a header and a mapper, e.g. for a custom board
+    ///
+    /// # Arguments
+    /// * `header` - The header describing the cartridge
+    /// * `mapper` - The mapper, owning the memories of the cartridge
+    pub fn new(header: INesHeader, mapper: Box<dyn Mapper>) -> Cartridge {
+        Cartridge {
+            header,
+            mapper: Rc::new(RefCell::new(mapper)),
+        }
+    }
+
+    /// The header of the cartridge
+    pub fn header(&self) -> &INesHeader {
+        &self.header
+    }
+
+    /// Read a byte of CHR memory, for the PPU
+    ///
+    /// # Arguments
+    /// * `address` - The PPU address, $0000-$1FFF
+    pub fn ppu_read(&self, address: u16) -> u8 {
+        self.mapper.borrow_mut().ppu_read(address & 0x1FFF)
+    }
+
+    /// Write a byte of CHR memory, for the PPU
+    ///
+    /// # Arguments
+    /// * `address` - The PPU address, $0000-$1FFF
+    /// * `data` - The byte to write, ignored for CHR ROM
+    pub fn ppu_write(&self, address: u16, data: u8) {
+        self.mapper.borrow_mut().ppu_write(address & 0x1FFF, data);
+    }
+
+    /// Current nametable mirroring, which some mappers switch at runtime
+    pub fn mirroring(&self) -> Mirroring {
+        self.mapper.borrow().mirroring()
+    }
+
+    /// Clock the scanline counter of the mapper, for the PPU
+    pub fn clock_scanline(&self) {
+        self.mapper.borrow_mut().clock_scanline();
+    }
+
+    /// Copy of the PRG RAM, e.g. to save a battery-backed game
+    pub fn prg_ram(&self) -> Vec<u8> {
+        self.mapper.borrow().memory().prg_ram().to_vec()
+    }
+
+    /// Restore the PRG RAM, e.g. from a battery save
+    ///
+    /// # Arguments
+    /// * `data` - The saved contents, truncated to the size of the PRG RAM
+    pub fn restore_prg_ram(&self, data: &[u8]) {
+        let mut mapper = self.mapper.borrow_mut();
+        let prg_ram = mapper.memory_mut().prg_ram_mut();
+        let length = data.len().min(prg_ram.len());
+        prg_ram[..length].copy_from_slice(&data[..length]);
+    }
+}
+
+impl BusDevice for Cartridge {
+    fn read(&mut self, address: u16) -> Result<u8, BusError> {
+        if address < CARTRIDGE_START {
+            return Err(BusError::AddressOutOfRange(address));
+        }
+        // Undriven addresses read back the open bus, approximated by the address high byte
+        Ok(self
+            .mapper
+            .borrow_mut()
+            .cpu_read(address)
+            .unwrap_or((address >> 8) as u8))
+    }
+
+    fn write(&mut self, address: u16, data: u8) -> Result<(), BusError> {
+        if address < CARTRIDGE_START {
+            return Err(BusError::AddressOutOfRange(address));
+        }
+        self.mapper.borrow_mut().cpu_write(address, data);
+        Ok(())
+    }
+
+    fn load(&mut self, address: u16, data: u8) -> Result<(), BusError> {
+        if self.mapper.borrow_mut().load(address, data) {
+            Ok(())
+        } else {
+            Err(BusError::AddressOutOfRange(address))
+        }
+    }
+
+    fn tick(&mut self) {
+        // Mappers are clocked by CPU writes and PPU scanlines only
+    }
+
+    fn check_irq(&self) -> bool {
+        self.mapper.borrow().irq()
+    }
+
+    fn check_nmi(&self) -> bool {
+        // Cartridges do not generate NMIs
+        false
+    }
+
+    fn check_rdy(&self) -> bool {
+        // Cartridges do not insert wait states
+        false
+    }
+
+    fn check_so(&self) -> bool {
+        // Cartridges do not drive the SO line
+        false
+    }
+}
@@ -0,0 +1,35 @@
+use std::fmt;
+
+#[derive(Debug)]
+/// Errors related to parsing cartridge images
+pub enum CartridgeError {
+    /// The image does not start with a valid iNES header
+    InvalidHeader(String),
+    /// The image is shorter than its header declares
+    Truncated {
+        /// Number of bytes declared by the header
+        expected: usize,
+        /// Number of bytes in the image
+        actual: usize,
+    },
+    /// The mapper of the cartridge is not implemented
+    UnsupportedMapper(u16),
+}
+
+impl fmt::Display for CartridgeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CartridgeError::InvalidHeader(msg) => write!(f, "Invalid iNES header: {}", msg),
+            CartridgeError::Truncated { expected, actual } => write!(
+                f,
+                "Truncated image: expected {} bytes, found {}",
+                expected, actual
+            ),
+            CartridgeError::UnsupportedMapper(mapper) => {
+                write!(f, "Unsupported mapper: {}", mapper)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CartridgeError {}
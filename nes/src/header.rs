@@ -0,0 +1,202 @@
+//! iNES and NES 2.0 header parsing.
+//!
+//! Every `.nes` image starts with a 16-byte header describing the sizes of the PRG and
+//! CHR memories, the mapper and the board wiring. NES 2.0 is a backwards compatible
+//! extension that is recognised by bits 2-3 of byte 7 being `10`.
+
+use crate::errors::CartridgeError;
+
+/// Magic number at the start of every iNES image
+const MAGIC: &[u8] = b"NES\x1A";
+/// Size of the header
+pub const HEADER_SIZE: usize = 16;
+/// Size of the optional trainer between header and PRG ROM
+pub const TRAINER_SIZE: usize = 512;
+/// Unit of the PRG ROM size field
+const PRG_ROM_UNIT: usize = 16 * 1024;
+/// Unit of the CHR ROM size field
+const CHR_ROM_UNIT: usize = 8 * 1024;
+/// PRG RAM size assumed by iNES images, which cannot express it reliably
+const DEFAULT_PRG_RAM_SIZE: usize = 8 * 1024;
+/// CHR RAM size used by boards without CHR ROM
+const DEFAULT_CHR_RAM_SIZE: usize = 8 * 1024;
+
+/// Version of the header format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderFormat {
+    /// Original iNES format
+    INes,
+    /// NES 2.0 format
+    Nes20,
+}
+
+/// Arrangement of the nametables in PPU address space
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mirroring {
+    /// $2000 = $2400 and $2800 = $2C00, for vertical scrolling
+    Horizontal,
+    /// $2000 = $2800 and $2400 = $2C00, for horizontal scrolling
+    Vertical,
+    /// All nametables show the first nametable
+    SingleScreenLower,
+    /// All nametables show the second nametable
+    SingleScreenUpper,
+    /// Four separate nametables, with extra RAM on the cartridge
+    FourScreen,
+}
+
+/// Contents of an iNES or NES 2.0 header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct INesHeader {
+    /// Version of the header format
+    pub format: HeaderFormat,
+    /// Size of the PRG ROM in bytes
+    pub prg_rom_size: usize,
+    /// Size of the CHR ROM in bytes, 0 if the board uses CHR RAM
+    pub chr_rom_size: usize,
+    /// Size of the PRG RAM at $6000-$7FFF in bytes, volatile and battery-backed combined
+    pub prg_ram_size: usize,
+    /// Size of the CHR RAM in bytes, volatile and battery-backed combined
+    pub chr_ram_size: usize,
+    /// Mapper number
+    pub mapper: u16,
+    /// Submapper number, always 0 for iNES
+    pub submapper: u8,
+    /// Hard-wired nametable mirroring
+    pub mirroring: Mirroring,
+    /// The PRG RAM is battery-backed
+    pub battery: bool,
+    /// A 512-byte trainer for $7000-$71FF precedes the PRG ROM
+    pub trainer: bool,
+}
+
+impl INesHeader {
+    /// Parse the header at the start of an image
+    ///
+    /// For iNES images with garbage in bytes 12-15, typically a ripper's signature such
+    /// as "DiskDude!", the upper mapper nibble is ignored as other emulators do.
+    ///
+    /// # Arguments
+    /// * `bytes` - The image, at least the first 16 bytes
+    ///
+    /// # Returns
+    /// * `Ok(INesHeader)` with the parsed header
+    /// * `Err(CartridgeError)` if the header is invalid
+    ///
+    /// # Errors
+    /// * `CartridgeError::InvalidHeader` if the magic number is missing, the image is
+    ///   shorter than the header or the PRG ROM is empty
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let header = INesHeader::parse(&std::fs::read("game.nes")?)?;
+    /// println!("mapper {}", header.mapper);
+    /// ```
+    pub fn parse(bytes: &[u8]) -> Result<INesHeader, CartridgeError> {
+        let Some(header) = bytes.get(..HEADER_SIZE) else {
+            return Err(CartridgeError::InvalidHeader(format!(
+                "image is only {} bytes long",
+                bytes.len()
+            )));
+        };
+        if &header[..4] != MAGIC {
+            return Err(CartridgeError::InvalidHeader(
+                "missing NES<EOF> magic number".to_string(),
+            ));
+        }
+
+        let flags6 = header[6];
+        let flags7 = header[7];
+        let format = if flags7 & 0x0C == 0x08 {
+            HeaderFormat::Nes20
+        } else {
+            HeaderFormat::INes
+        };
+        let mirroring = if flags6 & 0x08 != 0 {
+            Mirroring::FourScreen
+        } else if flags6 & 0x01 != 0 {
+            Mirroring::Vertical
+        } else {
+            Mirroring::Horizontal
+        };
+
+        let parsed = match format {
+            HeaderFormat::Nes20 => INesHeader {
+                format,
+                prg_rom_size: nes20_rom_size(header[4], header[9] & 0x0F, PRG_ROM_UNIT),
+                chr_rom_size: nes20_rom_size(header[5], header[9] >> 4, CHR_ROM_UNIT),
+                prg_ram_size: nes20_ram_size(header[10] & 0x0F) + nes20_ram_size(header[10] >> 4),
+                chr_ram_size: nes20_ram_size(header[11] & 0x0F) + nes20_ram_size(header[11] >> 4),
+                mapper: (flags6 >> 4) as u16
+                    | (flags7 & 0xF0) as u16
+                    | ((header[8] & 0x0F) as u16) << 8,
+                submapper: header[8] >> 4,
+                mirroring,
+                battery: flags6 & 0x02 != 0,
+                trainer: flags6 & 0x04 != 0,
+            },
+            HeaderFormat::INes => {
+                let dirty = header[12..].iter().any(|&byte| byte != 0);
+                let chr_rom_size = header[5] as usize * CHR_ROM_UNIT;
+                INesHeader {
+                    format,
+                    prg_rom_size: header[4] as usize * PRG_ROM_UNIT,
+                    chr_rom_size,
+                    prg_ram_size: match header[8] {
+                        0 => DEFAULT_PRG_RAM_SIZE,
+                        banks => banks as usize * DEFAULT_PRG_RAM_SIZE,
+                    },
+                    chr_ram_size: if chr_rom_size == 0 {
+                        DEFAULT_CHR_RAM_SIZE
+                    } else {
+                        0
+                    },
+                    mapper: if dirty {
+                        (flags6 >> 4) as u16
+                    } else {
+                        (flags6 >> 4) as u16 | (flags7 & 0xF0) as u16
+                    },
+                    submapper: 0,
+                    mirroring,
+                    battery: flags6 & 0x02 != 0,
+                    trainer: flags6 & 0x04 != 0,
+                }
+            }
+        };
+        if parsed.prg_rom_size == 0 {
+            return Err(CartridgeError::InvalidHeader(
+                "image has no PRG ROM".to_string(),
+            ));
+        }
+        Ok(parsed)
+    }
+
+    /// Total size of the image described by the header, including header and trainer
+    pub fn image_size(&self) -> usize {
+        let trainer_size = if self.trainer { TRAINER_SIZE } else { 0 };
+        (HEADER_SIZE + trainer_size)
+            .saturating_add(self.prg_rom_size)
+            .saturating_add(self.chr_rom_size)
+    }
+}
+
+/// Decode a NES 2.0 ROM size from its LSB and MSB nibble
+///
+/// An MSB nibble of $F selects the exponent-multiplier notation, where the LSB holds
+/// the exponent in bits 2-7 and the multiplier in bits 0-1.
+fn nes20_rom_size(lsb: u8, msb: u8, unit: usize) -> usize {
+    if msb == 0x0F {
+        let exponent = (lsb >> 2) as u32;
+        let multiplier = (lsb & 0x03) as usize * 2 + 1;
+        2usize
+            .checked_pow(exponent)
+            .map_or(usize::MAX, |size| size.saturating_mul(multiplier))
+    } else {
+        ((msb as usize) << 8 | lsb as usize) * unit
+    }
+}
+
+/// Decode a NES 2.0 RAM size from its shift count
+fn nes20_ram_size(shift: u8) -> usize {
+    if shift == 0 { 0 } else { 64 << shift }
+}
@@ -0,0 +1,19 @@
+//! Library for NES hardware: cartridges with their mappers, the 2A03 audio unit and the
+//! 2C02 picture processing unit.
+//!
+//! A `Cartridge` is parsed from an iNES or NES 2.0 image and mounted on the CPU bus
+//! at $4020-$FFFF. The same cartridge handle gives the picture processing unit access
+//! to the CHR memory, so bank switching done by the CPU is seen by both sides.
+
+/// Ricoh 2A03 audio processing unit
+pub mod apu;
+/// Cartridge device mounted on the CPU bus
+pub mod cartridge;
+/// Errors related to parsing cartridge images
+pub mod errors;
+/// iNES and NES 2.0 header parsing
+pub mod header;
+/// Mapper trait and the supported mapper implementations
+pub mod mappers;
+/// Ricoh 2C02 picture processing unit
+pub mod ppu;
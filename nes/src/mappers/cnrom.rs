@@ -0,0 +1,94 @@
+//! Mapper 3: CNROM.
+//!
+//! Any write to $8000-$FFFF selects the 8K CHR bank. PRG ROM is unbanked
+//! like on NROM, a 16K ROM being mirrored into $C000-$FFFF.
+
+use crate::header::Mirroring;
+use crate::mappers::{CartridgeMemory, Mapper};
+
+/// Size of the PRG ROM window
+const PRG_WINDOW: usize = 0x8000;
+/// Size of a CHR bank
+const CHR_BANK: usize = 0x2000;
+
+/// CNROM board
+#[derive(Debug)]
+pub struct CnRom {
+    memory: CartridgeMemory,
+    mirroring: Mirroring,
+    /// Bank at $0000-$1FFF
+    chr_bank: usize,
+}
+
+impl CnRom {
+    /// Create a CNROM board
+    ///
+    /// # Arguments
+    /// * `memory` - The memories of the board
+    /// * `mirroring` - The hard-wired nametable mirroring
+    pub fn new(memory: CartridgeMemory, mirroring: Mirroring) -> Self {
+        Self {
+            memory,
+            mirroring,
+            chr_bank: 0,
+        }
+    }
+}
+
+impl Mapper for CnRom {
+    fn cpu_read(&mut self, address: u16) -> Option<u8> {
+        match address {
+            0x6000..=0x7FFF => self.memory.read_prg_ram(address),
+            0x8000..=0xFFFF => Some(self.memory.read_prg_rom(
+                0,
+                PRG_WINDOW,
+                address as usize - 0x8000,
+            )),
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, address: u16, data: u8) {
+        match address {
+            0x6000..=0x7FFF => {
+                self.memory.write_prg_ram(address, data);
+            }
+            0x8000..=0xFFFF => self.chr_bank = data as usize,
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, address: u16) -> u8 {
+        self.memory
+            .read_chr(self.chr_bank, CHR_BANK, address as usize)
+    }
+
+    fn ppu_write(&mut self, address: u16, data: u8) {
+        self.memory
+            .write_chr(self.chr_bank, CHR_BANK, address as usize, data);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn load(&mut self, address: u16, data: u8) -> bool {
+        match address {
+            0x6000..=0x7FFF => self.memory.write_prg_ram(address, data),
+            0x8000..=0xFFFF => {
+                self.memory
+                    .load_prg_rom(0, PRG_WINDOW, address as usize - 0x8000, data);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn memory(&self) -> &CartridgeMemory {
+        &self.memory
+    }
+
+    fn memory_mut(&mut self) -> &mut CartridgeMemory {
+        &mut self.memory
+    }
+}
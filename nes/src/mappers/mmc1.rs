@@ -0,0 +1,182 @@
+//! Mapper 1: MMC1.
+//!
+//! Registers are loaded serially: five writes to $8000-$FFFF shift in bit 0 of the data,
+//! and the fifth write stores the value into the register selected by address bits 13-14.
+//! A write with bit 7 set resets the shift register and selects PRG mode 3.
+//!
+//! - Control ($8000): mirroring (bits 0-1), PRG mode (bits 2-3), CHR mode (bit 4)
+//! - CHR bank 0 ($A000) and CHR bank 1 ($C000): 4K banks, or one 8K bank in CHR mode 0
+//! - PRG bank ($E000): 16K bank (bits 0-3), PRG RAM disable (bit 4)
+//!
+//! On boards with 512K of PRG ROM (SUROM), bit 4 of the CHR bank registers selects
+//! the 256K half of the PRG ROM.
+
+use crate::header::Mirroring;
+use crate::mappers::{CartridgeMemory, Mapper};
+
+/// Size of a PRG ROM bank
+const PRG_BANK: usize = 0x4000;
+/// Size of a CHR bank
+const CHR_BANK: usize = 0x1000;
+/// Number of 16K banks in a 256K half of the PRG ROM
+const PRG_BANKS_PER_HALF: usize = 16;
+/// Control register value at power-on: PRG mode 3
+const CONTROL_POWER_ON: u8 = 0x0C;
+
+/// MMC1 board (SxROM)
+#[derive(Debug)]
+pub struct Mmc1 {
+    memory: CartridgeMemory,
+    /// Bits shifted in so far, starting at bit 4
+    shift: u8,
+    /// Number of bits shifted in
+    shift_count: u8,
+    control: u8,
+    chr_bank_0: u8,
+    chr_bank_1: u8,
+    prg_bank: u8,
+}
+
+impl Mmc1 {
+    /// Create an MMC1 board
+    ///
+    /// # Arguments
+    /// * `memory` - The memories of the board
+    pub fn new(memory: CartridgeMemory) -> Self {
+        Self {
+            memory,
+            shift: 0,
+            shift_count: 0,
+            control: CONTROL_POWER_ON,
+            chr_bank_0: 0,
+            chr_bank_1: 0,
+            prg_bank: 0,
+        }
+    }
+
+    /// Bank and offset of a CPU address in $8000-$FFFF
+    fn prg_location(&self, address: u16) -> (usize, usize) {
+        let offset = address as usize & (PRG_BANK - 1);
+        let upper = address >= 0xC000;
+        let outer = if self.memory.prg_banks(PRG_BANK) > PRG_BANKS_PER_HALF {
+            (self.chr_bank_0 as usize >> 4 & 0x01) * PRG_BANKS_PER_HALF
+        } else {
+            0
+        };
+        let bank = self.prg_bank as usize & 0x0F;
+        let last = (self.memory.prg_banks(PRG_BANK) - 1).min(PRG_BANKS_PER_HALF - 1);
+        let bank = match (self.control >> 2) & 0x03 {
+            0 | 1 => (bank & !0x01) + upper as usize,
+            2 if upper => bank,
+            2 => 0,
+            _ if upper => last,
+            _ => bank,
+        };
+        (outer + bank, offset)
+    }
+
+    /// Bank and offset of a PPU address in $0000-$1FFF
+    fn chr_location(&self, address: u16) -> (usize, usize) {
+        let offset = address as usize & (CHR_BANK - 1);
+        let upper = address >= 0x1000;
+        let bank = if self.control & 0x10 == 0 {
+            (self.chr_bank_0 as usize & !0x01) + upper as usize
+        } else if upper {
+            self.chr_bank_1 as usize
+        } else {
+            self.chr_bank_0 as usize
+        };
+        (bank, offset)
+    }
+
+    /// PRG RAM is enabled by bit 4 of the PRG bank register being clear
+    fn prg_ram_enabled(&self) -> bool {
+        self.prg_bank & 0x10 == 0
+    }
+
+    /// Shift a bit into the shift register, storing the register on the fifth write
+    fn write_register(&mut self, address: u16, data: u8) {
+        if data & 0x80 != 0 {
+            self.shift = 0;
+            self.shift_count = 0;
+            self.control |= CONTROL_POWER_ON;
+            return;
+        }
+        self.shift = (self.shift >> 1) | ((data & 0x01) << 4);
+        self.shift_count += 1;
+        if self.shift_count < 5 {
+            return;
+        }
+        let value = self.shift;
+        match address {
+            0x8000..=0x9FFF => self.control = value,
+            0xA000..=0xBFFF => self.chr_bank_0 = value,
+            0xC000..=0xDFFF => self.chr_bank_1 = value,
+            _ => self.prg_bank = value,
+        }
+        self.shift = 0;
+        self.shift_count = 0;
+    }
+}
+
+impl Mapper for Mmc1 {
+    fn cpu_read(&mut self, address: u16) -> Option<u8> {
+        match address {
+            0x6000..=0x7FFF if self.prg_ram_enabled() => self.memory.read_prg_ram(address),
+            0x8000..=0xFFFF => {
+                let (bank, offset) = self.prg_location(address);
+                Some(self.memory.read_prg_rom(bank, PRG_BANK, offset))
+            }
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, address: u16, data: u8) {
+        match address {
+            0x6000..=0x7FFF if self.prg_ram_enabled() => {
+                self.memory.write_prg_ram(address, data);
+            }
+            0x8000..=0xFFFF => self.write_register(address, data),
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, address: u16) -> u8 {
+        let (bank, offset) = self.chr_location(address);
+        self.memory.read_chr(bank, CHR_BANK, offset)
+    }
+
+    fn ppu_write(&mut self, address: u16, data: u8) {
+        let (bank, offset) = self.chr_location(address);
+        self.memory.write_chr(bank, CHR_BANK, offset, data);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        match self.control & 0x03 {
+            0 => Mirroring::SingleScreenLower,
+            1 => Mirroring::SingleScreenUpper,
+            2 => Mirroring::Vertical,
+            _ => Mirroring::Horizontal,
+        }
+    }
+
+    fn load(&mut self, address: u16, data: u8) -> bool {
+        match address {
+            0x6000..=0x7FFF => self.memory.write_prg_ram(address, data),
+            0x8000..=0xFFFF => {
+                let (bank, offset) = self.prg_location(address);
+                self.memory.load_prg_rom(bank, PRG_BANK, offset, data);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn memory(&self) -> &CartridgeMemory {
+        &self.memory
+    }
+
+    fn memory_mut(&mut self) -> &mut CartridgeMemory {
+        &mut self.memory
+    }
+}
@@ -0,0 +1,206 @@
+//! Mapper 4: MMC3.
+//!
+//! Registers are selected by the address range and bit 0 of the address:
+//! - $8000 even: bank select (target register in bits 0-2, PRG mode bit 6, CHR A12 inversion bit 7)
+//! - $8001 odd: bank data for the selected register R0-R7
+//! - $A000 even: mirroring (0 vertical, 1 horizontal)
+//! - $A001 odd: PRG RAM protect (enable bit 7, write protect bit 6)
+//! - $C000 even: IRQ latch, $C001 odd: IRQ reload
+//! - $E000 even: IRQ disable and acknowledge, $E001 odd: IRQ enable
+//!
+//! PRG ROM is switched in 8K banks with the second-to-last bank fixed at $8000 or
+//! $C000, and the last bank fixed at $E000. CHR is switched in two 2K and four 1K banks.
+//! The scanline counter is clocked by the PPU through `Mapper::clock_scanline`.
+
+use crate::header::Mirroring;
+use crate::mappers::{CartridgeMemory, Mapper};
+
+/// Size of a PRG ROM bank
+const PRG_BANK: usize = 0x2000;
+/// Size of a CHR bank
+const CHR_BANK: usize = 0x0400;
+
+/// MMC3 board (TxROM)
+#[derive(Debug)]
+pub struct Mmc3 {
+    memory: CartridgeMemory,
+    /// Mirroring from the header, used for four-screen boards which ignore $A000
+    header_mirroring: Mirroring,
+    bank_select: u8,
+    /// Bank registers R0-R7
+    banks: [u8; 8],
+    mirroring: Mirroring,
+    prg_ram_protect: u8,
+    irq_latch: u8,
+    irq_counter: u8,
+    irq_reload: bool,
+    irq_enabled: bool,
+    irq_pending: bool,
+}
+
+impl Mmc3 {
+    /// Create an MMC3 board
+    ///
+    /// # Arguments
+    /// * `memory` - The memories of the board
+    /// * `mirroring` - The mirroring from the header, only kept for four-screen boards
+    pub fn new(memory: CartridgeMemory, mirroring: Mirroring) -> Self {
+        Self {
+            memory,
+            header_mirroring: mirroring,
+            bank_select: 0,
+            banks: [0, 2, 4, 5, 6, 7, 0, 1],
+            mirroring: Mirroring::Vertical,
+            prg_ram_protect: 0x80,
+            irq_latch: 0,
+            irq_counter: 0,
+            irq_reload: false,
+            irq_enabled: false,
+            irq_pending: false,
+        }
+    }
+
+    /// Bank and offset of a CPU address in $8000-$FFFF
+    fn prg_location(&self, address: u16) -> (usize, usize) {
+        let offset = address as usize & (PRG_BANK - 1);
+        let last = self.memory.prg_banks(PRG_BANK) - 1;
+        let second_last = last.saturating_sub(1);
+        let swap = self.bank_select & 0x40 != 0;
+        let bank = match (address >> 13) & 0x03 {
+            0 if swap => second_last,
+            0 => self.banks[6] as usize & 0x3F,
+            1 => self.banks[7] as usize & 0x3F,
+            2 if swap => self.banks[6] as usize & 0x3F,
+            2 => second_last,
+            _ => last,
+        };
+        (bank, offset)
+    }
+
+    /// Bank and offset of a PPU address in $0000-$1FFF
+    fn chr_location(&self, address: u16) -> (usize, usize) {
+        let offset = address as usize & (CHR_BANK - 1);
+        let address = if self.bank_select & 0x80 != 0 {
+            address ^ 0x1000
+        } else {
+            address
+        };
+        let bank = match address >> 10 {
+            0 => self.banks[0] as usize & !0x01,
+            1 => self.banks[0] as usize | 0x01,
+            2 => self.banks[1] as usize & !0x01,
+            3 => self.banks[1] as usize | 0x01,
+            slot => self.banks[slot as usize - 2] as usize,
+        };
+        (bank, offset)
+    }
+
+    /// PRG RAM reads are enabled by bit 7 of the protect register
+    fn prg_ram_enabled(&self) -> bool {
+        self.prg_ram_protect & 0x80 != 0
+    }
+
+    /// PRG RAM writes are additionally blocked by bit 6 of the protect register
+    fn prg_ram_writable(&self) -> bool {
+        self.prg_ram_enabled() && self.prg_ram_protect & 0x40 == 0
+    }
+}
+
+impl Mapper for Mmc3 {
+    fn cpu_read(&mut self, address: u16) -> Option<u8> {
+        match address {
+            0x6000..=0x7FFF if self.prg_ram_enabled() => self.memory.read_prg_ram(address),
+            0x8000..=0xFFFF => {
+                let (bank, offset) = self.prg_location(address);
+                Some(self.memory.read_prg_rom(bank, PRG_BANK, offset))
+            }
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, address: u16, data: u8) {
+        let odd = address & 0x01 != 0;
+        match address {
+            0x6000..=0x7FFF if self.prg_ram_writable() => {
+                self.memory.write_prg_ram(address, data);
+            }
+            0x8000..=0x9FFF if odd => {
+                self.banks[(self.bank_select & 0x07) as usize] = data;
+            }
+            0x8000..=0x9FFF => self.bank_select = data,
+            0xA000..=0xBFFF if odd => self.prg_ram_protect = data,
+            0xA000..=0xBFFF => {
+                self.mirroring = if data & 0x01 == 0 {
+                    Mirroring::Vertical
+                } else {
+                    Mirroring::Horizontal
+                };
+            }
+            0xC000..=0xDFFF if odd => {
+                self.irq_counter = 0;
+                self.irq_reload = true;
+            }
+            0xC000..=0xDFFF => self.irq_latch = data,
+            0xE000..=0xFFFF if odd => self.irq_enabled = true,
+            0xE000..=0xFFFF => {
+                self.irq_enabled = false;
+                self.irq_pending = false;
+            }
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, address: u16) -> u8 {
+        let (bank, offset) = self.chr_location(address);
+        self.memory.read_chr(bank, CHR_BANK, offset)
+    }
+
+    fn ppu_write(&mut self, address: u16, data: u8) {
+        let (bank, offset) = self.chr_location(address);
+        self.memory.write_chr(bank, CHR_BANK, offset, data);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        if self.header_mirroring == Mirroring::FourScreen {
+            Mirroring::FourScreen
+        } else {
+            self.mirroring
+        }
+    }
+
+    fn load(&mut self, address: u16, data: u8) -> bool {
+        match address {
+            0x6000..=0x7FFF => self.memory.write_prg_ram(address, data),
+            0x8000..=0xFFFF => {
+                let (bank, offset) = self.prg_location(address);
+                self.memory.load_prg_rom(bank, PRG_BANK, offset, data);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn memory(&self) -> &CartridgeMemory {
+        &self.memory
+    }
+
+    fn memory_mut(&mut self) -> &mut CartridgeMemory {
+        &mut self.memory
+    }
+
+    fn irq(&self) -> bool {
+        self.irq_pending
+    }
+
+    fn clock_scanline(&mut self) {
+        if self.irq_counter == 0 || self.irq_reload {
+            self.irq_counter = self.irq_latch;
+            self.irq_reload = false;
+        } else {
+            self.irq_counter -= 1;
+        }
+        if self.irq_counter == 0 && self.irq_enabled {
+            self.irq_pending = true;
+        }
+    }
+}
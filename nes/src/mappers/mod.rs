@@ -0,0 +1,190 @@
+//! Mapper trait and the supported mapper implementations.
+//!
+//! A mapper is the bank switching logic of a cartridge board. It decodes CPU accesses
+//! to $4020-$FFFF and PPU accesses to the pattern tables at $0000-$1FFF.
+
+/// Mapper 3: CNROM
+pub mod cnrom;
+/// Mapper 1: MMC1
+pub mod mmc1;
+/// Mapper 4: MMC3
+pub mod mmc3;
+/// Mapper 0: NROM
+pub mod nrom;
+/// Mapper 2: UxROM
+pub mod uxrom;
+
+use crate::header::{INesHeader, Mirroring};
+
+/// Bank switching logic of a cartridge board
+pub trait Mapper {
+    /// Read a byte from the CPU side of the cartridge
+    ///
+    /// # Arguments
+    /// * `address` - The CPU address, $4020-$FFFF
+    ///
+    /// # Returns
+    /// * `Some(u8)` containing the data read
+    /// * `None` if nothing on the cartridge drives the data bus at this address
+    fn cpu_read(&mut self, address: u16) -> Option<u8>;
+    /// Write a byte to the CPU side of the cartridge, e.g. to a mapper register
+    fn cpu_write(&mut self, address: u16, data: u8);
+    /// Read a byte of CHR memory for the PPU
+    ///
+    /// # Arguments
+    /// * `address` - The PPU address, $0000-$1FFF
+    fn ppu_read(&mut self, address: u16) -> u8;
+    /// Write a byte of CHR memory for the PPU, ignored for CHR ROM
+    fn ppu_write(&mut self, address: u16, data: u8);
+    /// Current nametable mirroring
+    fn mirroring(&self) -> Mirroring;
+    /// Store a byte into PRG ROM or PRG RAM for a program loader, bypassing the mapper registers
+    ///
+    /// # Returns
+    /// * `true` if the address maps to cartridge memory
+    /// * `false` otherwise
+    fn load(&mut self, address: u16, data: u8) -> bool;
+    /// The memories of the cartridge
+    fn memory(&self) -> &CartridgeMemory;
+    /// Mutable access to the memories of the cartridge
+    fn memory_mut(&mut self) -> &mut CartridgeMemory;
+    /// Check the state of the IRQ line
+    fn irq(&self) -> bool {
+        false
+    }
+    /// Clock the scanline counter
+    ///
+    /// Called by the PPU once per rendered scanline, at the point where the PPU address
+    /// line A12 rises while fetching sprite patterns. Only scanline counting mappers react.
+    fn clock_scanline(&mut self) {}
+}
+
+/// Create the mapper for a parsed cartridge image
+///
+/// # Arguments
+/// * `header` - The parsed header
+/// * `memory` - The memories of the cartridge, filled from the image
+///
+/// # Returns
+/// * `Some(Box<dyn Mapper>)` if the mapper is supported
+/// * `None` otherwise
+pub(crate) fn create(header: &INesHeader, memory: CartridgeMemory) -> Option<Box<dyn Mapper>> {
+    let mapper: Box<dyn Mapper> = match header.mapper {
+        0 => Box::new(nrom::Nrom::new(memory, header.mirroring)),
+        1 => Box::new(mmc1::Mmc1::new(memory)),
+        2 => Box::new(uxrom::UxRom::new(memory, header.mirroring)),
+        3 => Box::new(cnrom::CnRom::new(memory, header.mirroring)),
+        4 => Box::new(mmc3::Mmc3::new(memory, header.mirroring)),
+        _ => return None,
+    };
+    Some(mapper)
+}
+
+/// The memories found on a cartridge board
+#[derive(Debug, Clone)]
+pub struct CartridgeMemory {
+    /// PRG ROM, never empty
+    prg_rom: Vec<u8>,
+    /// CHR ROM or CHR RAM
+    chr: Vec<u8>,
+    /// The CHR memory is RAM
+    chr_is_ram: bool,
+    /// PRG RAM at $6000-$7FFF, empty if the board has none
+    prg_ram: Vec<u8>,
+}
+
+impl CartridgeMemory {
+    /// Create the memories of a board
+    ///
+    /// An empty `chr_rom` gives the board `chr_ram_size` bytes of CHR RAM instead.
+    /// An empty `prg_rom` is replaced by a single 16K bank of zeroes.
+    ///
+    /// # Arguments
+    /// * `prg_rom` - Contents of the PRG ROM
+    /// * `chr_rom` - Contents of the CHR ROM
+    /// * `chr_ram_size` - Size of the CHR RAM for boards without CHR ROM
+    /// * `prg_ram_size` - Size of the PRG RAM
+    pub fn new(
+        prg_rom: Vec<u8>,
+        chr_rom: Vec<u8>,
+        chr_ram_size: usize,
+        prg_ram_size: usize,
+    ) -> CartridgeMemory {
+        let prg_rom = if prg_rom.is_empty() {
+            vec![0; 0x4000]
+        } else {
+            prg_rom
+        };
+        let chr_is_ram = chr_rom.is_empty();
+        let chr = if chr_is_ram {
+            vec![0; chr_ram_size.max(0x2000)]
+        } else {
+            chr_rom
+        };
+        CartridgeMemory {
+            prg_rom,
+            chr,
+            chr_is_ram,
+            prg_ram: vec![0; prg_ram_size],
+        }
+    }
+
+    /// Number of PRG ROM banks of the given size
+    pub(crate) fn prg_banks(&self, bank_size: usize) -> usize {
+        (self.prg_rom.len() / bank_size).max(1)
+    }
+
+    /// Read a byte from a PRG ROM bank, wrapping bank numbers past the end of the ROM
+    pub(crate) fn read_prg_rom(&self, bank: usize, bank_size: usize, offset: usize) -> u8 {
+        let index = (bank * bank_size + offset) % self.prg_rom.len();
+        self.prg_rom[index]
+    }
+
+    /// Store a byte into a PRG ROM bank for a program loader
+    pub(crate) fn load_prg_rom(&mut self, bank: usize, bank_size: usize, offset: usize, data: u8) {
+        let index = (bank * bank_size + offset) % self.prg_rom.len();
+        self.prg_rom[index] = data;
+    }
+
+    /// Read a byte from a CHR bank, wrapping bank numbers past the end of the memory
+    pub(crate) fn read_chr(&self, bank: usize, bank_size: usize, offset: usize) -> u8 {
+        let index = (bank * bank_size + offset) % self.chr.len();
+        self.chr[index]
+    }
+
+    /// Write a byte to a CHR bank, ignored for CHR ROM
+    pub(crate) fn write_chr(&mut self, bank: usize, bank_size: usize, offset: usize, data: u8) {
+        if self.chr_is_ram {
+            let index = (bank * bank_size + offset) % self.chr.len();
+            self.chr[index] = data;
+        }
+    }
+
+    /// Read a byte from PRG RAM, mirrored over $6000-$7FFF
+    pub(crate) fn read_prg_ram(&self, address: u16) -> Option<u8> {
+        if self.prg_ram.is_empty() {
+            return None;
+        }
+        Some(self.prg_ram[(address as usize - 0x6000) % self.prg_ram.len()])
+    }
+
+    /// Write a byte to PRG RAM, mirrored over $6000-$7FFF
+    pub(crate) fn write_prg_ram(&mut self, address: u16, data: u8) -> bool {
+        if self.prg_ram.is_empty() {
+            return false;
+        }
+        let index = (address as usize - 0x6000) % self.prg_ram.len();
+        self.prg_ram[index] = data;
+        true
+    }
+
+    /// Contents of the PRG RAM, e.g. to save a battery-backed game
+    pub fn prg_ram(&self) -> &[u8] {
+        &self.prg_ram
+    }
+
+    /// Mutable access to the PRG RAM, e.g. to restore a battery-backed game
+    pub fn prg_ram_mut(&mut self) -> &mut [u8] {
+        &mut self.prg_ram
+    }
+}
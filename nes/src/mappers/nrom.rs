@@ -0,0 +1,82 @@
+//! Mapper 0: NROM.
+//!
+//! No bank switching: 16K or 32K of PRG ROM at $8000-$FFFF, a 16K ROM being mirrored
+//! into $C000-$FFFF, and 8K of CHR.
+
+use crate::header::Mirroring;
+use crate::mappers::{CartridgeMemory, Mapper};
+
+/// Size of the PRG ROM window
+const PRG_WINDOW: usize = 0x8000;
+/// Size of the CHR window
+const CHR_WINDOW: usize = 0x2000;
+
+/// NROM board
+#[derive(Debug)]
+pub struct Nrom {
+    memory: CartridgeMemory,
+    mirroring: Mirroring,
+}
+
+impl Nrom {
+    /// Create an NROM board
+    ///
+    /// # Arguments
+    /// * `memory` - The memories of the board
+    /// * `mirroring` - The hard-wired nametable mirroring
+    pub fn new(memory: CartridgeMemory, mirroring: Mirroring) -> Self {
+        Self { memory, mirroring }
+    }
+}
+
+impl Mapper for Nrom {
+    fn cpu_read(&mut self, address: u16) -> Option<u8> {
+        match address {
+            0x6000..=0x7FFF => self.memory.read_prg_ram(address),
+            0x8000..=0xFFFF => Some(self.memory.read_prg_rom(
+                0,
+                PRG_WINDOW,
+                address as usize - 0x8000,
+            )),
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, address: u16, data: u8) {
+        if let 0x6000..=0x7FFF = address {
+            self.memory.write_prg_ram(address, data);
+        }
+    }
+
+    fn ppu_read(&mut self, address: u16) -> u8 {
+        self.memory.read_chr(0, CHR_WINDOW, address as usize)
+    }
+
+    fn ppu_write(&mut self, address: u16, data: u8) {
+        self.memory.write_chr(0, CHR_WINDOW, address as usize, data);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn load(&mut self, address: u16, data: u8) -> bool {
+        match address {
+            0x6000..=0x7FFF => self.memory.write_prg_ram(address, data),
+            0x8000..=0xFFFF => {
+                self.memory
+                    .load_prg_rom(0, PRG_WINDOW, address as usize - 0x8000, data);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn memory(&self) -> &CartridgeMemory {
+        &self.memory
+    }
+
+    fn memory_mut(&mut self) -> &mut CartridgeMemory {
+        &mut self.memory
+    }
+}
@@ -0,0 +1,102 @@
+//! Mapper 2: UxROM.
+//!
+//! Any write to $8000-$FFFF selects the 16K PRG ROM bank at $8000-$BFFF.
+//! The last bank is fixed at $C000-$FFFF. CHR is a single unbanked 8K, usually RAM.
+
+use crate::header::Mirroring;
+use crate::mappers::{CartridgeMemory, Mapper};
+
+/// Size of a PRG ROM bank
+const PRG_BANK: usize = 0x4000;
+/// Size of the CHR window
+const CHR_WINDOW: usize = 0x2000;
+
+/// UxROM board (UNROM, UOROM)
+#[derive(Debug)]
+pub struct UxRom {
+    memory: CartridgeMemory,
+    mirroring: Mirroring,
+    /// Bank at $8000-$BFFF
+    prg_bank: usize,
+}
+
+impl UxRom {
+    /// Create a UxROM board
+    ///
+    /// # Arguments
+    /// * `memory` - The memories of the board
+    /// * `mirroring` - The hard-wired nametable mirroring
+    pub fn new(memory: CartridgeMemory, mirroring: Mirroring) -> Self {
+        Self {
+            memory,
+            mirroring,
+            prg_bank: 0,
+        }
+    }
+
+    /// Bank mapped at a CPU address in $8000-$FFFF
+    fn bank_at(&self, address: u16) -> usize {
+        if address < 0xC000 {
+            self.prg_bank
+        } else {
+            self.memory.prg_banks(PRG_BANK) - 1
+        }
+    }
+}
+
+impl Mapper for UxRom {
+    fn cpu_read(&mut self, address: u16) -> Option<u8> {
+        match address {
+            0x6000..=0x7FFF => self.memory.read_prg_ram(address),
+            0x8000..=0xFFFF => Some(self.memory.read_prg_rom(
+                self.bank_at(address),
+                PRG_BANK,
+                address as usize & (PRG_BANK - 1),
+            )),
+            _ => None,
+        }
+    }
+
+    fn cpu_write(&mut self, address: u16, data: u8) {
+        match address {
+            0x6000..=0x7FFF => {
+                self.memory.write_prg_ram(address, data);
+            }
+            0x8000..=0xFFFF => self.prg_bank = data as usize,
+            _ => {}
+        }
+    }
+
+    fn ppu_read(&mut self, address: u16) -> u8 {
+        self.memory.read_chr(0, CHR_WINDOW, address as usize)
+    }
+
+    fn ppu_write(&mut self, address: u16, data: u8) {
+        self.memory.write_chr(0, CHR_WINDOW, address as usize, data);
+    }
+
+    fn mirroring(&self) -> Mirroring {
+        self.mirroring
+    }
+
+    fn load(&mut self, address: u16, data: u8) -> bool {
+        match address {
+            0x6000..=0x7FFF => self.memory.write_prg_ram(address, data),
+            0x8000..=0xFFFF => {
+                let bank = self.bank_at(address);
+                self.memory
+                    .load_prg_rom(bank, PRG_BANK, address as usize & (PRG_BANK - 1), data);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn memory(&self) -> &CartridgeMemory {
+        &self.memory
+    }
+
+    fn memory_mut(&mut self) -> &mut CartridgeMemory {
+        &mut self.memory
+    }
+}
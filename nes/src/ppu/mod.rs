@@ -0,0 +1,789 @@
+//! Picture processing unit of the NES, the Ricoh 2C02.
+//!
+//! Register map on the CPU bus, mirrored every 8 bytes through $2000-$3FFF:
+//! - $2000: PPUCTRL, nametable, increment, pattern tables, sprite size, NMI enable (write)
+//! - $2001: PPUMASK, grayscale, left column clipping, background and sprite enable (write)
+//! - $2002: PPUSTATUS, sprite overflow, sprite 0 hit, vblank (read)
+//! - $2003: OAMADDR (write)
+//! - $2004: OAMDATA (read/write)
+//! - $2005: PPUSCROLL, X then Y scroll (write twice)
+//! - $2006: PPUADDR, high then low byte of the VRAM address (write twice)
+//! - $2007: PPUDATA (read/write)
+//! - $4014: OAMDMA, copies page $XX00-$XXFF to OAM (write)
+//!
+//! Reading $2002 clears the vblank flag and the write toggle shared by $2005 and $2006.
+//! Reading $2007 below the palette returns the contents of an internal buffer and then
+//! refills it, so the first read after setting the address returns stale data. Reads of
+//! write-only registers return the last value on the PPU data bus.
+//!
+//! The PPU is clocked by the CPU clock, one `tick` per CPU cycle, and runs three dots
+//! per tick with NTSC timing: 262 scanlines of 341 dots, the odd frames one dot shorter
+//! while rendering. Pixels are rendered dot by dot into an RGB framebuffer of 256x240,
+//! which can be saved with the `png` module. Pattern tables are read through the
+//! `Cartridge`, which also selects the nametable mirroring and receives the scanline
+//! clock for the MMC3 IRQ counter. The NMI line is held low while vblank and the NMI
+//! enable are both set. Color emphasis is not modelled.
+
+mod palette;
+/// PNG files of rendered frames
+pub mod png;
+
+use bus::errors::BusError;
+use bus::trait_bus_device::{BusDevice, DmaRequest};
+
+use crate::cartridge::Cartridge;
+use crate::header::Mirroring;
+use crate::ppu::palette::SYSTEM_PALETTE;
+
+/// First CPU address of the PPU registers
+pub const PPU_START: u16 = 0x2000;
+/// Last CPU address of the PPU registers, which are mirrored up to here
+pub const PPU_END: u16 = 0x3FFF;
+/// OAM DMA register
+pub const OAM_DMA: u16 = 0x4014;
+/// Number of registers exposed on the bus
+pub const PPU_REGISTER_COUNT: u16 = 8;
+/// Width of the framebuffer in pixels
+pub const FRAME_WIDTH: u32 = 256;
+/// Height of the framebuffer in pixels
+pub const FRAME_HEIGHT: u32 = 240;
+
+const REGISTER_CTRL: u16 = 0x0;
+const REGISTER_MASK: u16 = 0x1;
+const REGISTER_STATUS: u16 = 0x2;
+const REGISTER_OAM_ADDRESS: u16 = 0x3;
+const REGISTER_OAM_DATA: u16 = 0x4;
+const REGISTER_SCROLL: u16 = 0x5;
+const REGISTER_ADDRESS: u16 = 0x6;
+const REGISTER_DATA: u16 = 0x7;
+
+/// PPUCTRL: add 32 to the VRAM address after each $2007 access instead of 1
+pub const CTRL_INCREMENT_32: u8 = 0x04;
+/// PPUCTRL: 8x8 sprite patterns from $1000
+pub const CTRL_SPRITE_TABLE: u8 = 0x08;
+/// PPUCTRL: background patterns from $1000
+pub const CTRL_BACKGROUND_TABLE: u8 = 0x10;
+/// PPUCTRL: 8x16 sprites
+pub const CTRL_SPRITE_8X16: u8 = 0x20;
+/// PPUCTRL: generate an NMI at the start of vblank
+pub const CTRL_NMI_ENABLE: u8 = 0x80;
+
+/// PPUMASK: grayscale
+pub const MASK_GRAYSCALE: u8 = 0x01;
+/// PPUMASK: show the background in the leftmost 8 pixels
+pub const MASK_BACKGROUND_LEFT: u8 = 0x02;
+/// PPUMASK: show sprites in the leftmost 8 pixels
+pub const MASK_SPRITES_LEFT: u8 = 0x04;
+/// PPUMASK: show the background
+pub const MASK_BACKGROUND: u8 = 0x08;
+/// PPUMASK: show sprites
+pub const MASK_SPRITES: u8 = 0x10;
+
+/// PPUSTATUS: more than 8 sprites on a scanline
+pub const STATUS_SPRITE_OVERFLOW: u8 = 0x20;
+/// PPUSTATUS: an opaque pixel of sprite 0 overlapped an opaque background pixel
+pub const STATUS_SPRITE_ZERO_HIT: u8 = 0x40;
+/// PPUSTATUS: vertical blank has started
+pub const STATUS_VBLANK: u8 = 0x80;
+
+/// Dots per scanline
+const DOTS: u16 = 341;
+/// First scanline of the vertical blank
+const VBLANK_LINE: u16 = 241;
+/// Pre-render scanline, the last of a frame
+const PRE_RENDER_LINE: u16 = 261;
+/// Sprite attributes: palette, behind background, horizontal and vertical flip
+const SPRITE_PALETTE: u8 = 0x03;
+const SPRITE_BEHIND: u8 = 0x20;
+const SPRITE_FLIP_X: u8 = 0x40;
+const SPRITE_FLIP_Y: u8 = 0x80;
+/// Sprites drawn per scanline
+const SPRITES_PER_LINE: usize = 8;
+
+/// A sprite selected for the next scanline, with its pattern row
+#[derive(Debug, Clone, Copy)]
+struct LineSprite {
+    x: u8,
+    attributes: u8,
+    pattern_low: u8,
+    pattern_high: u8,
+    /// The sprite is entry 0 of OAM, for the sprite 0 hit
+    zero: bool,
+}
+
+/// An OAM DMA transfer in progress
+#[derive(Debug, Clone, Copy)]
+struct OamDma {
+    page: u16,
+    index: u16,
+    /// Halted cycles before the first read, one more when starting on an odd cycle
+    wait: u8,
+    /// Byte read and waiting to be written to OAM
+    latch: Option<u8>,
+}
+
+/// Represents a 2C02 PPU.
+pub struct Ppu {
+    cartridge: Cartridge,
+    ctrl: u8,
+    mask: u8,
+    status: u8,
+    oam_address: u8,
+    oam: [u8; 256],
+    /// Current VRAM address, also the scroll position while rendering
+    v: u16,
+    /// Temporary VRAM address, the scroll position of the next frame
+    t: u16,
+    fine_x: u8,
+    /// Write toggle shared by $2005 and $2006
+    w: bool,
+    read_buffer: u8,
+    /// Last value on the PPU data bus, returned by write-only registers
+    io_latch: u8,
+    /// Nametable RAM, the second half only used by four-screen cartridges
+    vram: [u8; 0x1000],
+    palette: [u8; 32],
+    scanline: u16,
+    dot: u16,
+    odd_frame: bool,
+    frame_count: u64,
+    /// Background fetch latches for the next tile
+    next_tile: u8,
+    next_attribute: u8,
+    next_pattern_low: u8,
+    next_pattern_high: u8,
+    /// Background shift registers, 16 pixels of two tiles
+    pattern_low: u16,
+    pattern_high: u16,
+    attribute_low: u16,
+    attribute_high: u16,
+    sprites: Vec<LineSprite>,
+    oam_dma: Option<OamDma>,
+    /// Parity of the CPU cycle, for the OAM DMA alignment
+    odd_cpu_cycle: bool,
+    /// CPU cycles stolen by OAM DMA
+    dma_cycles: u64,
+    framebuffer: Vec<u8>,
+}
+
+impl Ppu {
+    /// Create a new PPU in its power-on state.
+    ///
+    /// # Arguments
+    /// * `cartridge` - Handle to the cartridge, for the pattern tables and mirroring
+    ///
+    /// # Returns
+    /// * A new Ppu instance at the start of the pre-render scanline
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let id = bus.register_device(PPU_START, PPU_END, Box::new(Ppu::new(cartridge.clone())))?;
+    /// bus.register_device_range(id, OAM_DMA, OAM_DMA)?;
+    /// ```
+    pub fn new(cartridge: Cartridge) -> Self {
+        Self {
+            cartridge,
+            ctrl: 0,
+            mask: 0,
+            status: 0,
+            oam_address: 0,
+            oam: [0; 256],
+            v: 0,
+            t: 0,
+            fine_x: 0,
+            w: false,
+            read_buffer: 0,
+            io_latch: 0,
+            vram: [0; 0x1000],
+            palette: [0; 32],
+            scanline: PRE_RENDER_LINE,
+            dot: 0,
+            odd_frame: false,
+            frame_count: 0,
+            next_tile: 0,
+            next_attribute: 0,
+            next_pattern_low: 0,
+            next_pattern_high: 0,
+            pattern_low: 0,
+            pattern_high: 0,
+            attribute_low: 0,
+            attribute_high: 0,
+            sprites: Vec::with_capacity(SPRITES_PER_LINE),
+            oam_dma: None,
+            odd_cpu_cycle: false,
+            dma_cycles: 0,
+            framebuffer: vec![0; (FRAME_WIDTH * FRAME_HEIGHT * 3) as usize],
+        }
+    }
+
+    /// Reset the PPU.
+    ///
+    /// Clears the registers and restarts at the pre-render scanline. OAM, nametables and
+    /// palette keep their contents, as on the console.
+    pub fn reset(&mut self) {
+        self.ctrl = 0;
+        self.mask = 0;
+        self.w = false;
+        self.read_buffer = 0;
+        self.scanline = PRE_RENDER_LINE;
+        self.dot = 0;
+        self.odd_frame = false;
+        self.oam_dma = None;
+    }
+
+    /// The rendered frame, 256x240 pixels row by row with three bytes per pixel
+    pub fn framebuffer(&self) -> &[u8] {
+        &self.framebuffer
+    }
+
+    /// Color of a pixel of the rendered frame
+    ///
+    /// # Arguments
+    /// * `x` - Column, 0-255
+    /// * `y` - Row, 0-239
+    ///
+    /// # Returns
+    /// * `Some([red, green, blue])` for a pixel inside the frame
+    /// * `None` otherwise
+    pub fn pixel(&self, x: u32, y: u32) -> Option<[u8; 3]> {
+        if x >= FRAME_WIDTH || y >= FRAME_HEIGHT {
+            return None;
+        }
+        let offset = ((y * FRAME_WIDTH + x) * 3) as usize;
+        let mut rgb = [0; 3];
+        rgb.copy_from_slice(&self.framebuffer[offset..offset + 3]);
+        Some(rgb)
+    }
+
+    /// Current scanline, 0-239 visible, 241-260 vblank, 261 pre-render
+    pub fn scanline(&self) -> u16 {
+        self.scanline
+    }
+
+    /// Current dot of the scanline, 0-340
+    pub fn dot(&self) -> u16 {
+        self.dot
+    }
+
+    /// Number of frames completed, counted at the start of each vblank
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    /// Object attribute memory: Y, tile, attributes and X of 64 sprites
+    pub fn oam(&self) -> &[u8; 256] {
+        &self.oam
+    }
+
+    /// Get the total number of CPU cycles stolen by OAM DMA.
+    ///
+    /// # Returns
+    /// * The number of cycles the CPU was halted for OAM DMA so far
+    pub fn dma_cycles(&self) -> u64 {
+        self.dma_cycles
+    }
+
+    /// Background or sprites are enabled, so the PPU fetches from VRAM
+    fn is_rendering(&self) -> bool {
+        self.mask & (MASK_BACKGROUND | MASK_SPRITES) != 0
+    }
+
+    /// Index into the nametable RAM for a nametable address
+    fn nametable_index(&self, address: u16) -> usize {
+        let offset = (address & 0x03FF) as usize;
+        let table = (address >> 10) & 0x03;
+        let bank = match self.cartridge.mirroring() {
+            Mirroring::Horizontal => table >> 1,
+            Mirroring::Vertical => table & 0x01,
+            Mirroring::SingleScreenLower => 0,
+            Mirroring::SingleScreenUpper => 1,
+            Mirroring::FourScreen => table,
+        };
+        bank as usize * 0x400 + offset
+    }
+
+    /// Index into the palette RAM, where the backdrop entries of the sprite palettes
+    /// mirror those of the background palettes
+    fn palette_index(address: u16) -> usize {
+        let index = address & 0x1F;
+        if index & 0x13 == 0x10 {
+            (index & 0x0F) as usize
+        } else {
+            index as usize
+        }
+    }
+
+    /// Read a byte from the PPU address space
+    fn vram_read(&self, address: u16) -> u8 {
+        let address = address & 0x3FFF;
+        match address {
+            0x0000..=0x1FFF => self.cartridge.ppu_read(address),
+            0x2000..=0x3EFF => self.vram[self.nametable_index(address)],
+            // $3F00-$3FFF: Palette
+            _ => self.palette[Self::palette_index(address)],
+        }
+    }
+
+    /// Write a byte to the PPU address space
+    fn vram_write(&mut self, address: u16, data: u8) {
+        let address = address & 0x3FFF;
+        match address {
+            0x0000..=0x1FFF => self.cartridge.ppu_write(address, data),
+            0x2000..=0x3EFF => {
+                let index = self.nametable_index(address);
+                self.vram[index] = data;
+            }
+            // $3F00-$3FFF: Palette
+            _ => self.palette[Self::palette_index(address)] = data & 0x3F,
+        }
+    }
+
+    /// Step the VRAM address after a $2007 access
+    fn increment_address(&mut self) {
+        let step = if self.ctrl & CTRL_INCREMENT_32 != 0 {
+            32
+        } else {
+            1
+        };
+        self.v = self.v.wrapping_add(step) & 0x7FFF;
+    }
+
+    fn read_data(&mut self) -> u8 {
+        let address = self.v & 0x3FFF;
+        let data = if address >= 0x3F00 {
+            // Palette reads are not buffered, the buffer gets the nametable underneath
+            self.read_buffer = self.vram_read(address - 0x1000);
+            (self.io_latch & 0xC0) | self.vram_read(address)
+        } else {
+            let data = self.read_buffer;
+            self.read_buffer = self.vram_read(address);
+            data
+        };
+        self.increment_address();
+        data
+    }
+
+    /// Move to the next tile column, wrapping into the horizontally adjacent nametable
+    fn increment_x(&mut self) {
+        if self.v & 0x001F == 31 {
+            self.v &= !0x001F;
+            self.v ^= 0x0400;
+        } else {
+            self.v += 1;
+        }
+    }
+
+    /// Move to the next pixel row, wrapping into the vertically adjacent nametable
+    fn increment_y(&mut self) {
+        if self.v & 0x7000 != 0x7000 {
+            self.v += 0x1000;
+            return;
+        }
+        self.v &= !0x7000;
+        let mut coarse_y = (self.v & 0x03E0) >> 5;
+        if coarse_y == 29 {
+            coarse_y = 0;
+            self.v ^= 0x0800;
+        } else if coarse_y == 31 {
+            // Rows 30 and 31 hold the attributes, and wrap without switching nametables
+            coarse_y = 0;
+        } else {
+            coarse_y += 1;
+        }
+        self.v = (self.v & !0x03E0) | coarse_y << 5;
+    }
+
+    fn copy_horizontal(&mut self) {
+        self.v = (self.v & !0x041F) | (self.t & 0x041F);
+    }
+
+    fn copy_vertical(&mut self) {
+        self.v = (self.v & !0x7BE0) | (self.t & 0x7BE0);
+    }
+
+    fn shift_background(&mut self) {
+        self.pattern_low <<= 1;
+        self.pattern_high <<= 1;
+        self.attribute_low <<= 1;
+        self.attribute_high <<= 1;
+    }
+
+    fn load_background(&mut self) {
+        self.pattern_low = (self.pattern_low & 0xFF00) | self.next_pattern_low as u16;
+        self.pattern_high = (self.pattern_high & 0xFF00) | self.next_pattern_high as u16;
+        let low = if self.next_attribute & 0x01 != 0 {
+            0xFF
+        } else {
+            0x00
+        };
+        let high = if self.next_attribute & 0x02 != 0 {
+            0xFF
+        } else {
+            0x00
+        };
+        self.attribute_low = (self.attribute_low & 0xFF00) | low;
+        self.attribute_high = (self.attribute_high & 0xFF00) | high;
+    }
+
+    /// Fetch one step of the next background tile, one step every two dots
+    fn fetch_background(&mut self) {
+        match (self.dot - 1) % 8 {
+            0 => {
+                self.load_background();
+                self.next_tile = self.vram_read(0x2000 | (self.v & 0x0FFF));
+            }
+            2 => {
+                let address =
+                    0x23C0 | (self.v & 0x0C00) | ((self.v >> 4) & 0x38) | ((self.v >> 2) & 0x07);
+                let shift = ((self.v >> 4) & 0x04) | (self.v & 0x02);
+                self.next_attribute = (self.vram_read(address) >> shift) & 0x03;
+            }
+            4 => self.next_pattern_low = self.vram_read(self.background_pattern_address()),
+            6 => self.next_pattern_high = self.vram_read(self.background_pattern_address() + 8),
+            7 => self.increment_x(),
+            _ => {}
+        }
+    }
+
+    fn background_pattern_address(&self) -> u16 {
+        let table = if self.ctrl & CTRL_BACKGROUND_TABLE != 0 {
+            0x1000
+        } else {
+            0x0000
+        };
+        table + self.next_tile as u16 * 16 + ((self.v >> 12) & 0x07)
+    }
+
+    fn sprite_height(&self) -> u16 {
+        if self.ctrl & CTRL_SPRITE_8X16 != 0 {
+            16
+        } else {
+            8
+        }
+    }
+
+    /// Select the sprites of the next scanline and fetch their patterns
+    ///
+    /// Overflow is flagged for a ninth sprite in range, without the diagonal OAM scan
+    /// of the hardware evaluation.
+    fn evaluate_sprites(&mut self) {
+        self.sprites.clear();
+        let height = self.sprite_height();
+        for index in 0..64 {
+            let entry = &self.oam[index * 4..index * 4 + 4];
+            let row = self.scanline.wrapping_sub(entry[0] as u16);
+            if row >= height {
+                continue;
+            }
+            if self.sprites.len() == SPRITES_PER_LINE {
+                self.status |= STATUS_SPRITE_OVERFLOW;
+                break;
+            }
+            let (tile, attributes, x) = (entry[1], entry[2], entry[3]);
+            let row = if attributes & SPRITE_FLIP_Y != 0 {
+                height - 1 - row
+            } else {
+                row
+            };
+            let address = if height == 16 {
+                let table = (tile as u16 & 0x01) * 0x1000;
+                let tile = (tile & 0xFE) as u16 + row / 8;
+                table + tile * 16 + row % 8
+            } else {
+                let table = if self.ctrl & CTRL_SPRITE_TABLE != 0 {
+                    0x1000
+                } else {
+                    0x0000
+                };
+                table + tile as u16 * 16 + row
+            };
+            let mut pattern_low = self.vram_read(address);
+            let mut pattern_high = self.vram_read(address + 8);
+            if attributes & SPRITE_FLIP_X != 0 {
+                pattern_low = pattern_low.reverse_bits();
+                pattern_high = pattern_high.reverse_bits();
+            }
+            self.sprites.push(LineSprite {
+                x,
+                attributes,
+                pattern_low,
+                pattern_high,
+                zero: index == 0,
+            });
+        }
+    }
+
+    /// Clock the scanline counter of the cartridge where A12 rises during the pattern
+    /// fetches: at the sprite fetches if sprites use $1000, else at the background
+    /// fetches for the next line if the background uses $1000
+    fn clock_cartridge_scanline(&self) {
+        let sprites_high = self.ctrl & (CTRL_SPRITE_TABLE | CTRL_SPRITE_8X16) != 0;
+        let background_high = self.ctrl & CTRL_BACKGROUND_TABLE != 0;
+        match self.dot {
+            260 if sprites_high && !background_high => self.cartridge.clock_scanline(),
+            324 if background_high && !sprites_high => self.cartridge.clock_scanline(),
+            _ => {}
+        }
+    }
+
+    /// Render the pixel of the current dot into the framebuffer
+    fn render_pixel(&mut self) {
+        let x = self.dot - 1;
+
+        let mut background = 0;
+        if self.mask & MASK_BACKGROUND != 0 && (x >= 8 || self.mask & MASK_BACKGROUND_LEFT != 0) {
+            let bit = 0x8000 >> self.fine_x;
+            let pixel =
+                ((self.pattern_high & bit != 0) as u8) << 1 | (self.pattern_low & bit != 0) as u8;
+            if pixel != 0 {
+                let palette = ((self.attribute_high & bit != 0) as u8) << 1
+                    | (self.attribute_low & bit != 0) as u8;
+                background = palette << 2 | pixel;
+            }
+        }
+
+        let mut sprite = None;
+        if self.mask & MASK_SPRITES != 0 && (x >= 8 || self.mask & MASK_SPRITES_LEFT != 0) {
+            for line_sprite in &self.sprites {
+                let offset = x.wrapping_sub(line_sprite.x as u16);
+                if offset >= 8 {
+                    continue;
+                }
+                let shift = 7 - offset;
+                let pixel = ((line_sprite.pattern_high >> shift) & 0x01) << 1
+                    | (line_sprite.pattern_low >> shift) & 0x01;
+                if pixel == 0 {
+                    continue;
+                }
+                if line_sprite.zero && background != 0 && x != 255 {
+                    self.status |= STATUS_SPRITE_ZERO_HIT;
+                }
+                if sprite.is_none() {
+                    sprite = Some((
+                        0x10 | (line_sprite.attributes & SPRITE_PALETTE) << 2 | pixel,
+                        line_sprite.attributes & SPRITE_BEHIND != 0,
+                    ));
+                }
+            }
+        }
+
+        let index = match sprite {
+            Some((sprite, behind)) if !behind || background == 0 => sprite,
+            _ => background,
+        };
+        self.put_pixel(x, self.palette[Self::palette_index(index as u16)]);
+    }
+
+    /// Store a palette color into the framebuffer
+    fn put_pixel(&mut self, x: u16, color: u8) {
+        let color = if self.mask & MASK_GRAYSCALE != 0 {
+            color & 0x30
+        } else {
+            color
+        };
+        let offset = (self.scanline as usize * FRAME_WIDTH as usize + x as usize) * 3;
+        self.framebuffer[offset..offset + 3]
+            .copy_from_slice(&SYSTEM_PALETTE[(color & 0x3F) as usize]);
+    }
+
+    /// Advance the PPU by one dot
+    fn step_dot(&mut self) {
+        let visible = self.scanline < FRAME_HEIGHT as u16;
+        let pre_render = self.scanline == PRE_RENDER_LINE;
+
+        if pre_render && self.dot == 1 {
+            self.status &= !(STATUS_VBLANK | STATUS_SPRITE_ZERO_HIT | STATUS_SPRITE_OVERFLOW);
+        }
+        if self.scanline == VBLANK_LINE && self.dot == 1 {
+            self.status |= STATUS_VBLANK;
+            self.frame_count += 1;
+        }
+
+        if (visible || pre_render) && self.is_rendering() {
+            if (2..=257).contains(&self.dot) || (321..=337).contains(&self.dot) {
+                self.shift_background();
+                self.fetch_background();
+            }
+            match self.dot {
+                256 => self.increment_y(),
+                257 => {
+                    self.copy_horizontal();
+                    if visible {
+                        self.evaluate_sprites();
+                    } else {
+                        self.sprites.clear();
+                    }
+                }
+                280..=304 if pre_render => self.copy_vertical(),
+                _ => {}
+            }
+            self.clock_cartridge_scanline();
+        }
+
+        if visible && (1..=256).contains(&self.dot) {
+            if self.is_rendering() {
+                self.render_pixel();
+            } else {
+                // With rendering off the backdrop shows, or the palette entry the VRAM
+                // address points to
+                let address = if self.v & 0x3F00 == 0x3F00 {
+                    self.v
+                } else {
+                    0x3F00
+                };
+                let color = self.palette[Self::palette_index(address)];
+                self.put_pixel(self.dot - 1, color);
+            }
+        }
+
+        self.dot += 1;
+        // The pre-render line of odd frames skips its last dot while rendering
+        if pre_render && self.dot == DOTS - 1 && self.odd_frame && self.is_rendering() {
+            self.dot += 1;
+        }
+        if self.dot == DOTS {
+            self.dot = 0;
+            self.scanline += 1;
+            if self.scanline > PRE_RENDER_LINE {
+                self.scanline = 0;
+                self.odd_frame = !self.odd_frame;
+            }
+        }
+    }
+}
+
+impl BusDevice for Ppu {
+    fn read(&mut self, address: u16) -> Result<u8, BusError> {
+        match address {
+            PPU_START..=PPU_END => {}
+            // OAM DMA is write only, reads see the open CPU bus
+            OAM_DMA => return Ok((address >> 8) as u8),
+            _ => return Err(BusError::AddressOutOfRange(address)),
+        }
+        match address % PPU_REGISTER_COUNT {
+            REGISTER_STATUS => {
+                self.io_latch = (self.status & 0xE0) | (self.io_latch & 0x1F);
+                self.status &= !STATUS_VBLANK;
+                self.w = false;
+            }
+            REGISTER_OAM_DATA => {
+                let mut data = self.oam[self.oam_address as usize];
+                // The unused attribute bits do not exist
+                if self.oam_address & 0x03 == 0x02 {
+                    data &= 0xE3;
+                }
+                self.io_latch = data;
+            }
+            REGISTER_DATA => self.io_latch = self.read_data(),
+            // Write-only registers return the data bus latch
+            _ => {}
+        }
+        Ok(self.io_latch)
+    }
+
+    fn write(&mut self, address: u16, data: u8) -> Result<(), BusError> {
+        match address {
+            PPU_START..=PPU_END => {}
+            OAM_DMA => {
+                self.oam_dma = Some(OamDma {
+                    page: (data as u16) << 8,
+                    index: 0,
+                    wait: if self.odd_cpu_cycle { 2 } else { 1 },
+                    latch: None,
+                });
+                return Ok(());
+            }
+            _ => return Err(BusError::AddressOutOfRange(address)),
+        }
+        self.io_latch = data;
+        match address % PPU_REGISTER_COUNT {
+            REGISTER_CTRL => {
+                self.ctrl = data;
+                self.t = (self.t & !0x0C00) | (data as u16 & 0x03) << 10;
+            }
+            REGISTER_MASK => self.mask = data,
+            REGISTER_STATUS => {}
+            REGISTER_OAM_ADDRESS => self.oam_address = data,
+            REGISTER_OAM_DATA => {
+                self.oam[self.oam_address as usize] = data;
+                self.oam_address = self.oam_address.wrapping_add(1);
+            }
+            REGISTER_SCROLL => {
+                if self.w {
+                    self.t =
+                        (self.t & !0x73E0) | (data as u16 & 0x07) << 12 | (data as u16 >> 3) << 5;
+                } else {
+                    self.t = (self.t & !0x001F) | data as u16 >> 3;
+                    self.fine_x = data & 0x07;
+                }
+                self.w = !self.w;
+            }
+            REGISTER_ADDRESS => {
+                if self.w {
+                    self.t = (self.t & 0xFF00) | data as u16;
+                    self.v = self.t;
+                } else {
+                    self.t = (self.t & 0x00FF) | (data as u16 & 0x3F) << 8;
+                }
+                self.w = !self.w;
+            }
+            // $7: PPUDATA
+            _ => {
+                self.vram_write(self.v, data);
+                self.increment_address();
+            }
+        }
+        Ok(())
+    }
+
+    fn tick(&mut self) {
+        for _ in 0..3 {
+            self.step_dot();
+        }
+        self.odd_cpu_cycle = !self.odd_cpu_cycle;
+    }
+
+    fn check_irq(&self) -> bool {
+        // The PPU only drives NMI
+        false
+    }
+
+    fn check_nmi(&self) -> bool {
+        self.status & STATUS_VBLANK != 0 && self.ctrl & CTRL_NMI_ENABLE != 0
+    }
+
+    fn check_rdy(&self) -> bool {
+        self.oam_dma.is_some()
+    }
+
+    fn check_so(&self) -> bool {
+        // The PPU does not drive the SO line
+        false
+    }
+
+    fn dma_request(&mut self) -> Option<DmaRequest> {
+        let dma = self.oam_dma.as_mut()?;
+        self.dma_cycles += 1;
+        if dma.wait > 0 {
+            dma.wait -= 1;
+            return None;
+        }
+        match dma.latch.take() {
+            None => Some(DmaRequest::Read(dma.page | dma.index)),
+            Some(data) => {
+                dma.index += 1;
+                if dma.index == 256 {
+                    self.oam_dma = None;
+                }
+                Some(DmaRequest::Write(PPU_START + REGISTER_OAM_DATA, data))
+            }
+        }
+    }
+
+    fn dma_response(&mut self, data: Option<u8>) {
+        if let Some(dma) = self.oam_dma.as_mut() {
+            // Reads from unmapped addresses return an open bus, approximated as 0xFF
+            dma.latch = Some(data.unwrap_or(0xFF));
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! PNG files of rendered frames.
+//!
+//! Images are written as 8-bit RGB without compression: the zlib stream uses stored
+//! blocks, so no compression library is needed and the output is byte-for-byte
+//! reproducible.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// PNG file signature
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+/// Largest payload of a stored deflate block
+const STORED_BLOCK_SIZE: usize = 0xFFFF;
+
+/// Write an RGB image as a PNG stream.
+///
+/// # Arguments
+/// * `writer` - Destination of the stream
+/// * `width` - Width of the image in pixels
+/// * `height` - Height of the image in pixels
+/// * `rgb` - Pixels row by row, three bytes per pixel
+///
+/// # Returns
+/// * `Ok(())` if the stream was written
+/// * `Err(io::Error)` if writing failed
+///
+/// # Errors
+/// * If the writer fails
+/// * `io::ErrorKind::InvalidInput` if the image is empty or `rgb` does not hold
+///   `width * height` pixels
+///
+/// # Examples
+/// ``` ignore
+/// let mut bytes = Vec::new();
+/// write_png(&mut bytes, FRAME_WIDTH, FRAME_HEIGHT, ppu.framebuffer())?;
+/// ```
+pub fn write_png<W: Write>(writer: &mut W, width: u32, height: u32, rgb: &[u8]) -> io::Result<()> {
+    let row_size = width as usize * 3;
+    if width == 0 || height == 0 || rgb.len() != row_size * height as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Image size does not match the pixel data",
+        ));
+    }
+
+    writer.write_all(&SIGNATURE)?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // 8 bits per channel, RGB, deflate, adaptive filtering, no interlace
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    write_chunk(writer, b"IHDR", &header)?;
+
+    // Every row starts with filter type 0, no filtering
+    let mut raw = Vec::with_capacity((row_size + 1) * height as usize);
+    for row in rgb.chunks(row_size) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+    write_chunk(writer, b"IDAT", &zlib_stored(&raw))?;
+
+    write_chunk(writer, b"IEND", &[])
+}
+
+/// Save an RGB image as a PNG file.
+///
+/// # Arguments
+/// * `path` - Path of the file to create
+/// * `width` - Width of the image in pixels
+/// * `height` - Height of the image in pixels
+/// * `rgb` - Pixels row by row, three bytes per pixel
+///
+/// # Errors
+/// * If the file cannot be created or written
+/// * `io::ErrorKind::InvalidInput` if `rgb` does not match the image size
+///
+/// # Examples
+/// ``` ignore
+/// save_png("frame.png", FRAME_WIDTH, FRAME_HEIGHT, ppu.framebuffer())?;
+/// ```
+pub fn save_png<P: AsRef<Path>>(path: P, width: u32, height: u32, rgb: &[u8]) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_png(&mut writer, width, height, rgb)?;
+    writer.flush()
+}
+
+/// Write a chunk with its length and CRC
+fn write_chunk<W: Write>(writer: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    let length = u32::try_from(data.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Chunk too large for PNG"))?;
+    writer.write_all(&length.to_be_bytes())?;
+    writer.write_all(kind)?;
+    writer.write_all(data)?;
+    let crc = crc32(crc32(0xFFFF_FFFF, kind), data) ^ 0xFFFF_FFFF;
+    writer.write_all(&crc.to_be_bytes())
+}
+
+/// Wrap data, which must not be empty, in a zlib stream of stored deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let blocks = data.len().div_ceil(STORED_BLOCK_SIZE);
+    let mut stream = Vec::with_capacity(data.len() + blocks * 5 + 6);
+    // Deflate with a 32K window, no preset dictionary, fastest compression
+    stream.extend_from_slice(&[0x78, 0x01]);
+    let mut chunks = data.chunks(STORED_BLOCK_SIZE).peekable();
+    while let Some(chunk) = chunks.next() {
+        let last = chunks.peek().is_none();
+        let length = chunk.len() as u16;
+        stream.push(last as u8);
+        stream.extend_from_slice(&length.to_le_bytes());
+        stream.extend_from_slice(&(!length).to_le_bytes());
+        stream.extend_from_slice(chunk);
+    }
+    stream.extend_from_slice(&adler32(data).to_be_bytes());
+    stream
+}
+
+/// Update a CRC-32 (ISO 3309, as used by PNG) with more data
+fn crc32(mut crc: u32, data: &[u8]) -> u32 {
+    for byte in data {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    crc
+}
+
+/// Adler-32 checksum of the zlib stream
+fn adler32(data: &[u8]) -> u32 {
+    let (a, b) = data.iter().fold((1u32, 0u32), |(a, b), byte| {
+        let a = (a + *byte as u32) % 65521;
+        (a, (b + a) % 65521)
+    });
+    b << 16 | a
+}
@@ -0,0 +1,456 @@
+//! Unit tests for the 2A03 APU
+//!
+//! This module contains tests for the channel registers, the status register,
+//! the frame counter interrupts, DMC sample fetches and the rendered samples.
+
+use bus::BusController;
+use bus::trait_bus_device::BusDevice;
+use nes::apu::{
+    APU_END, APU_FRAME_COUNTER, APU_START, APU_STATUS, Apu, STATUS_DMC_IRQ, STATUS_FRAME_IRQ,
+};
+use ram::{Ram, ram_size::RamSize};
+
+/// Tick the APU for a number of CPU cycles
+fn run(apu: &mut Apu, cycles: u32) {
+    for _ in 0..cycles {
+        apu.tick();
+    }
+}
+
+/// Non-linear mixer output for the channel levels
+fn mix(pulse: u8, triangle: u8, noise: u8, dmc: u8) -> f64 {
+    let pulse_out = if pulse == 0 {
+        0.0
+    } else {
+        95.88 / (8128.0 / pulse as f64 + 100.0)
+    };
+    let tnd = triangle as f64 / 8227.0 + noise as f64 / 12241.0 + dmc as f64 / 22638.0;
+    pulse_out + 159.79 / (1.0 / tnd + 100.0)
+}
+
+/// Mixer output of a silent APU, the idle triangle sequencer holds level 15
+fn idle() -> f64 {
+    mix(0, 15, 0, 0)
+}
+
+/// FNV-1a hash of the sample bytes
+fn fnv1a(samples: &[i16]) -> u64 {
+    samples
+        .iter()
+        .flat_map(|sample| sample.to_le_bytes())
+        .fold(0xCBF2_9CE4_8422_2325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01B3)
+        })
+}
+
+/// Bus with RAM at $8000-$FFFF for DMC samples and the APU at its registers
+fn create_bus() -> (BusController, bus::DeviceId) {
+    let mut bus = BusController::new();
+    bus.register_device(
+        0x8000,
+        0xFFFF,
+        Box::new(Ram::new(RamSize::_32K, 0x8000).unwrap()),
+    )
+    .unwrap();
+    let id = bus
+        .register_device(APU_START, APU_END, Box::new(Apu::new()))
+        .unwrap();
+    bus.register_device_range(id, APU_STATUS, APU_STATUS)
+        .unwrap();
+    bus.register_device_range(id, APU_FRAME_COUNTER, APU_FRAME_COUNTER)
+        .unwrap();
+    (bus, id)
+}
+
+// Test the register decoding
+#[test]
+fn test_power_on_state() {
+    let mut apu = Apu::new();
+
+    assert_eq!(apu.read(APU_STATUS).unwrap(), 0x00);
+    assert_eq!(apu.output(), idle());
+    assert!(!apu.check_irq());
+    assert!(!apu.check_rdy());
+    assert!(apu.samples().is_empty());
+}
+
+#[test]
+fn test_open_bus_and_unmapped_registers() {
+    let mut apu = Apu::new();
+
+    assert_eq!(apu.read(0x4000).unwrap(), 0x40);
+    assert_eq!(apu.read(APU_FRAME_COUNTER).unwrap(), 0x40);
+    // OAM DMA and the controller port belong to other devices
+    assert!(apu.read(0x4014).is_err());
+    assert!(apu.write(0x4016, 0x01).is_err());
+}
+
+#[test]
+fn test_bus_registration() {
+    let (mut bus, _) = create_bus();
+
+    bus.write(APU_STATUS, 0x01).unwrap();
+    bus.write(0x4003, 0x08).unwrap();
+
+    assert_eq!(bus.read(APU_STATUS).unwrap(), 0x01);
+    assert!(bus.read(0x4014).is_err());
+    assert!(bus.read(0x4016).is_err());
+}
+
+// Test the length counters
+#[test]
+fn test_length_counters_in_status() {
+    let mut apu = Apu::new();
+
+    // Loads are ignored while a channel is disabled
+    apu.write(0x4003, 0x08).unwrap();
+    assert_eq!(apu.read(APU_STATUS).unwrap(), 0x00);
+
+    apu.write(APU_STATUS, 0x0F).unwrap();
+    apu.write(0x4003, 0x08).unwrap();
+    apu.write(0x4007, 0x08).unwrap();
+    apu.write(0x400B, 0x08).unwrap();
+    apu.write(0x400F, 0x08).unwrap();
+    assert_eq!(apu.read(APU_STATUS).unwrap(), 0x0F);
+
+    // Disabling a channel clears its length counter
+    apu.write(APU_STATUS, 0x0E).unwrap();
+    assert_eq!(apu.read(APU_STATUS).unwrap(), 0x0E);
+}
+
+#[test]
+fn test_length_counter_expires() {
+    let mut apu = Apu::new();
+    apu.write(APU_FRAME_COUNTER, FIVE_STEP | IRQ_INHIBIT)
+        .unwrap();
+    apu.write(APU_STATUS, 0x01).unwrap();
+    // Length index 1 loads 254, clocked twice per 5-step sequence
+    apu.write(0x4003, 0x08).unwrap();
+
+    run(&mut apu, 37282 * 126);
+    assert_eq!(apu.read(APU_STATUS).unwrap() & 0x01, 0x01);
+    run(&mut apu, 37282);
+    assert_eq!(apu.read(APU_STATUS).unwrap() & 0x01, 0x00);
+}
+
+#[test]
+fn test_length_counter_halt() {
+    let mut apu = Apu::new();
+    apu.write(APU_STATUS, 0x01).unwrap();
+    apu.write(0x4000, 0x20).unwrap();
+    // Length index 3 loads 2
+    apu.write(0x4003, 0x18).unwrap();
+
+    run(&mut apu, 29830 * 4);
+    assert_eq!(apu.read(APU_STATUS).unwrap() & 0x01, 0x01);
+}
+
+// Test the pulse channels
+#[test]
+fn test_pulse_duty_and_volume() {
+    let mut apu = Apu::new();
+    apu.write(APU_STATUS, 0x01).unwrap();
+    // 50% duty, constant volume 15, period 100
+    apu.write(0x4000, 0xBF).unwrap();
+    apu.write(0x4002, 100).unwrap();
+    apu.write(0x4003, 0x08).unwrap();
+
+    let mut high = 0;
+    for _ in 0..8 * 101 * 2 {
+        apu.tick();
+        if apu.output() > idle() {
+            high += 1;
+        }
+    }
+    assert_eq!(high, 4 * 101 * 2);
+
+    // A single pulse channel at full volume
+    run(&mut apu, 2 * 101 * 2);
+    assert!((apu.output() - mix(15, 15, 0, 0)).abs() < 1e-9);
+}
+
+#[test]
+fn test_pulse_sweep() {
+    let mut apu = Apu::new();
+    apu.write(APU_FRAME_COUNTER, FIVE_STEP | IRQ_INHIBIT)
+        .unwrap();
+    // Sweep enabled, divider period 0, shift 1
+    apu.write(0x4001, 0x81).unwrap();
+    apu.write(0x4002, 0x00).unwrap();
+    apu.write(0x4003, 0x01).unwrap();
+    assert_eq!(apu.pulse_period(1), Some(0x100));
+
+    // The frame counter write clocks a half frame immediately
+    apu.write(APU_FRAME_COUNTER, FIVE_STEP | IRQ_INHIBIT)
+        .unwrap();
+    assert_eq!(apu.pulse_period(1), Some(0x180));
+
+    // Negated, pulse 1 subtracts one more than pulse 2
+    apu.write(0x4001, 0x89).unwrap();
+    apu.write(0x4005, 0x89).unwrap();
+    apu.write(0x4006, 0x80).unwrap();
+    apu.write(0x4007, 0x01).unwrap();
+    apu.write(APU_FRAME_COUNTER, FIVE_STEP | IRQ_INHIBIT)
+        .unwrap();
+    assert_eq!(apu.pulse_period(1), Some(0x180 - 0xC0 - 1));
+    assert_eq!(apu.pulse_period(2), Some(0x180 - 0xC0));
+    assert_eq!(apu.pulse_period(3), None);
+}
+
+#[test]
+fn test_pulse_sweep_mutes_overflow() {
+    let mut apu = Apu::new();
+    apu.write(APU_STATUS, 0x01).unwrap();
+    apu.write(0x4000, 0xFF).unwrap();
+    // Sweep disabled, but a target above $7FF still mutes the channel
+    apu.write(0x4001, 0x00).unwrap();
+    apu.write(0x4002, 0x00).unwrap();
+    apu.write(0x4003, 0x0C).unwrap();
+
+    for _ in 0..0x500 * 8 * 2 {
+        apu.tick();
+        assert_eq!(apu.output(), idle());
+    }
+}
+
+// Test the triangle channel
+#[test]
+fn test_triangle_linear_counter() {
+    let mut apu = Apu::new();
+    apu.write(APU_STATUS, 0x04).unwrap();
+    // Linear counter 1, period 10
+    apu.write(0x4008, 0x01).unwrap();
+    apu.write(0x400A, 10).unwrap();
+    apu.write(0x400B, 0x08).unwrap();
+
+    // Silent until the first quarter frame loads the linear counter
+    let start = apu.output();
+    run(&mut apu, 7000);
+    assert_eq!(apu.output(), start);
+
+    // Steps for one quarter frame, then holds its level
+    run(&mut apu, 1000);
+    let mut levels = Vec::new();
+    for _ in 0..14913 - 8000 {
+        apu.tick();
+        levels.push(apu.output());
+    }
+    levels.dedup();
+    assert!(levels.len() > 100);
+    let held = apu.output();
+    run(&mut apu, 5000);
+    assert_eq!(apu.output(), held);
+}
+
+// Test the noise channel
+#[test]
+fn test_noise_output() {
+    let mut apu = Apu::new();
+    apu.write(APU_STATUS, 0x08).unwrap();
+    apu.write(0x400C, 0x3F).unwrap();
+    apu.write(0x400E, 0x00).unwrap();
+    apu.write(0x400F, 0x08).unwrap();
+
+    let mut levels = [0; 2];
+    for _ in 0..4000 {
+        apu.tick();
+        levels[(apu.output() > idle()) as usize] += 1;
+    }
+    assert!(levels[0] > 1000);
+    assert!(levels[1] > 1000);
+
+    // Short mode repeats every 93 steps
+    apu.write(0x400E, 0x80).unwrap();
+    let mut sequence = Vec::new();
+    for _ in 0..4 * 93 * 2 {
+        apu.tick();
+        sequence.push(apu.output() > idle());
+    }
+    assert_eq!(sequence[..4 * 93], sequence[4 * 93..]);
+}
+
+// Test the frame counter
+const FIVE_STEP: u8 = 0x80;
+const IRQ_INHIBIT: u8 = 0x40;
+
+#[test]
+fn test_frame_irq_four_step() {
+    let mut apu = Apu::new();
+    apu.write(APU_FRAME_COUNTER, 0x00).unwrap();
+
+    run(&mut apu, 29828);
+    assert!(!apu.check_irq());
+    apu.tick();
+    assert!(apu.check_irq());
+
+    // Reading the status returns and acknowledges the interrupt
+    assert_eq!(apu.read(APU_STATUS).unwrap(), STATUS_FRAME_IRQ);
+    assert!(!apu.check_irq());
+    assert_eq!(apu.read(APU_STATUS).unwrap(), 0x00);
+
+    // Raised again every sequence
+    run(&mut apu, 29830);
+    assert!(apu.check_irq());
+}
+
+#[test]
+fn test_frame_irq_inhibit() {
+    let mut apu = Apu::new();
+    run(&mut apu, 29830);
+    assert!(apu.check_irq());
+
+    // Setting the inhibit flag clears a pending interrupt
+    apu.write(APU_FRAME_COUNTER, IRQ_INHIBIT).unwrap();
+    assert!(!apu.check_irq());
+    run(&mut apu, 29830 * 2);
+    assert!(!apu.check_irq());
+}
+
+#[test]
+fn test_frame_no_irq_five_step() {
+    let mut apu = Apu::new();
+    apu.write(APU_FRAME_COUNTER, FIVE_STEP).unwrap();
+
+    run(&mut apu, 37282 * 2);
+    assert!(!apu.check_irq());
+}
+
+// Test the DMC
+#[test]
+fn test_dmc_direct_load() {
+    let mut apu = Apu::new();
+    apu.write(0x4011, 0x7F).unwrap();
+
+    assert!((apu.output() - mix(0, 15, 0, 127)).abs() < 1e-9);
+}
+
+#[test]
+fn test_dmc_fetch_steals_cycles() {
+    let (mut bus, id) = create_bus();
+    // Sample of 17 bytes at $C000
+    for offset in 0..17 {
+        bus.write(0xC000 + offset, 0xFF).unwrap();
+    }
+    bus.write(0x4010, 0x8F).unwrap();
+    bus.write(0x4012, 0x00).unwrap();
+    bus.write(0x4013, 0x01).unwrap();
+    bus.write(APU_STATUS, 0x10).unwrap();
+    assert_eq!(bus.read(APU_STATUS).unwrap(), 0x10);
+    assert!(bus.check_rdy());
+
+    // Nothing is fetched until the CPU is halted
+    bus.tick();
+    assert!(bus.check_rdy());
+
+    bus.set_cpu_halted(true);
+    let mut cycles = 0;
+    while bus.check_rdy() {
+        bus.tick();
+        cycles += 1;
+    }
+    assert_eq!(cycles, 4);
+    assert_eq!(bus.device::<Apu>(id).unwrap().dmc_cycles(), 4);
+
+    // The next byte is fetched once the output unit took the first one
+    bus.set_cpu_halted(false);
+    let mut fetches = 1;
+    for _ in 0..54 * 8 * 20 {
+        bus.tick();
+        if bus.check_rdy() {
+            bus.set_cpu_halted(true);
+            while bus.check_rdy() {
+                bus.tick();
+            }
+            bus.set_cpu_halted(false);
+            fetches += 1;
+        }
+    }
+    assert_eq!(fetches, 17);
+    assert_eq!(bus.device::<Apu>(id).unwrap().dmc_cycles(), 17 * 4);
+
+    // The end of the sample raises the DMC interrupt
+    assert!(bus.check_irq());
+    assert_eq!(bus.read(APU_STATUS).unwrap(), STATUS_DMC_IRQ);
+    bus.write(APU_STATUS, 0x00).unwrap();
+    assert!(!bus.check_irq());
+
+    // All ones ramp the output level up from 0
+    let output = bus.device::<Apu>(id).unwrap().output();
+    assert!((output - mix(0, 15, 0, 126)).abs() < 1e-9);
+}
+
+#[test]
+fn test_dmc_loop() {
+    let (mut bus, id) = create_bus();
+    bus.write(0x4010, 0x4F).unwrap();
+    bus.write(0x4013, 0x00).unwrap();
+    bus.write(APU_STATUS, 0x10).unwrap();
+
+    bus.set_cpu_halted(true);
+    for _ in 0..54 * 8 * 10 {
+        bus.tick();
+    }
+
+    // A looping sample keeps fetching without an interrupt
+    assert_eq!(bus.read(APU_STATUS).unwrap(), 0x10);
+    assert!(!bus.check_irq());
+    assert!(bus.device::<Apu>(id).unwrap().dmc_cycles() >= 10 * 4);
+}
+
+// Test the rendered samples
+#[test]
+fn test_sample_count() {
+    let mut apu = Apu::new().with_sample_rate(1_000_000, 44_100);
+    assert_eq!(apu.sample_rate(), 44_100);
+
+    run(&mut apu, 100_000);
+    assert_eq!(apu.samples().len(), 4410);
+    let level = (idle() * 32767.0).round() as i16;
+    assert!(apu.samples().iter().all(|sample| *sample == level));
+
+    let samples = apu.take_samples();
+    assert_eq!(samples.len(), 4410);
+    assert!(apu.samples().is_empty());
+}
+
+#[test]
+fn test_reset() {
+    let mut apu = Apu::new().with_sample_rate(1_000_000, 10_000);
+    apu.write(APU_STATUS, 0x1F).unwrap();
+    apu.write(0x4003, 0x08).unwrap();
+    run(&mut apu, 1000);
+
+    apu.reset();
+    assert_eq!(apu.read(APU_STATUS).unwrap(), 0x00);
+    assert!(apu.samples().is_empty());
+    assert_eq!(apu.sample_rate(), 10_000);
+}
+
+#[test]
+fn test_render_golden() {
+    let mut apu = Apu::new();
+    apu.write(APU_STATUS, 0x0F).unwrap();
+    // Pulse 1: 25% duty, decaying envelope, A4
+    apu.write(0x4000, 0x44).unwrap();
+    apu.write(0x4002, 0xFD).unwrap();
+    apu.write(0x4003, 0x08).unwrap();
+    // Pulse 2: 50% duty, constant volume 8, sweeping down
+    apu.write(0x4004, 0x98).unwrap();
+    apu.write(0x4005, 0x9B).unwrap();
+    apu.write(0x4006, 0x80).unwrap();
+    apu.write(0x4007, 0x09).unwrap();
+    // Triangle
+    apu.write(0x4008, 0x7F).unwrap();
+    apu.write(0x400A, 0x50).unwrap();
+    apu.write(0x400B, 0x09).unwrap();
+    // Noise, short mode
+    apu.write(0x400C, 0x36).unwrap();
+    apu.write(0x400E, 0x84).unwrap();
+    apu.write(0x400F, 0x08).unwrap();
+
+    run(&mut apu, 1_789_773 / 20);
+    let samples = apu.take_samples();
+    assert_eq!(samples.len(), 2204);
+    assert!(samples.iter().any(|sample| *sample != samples[0]));
+    assert_eq!(fnv1a(&samples), 16454546284591164113);
+}
@@ -0,0 +1,218 @@
+//! Unit tests for iNES header parsing and the cartridge device
+//!
+//! This module contains tests for parsing iNES and NES 2.0 headers,
+//! building cartridges from images and accessing them through the bus.
+
+use bus::BusController;
+use bus::trait_bus_device::BusDevice;
+use nes::cartridge::{CARTRIDGE_END, CARTRIDGE_START, Cartridge};
+use nes::errors::CartridgeError;
+use nes::header::{HeaderFormat, INesHeader, Mirroring};
+
+/// Build an iNES image whose PRG bytes hold their 8K bank number and CHR bytes their 1K bank number
+fn build_image(header: [u8; 16], trainer: bool) -> Vec<u8> {
+    let mut image = header.to_vec();
+    if trainer {
+        image.extend((0..512).map(|index| index as u8));
+    }
+    for bank in 0..header[4] as usize * 2 {
+        image.extend(std::iter::repeat_n(bank as u8, 0x2000));
+    }
+    for bank in 0..header[5] as usize * 8 {
+        image.extend(std::iter::repeat_n(bank as u8, 0x0400));
+    }
+    image
+}
+
+/// iNES header with the given PRG and CHR sizes and flags 6
+fn ines_header(prg_banks: u8, chr_banks: u8, flags6: u8) -> [u8; 16] {
+    let mut header = [0; 16];
+    header[..4].copy_from_slice(b"NES\x1A");
+    header[4] = prg_banks;
+    header[5] = chr_banks;
+    header[6] = flags6;
+    header
+}
+
+// Test header parsing
+#[test]
+fn test_parse_ines_header() {
+    let mut header = ines_header(2, 1, 0x13);
+    header[7] = 0x40;
+
+    let parsed = INesHeader::parse(&header).unwrap();
+
+    assert_eq!(parsed.format, HeaderFormat::INes);
+    assert_eq!(parsed.prg_rom_size, 0x8000);
+    assert_eq!(parsed.chr_rom_size, 0x2000);
+    assert_eq!(parsed.chr_ram_size, 0);
+    assert_eq!(parsed.prg_ram_size, 0x2000);
+    assert_eq!(parsed.mapper, 0x41);
+    assert_eq!(parsed.mirroring, Mirroring::Vertical);
+    assert!(parsed.battery);
+    assert!(!parsed.trainer);
+}
+
+#[test]
+fn test_parse_ines_header_ignores_dirty_upper_mapper_nibble() {
+    let mut header = ines_header(1, 0, 0x20);
+    header[7] = 0x40;
+    header[12..].copy_from_slice(b"ude!");
+
+    let parsed = INesHeader::parse(&header).unwrap();
+
+    assert_eq!(parsed.mapper, 2);
+    assert_eq!(parsed.chr_ram_size, 0x2000);
+}
+
+#[test]
+fn test_parse_nes20_header() {
+    let mut header = ines_header(0x20, 0x00, 0x4C);
+    header[7] = 0x18;
+    header[8] = 0x21;
+    header[9] = 0x01;
+    header[10] = 0x70;
+    header[11] = 0x07;
+
+    let parsed = INesHeader::parse(&header).unwrap();
+
+    assert_eq!(parsed.format, HeaderFormat::Nes20);
+    assert_eq!(parsed.mapper, 0x114);
+    assert_eq!(parsed.submapper, 2);
+    assert_eq!(parsed.prg_rom_size, 0x120 * 0x4000);
+    assert_eq!(parsed.prg_ram_size, 0x2000);
+    assert_eq!(parsed.chr_ram_size, 0x2000);
+    assert_eq!(parsed.mirroring, Mirroring::FourScreen);
+    assert!(parsed.trainer);
+}
+
+#[test]
+fn test_parse_nes20_exponent_multiplier_size() {
+    let mut header = ines_header(0x2D, 0x00, 0x00);
+    header[7] = 0x08;
+    header[9] = 0x0F;
+
+    let parsed = INesHeader::parse(&header).unwrap();
+
+    // 2^11 * (1 * 2 + 1)
+    assert_eq!(parsed.prg_rom_size, 6144);
+}
+
+#[test]
+fn test_parse_invalid_magic() {
+    let result = INesHeader::parse(b"NES\x00\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00");
+
+    assert!(matches!(result, Err(CartridgeError::InvalidHeader(_))));
+}
+
+#[test]
+fn test_parse_missing_prg_rom() {
+    let result = INesHeader::parse(&ines_header(0, 1, 0));
+
+    assert!(matches!(result, Err(CartridgeError::InvalidHeader(_))));
+}
+
+// Test cartridge creation
+#[test]
+fn test_truncated_image() {
+    let mut image = build_image(ines_header(2, 1, 0), false);
+    image.truncate(0x8000);
+
+    let result = Cartridge::from_ines(&image);
+
+    assert!(matches!(
+        result,
+        Err(CartridgeError::Truncated {
+            expected: 0xA010,
+            actual: 0x8000
+        })
+    ));
+}
+
+#[test]
+fn test_unsupported_mapper() {
+    let image = build_image(ines_header(1, 1, 0x50), false);
+
+    let result = Cartridge::from_ines(&image);
+
+    assert!(matches!(result, Err(CartridgeError::UnsupportedMapper(5))));
+}
+
+#[test]
+fn test_trainer_is_loaded_at_7000() {
+    let image = build_image(ines_header(1, 1, 0x04), true);
+    let mut cartridge = Cartridge::from_ines(&image).unwrap();
+
+    assert_eq!(cartridge.read(0x7000).unwrap(), 0x00);
+    assert_eq!(cartridge.read(0x71FF).unwrap(), 0xFF);
+    assert_eq!(cartridge.read(0x8000).unwrap(), 0x00);
+}
+
+// Test NROM through the bus
+#[test]
+fn test_nrom_16k_is_mirrored() {
+    let image = build_image(ines_header(1, 1, 0x01), false);
+    let cartridge = Cartridge::from_ines(&image).unwrap();
+    let mut bus = BusController::new();
+    bus.register_device(CARTRIDGE_START, CARTRIDGE_END, Box::new(cartridge.clone()))
+        .unwrap();
+
+    assert_eq!(bus.read(0x8000).unwrap(), 0x00);
+    assert_eq!(bus.read(0xA000).unwrap(), 0x01);
+    assert_eq!(bus.read(0xC000).unwrap(), 0x00);
+    assert_eq!(bus.read(0xE000).unwrap(), 0x01);
+    assert_eq!(cartridge.mirroring(), Mirroring::Vertical);
+}
+
+#[test]
+fn test_nrom_prg_ram_and_open_bus() {
+    let image = build_image(ines_header(2, 1, 0), false);
+    let mut bus = BusController::new();
+    bus.register_device(
+        CARTRIDGE_START,
+        CARTRIDGE_END,
+        Box::new(Cartridge::from_ines(&image).unwrap()),
+    )
+    .unwrap();
+
+    bus.write(0x6000, 0x80).unwrap();
+
+    assert_eq!(bus.read(0x6000).unwrap(), 0x80);
+    assert_eq!(bus.read(0x5000).unwrap(), 0x50);
+    assert_eq!(bus.read(0xE000).unwrap(), 0x03);
+}
+
+#[test]
+fn test_chr_rom_is_read_only_and_chr_ram_writable() {
+    let rom_cartridge = Cartridge::from_ines(&build_image(ines_header(1, 1, 0), false)).unwrap();
+    let ram_cartridge = Cartridge::from_ines(&build_image(ines_header(1, 0, 0), false)).unwrap();
+
+    rom_cartridge.ppu_write(0x0400, 0xAA);
+    ram_cartridge.ppu_write(0x0400, 0xAA);
+
+    assert_eq!(rom_cartridge.ppu_read(0x0400), 0x01);
+    assert_eq!(ram_cartridge.ppu_read(0x0400), 0xAA);
+}
+
+#[test]
+fn test_load_stores_into_prg_rom() {
+    let mut cartridge = Cartridge::from_ines(&build_image(ines_header(2, 1, 0), false)).unwrap();
+
+    cartridge.load(0xFFFC, 0x34).unwrap();
+    cartridge.write(0xFFFD, 0x12).unwrap();
+
+    assert_eq!(cartridge.read(0xFFFC).unwrap(), 0x34);
+    assert_eq!(cartridge.read(0xFFFD).unwrap(), 0x03);
+}
+
+#[test]
+fn test_battery_ram_save_and_restore() {
+    let cartridge = Cartridge::from_ines(&build_image(ines_header(1, 1, 0x02), false)).unwrap();
+    let mut device = cartridge.clone();
+
+    cartridge.restore_prg_ram(&[1, 2, 3]);
+
+    assert_eq!(device.read(0x6001).unwrap(), 2);
+    device.write(0x6003, 4).unwrap();
+    assert_eq!(&cartridge.prg_ram()[..4], &[1, 2, 3, 4]);
+}
@@ -0,0 +1,210 @@
+//! Unit tests for the MMC1, UxROM, CNROM and MMC3 mappers
+//!
+//! This module contains tests for PRG and CHR bank switching, mirroring control,
+//! PRG RAM protection and the MMC3 scanline IRQ.
+
+use bus::trait_bus_device::BusDevice;
+use nes::cartridge::Cartridge;
+use nes::header::Mirroring;
+
+/// Build a cartridge whose PRG bytes hold their 8K bank number and CHR bytes their 1K bank number
+fn create_cartridge(mapper: u8, prg_banks: u8, chr_banks: u8) -> Cartridge {
+    let mut image = b"NES\x1A".to_vec();
+    image.extend_from_slice(&[prg_banks, chr_banks, mapper << 4, mapper & 0xF0]);
+    image.extend_from_slice(&[0; 8]);
+    for bank in 0..prg_banks as usize * 2 {
+        image.extend(std::iter::repeat_n(bank as u8, 0x2000));
+    }
+    for bank in 0..chr_banks as usize * 8 {
+        image.extend(std::iter::repeat_n(bank as u8, 0x0400));
+    }
+    Cartridge::from_ines(&image).unwrap()
+}
+
+/// Write a value to an MMC1 register through the serial port
+fn mmc1_write(cartridge: &mut Cartridge, address: u16, value: u8) {
+    for bit in 0..5 {
+        cartridge.write(address, (value >> bit) & 0x01).unwrap();
+    }
+}
+
+// Test MMC1
+#[test]
+fn test_mmc1_power_on_fixes_last_bank() {
+    let mut cartridge = create_cartridge(1, 8, 2);
+
+    assert_eq!(cartridge.read(0x8000).unwrap(), 0x00);
+    assert_eq!(cartridge.read(0xC000).unwrap(), 0x0E);
+    assert_eq!(cartridge.read(0xE000).unwrap(), 0x0F);
+}
+
+#[test]
+fn test_mmc1_prg_modes() {
+    let mut cartridge = create_cartridge(1, 8, 2);
+
+    mmc1_write(&mut cartridge, 0xE000, 0x03);
+    assert_eq!(cartridge.read(0x8000).unwrap(), 0x06);
+
+    // Fix first bank at $8000, switch $C000
+    mmc1_write(&mut cartridge, 0x8000, 0x08);
+    assert_eq!(cartridge.read(0x8000).unwrap(), 0x00);
+    assert_eq!(cartridge.read(0xC000).unwrap(), 0x06);
+
+    // 32K mode ignores the low bank bit
+    mmc1_write(&mut cartridge, 0x8000, 0x00);
+    assert_eq!(cartridge.read(0x8000).unwrap(), 0x04);
+    assert_eq!(cartridge.read(0xC000).unwrap(), 0x06);
+}
+
+#[test]
+fn test_mmc1_reset_write_restores_prg_mode() {
+    let mut cartridge = create_cartridge(1, 8, 2);
+    mmc1_write(&mut cartridge, 0x8000, 0x00);
+    cartridge.write(0x8000, 0x01).unwrap();
+
+    cartridge.write(0x8000, 0x80).unwrap();
+    mmc1_write(&mut cartridge, 0xE000, 0x02);
+
+    assert_eq!(cartridge.read(0x8000).unwrap(), 0x04);
+    assert_eq!(cartridge.read(0xC000).unwrap(), 0x0E);
+}
+
+#[test]
+fn test_mmc1_chr_modes_and_mirroring() {
+    let mut cartridge = create_cartridge(1, 2, 4);
+
+    // 8K mode ignores the low bank bit and bank 1
+    mmc1_write(&mut cartridge, 0xA000, 0x03);
+    assert_eq!(cartridge.ppu_read(0x0000), 0x08);
+    assert_eq!(cartridge.ppu_read(0x1000), 0x0C);
+
+    // 4K mode, vertical mirroring
+    mmc1_write(&mut cartridge, 0x8000, 0x1E);
+    mmc1_write(&mut cartridge, 0xC000, 0x05);
+    assert_eq!(cartridge.ppu_read(0x0000), 0x0C);
+    assert_eq!(cartridge.ppu_read(0x1000), 0x14);
+    assert_eq!(cartridge.mirroring(), Mirroring::Vertical);
+
+    mmc1_write(&mut cartridge, 0x8000, 0x01);
+    assert_eq!(cartridge.mirroring(), Mirroring::SingleScreenUpper);
+}
+
+#[test]
+fn test_mmc1_prg_ram_disable() {
+    let mut cartridge = create_cartridge(1, 2, 1);
+    cartridge.write(0x6000, 0x42).unwrap();
+
+    mmc1_write(&mut cartridge, 0xE000, 0x10);
+
+    assert_eq!(cartridge.read(0x6000).unwrap(), 0x60);
+    mmc1_write(&mut cartridge, 0xE000, 0x00);
+    assert_eq!(cartridge.read(0x6000).unwrap(), 0x42);
+}
+
+#[test]
+fn test_mmc1_surom_outer_bank() {
+    let mut cartridge = create_cartridge(1, 32, 0);
+
+    mmc1_write(&mut cartridge, 0xA000, 0x10);
+    mmc1_write(&mut cartridge, 0xE000, 0x01);
+
+    assert_eq!(cartridge.read(0x8000).unwrap(), 0x22);
+    assert_eq!(cartridge.read(0xC000).unwrap(), 0x3E);
+}
+
+// Test UxROM
+#[test]
+fn test_uxrom_switches_lower_bank() {
+    let mut cartridge = create_cartridge(2, 8, 0);
+    assert_eq!(cartridge.read(0xC000).unwrap(), 0x0E);
+
+    cartridge.write(0x8000, 0x05).unwrap();
+
+    assert_eq!(cartridge.read(0x8000).unwrap(), 0x0A);
+    assert_eq!(cartridge.read(0xBFFF).unwrap(), 0x0B);
+    assert_eq!(cartridge.read(0xC000).unwrap(), 0x0E);
+}
+
+// Test CNROM
+#[test]
+fn test_cnrom_switches_chr_bank() {
+    let mut cartridge = create_cartridge(3, 2, 4);
+
+    cartridge.write(0x8000, 0x02).unwrap();
+
+    assert_eq!(cartridge.ppu_read(0x0000), 0x10);
+    assert_eq!(cartridge.ppu_read(0x1C00), 0x17);
+    assert_eq!(cartridge.read(0xC000).unwrap(), 0x02);
+}
+
+// Test MMC3
+#[test]
+fn test_mmc3_prg_banking() {
+    let mut cartridge = create_cartridge(4, 8, 8);
+    cartridge.write(0x8000, 0x06).unwrap();
+    cartridge.write(0x8001, 0x03).unwrap();
+    cartridge.write(0x8000, 0x07).unwrap();
+    cartridge.write(0x8001, 0x05).unwrap();
+
+    assert_eq!(cartridge.read(0x8000).unwrap(), 0x03);
+    assert_eq!(cartridge.read(0xA000).unwrap(), 0x05);
+    assert_eq!(cartridge.read(0xC000).unwrap(), 0x0E);
+    assert_eq!(cartridge.read(0xE000).unwrap(), 0x0F);
+
+    cartridge.write(0x8000, 0x46).unwrap();
+    assert_eq!(cartridge.read(0x8000).unwrap(), 0x0E);
+    assert_eq!(cartridge.read(0xC000).unwrap(), 0x03);
+}
+
+#[test]
+fn test_mmc3_chr_banking_and_inversion() {
+    let mut cartridge = create_cartridge(4, 2, 8);
+    cartridge.write(0x8000, 0x00).unwrap();
+    cartridge.write(0x8001, 0x09).unwrap();
+    cartridge.write(0x8000, 0x05).unwrap();
+    cartridge.write(0x8001, 0x21).unwrap();
+
+    assert_eq!(cartridge.ppu_read(0x0000), 0x08);
+    assert_eq!(cartridge.ppu_read(0x0400), 0x09);
+    assert_eq!(cartridge.ppu_read(0x1C00), 0x21);
+
+    cartridge.write(0x8000, 0x80).unwrap();
+    assert_eq!(cartridge.ppu_read(0x1000), 0x08);
+    assert_eq!(cartridge.ppu_read(0x0C00), 0x21);
+}
+
+#[test]
+fn test_mmc3_mirroring_and_prg_ram_protect() {
+    let mut cartridge = create_cartridge(4, 2, 1);
+    cartridge.write(0xA000, 0x01).unwrap();
+    assert_eq!(cartridge.mirroring(), Mirroring::Horizontal);
+
+    cartridge.write(0x6000, 0x11).unwrap();
+    cartridge.write(0xA001, 0xC0).unwrap();
+    cartridge.write(0x6000, 0x22).unwrap();
+
+    assert_eq!(cartridge.read(0x6000).unwrap(), 0x11);
+}
+
+#[test]
+fn test_mmc3_scanline_irq() {
+    let mut cartridge = create_cartridge(4, 2, 1);
+    cartridge.write(0xC000, 0x02).unwrap();
+    cartridge.write(0xC001, 0x00).unwrap();
+    cartridge.write(0xE001, 0x00).unwrap();
+
+    // Reload to 2, then count down to 0
+    cartridge.clock_scanline();
+    cartridge.clock_scanline();
+    assert!(!cartridge.check_irq());
+    cartridge.clock_scanline();
+    assert!(cartridge.check_irq());
+
+    // Acknowledge and disable
+    cartridge.write(0xE000, 0x00).unwrap();
+    assert!(!cartridge.check_irq());
+    cartridge.clock_scanline();
+    cartridge.clock_scanline();
+    cartridge.clock_scanline();
+    assert!(!cartridge.check_irq());
+}
@@ -0,0 +1,560 @@
+//! Unit tests for the 2C02 PPU
+//!
+//! This module contains tests for the register side effects, nametable mirroring,
+//! frame timing and NMI, background and sprite rendering, OAM DMA, the MMC3 scanline
+//! clock and PNG output.
+
+use bus::BusController;
+use bus::trait_bus_device::BusDevice;
+use nes::cartridge::Cartridge;
+use nes::ppu::png::{save_png, write_png};
+use nes::ppu::{
+    CTRL_NMI_ENABLE, CTRL_SPRITE_TABLE, FRAME_HEIGHT, FRAME_WIDTH, MASK_BACKGROUND,
+    MASK_BACKGROUND_LEFT, MASK_SPRITES, MASK_SPRITES_LEFT, OAM_DMA, PPU_END, PPU_START, Ppu,
+    STATUS_SPRITE_OVERFLOW, STATUS_SPRITE_ZERO_HIT, STATUS_VBLANK,
+};
+use ram::{Ram, ram_size::RamSize};
+
+const CTRL: u16 = 0x2000;
+const MASK: u16 = 0x2001;
+const STATUS: u16 = 0x2002;
+const OAM_ADDRESS: u16 = 0x2003;
+const OAM_DATA: u16 = 0x2004;
+const SCROLL: u16 = 0x2005;
+const ADDRESS: u16 = 0x2006;
+const DATA: u16 = 0x2007;
+
+const BLACK: [u8; 3] = [0, 0, 0];
+const WHITE: [u8; 3] = [236, 238, 236];
+const RED: [u8; 3] = [152, 34, 32];
+const GREEN: [u8; 3] = [76, 208, 32];
+
+/// Show everything, including the leftmost column
+const MASK_ALL: u8 = MASK_BACKGROUND | MASK_BACKGROUND_LEFT | MASK_SPRITES | MASK_SPRITES_LEFT;
+
+/// NROM cartridge with CHR RAM, flags 6 select the mirroring
+fn create_cartridge(mapper: u8, chr_banks: u8, flags6: u8) -> Cartridge {
+    let mut image = b"NES\x1A".to_vec();
+    image.extend_from_slice(&[2, chr_banks, mapper << 4 | flags6, mapper & 0xF0]);
+    image.extend_from_slice(&[0; 8]);
+    image.extend(std::iter::repeat_n(0xEA, 0x8000));
+    image.extend(std::iter::repeat_n(0x00, chr_banks as usize * 0x2000));
+    Cartridge::from_ines(&image).unwrap()
+}
+
+/// Set the VRAM address through $2006
+fn set_address(ppu: &mut Ppu, address: u16) {
+    ppu.write(ADDRESS, (address >> 8) as u8).unwrap();
+    ppu.write(ADDRESS, address as u8).unwrap();
+}
+
+/// Write bytes to VRAM through $2007
+fn write_vram(ppu: &mut Ppu, address: u16, data: &[u8]) {
+    set_address(ppu, address);
+    for byte in data {
+        ppu.write(DATA, *byte).unwrap();
+    }
+}
+
+/// Tick until the next vblank starts, with the whole frame rendered
+fn run_frame(ppu: &mut Ppu) {
+    let frame = ppu.frame_count();
+    while ppu.frame_count() == frame {
+        ppu.tick();
+    }
+}
+
+/// PPU with a solid color 1 pattern in tile 1, solid color 3 in tile 2 and a palette of
+/// black backdrop, white background color 1 and green sprite color 3, and no sprites
+fn create_scene() -> Ppu {
+    let mut ppu = Ppu::new(create_cartridge(0, 0, 0));
+    write_vram(&mut ppu, 0x0010, &[0xFF; 8]);
+    write_vram(&mut ppu, 0x0020, &[0xFF; 16]);
+    write_vram(&mut ppu, 0x3F00, &[0x0F, 0x30]);
+    write_vram(&mut ppu, 0x3F05, &[0x16]);
+    write_vram(&mut ppu, 0x3F13, &[0x2A]);
+    // Hide all sprites below the frame
+    ppu.write(OAM_ADDRESS, 0x00).unwrap();
+    for _ in 0..256 {
+        ppu.write(OAM_DATA, 0xFF).unwrap();
+    }
+    ppu
+}
+
+/// Reset the scroll position after VRAM writes and enable rendering
+fn start_rendering(ppu: &mut Ppu, mask: u8) {
+    ppu.write(CTRL, 0x00).unwrap();
+    ppu.write(SCROLL, 0x00).unwrap();
+    ppu.write(SCROLL, 0x00).unwrap();
+    ppu.write(MASK, mask).unwrap();
+}
+
+/// Store a sprite in OAM through $2003/$2004
+fn set_sprite(ppu: &mut Ppu, index: u8, y: u8, tile: u8, attributes: u8, x: u8) {
+    ppu.write(OAM_ADDRESS, index * 4).unwrap();
+    for byte in [y, tile, attributes, x] {
+        ppu.write(OAM_DATA, byte).unwrap();
+    }
+}
+
+/// FNV-1a hash of the bytes
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xCBF2_9CE4_8422_2325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x0100_0000_01B3)
+    })
+}
+
+// Test the registers
+#[test]
+fn test_buffered_data_read() {
+    let mut ppu = Ppu::new(create_cartridge(0, 0, 0));
+    write_vram(&mut ppu, 0x2400, &[0x11, 0x22]);
+
+    set_address(&mut ppu, 0x2400);
+    // The first read returns the stale buffer
+    ppu.read(DATA).unwrap();
+    assert_eq!(ppu.read(DATA).unwrap(), 0x11);
+    assert_eq!(ppu.read(DATA).unwrap(), 0x22);
+}
+
+#[test]
+fn test_palette_read_is_not_buffered() {
+    let mut ppu = Ppu::new(create_cartridge(0, 0, 0));
+    write_vram(&mut ppu, 0x2F01, &[0x5A]);
+    write_vram(&mut ppu, 0x3F01, &[0x21]);
+
+    set_address(&mut ppu, 0x3F01);
+    assert_eq!(ppu.read(DATA).unwrap(), 0x21);
+    // The buffer was filled from the nametable below the palette
+    set_address(&mut ppu, 0x2000);
+    assert_eq!(ppu.read(DATA).unwrap(), 0x5A);
+}
+
+#[test]
+fn test_palette_mirrors() {
+    let mut ppu = Ppu::new(create_cartridge(0, 0, 0));
+    write_vram(&mut ppu, 0x3F10, &[0x12]);
+    write_vram(&mut ppu, 0x3F24, &[0x34]);
+
+    set_address(&mut ppu, 0x3F00);
+    assert_eq!(ppu.read(DATA).unwrap(), 0x12);
+    set_address(&mut ppu, 0x3F14);
+    assert_eq!(ppu.read(DATA).unwrap(), 0x34);
+}
+
+#[test]
+fn test_address_increment_32() {
+    let mut ppu = Ppu::new(create_cartridge(0, 0, 0));
+    ppu.write(CTRL, 0x04).unwrap();
+    write_vram(&mut ppu, 0x2000, &[0x01, 0x02]);
+    ppu.write(CTRL, 0x00).unwrap();
+
+    set_address(&mut ppu, 0x2020);
+    ppu.read(DATA).unwrap();
+    assert_eq!(ppu.read(DATA).unwrap(), 0x02);
+}
+
+#[test]
+fn test_chr_ram_through_cartridge() {
+    let cartridge = create_cartridge(0, 0, 0);
+    let mut ppu = Ppu::new(cartridge.clone());
+    write_vram(&mut ppu, 0x1234, &[0xA5]);
+
+    assert_eq!(cartridge.ppu_read(0x1234), 0xA5);
+}
+
+#[test]
+fn test_status_read_side_effects() {
+    let mut ppu = Ppu::new(create_cartridge(0, 0, 0));
+    run_frame(&mut ppu);
+
+    // The low bits come from the last value written to the PPU
+    ppu.write(OAM_ADDRESS, 0x1F).unwrap();
+    assert_eq!(ppu.read(STATUS).unwrap(), STATUS_VBLANK | 0x1F);
+    assert_eq!(ppu.read(STATUS).unwrap() & STATUS_VBLANK, 0x00);
+
+    // Reading the status resets the write toggle
+    ppu.write(ADDRESS, 0x24).unwrap();
+    ppu.read(STATUS).unwrap();
+    set_address(&mut ppu, 0x2400);
+    ppu.write(DATA, 0x77).unwrap();
+    set_address(&mut ppu, 0x2400);
+    ppu.read(DATA).unwrap();
+    assert_eq!(ppu.read(DATA).unwrap(), 0x77);
+}
+
+#[test]
+fn test_register_mirrors() {
+    let mut ppu = Ppu::new(create_cartridge(0, 0, 0));
+    ppu.write(0x3FFE, 0x21).unwrap();
+    ppu.write(0x3FFE, 0x08).unwrap();
+    ppu.write(0x2A0F, 0x66).unwrap();
+
+    set_address(&mut ppu, 0x2108);
+    ppu.read(0x2A0F).unwrap();
+    assert_eq!(ppu.read(PPU_END).unwrap(), 0x66);
+    assert!(ppu.read(0x4000).is_err());
+}
+
+#[test]
+fn test_oam_access() {
+    let mut ppu = Ppu::new(create_cartridge(0, 0, 0));
+    set_sprite(&mut ppu, 1, 0x10, 0x20, 0xFF, 0x40);
+
+    ppu.write(OAM_ADDRESS, 0x04).unwrap();
+    assert_eq!(ppu.read(OAM_DATA).unwrap(), 0x10);
+    assert_eq!(&ppu.oam()[4..8], &[0x10, 0x20, 0xFF, 0x40]);
+    // Reads do not increment the address, and the attributes have no bits 2-4
+    ppu.write(OAM_ADDRESS, 0x06).unwrap();
+    assert_eq!(ppu.read(OAM_DATA).unwrap(), 0xE3);
+}
+
+// Test nametable mirroring
+#[test]
+fn test_horizontal_mirroring() {
+    let mut ppu = Ppu::new(create_cartridge(0, 0, 0x00));
+    write_vram(&mut ppu, 0x2000, &[0x11]);
+    write_vram(&mut ppu, 0x2800, &[0x22]);
+
+    set_address(&mut ppu, 0x2400);
+    ppu.read(DATA).unwrap();
+    assert_eq!(ppu.read(DATA).unwrap(), 0x11);
+    set_address(&mut ppu, 0x2C00);
+    ppu.read(DATA).unwrap();
+    assert_eq!(ppu.read(DATA).unwrap(), 0x22);
+    // $3000-$3EFF mirrors $2000-$2EFF
+    set_address(&mut ppu, 0x3000);
+    ppu.read(DATA).unwrap();
+    assert_eq!(ppu.read(DATA).unwrap(), 0x11);
+}
+
+#[test]
+fn test_vertical_mirroring() {
+    let mut ppu = Ppu::new(create_cartridge(0, 0, 0x01));
+    write_vram(&mut ppu, 0x2000, &[0x11]);
+    write_vram(&mut ppu, 0x2400, &[0x22]);
+
+    set_address(&mut ppu, 0x2800);
+    ppu.read(DATA).unwrap();
+    assert_eq!(ppu.read(DATA).unwrap(), 0x11);
+    set_address(&mut ppu, 0x2C00);
+    ppu.read(DATA).unwrap();
+    assert_eq!(ppu.read(DATA).unwrap(), 0x22);
+}
+
+// Test frame timing and NMI
+#[test]
+fn test_vblank_and_nmi() {
+    let mut ppu = Ppu::new(create_cartridge(0, 0, 0));
+    run_frame(&mut ppu);
+    assert_eq!(ppu.scanline(), 241);
+    assert!(!ppu.check_nmi());
+
+    // Enabling NMI during vblank asserts the line at once
+    ppu.write(CTRL, CTRL_NMI_ENABLE).unwrap();
+    assert!(ppu.check_nmi());
+    // Reading the status acknowledges it
+    ppu.read(STATUS).unwrap();
+    assert!(!ppu.check_nmi());
+
+    run_frame(&mut ppu);
+    assert!(ppu.check_nmi());
+    // Cleared at the pre-render scanline
+    while ppu.scanline() != 261 || ppu.dot() < 2 {
+        ppu.tick();
+    }
+    assert!(!ppu.check_nmi());
+    assert_eq!(ppu.read(STATUS).unwrap() & STATUS_VBLANK, 0x00);
+}
+
+#[test]
+fn test_frame_length() {
+    let mut ppu = Ppu::new(create_cartridge(0, 0, 0));
+    run_frame(&mut ppu);
+    let position = (ppu.scanline(), ppu.dot());
+
+    // Six frames of 262 x 341 dots, three dots per tick
+    for _ in 0..178_684 {
+        ppu.tick();
+    }
+    assert_eq!((ppu.scanline(), ppu.dot()), position);
+    assert_eq!(ppu.frame_count(), 7);
+
+    // While rendering every odd frame is one dot shorter
+    ppu.write(MASK, MASK_BACKGROUND).unwrap();
+    for _ in 0..178_683 {
+        ppu.tick();
+    }
+    assert_eq!((ppu.scanline(), ppu.dot()), position);
+}
+
+// Test background rendering
+#[test]
+fn test_background_rendering() {
+    let mut ppu = create_scene();
+    // Tile 1 across the second tile row, palette 1 for the top left 16x16 pixels
+    write_vram(&mut ppu, 0x2020, &[1; 32]);
+    write_vram(&mut ppu, 0x23C0, &[0x01]);
+    start_rendering(&mut ppu, MASK_ALL);
+    run_frame(&mut ppu);
+
+    assert_eq!(ppu.pixel(0, 0), Some(BLACK));
+    assert_eq!(ppu.pixel(0, 8), Some(RED));
+    assert_eq!(ppu.pixel(15, 15), Some(RED));
+    assert_eq!(ppu.pixel(16, 8), Some(WHITE));
+    assert_eq!(ppu.pixel(255, 15), Some(WHITE));
+    assert_eq!(ppu.pixel(255, 16), Some(BLACK));
+    assert_eq!(ppu.pixel(256, 0), None);
+}
+
+#[test]
+fn test_background_left_clipping() {
+    let mut ppu = create_scene();
+    write_vram(&mut ppu, 0x2000, &[1, 1]);
+    start_rendering(&mut ppu, MASK_BACKGROUND);
+    run_frame(&mut ppu);
+
+    assert_eq!(ppu.pixel(7, 0), Some(BLACK));
+    assert_eq!(ppu.pixel(8, 0), Some(WHITE));
+}
+
+#[test]
+fn test_scrolling() {
+    let mut ppu = create_scene();
+    write_vram(&mut ppu, 0x2000, &[1]);
+    // Row 1 of the right nametable, which is a mirror with horizontal mirroring
+    write_vram(&mut ppu, 0x2420, &[0, 1]);
+    write_vram(&mut ppu, 0x2800, &[1]);
+    ppu.write(MASK, MASK_ALL).unwrap();
+    ppu.write(CTRL, 0x00).unwrap();
+    // Fine X scroll of 4 and Y scroll of 8
+    ppu.write(SCROLL, 0x04).unwrap();
+    ppu.write(SCROLL, 0x08).unwrap();
+    run_frame(&mut ppu);
+
+    assert_eq!(ppu.pixel(3, 0), Some(BLACK));
+    assert_eq!(ppu.pixel(4, 0), Some(WHITE));
+    assert_eq!(ppu.pixel(11, 0), Some(WHITE));
+    assert_eq!(ppu.pixel(12, 0), Some(BLACK));
+    // Below the last tile row the nametable underneath follows
+    assert_eq!(ppu.pixel(0, 232), Some(WHITE));
+}
+
+#[test]
+fn test_rendering_disabled_shows_backdrop() {
+    let mut ppu = create_scene();
+    write_vram(&mut ppu, 0x2000, &[1; 32]);
+    start_rendering(&mut ppu, 0x00);
+    run_frame(&mut ppu);
+
+    assert!(ppu.framebuffer().chunks(3).all(|rgb| rgb == BLACK));
+}
+
+// Test sprites
+#[test]
+fn test_sprite_rendering_and_zero_hit() {
+    let mut ppu = create_scene();
+    write_vram(&mut ppu, 0x2020, &[1; 32]);
+    // Sprite 0 on lines 10-17, overlapping the background on lines 10-15
+    set_sprite(&mut ppu, 0, 9, 2, 0x00, 16);
+    start_rendering(&mut ppu, MASK_ALL);
+    run_frame(&mut ppu);
+
+    assert_eq!(ppu.pixel(16, 9), Some(WHITE));
+    assert_eq!(ppu.pixel(16, 10), Some(GREEN));
+    assert_eq!(ppu.pixel(23, 17), Some(GREEN));
+    assert_eq!(ppu.pixel(24, 17), Some(BLACK));
+    assert_eq!(ppu.pixel(16, 18), Some(BLACK));
+    assert_eq!(
+        ppu.read(STATUS).unwrap() & STATUS_SPRITE_ZERO_HIT,
+        STATUS_SPRITE_ZERO_HIT
+    );
+}
+
+#[test]
+fn test_no_zero_hit_on_transparent_background() {
+    let mut ppu = create_scene();
+    set_sprite(&mut ppu, 0, 99, 2, 0x00, 16);
+    start_rendering(&mut ppu, MASK_ALL);
+    run_frame(&mut ppu);
+
+    assert_eq!(ppu.pixel(16, 100), Some(GREEN));
+    assert_eq!(ppu.read(STATUS).unwrap() & STATUS_SPRITE_ZERO_HIT, 0x00);
+}
+
+#[test]
+fn test_sprite_behind_background() {
+    let mut ppu = create_scene();
+    write_vram(&mut ppu, 0x2020, &[1; 32]);
+    set_sprite(&mut ppu, 0, 9, 2, 0x20, 16);
+    start_rendering(&mut ppu, MASK_ALL);
+    run_frame(&mut ppu);
+
+    assert_eq!(ppu.pixel(16, 10), Some(WHITE));
+    assert_eq!(ppu.pixel(16, 17), Some(GREEN));
+    // Priority does not prevent the hit
+    assert_eq!(
+        ppu.read(STATUS).unwrap() & STATUS_SPRITE_ZERO_HIT,
+        STATUS_SPRITE_ZERO_HIT
+    );
+}
+
+#[test]
+fn test_sprite_flip() {
+    let mut ppu = create_scene();
+    // Tile 3: a single pixel at the top left
+    write_vram(&mut ppu, 0x0030, &[0x80, 0, 0, 0, 0, 0, 0, 0, 0x80]);
+    set_sprite(&mut ppu, 0, 49, 3, 0xC0, 40);
+    start_rendering(&mut ppu, MASK_ALL);
+    run_frame(&mut ppu);
+
+    assert_eq!(ppu.pixel(40, 50), Some(BLACK));
+    assert_eq!(ppu.pixel(47, 57), Some(GREEN));
+}
+
+#[test]
+fn test_sprite_left_clipping() {
+    let mut ppu = create_scene();
+    set_sprite(&mut ppu, 0, 49, 2, 0x00, 0);
+    start_rendering(&mut ppu, MASK_BACKGROUND | MASK_SPRITES);
+    run_frame(&mut ppu);
+
+    assert_eq!(ppu.pixel(7, 50), Some(BLACK));
+}
+
+#[test]
+fn test_sprite_overflow() {
+    let mut ppu = create_scene();
+    for index in 0..8 {
+        set_sprite(&mut ppu, index, 49, 2, 0x00, index * 10);
+    }
+    start_rendering(&mut ppu, MASK_ALL);
+    run_frame(&mut ppu);
+    assert_eq!(ppu.read(STATUS).unwrap() & STATUS_SPRITE_OVERFLOW, 0x00);
+
+    // Only eight sprites are drawn on a scanline
+    set_sprite(&mut ppu, 8, 49, 2, 0x00, 100);
+    run_frame(&mut ppu);
+    assert_eq!(
+        ppu.read(STATUS).unwrap() & STATUS_SPRITE_OVERFLOW,
+        STATUS_SPRITE_OVERFLOW
+    );
+    assert_eq!(ppu.pixel(70, 50), Some(GREEN));
+    assert_eq!(ppu.pixel(100, 50), Some(BLACK));
+}
+
+// Test OAM DMA
+#[test]
+fn test_oam_dma() {
+    let mut bus = BusController::new();
+    bus.register_device(
+        0x0000,
+        0x07FF,
+        Box::new(Ram::new(RamSize::_2K, 0x0000).unwrap()),
+    )
+    .unwrap();
+    let id = bus
+        .register_device(
+            PPU_START,
+            PPU_END,
+            Box::new(Ppu::new(create_cartridge(0, 0, 0))),
+        )
+        .unwrap();
+    bus.register_device_range(id, OAM_DMA, OAM_DMA).unwrap();
+    for offset in 0..256 {
+        bus.write(0x0200 + offset, offset as u8).unwrap();
+    }
+
+    // The copy starts at the OAM address
+    bus.write(OAM_ADDRESS, 0x04).unwrap();
+    bus.write(OAM_DMA, 0x02).unwrap();
+    assert!(bus.check_rdy());
+
+    bus.set_cpu_halted(true);
+    let mut cycles = 0;
+    while bus.check_rdy() {
+        bus.tick();
+        cycles += 1;
+    }
+
+    assert_eq!(cycles, 513);
+    let ppu = bus.device::<Ppu>(id).unwrap();
+    assert_eq!(ppu.dma_cycles(), 513);
+    assert_eq!(ppu.oam()[4], 0x00);
+    assert_eq!(ppu.oam()[3], 0xFF);
+
+    // One more alignment cycle when starting on an odd CPU cycle
+    bus.set_cpu_halted(false);
+    bus.write(OAM_DMA, 0x02).unwrap();
+    bus.set_cpu_halted(true);
+    while bus.check_rdy() {
+        bus.tick();
+    }
+    assert_eq!(bus.device::<Ppu>(id).unwrap().dma_cycles(), 513 + 514);
+}
+
+// Test the MMC3 scanline clock
+#[test]
+fn test_mmc3_scanline_clock() {
+    let mut cartridge = create_cartridge(4, 1, 0);
+    let mut ppu = Ppu::new(cartridge.clone());
+    cartridge.write(0xC000, 10).unwrap();
+    cartridge.write(0xC001, 0x00).unwrap();
+    cartridge.write(0xE001, 0x00).unwrap();
+
+    // Without rendering A12 does not toggle
+    run_frame(&mut ppu);
+    assert!(!cartridge.check_irq());
+
+    // Background from $0000 and sprites from $1000, clocked once per line from the
+    // pre-render line, which reloads the counter
+    ppu.write(CTRL, CTRL_SPRITE_TABLE).unwrap();
+    ppu.write(MASK, MASK_BACKGROUND | MASK_SPRITES).unwrap();
+    while !cartridge.check_irq() {
+        ppu.tick();
+    }
+    assert_eq!(ppu.scanline(), 9);
+}
+
+// Test PNG output
+#[test]
+fn test_png_encoding() {
+    let mut bytes = Vec::new();
+    write_png(&mut bytes, 2, 1, &[1, 2, 3, 4, 5, 6]).unwrap();
+
+    assert_eq!(&bytes[..8], b"\x89PNG\r\n\x1A\n");
+    assert_eq!(&bytes[8..16], b"\x00\x00\x00\x0DIHDR");
+    assert_eq!(&bytes[16..29], &[0, 0, 0, 2, 0, 0, 0, 1, 8, 2, 0, 0, 0]);
+    // IDAT holds a zlib stream of one stored block with the filtered row
+    assert_eq!(&bytes[33..41], b"\x00\x00\x00\x12IDAT");
+    assert_eq!(&bytes[41..48], &[0x78, 0x01, 0x01, 0x07, 0x00, 0xF8, 0xFF]);
+    assert_eq!(&bytes[48..55], &[0, 1, 2, 3, 4, 5, 6]);
+    assert_eq!(
+        &bytes[bytes.len() - 12..],
+        b"\x00\x00\x00\x00IEND\xAE\x42\x60\x82"
+    );
+
+    assert!(write_png(&mut bytes, 2, 2, &[0; 6]).is_err());
+    assert!(write_png(&mut bytes, 0, 0, &[]).is_err());
+}
+
+#[test]
+fn test_frame_png_golden() {
+    let mut ppu = create_scene();
+    let tiles: Vec<u8> = (0..32 * 30).map(|index| (index % 3) as u8).collect();
+    write_vram(&mut ppu, 0x2000, &tiles);
+    write_vram(&mut ppu, 0x23C0, &[0x1B; 64]);
+    set_sprite(&mut ppu, 0, 100, 2, 0x00, 100);
+    set_sprite(&mut ppu, 1, 120, 1, 0x20, 120);
+    start_rendering(&mut ppu, MASK_ALL);
+    run_frame(&mut ppu);
+
+    let mut bytes = Vec::new();
+    write_png(&mut bytes, FRAME_WIDTH, FRAME_HEIGHT, ppu.framebuffer()).unwrap();
+    assert_eq!(fnv1a(&bytes), 13511141417506031063);
+
+    let path = std::env::temp_dir().join(format!("nes-ppu-{}.png", std::process::id()));
+    save_png(&path, FRAME_WIDTH, FRAME_HEIGHT, ppu.framebuffer()).unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), bytes);
+    std::fs::remove_file(&path).unwrap();
+}
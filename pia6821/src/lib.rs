@@ -0,0 +1,437 @@
+//! Motorola MC6821 / MOS 6520 Peripheral Interface Adapter (PIA).
+//!
+//! Register map, selected by address lines RS0-RS1 and mirrored every 4 bytes:
+//! - `$0`: ORA when CRA bit 2 is set, DDRA otherwise
+//! - `$1`: CRA, control register A
+//! - `$2`: ORB when CRB bit 2 is set, DDRB otherwise
+//! - `$3`: CRB, control register B
+//!
+//! Control register bits:
+//! - bit 0: C1 interrupt enable
+//! - bit 1: C1 active edge (1 = rising)
+//! - bit 2: data register rather than data direction register access
+//! - bits 3-5: C2 control, an input with interrupt enable (bit 3) and active edge
+//!   (bit 4) when bit 5 is clear; handshake, pulse or manual output when bit 5 is set
+//! - bit 6: C2 interrupt flag (read only)
+//! - bit 7: C1 interrupt flag (read only)
+//!
+//! The flags of a side are cleared by reading its data register. The PIA is clocked
+//! by Φ2, one `tick` per CPU cycle. Besides the pin methods, the host can queue
+//! keyboard bytes with `Pia::push_keys` and capture display bytes with
+//! `Pia::take_display_output`, following the Apple I wiring. The captured display is
+//! always ready, so PB7 (display busy) is driven low unless `Pia::set_port_b_input`
+//! says otherwise.
+
+use std::collections::VecDeque;
+
+use bus::errors::BusError;
+use bus::trait_bus_device::BusDevice;
+
+/// Number of registers exposed on the bus
+pub const PIA_REGISTER_COUNT: u16 = 4;
+
+const REGISTER_DATA_A: u16 = 0x0;
+const REGISTER_CONTROL_A: u16 = 0x1;
+const REGISTER_DATA_B: u16 = 0x2;
+
+/// Control register bit: C1 interrupt enable
+pub const CONTROL_C1_IRQ_ENABLE: u8 = 0x01;
+/// Control register bit: C1 active on the rising edge
+pub const CONTROL_C1_RISING: u8 = 0x02;
+/// Control register bit: access the data register rather than the data direction register
+pub const CONTROL_DATA_REGISTER: u8 = 0x04;
+/// Control register bit: C2 interrupt flag
+pub const CONTROL_IRQ2: u8 = 0x40;
+/// Control register bit: C1 interrupt flag
+pub const CONTROL_IRQ1: u8 = 0x80;
+
+/// Port B input driven high by an Apple I display while it is busy
+const DISPLAY_BUSY: u8 = 0x80;
+
+/// Operating mode of a CA2 or CB2 line, from bits 3-5 of the control register
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ControlMode {
+    /// Input, flag set on the given edge (`true` = rising)
+    Input(bool),
+    /// Output low after a data register access until the active edge of C1
+    Handshake,
+    /// Output low for one cycle after a data register access
+    Pulse,
+    /// Output held at the given level
+    Manual(bool),
+}
+
+impl ControlMode {
+    /// Decode bits 3-5 of a control register
+    fn from_control(control: u8) -> ControlMode {
+        match (control >> 3) & 0x07 {
+            0 | 1 => ControlMode::Input(false),
+            2 | 3 => ControlMode::Input(true),
+            4 => ControlMode::Handshake,
+            5 => ControlMode::Pulse,
+            6 => ControlMode::Manual(false),
+            _ => ControlMode::Manual(true),
+        }
+    }
+}
+
+/// One side of the PIA, with its data, direction and control registers and lines
+#[derive(Debug)]
+struct Port {
+    output: u8,
+    ddr: u8,
+    /// Control register bits 0-5
+    control: u8,
+    /// Levels driven onto the port by external hardware
+    input: u8,
+    irq1: bool,
+    irq2: bool,
+    c1: bool,
+    c2_input: bool,
+    /// C2 output level in handshake and pulse modes
+    c2_handshake: bool,
+}
+
+impl Port {
+    fn new() -> Self {
+        Self {
+            output: 0,
+            ddr: 0,
+            control: 0,
+            // Undriven inputs are pulled high
+            input: 0xFF,
+            irq1: false,
+            irq2: false,
+            c1: true,
+            c2_input: true,
+            c2_handshake: true,
+        }
+    }
+
+    fn c2_mode(&self) -> ControlMode {
+        ControlMode::from_control(self.control)
+    }
+
+    /// Levels of the port pins
+    fn pins(&self) -> u8 {
+        (self.output & self.ddr) | (self.input & !self.ddr)
+    }
+
+    /// Value of the control register including the flags
+    fn read_control(&self) -> u8 {
+        let mut value = self.control;
+        if self.irq1 {
+            value |= CONTROL_IRQ1;
+        }
+        if self.irq2 {
+            value |= CONTROL_IRQ2;
+        }
+        value
+    }
+
+    /// Read the data or data direction register selected by the control register
+    fn read_data(&mut self) -> u8 {
+        if self.control & CONTROL_DATA_REGISTER == 0 {
+            return self.ddr;
+        }
+        self.irq1 = false;
+        self.irq2 = false;
+        self.pins()
+    }
+
+    /// Write the data or data direction register selected by the control register
+    fn write_data(&mut self, data: u8) {
+        if self.control & CONTROL_DATA_REGISTER == 0 {
+            self.ddr = data;
+        } else {
+            self.output = data;
+        }
+    }
+
+    /// Start a C2 handshake or pulse after the data register access that triggers it
+    fn start_handshake(&mut self) {
+        if matches!(self.c2_mode(), ControlMode::Handshake | ControlMode::Pulse) {
+            self.c2_handshake = false;
+        }
+    }
+
+    fn set_c1(&mut self, level: bool) {
+        if level == self.c1 {
+            return;
+        }
+        self.c1 = level;
+        if level == (self.control & CONTROL_C1_RISING != 0) {
+            self.irq1 = true;
+            if self.c2_mode() == ControlMode::Handshake {
+                self.c2_handshake = true;
+            }
+        }
+    }
+
+    fn set_c2(&mut self, level: bool) {
+        let rising = level && !self.c2_input;
+        let changed = level != self.c2_input;
+        self.c2_input = level;
+        if changed && self.c2_mode() == ControlMode::Input(rising) {
+            self.irq2 = true;
+        }
+    }
+
+    fn c2(&self) -> bool {
+        match self.c2_mode() {
+            ControlMode::Handshake | ControlMode::Pulse => self.c2_handshake,
+            ControlMode::Manual(level) => level,
+            ControlMode::Input(_) => self.c2_input,
+        }
+    }
+
+    /// Level of the IRQ output of this side
+    fn irq(&self) -> bool {
+        let c2_enabled = self.control & 0x28 == 0x08;
+        (self.irq1 && self.control & CONTROL_C1_IRQ_ENABLE != 0) || (self.irq2 && c2_enabled)
+    }
+}
+
+/// Represents an MC6821 PIA.
+#[derive(Debug)]
+pub struct Pia {
+    /// Start address of the register window
+    start_address: u16,
+    a: Port,
+    b: Port,
+    /// Keyboard bytes waiting to be presented on port A
+    keys: VecDeque<u8>,
+    /// Bytes written to ORB, as seen by a display on port B
+    display: Vec<u8>,
+}
+
+impl Pia {
+    /// Create a new PIA with its registers at the specified start address.
+    ///
+    /// # Arguments
+    /// * `start_address` - Start address of the register window
+    ///
+    /// # Returns
+    /// * A new Pia instance in its reset state
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let pia = Pia::new(0xD010);
+    /// bus.register_device(0xD010, 0xD013, Box::new(pia))?;
+    /// ```
+    pub fn new(start_address: u16) -> Self {
+        let mut b = Port::new();
+        // The captured display never gets busy
+        b.input &= !DISPLAY_BUSY;
+        Self {
+            start_address,
+            a: Port::new(),
+            b,
+            keys: VecDeque::new(),
+            display: Vec::new(),
+        }
+    }
+
+    /// Pull the RES pin low.
+    ///
+    /// Clears all registers and flags. Queued keys and captured display output are kept.
+    pub fn reset(&mut self) {
+        let (a_input, b_input) = (self.a.input, self.b.input);
+        self.a = Port::new();
+        self.b = Port::new();
+        self.a.input = a_input;
+        self.b.input = b_input;
+    }
+
+    /// Queue bytes typed on a keyboard connected to port A.
+    ///
+    /// Each byte is put on the port A inputs with a strobe on CA1 once the previous
+    /// one has been read from ORA. Apple I keyboards set bit 7 of every byte.
+    ///
+    /// # Arguments
+    /// * `keys` - Bytes to type, in order
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// pia.push_keys(&[b'0' | 0x80, b'.' | 0x80, b'F' | 0x80, b'\r' | 0x80]);
+    /// ```
+    pub fn push_keys(&mut self, keys: &[u8]) {
+        self.keys.extend(keys);
+    }
+
+    /// Number of queued keys not presented on port A yet
+    pub fn pending_keys(&self) -> usize {
+        self.keys.len()
+    }
+
+    /// Take the bytes written to ORB since the last call.
+    ///
+    /// Only the bits configured as outputs in DDRB are kept, as a display connected
+    /// to port B would see them. The display is always ready to take the next byte:
+    /// PB7 reads low, so programs polling it with `BIT DSP; BMI` do not wait.
+    ///
+    /// # Returns
+    /// * The captured bytes, oldest first
+    pub fn take_display_output(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.display)
+    }
+
+    /// Drive the port A pins from external hardware.
+    ///
+    /// # Arguments
+    /// * `value` - Levels of PA0-PA7
+    pub fn set_port_a_input(&mut self, value: u8) {
+        self.a.input = value;
+    }
+
+    /// Drive the port B pins from external hardware.
+    ///
+    /// # Arguments
+    /// * `value` - Levels of PB0-PB7
+    pub fn set_port_b_input(&mut self, value: u8) {
+        self.b.input = value;
+    }
+
+    /// Levels of the port A pins: outputs from ORA, inputs from external hardware
+    pub fn port_a_output(&self) -> u8 {
+        self.a.pins()
+    }
+
+    /// Levels of the port B pins: outputs from ORB, inputs from external hardware
+    pub fn port_b_output(&self) -> u8 {
+        self.b.pins()
+    }
+
+    /// Drive the CA1 input.
+    ///
+    /// # Arguments
+    /// * `level` - New level of the pin
+    pub fn set_ca1(&mut self, level: bool) {
+        self.a.set_c1(level);
+    }
+
+    /// Drive the CA2 pin, when it is configured as an input.
+    ///
+    /// # Arguments
+    /// * `level` - New level of the pin
+    pub fn set_ca2(&mut self, level: bool) {
+        self.a.set_c2(level);
+    }
+
+    /// Drive the CB1 input.
+    ///
+    /// # Arguments
+    /// * `level` - New level of the pin
+    pub fn set_cb1(&mut self, level: bool) {
+        self.b.set_c1(level);
+    }
+
+    /// Drive the CB2 pin, when it is configured as an input.
+    ///
+    /// # Arguments
+    /// * `level` - New level of the pin
+    pub fn set_cb2(&mut self, level: bool) {
+        self.b.set_c2(level);
+    }
+
+    /// Level of the CA2 pin
+    pub fn ca2(&self) -> bool {
+        self.a.c2()
+    }
+
+    /// Level of the CB2 pin
+    pub fn cb2(&self) -> bool {
+        self.b.c2()
+    }
+
+    /// Level of the IRQA output
+    pub fn irq_a(&self) -> bool {
+        self.a.irq()
+    }
+
+    /// Level of the IRQB output
+    pub fn irq_b(&self) -> bool {
+        self.b.irq()
+    }
+
+    /// Present the next queued key on port A once the previous one was read
+    fn present_key(&mut self) {
+        if self.a.irq1 {
+            return;
+        }
+        let Some(key) = self.keys.pop_front() else {
+            return;
+        };
+        self.a.input = key;
+        // Strobe CA1 from its inactive to its active level
+        let active = self.a.control & CONTROL_C1_RISING != 0;
+        self.a.set_c1(!active);
+        self.a.set_c1(active);
+    }
+}
+
+impl BusDevice for Pia {
+    fn read(&mut self, address: u16) -> Result<u8, BusError> {
+        let value = match address.wrapping_sub(self.start_address) & 0x03 {
+            REGISTER_DATA_A => {
+                let data_register = self.a.control & CONTROL_DATA_REGISTER != 0;
+                let value = self.a.read_data();
+                if data_register {
+                    self.a.start_handshake();
+                }
+                value
+            }
+            REGISTER_CONTROL_A => self.a.read_control(),
+            REGISTER_DATA_B => self.b.read_data(),
+            // $3: CRB
+            _ => self.b.read_control(),
+        };
+        Ok(value)
+    }
+
+    fn write(&mut self, address: u16, data: u8) -> Result<(), BusError> {
+        match address.wrapping_sub(self.start_address) & 0x03 {
+            REGISTER_DATA_A => self.a.write_data(data),
+            REGISTER_CONTROL_A => self.a.control = data & 0x3F,
+            REGISTER_DATA_B => {
+                self.b.write_data(data);
+                if self.b.control & CONTROL_DATA_REGISTER != 0 {
+                    self.b.start_handshake();
+                    self.display.push(data & self.b.ddr);
+                }
+            }
+            // $3: CRB
+            _ => self.b.control = data & 0x3F,
+        }
+        Ok(())
+    }
+
+    fn tick(&mut self) {
+        // A pulse output returns high after one cycle
+        for port in [&mut self.a, &mut self.b] {
+            if port.c2_mode() == ControlMode::Pulse {
+                port.c2_handshake = true;
+            }
+        }
+        self.present_key();
+    }
+
+    fn check_irq(&self) -> bool {
+        self.a.irq() || self.b.irq()
+    }
+
+    fn check_nmi(&self) -> bool {
+        // IRQA and IRQB may be wired to NMI, but this device drives them onto IRQ
+        false
+    }
+
+    fn check_rdy(&self) -> bool {
+        // The PIA does not insert wait states
+        false
+    }
+
+    fn check_so(&self) -> bool {
+        // The PIA does not drive the SO line
+        false
+    }
+}
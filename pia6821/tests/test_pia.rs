@@ -0,0 +1,212 @@
+//! Unit tests for the 6821 PIA implementation
+//!
+//! This module tests the register selection, the C1/C2 interrupt flags, the
+//! handshake modes and the keyboard and display hooks driving a small program.
+
+use bus::BusController;
+use bus::trait_bus_device::BusDevice;
+use cpu6502::cpu::Cpu;
+use pia6821::{CONTROL_IRQ1, CONTROL_IRQ2, Pia};
+use ram::{Ram, ram_size::RamSize};
+
+/// Apple I register addresses
+const KBD: u16 = 0xD010;
+const KBDCR: u16 = 0xD011;
+const DSP: u16 = 0xD012;
+const DSPCR: u16 = 0xD013;
+
+// Test register selection
+#[test]
+fn test_ddr_and_data_register_selection() {
+    let mut pia = Pia::new(KBD);
+    // After reset the control register selects the data direction register
+    pia.write(KBD, 0x0F).unwrap();
+    assert_eq!(pia.read(KBD).unwrap(), 0x0F);
+    assert_eq!(pia.port_a_output(), 0xF0);
+
+    pia.write(KBDCR, 0x04).unwrap();
+    pia.write(KBD, 0x50).unwrap();
+    pia.set_port_a_input(0xA0);
+    assert_eq!(pia.read(KBD).unwrap(), 0xA0);
+    assert_eq!(pia.port_a_output(), 0xA0);
+
+    pia.write(DSP, 0xFF).unwrap();
+    pia.write(DSPCR, 0x04).unwrap();
+    pia.write(DSP, 0x3C).unwrap();
+    assert_eq!(pia.port_b_output(), 0x3C);
+    // Registers are mirrored every four bytes
+    assert_eq!(pia.read(DSPCR + 4).unwrap(), 0x04);
+}
+
+#[test]
+fn test_reset() {
+    let mut pia = Pia::new(KBD);
+    pia.write(KBDCR, 0x3F).unwrap();
+    pia.write(KBD, 0xFF).unwrap();
+    pia.reset();
+    assert_eq!(pia.read(KBDCR).unwrap(), 0x00);
+    assert_eq!(pia.read(KBD).unwrap(), 0x00);
+}
+
+// Test interrupts
+#[test]
+fn test_c1_falling_edge() {
+    let mut pia = Pia::new(KBD);
+    pia.write(KBDCR, 0x05).unwrap();
+    pia.set_ca1(true);
+    assert!(!pia.check_irq());
+    pia.set_ca1(false);
+    assert!(pia.check_irq());
+    assert!(pia.irq_a());
+    assert_eq!(pia.read(KBDCR).unwrap(), CONTROL_IRQ1 | 0x05);
+    // Writing the control register does not clear the flag, reading the data does
+    pia.write(KBDCR, 0x05).unwrap();
+    assert!(pia.check_irq());
+    pia.read(KBD).unwrap();
+    assert!(!pia.check_irq());
+}
+
+#[test]
+fn test_c1_rising_edge_without_interrupt() {
+    let mut pia = Pia::new(KBD);
+    pia.write(DSPCR, 0x06).unwrap();
+    pia.set_cb1(false);
+    assert_eq!(pia.read(DSPCR).unwrap() & CONTROL_IRQ1, 0);
+    pia.set_cb1(true);
+    assert_eq!(pia.read(DSPCR).unwrap() & CONTROL_IRQ1, CONTROL_IRQ1);
+    assert!(!pia.check_irq());
+    assert!(!pia.irq_b());
+}
+
+#[test]
+fn test_c2_input_interrupt() {
+    let mut pia = Pia::new(KBD);
+    // CB2 input, rising edge, interrupt enabled
+    pia.write(DSPCR, 0x1C).unwrap();
+    pia.set_cb2(false);
+    assert!(!pia.check_irq());
+    pia.set_cb2(true);
+    assert!(pia.check_irq());
+    assert_eq!(pia.read(DSPCR).unwrap() & CONTROL_IRQ2, CONTROL_IRQ2);
+    pia.read(DSP).unwrap();
+    assert!(!pia.check_irq());
+}
+
+// Test handshake modes
+#[test]
+fn test_ca2_read_handshake() {
+    let mut pia = Pia::new(KBD);
+    // CA2 handshake output, CA1 rising edge
+    pia.write(KBDCR, 0x26).unwrap();
+    assert!(pia.ca2());
+    pia.read(KBD).unwrap();
+    assert!(!pia.ca2());
+    pia.tick();
+    assert!(!pia.ca2());
+    pia.set_ca1(false);
+    pia.set_ca1(true);
+    assert!(pia.ca2());
+}
+
+#[test]
+fn test_cb2_write_pulse() {
+    let mut pia = Pia::new(KBD);
+    pia.write(DSPCR, 0x2C).unwrap();
+    pia.read(DSP).unwrap();
+    assert!(pia.cb2());
+    pia.write(DSP, 0x00).unwrap();
+    assert!(!pia.cb2());
+    pia.tick();
+    assert!(pia.cb2());
+}
+
+#[test]
+fn test_c2_manual_output() {
+    let mut pia = Pia::new(KBD);
+    pia.write(KBDCR, 0x30).unwrap();
+    assert!(!pia.ca2());
+    pia.write(KBDCR, 0x38).unwrap();
+    assert!(pia.ca2());
+}
+
+// Test host hooks
+#[test]
+fn test_keyboard_queue() {
+    let mut pia = Pia::new(KBD);
+    pia.write(KBDCR, 0xA7).unwrap();
+    pia.push_keys(b"HI");
+    pia.tick();
+    assert_eq!(pia.pending_keys(), 1);
+    assert_eq!(pia.read(KBDCR).unwrap() & CONTROL_IRQ1, CONTROL_IRQ1);
+    assert!(pia.check_irq());
+    assert_eq!(pia.read(KBD).unwrap(), b'H');
+
+    // The next key is only presented after the previous one was read
+    pia.tick();
+    assert_eq!(pia.read(KBD).unwrap(), b'I');
+    pia.tick();
+    assert_eq!(pia.read(KBDCR).unwrap() & CONTROL_IRQ1, 0);
+}
+
+#[test]
+fn test_display_capture() {
+    let mut pia = Pia::new(KBD);
+    pia.write(DSP, 0x7F).unwrap();
+    pia.write(DSPCR, 0x04).unwrap();
+    pia.write(DSP, b'O' | 0x80).unwrap();
+    pia.write(DSP, b'K').unwrap();
+    assert_eq!(pia.take_display_output(), b"OK");
+    assert!(pia.take_display_output().is_empty());
+
+    // The display is always ready, PB7 reads low
+    assert_eq!(pia.read(DSP).unwrap() & 0x80, 0x00);
+    pia.set_port_b_input(0xFF);
+    assert_eq!(pia.read(DSP).unwrap() & 0x80, 0x80);
+}
+
+#[test]
+fn test_echo_program() {
+    let mut bus = BusController::new();
+    bus.register_device(
+        0x0000,
+        0x7FFF,
+        Box::new(Ram::new(RamSize::_32K, 0x0000).unwrap()),
+    )
+    .unwrap();
+    let pia_id = bus
+        .register_device(KBD, DSPCR, Box::new(Pia::new(KBD)))
+        .unwrap();
+    let mut cpu = Cpu::new(bus);
+
+    // Woz Monitor style set-up, then echo three keys to the display
+    let program = [
+        0xA9, 0x7F, // LDA #$7F
+        0x8D, 0x12, 0xD0, // STA DSP (DDRB)
+        0xA9, 0xA7, // LDA #$A7
+        0x8D, 0x11, 0xD0, // STA KBDCR
+        0x8D, 0x13, 0xD0, // STA DSPCR
+        0xA2, 0x03, // LDX #3
+        0xAD, 0x11, 0xD0, // NEXTCHAR: LDA KBDCR
+        0x10, 0xFB, // BPL NEXTCHAR
+        0xAD, 0x10, 0xD0, // LDA KBD
+        0x2C, 0x12, 0xD0, // ECHO: BIT DSP
+        0x30, 0xFB, // BMI ECHO
+        0x8D, 0x12, 0xD0, // STA DSP
+        0xCA, // DEX
+        0xD0, 0xED, // BNE NEXTCHAR
+        0x60, // RTS
+    ];
+    for (offset, byte) in program.iter().enumerate() {
+        cpu.bus_mut().write(0x0200 + offset as u16, *byte).unwrap();
+    }
+    {
+        let pia = cpu.bus_mut().device_mut::<Pia>(pia_id).unwrap();
+        pia.push_keys(&[b'A' | 0x80, b'1' | 0x80, b'\r' | 0x80]);
+    }
+
+    cpu.call_subroutine(0x0200, 10_000).unwrap();
+
+    let pia = cpu.bus_mut().device_mut::<Pia>(pia_id).unwrap();
+    assert_eq!(pia.take_display_output(), b"A1\r");
+    assert_eq!(pia.pending_keys(), 0);
+}
@@ -0,0 +1,217 @@
+//! Battery-backed RAM persisted to a host file.
+//!
+//! Models NVRAM, cartridge save RAM and FRAM: the contents are loaded from the file
+//! when the RAM is created, and saved back by `BatteryRam::sync`, periodically from
+//! `tick` and when the RAM is dropped. Saving writes a temporary file next to the
+//! save file and renames it over the save file, so a crash leaves either the old
+//! or the new contents, never a partial file.
+
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use bus::errors::BusError;
+use bus::trait_bus_device::BusDevice;
+
+use crate::Ram;
+
+/// RAM whose contents survive power-off in a host file
+#[derive(Debug)]
+pub struct BatteryRam {
+    ram: Ram,
+    /// Save file
+    path: PathBuf,
+    /// Ticks between periodic saves, `None` to only save explicitly and on drop
+    flush_interval: Option<u64>,
+    /// Ticks since the last periodic save
+    ticks_since_flush: u64,
+    /// The contents have changed since they were last saved
+    dirty: bool,
+}
+
+impl BatteryRam {
+    /// Create a battery-backed RAM, loading its contents from the save file if it exists.
+    ///
+    /// # Arguments
+    /// * `length` - Size of the RAM in bytes
+    /// * `start_address` - Start address of the RAM in memory
+    /// * `path` - Path of the save file
+    ///
+    /// # Returns
+    /// * `Ok(BatteryRam)` containing the saved contents, or zeroes without a save file
+    /// * `Err(String)` if the RAM is invalid or the save file cannot be used
+    ///
+    /// # Errors
+    /// * If the length is zero or the RAM does not fit below $FFFF
+    /// * If the save file exists but cannot be read
+    /// * If the size of the save file differs from the size of the RAM
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let sram = BatteryRam::new(0x2000, 0x6000, "game.sav")?;
+    /// ```
+    pub fn new(length: usize, start_address: u16, path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref().to_path_buf();
+        let mut ram = Ram::with_length(length, start_address)?;
+        match fs::read(&path) {
+            Ok(contents) if contents.len() == length => ram.import(&contents, 0)?,
+            Ok(contents) => {
+                return Err(format!(
+                    "Save file {} has {} bytes, expected {}",
+                    path.display(),
+                    contents.len(),
+                    length
+                ));
+            }
+            Err(error) if error.kind() == ErrorKind::NotFound => {}
+            Err(error) => {
+                return Err(format!(
+                    "Failed to read save file {}: {}",
+                    path.display(),
+                    error
+                ));
+            }
+        }
+        Ok(Self {
+            ram,
+            path,
+            flush_interval: None,
+            ticks_since_flush: 0,
+            dirty: false,
+        })
+    }
+
+    /// Also save the contents periodically, if they have changed.
+    ///
+    /// Errors of periodic saves are ignored, the next save retries.
+    ///
+    /// # Arguments
+    /// * `ticks` - Ticks between saves, e.g. 1_000_000 for once a second at 1 MHz
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let sram = BatteryRam::new(0x2000, 0x6000, "game.sav")?.with_flush_interval(1_000_000);
+    /// ```
+    pub fn with_flush_interval(mut self, ticks: u64) -> Self {
+        self.flush_interval = Some(ticks.max(1));
+        self
+    }
+
+    /// Path of the save file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The underlying RAM
+    pub fn ram(&self) -> &Ram {
+        &self.ram
+    }
+
+    /// Mutable access to the underlying RAM, changes through it are saved
+    pub fn ram_mut(&mut self) -> &mut Ram {
+        self.dirty = true;
+        &mut self.ram
+    }
+
+    /// Check whether the contents have changed since they were last saved
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Save the contents to the save file, if they have changed.
+    ///
+    /// # Returns
+    /// * `Ok(())` if the contents were saved or had not changed
+    /// * `Err(String)` if the save file cannot be written
+    ///
+    /// # Errors
+    /// * If the temporary file cannot be written or renamed over the save file
+    pub fn sync(&mut self) -> Result<(), String> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let mut temporary = self.path.clone().into_os_string();
+        temporary.push(".tmp");
+        let temporary = PathBuf::from(temporary);
+        let contents = self.ram.export(0, self.ram.size());
+        let result = fs::File::create(&temporary)
+            .and_then(|mut file| {
+                file.write_all(&contents)?;
+                file.sync_all()
+            })
+            .and_then(|_| fs::rename(&temporary, &self.path));
+        if let Err(error) = result {
+            let _ = fs::remove_file(&temporary);
+            return Err(format!(
+                "Failed to write save file {}: {}",
+                self.path.display(),
+                error
+            ));
+        }
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+impl Drop for BatteryRam {
+    fn drop(&mut self) {
+        // Errors cannot be reported from drop, call `sync` to handle them
+        let _ = self.sync();
+    }
+}
+
+impl BusDevice for BatteryRam {
+    fn read(&mut self, address: u16) -> Result<u8, BusError> {
+        self.ram.read(address)
+    }
+
+    fn write(&mut self, address: u16, data: u8) -> Result<(), BusError> {
+        self.ram.write(address, data)?;
+        self.dirty = true;
+        Ok(())
+    }
+
+    fn load(&mut self, address: u16, data: u8) -> Result<(), BusError> {
+        self.ram.load(address, data)?;
+        self.dirty = true;
+        Ok(())
+    }
+
+    fn sync(&mut self, address: u16) {
+        BusDevice::sync(&mut self.ram, address);
+    }
+
+    fn tick(&mut self) {
+        self.advance(1);
+    }
+
+    fn advance(&mut self, ticks: u64) {
+        if let Some(interval) = self.flush_interval {
+            self.ticks_since_flush += ticks;
+            if self.ticks_since_flush >= interval {
+                self.ticks_since_flush = 0;
+                let _ = BatteryRam::sync(self);
+            }
+        }
+    }
+
+    fn check_irq(&self) -> bool {
+        // RAM does not generate IRQs
+        false
+    }
+
+    fn check_nmi(&self) -> bool {
+        // RAM does not generate NMIs
+        false
+    }
+
+    fn check_rdy(&self) -> bool {
+        // RAM does not insert wait states
+        false
+    }
+
+    fn check_so(&self) -> bool {
+        // RAM does not drive the SO line
+        false
+    }
+}
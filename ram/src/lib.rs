@@ -4,22 +4,60 @@
 
 /// RAM size definitions and utilities.
 pub mod ram_size;
+/// Power-on memory patterns.
+pub mod power_on;
+/// Battery-backed RAM persisted to a host file.
+pub mod battery;
 
 use bus::errors::BusError;
 use bus::trait_bus_device::BusDevice;
 
+use crate::power_on::PowerOnPattern;
 use crate::ram_size::RamSize;
 
+/// Size of a page of sparse RAM
+const PAGE_SIZE: usize = 0x100;
+
+/// Storage behind a RAM module
+#[derive(Debug)]
+enum Backing {
+    /// One contiguous buffer
+    Dense(Vec<u8>),
+    /// 256-byte pages, allocated on the first write
+    Sparse(Vec<Option<Box<[u8; PAGE_SIZE]>>>),
+}
+
+/// Shadow flag: the byte has been written or imported
+const SHADOW_WRITTEN: u8 = 0x01;
+/// Shadow flag: a read of the byte before it was written has been reported
+const SHADOW_REPORTED: u8 = 0x02;
+
+/// A read of a RAM byte that was never written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UninitialisedRead {
+    /// Address that was read
+    pub address: u16,
+    /// Address of the instruction that performed the read, if the CPU signalled one
+    pub program_counter: Option<u16>,
+}
+
 /// Represents a Random Access Memory (RAM) module.
 #[derive(Debug)]
-#[allow(dead_code)]
 pub struct Ram {
     /// Actual RAM memory
-    memory: Vec<u8>,
-    /// Size of RAM
-    size: RamSize,
+    memory: Backing,
+    /// Size of RAM in bytes
+    length: usize,
     /// Start address of RAM
     start_address: u16,
+    /// Power-on contents, also used for sparse pages that have not been allocated yet
+    pattern: PowerOnPattern,
+    /// Per-byte shadow flags, present while uninitialised reads are tracked
+    shadow: Option<Vec<u8>>,
+    /// Address of the instruction currently executing, from the CPU's SYNC signal
+    program_counter: Option<u16>,
+    /// Reads of bytes that were never written, at most one per byte
+    uninitialised_reads: Vec<UninitialisedRead>,
 }
 
 impl Ram {
@@ -30,18 +68,140 @@ impl Ram {
     /// * `start_address` - Start address of the RAM in memory (default is 0x8000)
     ///
     /// # Returns
-    /// * A new Ram instance
+    /// * `Ok(Ram)` containing the new RAM instance
+    /// * `Err(String)` if the RAM does not fit below $FFFF
+    ///
+    /// # Errors
+    /// * If the start address plus the size exceeds the 64K address space
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let ram = Ram::new(RamSize::_32K, 0x8000)?;
+    /// ```
+    pub fn new(size: RamSize, start_address: u16) -> Result<Self, String> {
+        Self::with_length(size as usize, start_address)
+    }
+
+    /// Create a new RAM instance of any length, e.g. 6K at $0000.
+    ///
+    /// # Arguments
+    /// * `length` - Size of the RAM in bytes
+    /// * `start_address` - Start address of the RAM in memory
+    ///
+    /// # Returns
+    /// * `Ok(Ram)` containing the new RAM instance
+    /// * `Err(String)` if the length is zero or the RAM does not fit below $FFFF
+    ///
+    /// # Errors
+    /// * If the length is zero or the start address plus the length exceeds the 64K address space
     ///
     /// # Examples
     /// ``` ignore
-    /// let ram = Ram::new(RamSize::_32K, 0x8000);
+    /// let ram = Ram::with_length(0x1800, 0x0000)?;
     /// ```
-    pub fn new(size: RamSize, start_address: u16) -> Self {
-        Self {
-            memory: vec![0; size as usize],
-            size,
+    pub fn with_length(length: usize, start_address: u16) -> Result<Self, String> {
+        check_region(length, start_address)?;
+        Ok(Self {
+            memory: Backing::Dense(vec![0; length]),
+            length,
             start_address,
+            pattern: PowerOnPattern::Zero,
+            shadow: None,
+            program_counter: None,
+            uninitialised_reads: Vec::new(),
+        })
+    }
+
+    /// Create a new RAM instance whose memory is only allocated where it is written.
+    ///
+    /// Useful for test setups that map a full 64K of RAM but only touch a few pages.
+    /// Memory is allocated in 256-byte pages; unwritten pages read as the power-on
+    /// pattern, see `with_pattern`.
+    ///
+    /// # Arguments
+    /// * `length` - Size of the RAM in bytes
+    /// * `start_address` - Start address of the RAM in memory
+    ///
+    /// # Returns
+    /// * `Ok(Ram)` containing the new RAM instance
+    /// * `Err(String)` if the length is zero or the RAM does not fit below $FFFF
+    ///
+    /// # Errors
+    /// * If the length is zero or the start address plus the length exceeds the 64K address space
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let ram = Ram::sparse(0x10000, 0x0000)?;
+    /// ```
+    pub fn sparse(length: usize, start_address: u16) -> Result<Self, String> {
+        check_region(length, start_address)?;
+        Ok(Self {
+            memory: Backing::Sparse(vec![None; length.div_ceil(PAGE_SIZE)]),
+            length,
+            start_address,
+            pattern: PowerOnPattern::Zero,
+            shadow: None,
+            program_counter: None,
+            uninitialised_reads: Vec::new(),
+        })
+    }
+
+    /// Fill the RAM with a power-on pattern instead of zeroes.
+    ///
+    /// # Arguments
+    /// * `pattern` - The pattern to fill the RAM with
+    ///
+    /// # Returns
+    /// * The RAM, filled with the pattern
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let ram = Ram::new(RamSize::_2K, 0x0000)?.with_pattern(PowerOnPattern::Random(42));
+    /// ```
+    pub fn with_pattern(mut self, pattern: PowerOnPattern) -> Self {
+        match &mut self.memory {
+            Backing::Dense(memory) => pattern.fill(memory, 0),
+            Backing::Sparse(pages) => pages.iter_mut().for_each(|page| *page = None),
         }
+        self.pattern = pattern;
+        self
+    }
+
+    /// Record reads of bytes that have not been written or imported yet.
+    ///
+    /// Each byte is reported once, together with the address of the instruction that
+    /// read it. The instruction address comes from the CPU's SYNC signal, see `BusDevice::sync`.
+    ///
+    /// # Returns
+    /// * The RAM, with tracking enabled and every byte marked as uninitialised
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let ram = Ram::new(RamSize::_2K, 0x0000)?.with_uninitialised_read_tracking();
+    /// ```
+    pub fn with_uninitialised_read_tracking(mut self) -> Self {
+        self.shadow = Some(vec![0; self.length]);
+        self
+    }
+
+    /// Reads of bytes that were never written, in the order they happened
+    pub fn uninitialised_reads(&self) -> &[UninitialisedRead] {
+        &self.uninitialised_reads
+    }
+
+    /// Forget the reported uninitialised reads, so each byte can be reported again
+    pub fn clear_uninitialised_reads(&mut self) {
+        self.uninitialised_reads.clear();
+        if let Some(shadow) = &mut self.shadow {
+            shadow
+                .iter_mut()
+                .for_each(|flags| *flags &= !SHADOW_REPORTED);
+        }
+    }
+
+    /// Size of the RAM in bytes
+    pub fn size(&self) -> usize {
+        self.length
     }
 
     /// Import data into the RAM at the specified offset.
@@ -59,16 +219,27 @@ impl Ram {
     ///
     /// # Examples
     /// ``` ignore
-    /// let mut ram = Ram::new(RamSize::_32K, 0x8000);
+    /// let mut ram = Ram::new(RamSize::_32K, 0x8000)?;
     /// let data = vec![0x00, 0x01, 0x02, 0x03];
-    /// ram.load(&data, 0).unwrap();
+    /// ram.import(&data, 0)?;
     /// ```
-    pub fn import(&mut self, data: &[u8], start_address: u16) -> Result<(), String> {
-        let offset = start_address as usize;
-        if offset + data.len() > self.memory.len() {
+    pub fn import(&mut self, data: &[u8], offset: usize) -> Result<(), String> {
+        if offset + data.len() > self.length {
             return Err("Data exceeds RAM size".to_string());
         }
-        self.memory[offset..offset + data.len()].copy_from_slice(data);
+        match &mut self.memory {
+            Backing::Dense(memory) => memory[offset..offset + data.len()].copy_from_slice(data),
+            Backing::Sparse(_) => {
+                for (index, &byte) in data.iter().enumerate() {
+                    self.store(offset + index, byte);
+                }
+            }
+        }
+        if let Some(shadow) = &mut self.shadow {
+            shadow[offset..offset + data.len()]
+                .iter_mut()
+                .for_each(|flags| *flags |= SHADOW_WRITTEN);
+        }
         Ok(())
     }
 
@@ -83,36 +254,95 @@ impl Ram {
     ///
     /// # Examples
     /// ``` ignore
-    /// let ram = Ram::new(RamSize::_32K, 0x8000);
+    /// let ram = Ram::new(RamSize::_32K, 0x8000)?;
     /// let data = ram.export(0, 16);
     /// ```
-    pub fn export(&self, start_address: u16, length: usize) -> Vec<u8> {
-        let offset = start_address as usize;
-        let end = (offset + length).min(self.memory.len());
-        self.memory[offset..end].to_vec()
+    pub fn export(&self, offset: usize, length: usize) -> Vec<u8> {
+        let end = (offset + length).min(self.length);
+        match &self.memory {
+            Backing::Dense(memory) => memory[offset..end].to_vec(),
+            Backing::Sparse(_) => (offset..end).map(|index| self.fetch(index)).collect(),
+        }
+    }
+
+    /// Read the byte at an offset within the RAM
+    fn fetch(&self, offset: usize) -> u8 {
+        match &self.memory {
+            Backing::Dense(memory) => memory[offset],
+            Backing::Sparse(pages) => pages[offset / PAGE_SIZE].as_ref().map_or_else(
+                || self.pattern.byte(offset),
+                |page| page[offset % PAGE_SIZE],
+            ),
+        }
+    }
+
+    /// Write the byte at an offset within the RAM, allocating sparse pages as needed
+    fn store(&mut self, offset: usize, data: u8) {
+        match &mut self.memory {
+            Backing::Dense(memory) => memory[offset] = data,
+            Backing::Sparse(pages) => {
+                let page = pages[offset / PAGE_SIZE].get_or_insert_with(|| {
+                    let mut page = Box::new([0; PAGE_SIZE]);
+                    self.pattern
+                        .fill(page.as_mut_slice(), offset - offset % PAGE_SIZE);
+                    page
+                });
+                page[offset % PAGE_SIZE] = data;
+            }
+        }
+    }
+}
+
+/// Check that a memory region is not empty and fits below $FFFF
+fn check_region(length: usize, start_address: u16) -> Result<(), String> {
+    if length == 0 {
+        return Err("RAM size must not be zero".to_string());
+    }
+    if start_address as usize + length > 0x10000 {
+        return Err(format!(
+            "RAM of {} bytes at 0x{:04X} extends past 0xFFFF",
+            length, start_address
+        ));
     }
+    Ok(())
 }
 
 impl BusDevice for Ram {
-    fn read(&self, address: u16) -> Result<u8, BusError> {
+    fn read(&mut self, address: u16) -> Result<u8, BusError> {
         let offset = address.wrapping_sub(self.start_address) as usize;
-        if offset < self.memory.len() {
-            Ok(self.memory[offset])
+        if offset < self.length {
+            if let Some(shadow) = &mut self.shadow
+                && shadow[offset] == 0
+            {
+                shadow[offset] = SHADOW_REPORTED;
+                self.uninitialised_reads.push(UninitialisedRead {
+                    address,
+                    program_counter: self.program_counter,
+                });
+            }
+            Ok(self.fetch(offset))
         } else {
             Err(BusError::AddressOutOfRange(address))
         }
     }
 
-    fn write(&mut self, _address: u16, _data: u8) -> Result<(), BusError> {
-        let offset = _address.wrapping_sub(self.start_address) as usize;
-        if offset < self.memory.len() {
-            self.memory[offset] = _data;
+    fn write(&mut self, address: u16, data: u8) -> Result<(), BusError> {
+        let offset = address.wrapping_sub(self.start_address) as usize;
+        if offset < self.length {
+            self.store(offset, data);
+            if let Some(shadow) = &mut self.shadow {
+                shadow[offset] |= SHADOW_WRITTEN;
+            }
             Ok(())
         } else {
-            Err(BusError::AddressOutOfRange(_address))
+            Err(BusError::AddressOutOfRange(address))
         }
     }
 
+    fn sync(&mut self, address: u16) {
+        self.program_counter = Some(address);
+    }
+
     fn tick(&mut self) {
         // RAM does not need to do anything on tick
     }
@@ -126,4 +356,14 @@ impl BusDevice for Ram {
         // RAM does not generate NMIs
         false
     }
+
+    fn check_rdy(&self) -> bool {
+        // RAM does not insert wait states
+        false
+    }
+
+    fn check_so(&self) -> bool {
+        // RAM does not drive the SO line
+        false
+    }
 }
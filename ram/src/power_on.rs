@@ -0,0 +1,87 @@
+//! Initial contents of RAM at power-on.
+//!
+//! Real static and dynamic RAM powers up with garbage, so programs that forget to
+//! initialise memory may only fail on hardware. The patterns below reproduce the
+//! power-on states assumed by common emulators.
+
+/// Multiplier of the 64-bit linear congruential generator used for random patterns
+const LCG_MULTIPLIER: u64 = 6364136223846793005;
+/// Increment of the 64-bit linear congruential generator used for random patterns
+const LCG_INCREMENT: u64 = 1442695040888963407;
+
+/// Pattern RAM is filled with at power-on
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum PowerOnPattern {
+    /// All bytes $00 (default)
+    #[default]
+    Zero,
+    /// All bytes $FF
+    Ones,
+    /// Alternating $00 and $FF bytes
+    Alternating,
+    /// Pseudo-random bytes, reproducible for the same seed
+    Random(u64),
+    /// Four $00 bytes followed by four $FF bytes, as used by FCEUX for the NES
+    Fceux,
+    /// 64 $00 bytes followed by 64 $FF bytes, as used by VICE for the C64
+    Vice,
+    /// $FF, $FF, $00, $00 repeated, as used by AppleWin for the Apple II
+    AppleWin,
+    /// A custom sequence of bytes, repeated over the whole memory
+    Repeating(Vec<u8>),
+}
+
+impl PowerOnPattern {
+    /// Fill a memory with the pattern
+    ///
+    /// # Arguments
+    /// * `memory` - The memory to fill
+    /// * `offset` - Offset of the first byte of `memory` within the RAM, so that
+    ///   partially filled memories continue the same pattern
+    pub fn fill(&self, memory: &mut [u8], offset: usize) {
+        for (index, byte) in memory.iter_mut().enumerate() {
+            *byte = self.byte(offset + index);
+        }
+    }
+
+    /// Byte at an offset within the RAM
+    ///
+    /// # Arguments
+    /// * `offset` - Offset within the RAM
+    ///
+    /// # Returns
+    /// * The power-on value of the byte
+    pub fn byte(&self, offset: usize) -> u8 {
+        match self {
+            PowerOnPattern::Zero => 0x00,
+            PowerOnPattern::Ones => 0xFF,
+            PowerOnPattern::Alternating => alternate(offset, 1),
+            PowerOnPattern::Random(seed) => random_byte(*seed, offset),
+            PowerOnPattern::Fceux => alternate(offset, 4),
+            PowerOnPattern::Vice => alternate(offset, 64),
+            PowerOnPattern::AppleWin => !alternate(offset, 2),
+            PowerOnPattern::Repeating(bytes) if bytes.is_empty() => 0x00,
+            PowerOnPattern::Repeating(bytes) => bytes[offset % bytes.len()],
+        }
+    }
+}
+
+/// $00 for `run` bytes, then $FF for `run` bytes, repeated
+fn alternate(offset: usize, run: usize) -> u8 {
+    if (offset / run).is_multiple_of(2) {
+        0x00
+    } else {
+        0xFF
+    }
+}
+
+/// Pseudo-random byte for an offset, independent of the order bytes are generated in
+fn random_byte(seed: u64, offset: usize) -> u8 {
+    let mut state = seed ^ (offset as u64).wrapping_mul(LCG_MULTIPLIER);
+    for _ in 0..2 {
+        state = state
+            .wrapping_mul(LCG_MULTIPLIER)
+            .wrapping_add(LCG_INCREMENT);
+    }
+    (state >> 56) as u8
+}
@@ -0,0 +1,122 @@
+//! Unit tests for the battery-backed RAM
+//!
+//! This module tests loading and saving the contents of battery-backed RAM,
+//! the periodic and drop saves, and size mismatch reporting.
+
+use std::fs;
+use std::path::PathBuf;
+
+use bus::trait_bus_device::BusDevice;
+use ram::battery::BatteryRam;
+
+/// Unique path for a save file in the temporary directory, removed if it exists
+fn save_path(name: &str) -> PathBuf {
+    let path =
+        std::env::temp_dir().join(format!("battery_test_{}_{}.sav", std::process::id(), name));
+    let _ = fs::remove_file(&path);
+    path
+}
+
+// Test loading
+#[test]
+fn test_new_without_save_file() {
+    let path = save_path("new");
+    let mut sram = BatteryRam::new(0x2000, 0x6000, &path).unwrap();
+    assert_eq!(sram.read(0x6000).unwrap(), 0x00);
+    assert_eq!(sram.path(), path.as_path());
+    assert!(!sram.is_dirty());
+    drop(sram);
+    // Nothing was written, so no save file is created
+    assert!(!path.exists());
+}
+
+#[test]
+fn test_load_existing_save_file() {
+    let path = save_path("load");
+    fs::write(&path, [0x42; 0x100]).unwrap();
+    let mut sram = BatteryRam::new(0x100, 0x0200, &path).unwrap();
+    assert_eq!(sram.read(0x0200).unwrap(), 0x42);
+    assert_eq!(sram.read(0x02FF).unwrap(), 0x42);
+    drop(sram);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_size_mismatch() {
+    let path = save_path("mismatch");
+    fs::write(&path, [0x00; 0x80]).unwrap();
+    let error = BatteryRam::new(0x100, 0x0200, &path).err().unwrap();
+    assert!(error.contains("128 bytes, expected 256"));
+    // The mismatched file is left untouched
+    assert_eq!(fs::read(&path).unwrap().len(), 0x80);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_invalid_region() {
+    let path = save_path("region");
+    assert!(BatteryRam::new(0x2000, 0xF000, &path).is_err());
+}
+
+// Test saving
+#[test]
+fn test_explicit_sync() {
+    let path = save_path("sync");
+    let mut sram = BatteryRam::new(0x10, 0x6000, &path).unwrap();
+    sram.write(0x6001, 0xAB).unwrap();
+    assert!(sram.is_dirty());
+    sram.sync().unwrap();
+    assert!(!sram.is_dirty());
+
+    let saved = fs::read(&path).unwrap();
+    assert_eq!(saved.len(), 0x10);
+    assert_eq!(saved[1], 0xAB);
+    let mut temporary = path.clone().into_os_string();
+    temporary.push(".tmp");
+    assert!(!PathBuf::from(temporary).exists());
+    drop(sram);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_save_on_drop_and_reload() {
+    let path = save_path("drop");
+    {
+        let mut sram = BatteryRam::new(0x10, 0x6000, &path).unwrap();
+        sram.write(0x600F, 0x5A).unwrap();
+        sram.ram_mut().import(&[0x01, 0x02], 0).unwrap();
+    }
+    let mut sram = BatteryRam::new(0x10, 0x6000, &path).unwrap();
+    assert_eq!(sram.read(0x6000).unwrap(), 0x01);
+    assert_eq!(sram.read(0x6001).unwrap(), 0x02);
+    assert_eq!(sram.read(0x600F).unwrap(), 0x5A);
+    drop(sram);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_periodic_flush() {
+    let path = save_path("periodic");
+    let mut sram = BatteryRam::new(0x10, 0x6000, &path)
+        .unwrap()
+        .with_flush_interval(100);
+    sram.write(0x6000, 0x77).unwrap();
+    sram.advance(99);
+    assert!(!path.exists());
+    sram.tick();
+    assert!(!sram.is_dirty());
+    assert_eq!(fs::read(&path).unwrap()[0], 0x77);
+    drop(sram);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_sync_failure_is_reported() {
+    let path = std::env::temp_dir()
+        .join(format!("battery_test_{}_missing", std::process::id()))
+        .join("game.sav");
+    let mut sram = BatteryRam::new(0x10, 0x6000, &path).unwrap();
+    sram.write(0x6000, 0x01).unwrap();
+    assert!(sram.sync().is_err());
+    assert!(sram.is_dirty());
+}
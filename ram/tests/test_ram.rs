@@ -6,12 +6,13 @@
 
 use bus::errors::BusError;
 use bus::trait_bus_device::BusDevice;
-use ram::{Ram, ram_size::RamSize};
+use ram::power_on::PowerOnPattern;
+use ram::{Ram, UninitialisedRead, ram_size::RamSize};
 
 // Test RAM creation and initialization
 #[test]
 fn test_ram_creation_default() {
-    let ram = Ram::new(RamSize::_32K, 0x8000);
+    let ram = Ram::new(RamSize::_32K, 0x8000).unwrap();
 
     // Test that RAM was created with correct size
     // We can't directly access memory field, so we test via export
@@ -32,7 +33,7 @@ fn test_ram_creation_different_sizes() {
     ];
 
     for (size, expected_bytes) in sizes {
-        let ram = Ram::new(size, 0x0000);
+        let ram = Ram::new(size, 0x0000).unwrap();
         let exported = ram.export(0, expected_bytes);
         assert_eq!(exported.len(), expected_bytes);
         assert_eq!(exported, vec![0; expected_bytes]);
@@ -41,19 +42,55 @@ fn test_ram_creation_different_sizes() {
 
 #[test]
 fn test_ram_creation_different_start_addresses() {
-    let start_addresses = [0x0000, 0x2000, 0x4000, 0x8000, 0xC000, 0xFF00];
+    let start_addresses = [0x0000, 0x2000, 0x4000, 0x8000, 0xC000, 0xF000];
 
     for start_addr in start_addresses {
-        let ram = Ram::new(RamSize::_4K, start_addr);
+        let ram = Ram::new(RamSize::_4K, start_addr).unwrap();
         let exported = ram.export(0, 10);
         assert_eq!(exported, vec![0; 10]);
     }
 }
 
+#[test]
+fn test_ram_creation_past_end_of_address_space() {
+    assert!(Ram::new(RamSize::_4K, 0xFF00).is_err());
+    assert!(Ram::new(RamSize::_64K, 0x0001).is_err());
+    assert!(Ram::new(RamSize::_64K, 0x0000).is_ok());
+}
+
+#[test]
+fn test_ram_with_arbitrary_length() {
+    let mut ram = Ram::with_length(0x1800, 0xE800).unwrap();
+
+    assert_eq!(ram.size(), 0x1800);
+    assert_eq!(ram.read(0xFFFF).unwrap(), 0x00);
+    assert!(matches!(
+        ram.read(0xE7FF),
+        Err(BusError::AddressOutOfRange(0xE7FF))
+    ));
+    assert!(Ram::with_length(0, 0x0000).is_err());
+    assert!(Ram::with_length(0x101, 0xFF00).is_err());
+}
+
+#[test]
+fn test_sparse_ram() {
+    let mut ram = Ram::sparse(0x10000, 0x0000).unwrap();
+
+    assert_eq!(ram.size(), 0x10000);
+    assert_eq!(ram.read(0x1234).unwrap(), 0x00);
+    ram.write(0x1234, 0x56).unwrap();
+    ram.import(&[0x01, 0x02], 0xFFFE).unwrap();
+
+    assert_eq!(ram.read(0x1234).unwrap(), 0x56);
+    assert_eq!(ram.export(0x1233, 3), vec![0x00, 0x56, 0x00]);
+    assert_eq!(ram.read(0xFFFF).unwrap(), 0x02);
+    assert!(ram.import(&[0x00; 3], 0xFFFE).is_err());
+}
+
 // Test data import functionality
 #[test]
 fn test_import_basic() {
-    let mut ram = Ram::new(RamSize::_4K, 0x0000);
+    let mut ram = Ram::new(RamSize::_4K, 0x0000).unwrap();
     let data = vec![0xAA, 0xBB, 0xCC, 0xDD];
 
     let result = ram.import(&data, 0);
@@ -66,7 +103,7 @@ fn test_import_basic() {
 
 #[test]
 fn test_import_with_offset() {
-    let mut ram = Ram::new(RamSize::_4K, 0x0000);
+    let mut ram = Ram::new(RamSize::_4K, 0x0000).unwrap();
     let data = vec![0x11, 0x22, 0x33];
     let offset = 100;
 
@@ -86,7 +123,7 @@ fn test_import_with_offset() {
 
 #[test]
 fn test_import_empty_data() {
-    let mut ram = Ram::new(RamSize::_4K, 0x0000);
+    let mut ram = Ram::new(RamSize::_4K, 0x0000).unwrap();
     let data: Vec<u8> = vec![];
 
     let result = ram.import(&data, 0);
@@ -99,7 +136,7 @@ fn test_import_empty_data() {
 
 #[test]
 fn test_import_full_ram() {
-    let mut ram = Ram::new(RamSize::_2K, 0x0000);
+    let mut ram = Ram::new(RamSize::_2K, 0x0000).unwrap();
     let data = vec![0xFF; 0x0800]; // Fill entire 2K RAM
 
     let result = ram.import(&data, 0);
@@ -113,7 +150,7 @@ fn test_import_full_ram() {
 
 #[test]
 fn test_import_exceeds_ram_size() {
-    let mut ram = Ram::new(RamSize::_2K, 0x0000);
+    let mut ram = Ram::new(RamSize::_2K, 0x0000).unwrap();
     let data = vec![0xFF; 0x0801]; // One byte too many for 2K RAM
 
     let result = ram.import(&data, 0);
@@ -127,7 +164,7 @@ fn test_import_exceeds_ram_size() {
 
 #[test]
 fn test_import_with_offset_exceeds_ram() {
-    let mut ram = Ram::new(RamSize::_2K, 0x0000);
+    let mut ram = Ram::new(RamSize::_2K, 0x0000).unwrap();
     let data = vec![0xAA; 10];
     let offset = 0x0800 - 5; // This would go beyond RAM size
 
@@ -139,7 +176,7 @@ fn test_import_with_offset_exceeds_ram() {
 // Test data export functionality
 #[test]
 fn test_export_basic() {
-    let mut ram = Ram::new(RamSize::_4K, 0x0000);
+    let mut ram = Ram::new(RamSize::_4K, 0x0000).unwrap();
     let data = vec![0x12, 0x34, 0x56, 0x78, 0x9A];
     ram.import(&data, 10).unwrap();
 
@@ -149,14 +186,14 @@ fn test_export_basic() {
 
 #[test]
 fn test_export_zero_length() {
-    let ram = Ram::new(RamSize::_4K, 0x0000);
+    let ram = Ram::new(RamSize::_4K, 0x0000).unwrap();
     let exported = ram.export(0, 0);
     assert_eq!(exported.len(), 0);
 }
 
 #[test]
 fn test_export_exceeds_ram_size() {
-    let ram = Ram::new(RamSize::_2K, 0x0000);
+    let ram = Ram::new(RamSize::_2K, 0x0000).unwrap();
     // Try to export more data than RAM size
     let exported = ram.export(0, 0x1000); // Request 4K from 2K RAM
     assert_eq!(exported.len(), 0x0800); // Should only get 2K
@@ -164,14 +201,14 @@ fn test_export_exceeds_ram_size() {
 
 #[test]
 fn test_export_with_offset_exceeds_ram() {
-    let ram = Ram::new(RamSize::_2K, 0x0000);
+    let ram = Ram::new(RamSize::_2K, 0x0000).unwrap();
     let exported = ram.export(0x0700, 0x200); // Start near end, request more than available
     assert_eq!(exported.len(), 0x0100); // Should only get what's available
 }
 
 #[test]
 fn test_export_offset_beyond_ram() {
-    let ram = Ram::new(RamSize::_2K, 0x0000);
+    let ram = Ram::new(RamSize::_2K, 0x0000).unwrap();
     // The export function currently panics if offset > memory.len()
     // This is actually testing current behavior - the function should be improved
     // to handle this case more gracefully, but for now we test what it actually does
@@ -184,7 +221,7 @@ fn test_export_offset_beyond_ram() {
 // Test BusDevice trait implementation
 #[test]
 fn test_bus_device_read_write_basic() {
-    let mut ram = Ram::new(RamSize::_4K, 0x2000);
+    let mut ram = Ram::new(RamSize::_4K, 0x2000).unwrap();
 
     // Write data to RAM via bus interface
     let result = ram.write(0x2000, 0xAA);
@@ -198,7 +235,7 @@ fn test_bus_device_read_write_basic() {
 
 #[test]
 fn test_bus_device_read_write_different_addresses() {
-    let mut ram = Ram::new(RamSize::_8K, 0x4000);
+    let mut ram = Ram::new(RamSize::_8K, 0x4000).unwrap();
 
     let test_data = [
         (0x4000, 0x11), // First address
@@ -227,7 +264,7 @@ fn test_bus_device_read_write_different_addresses() {
 
 #[test]
 fn test_bus_device_read_before_start_address() {
-    let ram = Ram::new(RamSize::_4K, 0x8000);
+    let mut ram = Ram::new(RamSize::_4K, 0x8000).unwrap();
 
     let result = ram.read(0x7FFF); // One address before start
     assert!(result.is_err());
@@ -239,7 +276,7 @@ fn test_bus_device_read_before_start_address() {
 
 #[test]
 fn test_bus_device_read_after_end_address() {
-    let ram = Ram::new(RamSize::_4K, 0x8000);
+    let mut ram = Ram::new(RamSize::_4K, 0x8000).unwrap();
 
     let result = ram.read(0x9000); // Beyond end address (0x8000 + 0x1000)
     assert!(result.is_err());
@@ -251,7 +288,7 @@ fn test_bus_device_read_after_end_address() {
 
 #[test]
 fn test_bus_device_write_before_start_address() {
-    let mut ram = Ram::new(RamSize::_4K, 0x8000);
+    let mut ram = Ram::new(RamSize::_4K, 0x8000).unwrap();
 
     let result = ram.write(0x7FFF, 0xFF);
     assert!(result.is_err());
@@ -263,7 +300,7 @@ fn test_bus_device_write_before_start_address() {
 
 #[test]
 fn test_bus_device_write_after_end_address() {
-    let mut ram = Ram::new(RamSize::_4K, 0x8000);
+    let mut ram = Ram::new(RamSize::_4K, 0x8000).unwrap();
 
     let result = ram.write(0x9000, 0xFF);
     assert!(result.is_err());
@@ -275,7 +312,7 @@ fn test_bus_device_write_after_end_address() {
 
 #[test]
 fn test_bus_device_address_wrapping() {
-    let mut ram = Ram::new(RamSize::_4K, 0xF000);
+    let mut ram = Ram::new(RamSize::_4K, 0xF000).unwrap();
 
     // Test address wrapping behavior
     let result = ram.write(0xF000, 0xAA);
@@ -289,7 +326,7 @@ fn test_bus_device_address_wrapping() {
 // Test BusDevice trait methods that don't do anything for RAM
 #[test]
 fn test_bus_device_tick() {
-    let mut ram = Ram::new(RamSize::_4K, 0x0000);
+    let mut ram = Ram::new(RamSize::_4K, 0x0000).unwrap();
 
     // tick() should not panic or change anything
     ram.tick();
@@ -301,13 +338,156 @@ fn test_bus_device_tick() {
 
 #[test]
 fn test_bus_device_interrupts() {
-    let ram = Ram::new(RamSize::_4K, 0x0000);
+    let ram = Ram::new(RamSize::_4K, 0x0000).unwrap();
 
     // RAM should never generate interrupts
     assert_eq!(ram.check_irq(), false);
     assert_eq!(ram.check_nmi(), false);
 }
 
+// Test power-on patterns
+#[test]
+fn test_power_on_patterns() {
+    let patterns = [
+        (PowerOnPattern::Zero, [0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+        (PowerOnPattern::Ones, [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]),
+        (
+            PowerOnPattern::Alternating,
+            [0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF],
+        ),
+        (
+            PowerOnPattern::AppleWin,
+            [0xFF, 0xFF, 0x00, 0x00, 0xFF, 0xFF],
+        ),
+        (
+            PowerOnPattern::Repeating(vec![0x12, 0x34, 0x56]),
+            [0x12, 0x34, 0x56, 0x12, 0x34, 0x56],
+        ),
+    ];
+
+    for (pattern, expected) in patterns {
+        let ram = Ram::new(RamSize::_2K, 0x0000)
+            .unwrap()
+            .with_pattern(pattern);
+        assert_eq!(ram.export(0, 6), expected);
+    }
+}
+
+#[test]
+fn test_vendor_patterns_run_lengths() {
+    let fceux = Ram::new(RamSize::_2K, 0x0000)
+        .unwrap()
+        .with_pattern(PowerOnPattern::Fceux);
+    let vice = Ram::new(RamSize::_2K, 0x0000)
+        .unwrap()
+        .with_pattern(PowerOnPattern::Vice);
+
+    assert_eq!(fceux.export(2, 4), vec![0x00, 0x00, 0xFF, 0xFF]);
+    assert_eq!(vice.export(62, 4), vec![0x00, 0x00, 0xFF, 0xFF]);
+}
+
+#[test]
+fn test_random_pattern_is_reproducible() {
+    let first = Ram::new(RamSize::_2K, 0x0000)
+        .unwrap()
+        .with_pattern(PowerOnPattern::Random(42));
+    let second = Ram::new(RamSize::_2K, 0x0000)
+        .unwrap()
+        .with_pattern(PowerOnPattern::Random(42));
+    let other = Ram::new(RamSize::_2K, 0x0000)
+        .unwrap()
+        .with_pattern(PowerOnPattern::Random(43));
+
+    assert_eq!(first.export(0, 0x800), second.export(0, 0x800));
+    assert_ne!(first.export(0, 0x800), other.export(0, 0x800));
+    assert!(first.export(0, 0x800).iter().any(|&byte| byte != 0x00));
+}
+
+#[test]
+fn test_sparse_ram_uses_pattern() {
+    let mut ram = Ram::sparse(0x10000, 0x0000)
+        .unwrap()
+        .with_pattern(PowerOnPattern::Alternating);
+
+    ram.write(0x1234, 0x56).unwrap();
+
+    assert_eq!(ram.export(0x1232, 4), vec![0x00, 0xFF, 0x56, 0xFF]);
+    assert_eq!(ram.read(0x8001).unwrap(), 0xFF);
+}
+
+// Test uninitialised read detection
+#[test]
+fn test_uninitialised_reads_are_reported_once() {
+    let mut ram = Ram::new(RamSize::_2K, 0x0000)
+        .unwrap()
+        .with_uninitialised_read_tracking();
+
+    ram.sync(0x0200);
+    ram.read(0x0010).unwrap();
+    ram.read(0x0010).unwrap();
+    ram.sync(0x0203);
+    ram.write(0x0011, 0x00).unwrap();
+    ram.read(0x0011).unwrap();
+    ram.read(0x0012).unwrap();
+
+    assert_eq!(
+        ram.uninitialised_reads(),
+        &[
+            UninitialisedRead {
+                address: 0x0010,
+                program_counter: Some(0x0200),
+            },
+            UninitialisedRead {
+                address: 0x0012,
+                program_counter: Some(0x0203),
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_imported_bytes_are_initialised() {
+    let mut ram = Ram::new(RamSize::_2K, 0x0000)
+        .unwrap()
+        .with_uninitialised_read_tracking();
+    ram.import(&[0x01, 0x02], 0x0100).unwrap();
+
+    ram.read(0x0100).unwrap();
+    ram.read(0x0101).unwrap();
+    ram.read(0x0102).unwrap();
+
+    assert_eq!(
+        ram.uninitialised_reads(),
+        &[UninitialisedRead {
+            address: 0x0102,
+            program_counter: None,
+        }]
+    );
+}
+
+#[test]
+fn test_clear_uninitialised_reads() {
+    let mut ram = Ram::new(RamSize::_2K, 0x0000)
+        .unwrap()
+        .with_uninitialised_read_tracking();
+    ram.read(0x0010).unwrap();
+
+    ram.clear_uninitialised_reads();
+    assert!(ram.uninitialised_reads().is_empty());
+    ram.read(0x0010).unwrap();
+
+    assert_eq!(ram.uninitialised_reads().len(), 1);
+}
+
+#[test]
+fn test_untracked_ram_reports_nothing() {
+    let mut ram = Ram::new(RamSize::_2K, 0x0000).unwrap();
+
+    ram.read(0x0010).unwrap();
+
+    assert!(ram.uninitialised_reads().is_empty());
+}
+
 // Test RamSize enum
 #[test]
 fn test_ram_size_values() {
@@ -350,7 +530,7 @@ fn test_ram_size_traits() {
 // Integration tests combining multiple features
 #[test]
 fn test_ram_integration_import_export_bus() {
-    let mut ram = Ram::new(RamSize::_4K, 0x2000);
+    let mut ram = Ram::new(RamSize::_4K, 0x2000).unwrap();
 
     // Import data using import method
     let original_data = vec![0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE, 0xF0];
@@ -380,7 +560,7 @@ fn test_ram_integration_import_export_bus() {
 
 #[test]
 fn test_ram_persistence_across_operations() {
-    let mut ram = Ram::new(RamSize::_2K, 0x0000);
+    let mut ram = Ram::new(RamSize::_2K, 0x0000).unwrap();
 
     // Fill RAM with pattern
     for i in 0..256 {
@@ -409,7 +589,7 @@ fn test_ram_persistence_across_operations() {
 
 #[test]
 fn test_ram_boundary_conditions() {
-    let mut ram = Ram::new(RamSize::_4K, 0x8000);
+    let mut ram = Ram::new(RamSize::_4K, 0x8000).unwrap();
 
     // Test first address
     ram.write(0x8000, 0xAA).unwrap();
@@ -431,7 +611,7 @@ fn test_ram_boundary_conditions() {
 // Performance-related tests
 #[test]
 fn test_ram_large_operations() {
-    let mut ram = Ram::new(RamSize::_64K, 0x0000);
+    let mut ram = Ram::new(RamSize::_64K, 0x0000).unwrap();
 
     // Import large amount of data
     let large_data = vec![0xFF; 32768]; // 32K of data
@@ -451,7 +631,7 @@ fn test_ram_large_operations() {
 
 #[test]
 fn test_debug_trait() {
-    let ram = Ram::new(RamSize::_4K, 0x8000);
+    let ram = Ram::new(RamSize::_4K, 0x8000).unwrap();
     let debug_string = format!("{:?}", ram);
 
     // Should contain key information
@@ -0,0 +1,332 @@
+//! MOS 6532 RAM-I/O-Timer (RIOT).
+//!
+//! The RIOT has two chip selects. With RS low the 128 bytes of RAM are selected by
+//! address lines A0-A6. With RS high the I/O registers are selected by A0-A4:
+//! - A2 = 0: `$0` ORA, `$1` DDRA, `$2` ORB, `$3` DDRB (A3 and A4 ignored)
+//! - A2 = 1, write, A4 = 1: write the interval timer, A0-A1 select the prescaler
+//!   (1, 8, 64 or 1024 cycles) and A3 enables the timer interrupt
+//! - A2 = 1, write, A4 = 0: PA7 edge detect control, A0 selects the active edge
+//!   (1 = rising) and A1 enables the PA7 interrupt
+//! - A2 = 1, read, A0 = 0: read the timer, A3 enables the timer interrupt
+//! - A2 = 1, read, A0 = 1: read the interrupt flags, which clears the PA7 flag
+//!
+//! The RAM and the I/O registers are usually decoded at unrelated addresses, so the
+//! device is registered for its RAM and the I/O window is added with
+//! `BusController::register_device_range`. The RIOT is clocked by Φ2, one `tick`
+//! per CPU cycle.
+
+use bus::errors::BusError;
+use bus::trait_bus_device::BusDevice;
+
+/// Number of bytes of RAM
+pub const RIOT_RAM_SIZE: u16 = 128;
+/// Number of I/O register addresses decoded by A0-A4
+pub const RIOT_REGISTER_COUNT: u16 = 32;
+
+/// Interrupt flag: the interval timer underflowed
+pub const INTERRUPT_TIMER: u8 = 0x80;
+/// Interrupt flag: active edge on PA7
+pub const INTERRUPT_PA7: u8 = 0x40;
+
+const REGISTER_ORA: u16 = 0x0;
+const REGISTER_DDRA: u16 = 0x1;
+const REGISTER_ORB: u16 = 0x2;
+/// A2 selects the timer and interrupt registers rather than the ports
+const SELECT_TIMER: u16 = 0x04;
+/// A3 enables the timer interrupt when the timer is read or written
+const SELECT_TIMER_IRQ: u16 = 0x08;
+/// A4 selects a timer write rather than the edge detect control
+const SELECT_TIMER_WRITE: u16 = 0x10;
+
+/// Prescaler intervals selected by A0-A1 on a timer write
+const PRESCALERS: [u16; 4] = [1, 8, 64, 1024];
+
+/// Represents a MOS 6532 RIOT.
+#[derive(Debug)]
+pub struct Riot {
+    /// Start address of the RAM
+    ram_start: u16,
+    /// Start address of the I/O register window
+    io_start: u16,
+    ram: [u8; RIOT_RAM_SIZE as usize],
+    ora: u8,
+    orb: u8,
+    ddra: u8,
+    ddrb: u8,
+    /// Levels driven onto port A by external hardware
+    port_a_input: u8,
+    /// Levels driven onto port B by external hardware
+    port_b_input: u8,
+    timer: u8,
+    /// Cycles per timer decrement, as programmed by the last timer write
+    prescaler: u16,
+    /// Cycles left until the next timer decrement
+    prescale_count: u16,
+    /// The timer underflowed and counts down once per cycle until it is read
+    underflowed: bool,
+    timer_irq_enabled: bool,
+    /// The PA7 edge detector triggers on rising rather than falling edges
+    pa7_rising: bool,
+    pa7_irq_enabled: bool,
+    /// Timer and PA7 interrupt flags
+    flags: u8,
+}
+
+impl Riot {
+    /// Create a new RIOT with its RAM and I/O registers at the specified addresses.
+    ///
+    /// # Arguments
+    /// * `ram_start` - Start address of the 128 bytes of RAM
+    /// * `io_start` - Start address of the 32 byte I/O register window
+    ///
+    /// # Returns
+    /// * A new Riot instance in its reset state
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let riot = Riot::new(0x0080, 0x0280);
+    /// let id = bus.register_device(0x0080, 0x00FF, Box::new(riot))?;
+    /// bus.register_device_range(id, 0x0280, 0x029F)?;
+    /// ```
+    pub fn new(ram_start: u16, io_start: u16) -> Self {
+        Self {
+            ram_start,
+            io_start,
+            ram: [0; RIOT_RAM_SIZE as usize],
+            ora: 0,
+            orb: 0,
+            ddra: 0,
+            ddrb: 0,
+            // Undriven inputs are pulled high
+            port_a_input: 0xFF,
+            port_b_input: 0xFF,
+            timer: 0xFF,
+            prescaler: 1024,
+            prescale_count: 1024,
+            underflowed: false,
+            timer_irq_enabled: false,
+            pa7_rising: false,
+            pa7_irq_enabled: false,
+            flags: 0,
+        }
+    }
+
+    /// Pull the RES pin low.
+    ///
+    /// Clears the port and data direction registers and disables interrupts.
+    /// The RAM and the timer keep their contents.
+    pub fn reset(&mut self) {
+        self.ora = 0;
+        self.orb = 0;
+        self.ddra = 0;
+        self.ddrb = 0;
+        self.timer_irq_enabled = false;
+        self.pa7_rising = false;
+        self.pa7_irq_enabled = false;
+        self.flags = 0;
+    }
+
+    /// Contents of the RAM
+    pub fn ram(&self) -> &[u8] {
+        &self.ram
+    }
+
+    /// Drive the port A pins from external hardware.
+    ///
+    /// Only pins configured as inputs in DDRA are affected. An active edge on PA7
+    /// sets the PA7 interrupt flag.
+    ///
+    /// # Arguments
+    /// * `value` - Levels of PA0-PA7
+    pub fn set_port_a_input(&mut self, value: u8) {
+        let pa7 = self.port_a_output() & 0x80 != 0;
+        self.port_a_input = value;
+        self.detect_pa7_edge(pa7);
+    }
+
+    /// Drive the port B pins from external hardware.
+    ///
+    /// Only pins configured as inputs in DDRB are affected.
+    ///
+    /// # Arguments
+    /// * `value` - Levels of PB0-PB7
+    pub fn set_port_b_input(&mut self, value: u8) {
+        self.port_b_input = value;
+    }
+
+    /// Levels of the port A pins: outputs from ORA, inputs from external hardware
+    pub fn port_a_output(&self) -> u8 {
+        (self.ora & self.ddra) | (self.port_a_input & !self.ddra)
+    }
+
+    /// Levels of the port B pins: outputs from ORB, inputs from external hardware
+    pub fn port_b_output(&self) -> u8 {
+        (self.orb & self.ddrb) | (self.port_b_input & !self.ddrb)
+    }
+
+    /// Set the PA7 flag if PA7 moved from `previous` to the active edge
+    fn detect_pa7_edge(&mut self, previous: bool) {
+        let pa7 = self.port_a_output() & 0x80 != 0;
+        if pa7 != previous && pa7 == self.pa7_rising {
+            self.flags |= INTERRUPT_PA7;
+        }
+    }
+
+    /// Ticks until the timer underflows and sets its flag, unless it already has
+    fn ticks_until_underflow(&self) -> Option<u64> {
+        if self.underflowed {
+            return None;
+        }
+        Some(self.timer as u64 * self.prescaler as u64 + self.prescale_count as u64)
+    }
+
+    /// Offset of an address in the RAM, if it is inside the RAM
+    fn ram_offset(&self, address: u16) -> Option<usize> {
+        let offset = address.wrapping_sub(self.ram_start);
+        (offset < RIOT_RAM_SIZE).then_some(offset as usize)
+    }
+
+    /// Register select lines A0-A4 of an address, if it is inside the I/O window
+    fn io_offset(&self, address: u16) -> Option<u16> {
+        let offset = address.wrapping_sub(self.io_start);
+        (offset < RIOT_REGISTER_COUNT).then_some(offset)
+    }
+
+    /// Read one of the port registers, selected by A0-A1
+    fn read_port(&self, offset: u16) -> u8 {
+        match offset & 0x03 {
+            REGISTER_ORA => self.port_a_output(),
+            REGISTER_DDRA => self.ddra,
+            REGISTER_ORB => self.port_b_output(),
+            // $3: DDRB
+            _ => self.ddrb,
+        }
+    }
+
+    /// Write one of the port registers, selected by A0-A1
+    fn write_port(&mut self, offset: u16, data: u8) {
+        // Port A outputs can produce an edge on PA7 as well
+        let pa7 = self.port_a_output() & 0x80 != 0;
+        match offset & 0x03 {
+            REGISTER_ORA => self.ora = data,
+            REGISTER_DDRA => self.ddra = data,
+            REGISTER_ORB => self.orb = data,
+            // $3: DDRB
+            _ => self.ddrb = data,
+        }
+        self.detect_pa7_edge(pa7);
+    }
+}
+
+impl BusDevice for Riot {
+    fn read(&mut self, address: u16) -> Result<u8, BusError> {
+        if let Some(offset) = self.ram_offset(address) {
+            return Ok(self.ram[offset]);
+        }
+        let offset = self
+            .io_offset(address)
+            .ok_or(BusError::AddressOutOfRange(address))?;
+        if offset & SELECT_TIMER == 0 {
+            return Ok(self.read_port(offset));
+        }
+        if offset & 0x01 != 0 {
+            // Interrupt flags, reading clears the PA7 flag
+            let value = self.flags;
+            self.flags &= !INTERRUPT_PA7;
+            return Ok(value);
+        }
+        // Reading the timer clears its flag and returns it to the programmed interval
+        self.timer_irq_enabled = offset & SELECT_TIMER_IRQ != 0;
+        self.flags &= !INTERRUPT_TIMER;
+        if self.underflowed {
+            self.underflowed = false;
+            self.prescale_count = self.prescaler;
+        }
+        Ok(self.timer)
+    }
+
+    fn write(&mut self, address: u16, data: u8) -> Result<(), BusError> {
+        if let Some(offset) = self.ram_offset(address) {
+            self.ram[offset] = data;
+            return Ok(());
+        }
+        let offset = self
+            .io_offset(address)
+            .ok_or(BusError::AddressOutOfRange(address))?;
+        if offset & SELECT_TIMER == 0 {
+            self.write_port(offset, data);
+        } else if offset & SELECT_TIMER_WRITE != 0 {
+            self.timer = data;
+            self.prescaler = PRESCALERS[(offset & 0x03) as usize];
+            self.prescale_count = self.prescaler;
+            self.underflowed = false;
+            self.timer_irq_enabled = offset & SELECT_TIMER_IRQ != 0;
+            self.flags &= !INTERRUPT_TIMER;
+        } else {
+            // Edge detect control, the written data is ignored
+            self.pa7_rising = offset & 0x01 != 0;
+            self.pa7_irq_enabled = offset & 0x02 != 0;
+        }
+        Ok(())
+    }
+
+    fn tick(&mut self) {
+        if !self.underflowed {
+            self.prescale_count -= 1;
+            if self.prescale_count > 0 {
+                return;
+            }
+            self.prescale_count = self.prescaler;
+        }
+        if self.timer == 0 {
+            // After an underflow the timer keeps counting down once per cycle
+            self.underflowed = true;
+            self.flags |= INTERRUPT_TIMER;
+        }
+        self.timer = self.timer.wrapping_sub(1);
+    }
+
+    fn advance(&mut self, ticks: u64) {
+        let mut ticks = ticks;
+        if let Some(underflow) = self.ticks_until_underflow() {
+            if ticks < underflow {
+                let remaining = underflow - ticks - 1;
+                let prescaler = self.prescaler as u64;
+                self.timer = (remaining / prescaler) as u8;
+                self.prescale_count = (remaining % prescaler) as u16 + 1;
+                return;
+            }
+            self.prescale_count = self.prescaler;
+            self.underflowed = true;
+            self.flags |= INTERRUPT_TIMER;
+            self.timer = 0xFF;
+            ticks -= underflow;
+        }
+        // After an underflow the timer keeps counting down once per cycle
+        self.timer = self.timer.wrapping_sub(ticks as u8);
+    }
+
+    fn ticks_until_event(&self) -> Option<u64> {
+        self.ticks_until_underflow()
+    }
+
+    fn check_irq(&self) -> bool {
+        (self.timer_irq_enabled && self.flags & INTERRUPT_TIMER != 0)
+            || (self.pa7_irq_enabled && self.flags & INTERRUPT_PA7 != 0)
+    }
+
+    fn check_nmi(&self) -> bool {
+        // The IRQ output may be wired to NMI, but the RIOT itself only has one output
+        false
+    }
+
+    fn check_rdy(&self) -> bool {
+        // The RIOT does not insert wait states
+        false
+    }
+
+    fn check_so(&self) -> bool {
+        // The RIOT does not drive the SO line
+        false
+    }
+}
@@ -0,0 +1,270 @@
+//! Unit tests for the 6532 RIOT implementation
+//!
+//! This module tests the RAM, the ports, the interval timer with its prescalers and
+//! post-underflow behaviour, the PA7 edge detector, and mapping on a bus.
+
+use bus::BusController;
+use bus::timing_mode::TimingMode;
+use bus::trait_bus_device::BusDevice;
+use riot6532::{INTERRUPT_PA7, INTERRUPT_TIMER, Riot};
+
+const RAM: u16 = 0x0080;
+const IO: u16 = 0x0280;
+const ORA: u16 = IO;
+const DDRA: u16 = IO + 1;
+const ORB: u16 = IO + 2;
+const DDRB: u16 = IO + 3;
+/// Read the timer with its interrupt disabled
+const READ_TIMER: u16 = IO + 0x04;
+/// Read the timer with its interrupt enabled
+const READ_TIMER_IRQ: u16 = IO + 0x0C;
+const READ_FLAGS: u16 = IO + 0x05;
+/// Write the timer with the 1T prescaler, add 0-3 for the other prescalers
+const WRITE_TIMER: u16 = IO + 0x14;
+/// Write the timer with the 1T prescaler and its interrupt enabled
+const WRITE_TIMER_IRQ: u16 = IO + 0x1C;
+/// Edge detect control, add 1 for rising edges and 2 to enable the interrupt
+const EDGE_CONTROL: u16 = IO + 0x04;
+
+/// Tick the RIOT a number of times
+fn run(riot: &mut Riot, ticks: u32) {
+    for _ in 0..ticks {
+        riot.tick();
+    }
+}
+
+// Test RAM
+#[test]
+fn test_ram() {
+    let mut riot = Riot::new(RAM, IO);
+    riot.write(0x0080, 0x12).unwrap();
+    riot.write(0x00FF, 0x34).unwrap();
+    assert_eq!(riot.read(0x0080).unwrap(), 0x12);
+    assert_eq!(riot.read(0x00FF).unwrap(), 0x34);
+    assert_eq!(riot.ram()[0x7F], 0x34);
+    assert!(riot.read(0x0100).is_err());
+    assert!(riot.read(IO + 0x20).is_err());
+}
+
+// Test ports
+#[test]
+fn test_ports() {
+    let mut riot = Riot::new(RAM, IO);
+    // Inputs are pulled high
+    assert_eq!(riot.read(ORA).unwrap(), 0xFF);
+
+    riot.write(DDRA, 0x0F).unwrap();
+    riot.write(ORA, 0xA5).unwrap();
+    riot.set_port_a_input(0x30);
+    assert_eq!(riot.read(ORA).unwrap(), 0x35);
+    assert_eq!(riot.port_a_output(), 0x35);
+    assert_eq!(riot.read(DDRA).unwrap(), 0x0F);
+
+    riot.write(DDRB, 0xFF).unwrap();
+    riot.write(ORB, 0x5A).unwrap();
+    assert_eq!(riot.port_b_output(), 0x5A);
+    // A3 and A4 are ignored for the port registers
+    assert_eq!(riot.read(ORB + 0x18).unwrap(), 0x5A);
+}
+
+#[test]
+fn test_reset() {
+    let mut riot = Riot::new(RAM, IO);
+    riot.write(RAM, 0x77).unwrap();
+    riot.write(DDRB, 0xFF).unwrap();
+    riot.write(ORB, 0x00).unwrap();
+    riot.reset();
+    assert_eq!(riot.read(DDRB).unwrap(), 0x00);
+    assert_eq!(riot.port_b_output(), 0xFF);
+    assert_eq!(riot.read(RAM).unwrap(), 0x77);
+}
+
+// Test interval timer
+#[test]
+fn test_timer_prescalers() {
+    for (select, interval) in [(0, 1), (1, 8), (2, 64), (3, 1024)] {
+        let mut riot = Riot::new(RAM, IO);
+        riot.write(WRITE_TIMER + select, 10).unwrap();
+        run(&mut riot, interval - 1);
+        assert_eq!(riot.read(READ_TIMER).unwrap(), 10);
+        riot.tick();
+        assert_eq!(riot.read(READ_TIMER).unwrap(), 9);
+        run(&mut riot, interval * 9);
+        assert_eq!(riot.read(READ_TIMER).unwrap(), 0);
+        assert_eq!(riot.read(READ_FLAGS).unwrap() & INTERRUPT_TIMER, 0);
+    }
+}
+
+#[test]
+fn test_timer_underflow() {
+    let mut riot = Riot::new(RAM, IO);
+    riot.write(WRITE_TIMER_IRQ + 1, 2).unwrap();
+    run(&mut riot, 3 * 8 - 1);
+    assert!(!riot.check_irq());
+    riot.tick();
+    assert!(riot.check_irq());
+    assert_eq!(riot.read(READ_FLAGS).unwrap(), INTERRUPT_TIMER);
+
+    // After the underflow the timer counts down once per cycle
+    run(&mut riot, 5);
+    assert_eq!(riot.read(READ_TIMER_IRQ).unwrap(), 0xFA);
+    assert!(!riot.check_irq());
+
+    // Reading the timer returns it to the programmed interval
+    run(&mut riot, 7);
+    assert_eq!(riot.read(READ_TIMER).unwrap(), 0xFA);
+    riot.tick();
+    assert_eq!(riot.read(READ_TIMER).unwrap(), 0xF9);
+}
+
+#[test]
+fn test_timer_interrupt_disabled() {
+    let mut riot = Riot::new(RAM, IO);
+    riot.write(WRITE_TIMER, 0).unwrap();
+    riot.tick();
+    assert!(!riot.check_irq());
+    assert_eq!(riot.read(READ_FLAGS).unwrap(), INTERRUPT_TIMER);
+
+    // Reading the timer with A3 set enables the interrupt, but clears the flag
+    assert_eq!(riot.read(READ_TIMER_IRQ).unwrap(), 0xFF);
+    assert!(!riot.check_irq());
+}
+
+#[test]
+fn test_timer_write_clears_flag() {
+    let mut riot = Riot::new(RAM, IO);
+    riot.write(WRITE_TIMER_IRQ, 0).unwrap();
+    riot.tick();
+    assert!(riot.check_irq());
+    riot.write(WRITE_TIMER_IRQ, 0x40).unwrap();
+    assert!(!riot.check_irq());
+    riot.tick();
+    assert_eq!(riot.read(READ_TIMER).unwrap(), 0x3F);
+}
+
+#[test]
+fn test_advance_matches_ticks() {
+    for select in 0..4 {
+        for ticks in [0, 1, 7, 8, 100, 1_000, 0x2800, 0x4_0000] {
+            let mut ticked = Riot::new(RAM, IO);
+            let mut advanced = Riot::new(RAM, IO);
+            ticked.write(WRITE_TIMER + select, 0x27).unwrap();
+            advanced.write(WRITE_TIMER + select, 0x27).unwrap();
+            run(&mut ticked, ticks);
+            advanced.advance(ticks as u64);
+
+            assert_eq!(advanced.ticks_until_event(), ticked.ticks_until_event());
+            assert_eq!(
+                advanced.read(READ_FLAGS).unwrap(),
+                ticked.read(READ_FLAGS).unwrap()
+            );
+            assert_eq!(
+                advanced.read(READ_TIMER).unwrap(),
+                ticked.read(READ_TIMER).unwrap()
+            );
+            // Both continue from the same prescaler position
+            run(&mut ticked, 1);
+            advanced.advance(1);
+            assert_eq!(
+                advanced.read(READ_TIMER).unwrap(),
+                ticked.read(READ_TIMER).unwrap()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_ticks_until_event() {
+    let mut riot = Riot::new(RAM, IO);
+    riot.write(WRITE_TIMER + 2, 3).unwrap();
+    assert_eq!(riot.ticks_until_event(), Some(4 * 64));
+    run(&mut riot, 10);
+    assert_eq!(riot.ticks_until_event(), Some(4 * 64 - 10));
+
+    // Once the flag is set there is nothing left to report
+    run(&mut riot, 4 * 64 - 10);
+    assert_eq!(riot.ticks_until_event(), None);
+}
+
+#[test]
+fn test_scheduled_timer_raises_irq() {
+    let mut bus = BusController::new();
+    let id = bus
+        .register_device(RAM, RAM + 0x7F, Box::new(Riot::new(RAM, IO)))
+        .unwrap();
+    bus.register_device_range(id, IO, IO + 0x1F).unwrap();
+    bus.set_timing_mode(id, TimingMode::Scheduled).unwrap();
+    bus.write(WRITE_TIMER_IRQ + 3, 2).unwrap();
+
+    // The interrupt fires without the CPU touching the RIOT
+    for _ in 0..3 * 1024 - 1 {
+        bus.tick();
+    }
+    assert!(!bus.check_irq());
+    bus.tick();
+    assert!(bus.check_irq());
+    assert_eq!(bus.read(READ_TIMER).unwrap(), 0xFF);
+}
+
+// Test PA7 edge detection
+#[test]
+fn test_pa7_falling_edge() {
+    let mut riot = Riot::new(RAM, IO);
+    riot.write(EDGE_CONTROL + 2, 0).unwrap();
+    riot.set_port_a_input(0xFF);
+    assert!(!riot.check_irq());
+    riot.set_port_a_input(0x7F);
+    assert!(riot.check_irq());
+    assert_eq!(riot.read(READ_FLAGS).unwrap(), INTERRUPT_PA7);
+    assert!(!riot.check_irq());
+    assert_eq!(riot.read(READ_FLAGS).unwrap(), 0);
+}
+
+#[test]
+fn test_pa7_rising_edge() {
+    let mut riot = Riot::new(RAM, IO);
+    riot.write(EDGE_CONTROL + 3, 0).unwrap();
+    riot.set_port_a_input(0x7F);
+    assert!(!riot.check_irq());
+    riot.set_port_a_input(0xFF);
+    assert!(riot.check_irq());
+}
+
+#[test]
+fn test_pa7_flag_without_interrupt() {
+    let mut riot = Riot::new(RAM, IO);
+    riot.set_port_a_input(0x00);
+    assert!(!riot.check_irq());
+    assert_eq!(riot.read(READ_FLAGS).unwrap(), INTERRUPT_PA7);
+}
+
+#[test]
+fn test_pa7_output_edge() {
+    let mut riot = Riot::new(RAM, IO);
+    riot.write(EDGE_CONTROL + 2, 0).unwrap();
+    riot.write(ORA, 0x00).unwrap();
+    assert!(!riot.check_irq());
+    // Turning PA7 into an output driving low is a falling edge on the pin
+    riot.write(DDRA, 0x80).unwrap();
+    assert!(riot.check_irq());
+}
+
+// Test bus mapping
+#[test]
+fn test_separate_ram_and_io_ranges() {
+    let mut bus = BusController::new();
+    let id = bus
+        .register_device(RAM, RAM + 0x7F, Box::new(Riot::new(RAM, IO)))
+        .unwrap();
+    bus.register_device_range(id, IO, IO + 0x1F).unwrap();
+
+    bus.write(0x0090, 0xAB).unwrap();
+    bus.write(DDRB, 0xFF).unwrap();
+    bus.write(ORB, 0x42).unwrap();
+    assert_eq!(bus.read(0x0090).unwrap(), 0xAB);
+    assert!(bus.read(0x0200).is_err());
+
+    let riot = bus.device::<Riot>(id).unwrap();
+    assert_eq!(riot.ram()[0x10], 0xAB);
+    assert_eq!(riot.port_b_output(), 0x42);
+}
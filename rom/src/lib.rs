@@ -10,12 +10,9 @@ use crate::rom_size::RomSize;
 
 /// Represents a Read-Only Memory (ROM) module.
 #[derive(Debug)]
-#[allow(dead_code)]
 pub struct Rom {
     /// Actual ROM memory
     memory: Vec<u8>,
-    /// Size of ROM
-    size: RomSize,
     /// Start address of ROM
     start_address: u16,
 }
@@ -28,18 +25,78 @@ impl Rom {
     /// * `start_address` - Start address of the ROM in memory (default is 0x8000)
     ///
     /// # Returns
-    /// * A new Rom instance
+    /// * `Ok(Rom)` containing the new ROM instance
+    /// * `Err(String)` if the ROM does not fit below $FFFF
+    ///
+    /// # Errors
+    /// * If the start address plus the size exceeds the 64K address space
     ///
     /// # Examples
     /// ``` ignore
-    /// let rom = Rom::new(RomSize::_32K, 0x8000);
+    /// let rom = Rom::new(RomSize::_32K, 0x8000)?;
     /// ```
-    pub fn new(size: RomSize, start_address: u16) -> Self {
-        Self {
-            memory: vec![0; size as usize],
-            size,
-            start_address,
+    pub fn new(size: RomSize, start_address: u16) -> Result<Self, String> {
+        Self::with_length(size as usize, start_address)
+    }
+
+    /// Create a new, zero-filled ROM instance of any length, e.g. a 256-byte boot PROM.
+    ///
+    /// # Arguments
+    /// * `length` - Size of the ROM in bytes
+    /// * `start_address` - Start address of the ROM in memory
+    ///
+    /// # Returns
+    /// * `Ok(Rom)` containing the new ROM instance
+    /// * `Err(String)` if the length is zero or the ROM does not fit below $FFFF
+    ///
+    /// # Errors
+    /// * If the length is zero or the start address plus the length exceeds the 64K address space
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let prom = Rom::with_length(0x100, 0xFF00)?;
+    /// ```
+    pub fn with_length(length: usize, start_address: u16) -> Result<Self, String> {
+        Self::from_image(&vec![0; length], start_address)
+    }
+
+    /// Create a new ROM instance sized to and filled with an image.
+    ///
+    /// # Arguments
+    /// * `image` - Contents of the ROM
+    /// * `start_address` - Start address of the ROM in memory
+    ///
+    /// # Returns
+    /// * `Ok(Rom)` containing the new ROM instance
+    /// * `Err(String)` if the image is empty or does not fit below $FFFF
+    ///
+    /// # Errors
+    /// * If the image is empty or the start address plus its length exceeds the 64K address space
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let rom = Rom::from_image(&std::fs::read("basic.bin")?, 0xD000)?;
+    /// ```
+    pub fn from_image(image: &[u8], start_address: u16) -> Result<Self, String> {
+        if image.is_empty() {
+            return Err("ROM size must not be zero".to_string());
+        }
+        if start_address as usize + image.len() > 0x10000 {
+            return Err(format!(
+                "ROM of {} bytes at 0x{:04X} extends past 0xFFFF",
+                image.len(),
+                start_address
+            ));
         }
+        Ok(Self {
+            memory: image.to_vec(),
+            start_address,
+        })
+    }
+
+    /// Size of the ROM in bytes
+    pub fn size(&self) -> usize {
+        self.memory.len()
     }
 
     /// Import data into the ROM at the specified offset.
@@ -57,9 +114,9 @@ impl Rom {
     ///
     /// # Examples
     /// ``` ignore
-    /// let mut rom = Rom::new(RomSize::_32K, 0x8000);
+    /// let mut rom = Rom::new(RomSize::_32K, 0x8000)?;
     /// let data = vec![0x00, 0x01, 0x02, 0x03];
-    /// rom.load(&data, 0).unwrap();
+    /// rom.import(&data, 0)?;
     /// ```
     pub fn import(&mut self, data: &[u8], offset: usize) -> Result<(), String> {
         if offset + data.len() > self.memory.len() {
@@ -80,7 +137,7 @@ impl Rom {
     ///
     /// # Examples
     /// ``` ignore
-    /// let rom = Rom::new(RomSize::_32K, 0x8000);
+    /// let rom = Rom::new(RomSize::_32K, 0x8000)?;
     /// let data = rom.export(0, 16);
     /// ```
     pub fn export(&self, offset: usize, length: usize) -> Vec<u8> {
@@ -90,7 +147,7 @@ impl Rom {
 }
 
 impl BusDevice for Rom {
-    fn read(&self, address: u16) -> Result<u8, BusError> {
+    fn read(&mut self, address: u16) -> Result<u8, BusError> {
         let offset = address.wrapping_sub(self.start_address) as usize;
         if offset < self.memory.len() {
             Ok(self.memory[offset])
@@ -103,6 +160,17 @@ impl BusDevice for Rom {
         Err(BusError::ReadOnly(address))
     }
 
+    fn load(&mut self, address: u16, data: u8) -> Result<(), BusError> {
+        let offset = address.wrapping_sub(self.start_address) as usize;
+        match self.memory.get_mut(offset) {
+            Some(byte) => {
+                *byte = data;
+                Ok(())
+            }
+            None => Err(BusError::AddressOutOfRange(address)),
+        }
+    }
+
     fn tick(&mut self) {
         // ROM does not need to do anything on tick
     }
@@ -116,4 +184,14 @@ impl BusDevice for Rom {
         // ROM does not generate NMIs
         false
     }
+
+    fn check_rdy(&self) -> bool {
+        // ROM does not insert wait states
+        false
+    }
+
+    fn check_so(&self) -> bool {
+        // ROM does not drive the SO line
+        false
+    }
 }
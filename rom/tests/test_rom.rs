@@ -11,7 +11,7 @@ use bus::errors::BusError;
 // Test ROM creation and initialization
 #[test]
 fn test_rom_creation_default() {
-    let rom = Rom::new(RomSize::_32K, 0x8000);
+    let rom = Rom::new(RomSize::_32K, 0x8000).unwrap();
     
     // Test that ROM was created with correct size
     // We can't directly access memory field, so we test via export
@@ -32,7 +32,7 @@ fn test_rom_creation_different_sizes() {
     ];
     
     for (size, expected_bytes) in sizes {
-        let rom = Rom::new(size, 0x0000);
+        let rom = Rom::new(size, 0x0000).unwrap();
         let exported = rom.export(0, expected_bytes);
         assert_eq!(exported.len(), expected_bytes);
         assert_eq!(exported, vec![0; expected_bytes]);
@@ -41,19 +41,49 @@ fn test_rom_creation_different_sizes() {
 
 #[test]
 fn test_rom_creation_different_start_addresses() {
-    let start_addresses = [0x0000, 0x2000, 0x4000, 0x8000, 0xC000, 0xFF00];
+    let start_addresses = [0x0000, 0x2000, 0x4000, 0x8000, 0xC000, 0xF000];
     
     for start_addr in start_addresses {
-        let rom = Rom::new(RomSize::_4K, start_addr);
+        let rom = Rom::new(RomSize::_4K, start_addr).unwrap();
         let exported = rom.export(0, 10);
         assert_eq!(exported, vec![0; 10]);
     }
 }
 
+#[test]
+fn test_rom_creation_past_end_of_address_space() {
+    assert!(Rom::new(RomSize::_4K, 0xFF00).is_err());
+    assert!(Rom::new(RomSize::_64K, 0x0001).is_err());
+    assert!(Rom::new(RomSize::_64K, 0x0000).is_ok());
+}
+
+#[test]
+fn test_rom_with_arbitrary_length() {
+    let mut rom = Rom::with_length(0x1800, 0xE800).unwrap();
+
+    assert_eq!(rom.size(), 0x1800);
+    assert_eq!(rom.read(0xFFFF).unwrap(), 0x00);
+    assert!(matches!(rom.read(0xE7FF), Err(BusError::AddressOutOfRange(0xE7FF))));
+    assert!(Rom::with_length(0, 0x0000).is_err());
+    assert!(Rom::with_length(0x101, 0xFF00).is_err());
+}
+
+#[test]
+fn test_rom_from_image() {
+    let mut rom = Rom::from_image(&[0xA9, 0x01, 0x60], 0xFF00).unwrap();
+
+    assert_eq!(rom.size(), 3);
+    assert_eq!(rom.read(0xFF00).unwrap(), 0xA9);
+    assert_eq!(rom.read(0xFF02).unwrap(), 0x60);
+    assert!(matches!(rom.read(0xFF03), Err(BusError::AddressOutOfRange(0xFF03))));
+    assert!(Rom::from_image(&[], 0xFF00).is_err());
+    assert!(Rom::from_image(&[0; 0x200], 0xFF00).is_err());
+}
+
 // Test data import functionality
 #[test]
 fn test_import_basic() {
-    let mut rom = Rom::new(RomSize::_4K, 0x0000);
+    let mut rom = Rom::new(RomSize::_4K, 0x0000).unwrap();
     let data = vec![0xAA, 0xBB, 0xCC, 0xDD];
     
     let result = rom.import(&data, 0);
@@ -66,7 +96,7 @@ fn test_import_basic() {
 
 #[test]
 fn test_import_with_offset() {
-    let mut rom = Rom::new(RomSize::_4K, 0x0000);
+    let mut rom = Rom::new(RomSize::_4K, 0x0000).unwrap();
     let data = vec![0x11, 0x22, 0x33];
     let offset = 100;
     
@@ -86,7 +116,7 @@ fn test_import_with_offset() {
 
 #[test]
 fn test_import_empty_data() {
-    let mut rom = Rom::new(RomSize::_4K, 0x0000);
+    let mut rom = Rom::new(RomSize::_4K, 0x0000).unwrap();
     let data: Vec<u8> = vec![];
     
     let result = rom.import(&data, 0);
@@ -99,7 +129,7 @@ fn test_import_empty_data() {
 
 #[test]
 fn test_import_full_rom() {
-    let mut rom = Rom::new(RomSize::_2K, 0x0000);
+    let mut rom = Rom::new(RomSize::_2K, 0x0000).unwrap();
     let data = vec![0xFF; 0x0800]; // Fill entire 2K ROM
     
     let result = rom.import(&data, 0);
@@ -113,7 +143,7 @@ fn test_import_full_rom() {
 
 #[test]
 fn test_import_exceeds_rom_size() {
-    let mut rom = Rom::new(RomSize::_2K, 0x0000);
+    let mut rom = Rom::new(RomSize::_2K, 0x0000).unwrap();
     let data = vec![0xFF; 0x0801]; // One byte too many for 2K ROM
     
     let result = rom.import(&data, 0);
@@ -127,7 +157,7 @@ fn test_import_exceeds_rom_size() {
 
 #[test]
 fn test_import_with_offset_exceeds_rom() {
-    let mut rom = Rom::new(RomSize::_2K, 0x0000);
+    let mut rom = Rom::new(RomSize::_2K, 0x0000).unwrap();
     let data = vec![0xAA; 10];
     let offset = 0x0800 - 5; // This would go beyond ROM size
     
@@ -139,7 +169,7 @@ fn test_import_with_offset_exceeds_rom() {
 // Test data export functionality
 #[test]
 fn test_export_basic() {
-    let mut rom = Rom::new(RomSize::_4K, 0x0000);
+    let mut rom = Rom::new(RomSize::_4K, 0x0000).unwrap();
     let data = vec![0x12, 0x34, 0x56, 0x78, 0x9A];
     rom.import(&data, 10).unwrap();
     
@@ -149,14 +179,14 @@ fn test_export_basic() {
 
 #[test]
 fn test_export_zero_length() {
-    let rom = Rom::new(RomSize::_4K, 0x0000);
+    let rom = Rom::new(RomSize::_4K, 0x0000).unwrap();
     let exported = rom.export(0, 0);
     assert_eq!(exported.len(), 0);
 }
 
 #[test]
 fn test_export_exceeds_rom_size() {
-    let rom = Rom::new(RomSize::_2K, 0x0000);
+    let rom = Rom::new(RomSize::_2K, 0x0000).unwrap();
     // Try to export more data than ROM size
     let exported = rom.export(0, 0x1000); // Request 4K from 2K ROM
     assert_eq!(exported.len(), 0x0800); // Should only get 2K
@@ -164,14 +194,14 @@ fn test_export_exceeds_rom_size() {
 
 #[test]
 fn test_export_with_offset_exceeds_rom() {
-    let rom = Rom::new(RomSize::_2K, 0x0000);
+    let rom = Rom::new(RomSize::_2K, 0x0000).unwrap();
     let exported = rom.export(0x0700, 0x200); // Start near end, request more than available
     assert_eq!(exported.len(), 0x0100); // Should only get what's available
 }
 
 #[test]
 fn test_export_offset_at_rom_boundary() {
-    let rom = Rom::new(RomSize::_2K, 0x0000);
+    let rom = Rom::new(RomSize::_2K, 0x0000).unwrap();
     // Test with offset exactly at end of ROM (should return empty)
     let exported = rom.export(0x0800, 100); // 2K = 0x0800 bytes
     assert_eq!(exported.len(), 0);
@@ -180,7 +210,7 @@ fn test_export_offset_at_rom_boundary() {
 // Test BusDevice trait implementation - READ operations
 #[test]
 fn test_bus_device_read_basic() {
-    let mut rom = Rom::new(RomSize::_4K, 0x2000);
+    let mut rom = Rom::new(RomSize::_4K, 0x2000).unwrap();
     
     // Import data first
     let data = vec![0xAA, 0xBB, 0xCC, 0xDD];
@@ -198,7 +228,7 @@ fn test_bus_device_read_basic() {
 
 #[test]
 fn test_bus_device_read_different_addresses() {
-    let mut rom = Rom::new(RomSize::_8K, 0x4000);
+    let mut rom = Rom::new(RomSize::_8K, 0x4000).unwrap();
     
     let test_data = [
         (0, 0x11), // Offset 0
@@ -222,7 +252,7 @@ fn test_bus_device_read_different_addresses() {
 
 #[test]
 fn test_bus_device_read_before_start_address() {
-    let rom = Rom::new(RomSize::_4K, 0x8000);
+    let mut rom = Rom::new(RomSize::_4K, 0x8000).unwrap();
     
     let result = rom.read(0x7FFF); // One address before start
     assert!(result.is_err());
@@ -231,7 +261,7 @@ fn test_bus_device_read_before_start_address() {
 
 #[test]
 fn test_bus_device_read_after_end_address() {
-    let rom = Rom::new(RomSize::_4K, 0x8000);
+    let mut rom = Rom::new(RomSize::_4K, 0x8000).unwrap();
     
     let result = rom.read(0x9000); // Beyond end address (0x8000 + 0x1000)
     assert!(result.is_err());
@@ -241,7 +271,7 @@ fn test_bus_device_read_after_end_address() {
 // Test BusDevice trait implementation - WRITE operations (should all fail)
 #[test]
 fn test_bus_device_write_fails_read_only() {
-    let mut rom = Rom::new(RomSize::_4K, 0x8000);
+    let mut rom = Rom::new(RomSize::_4K, 0x8000).unwrap();
     
     // All write attempts should fail with ReadOnly error
     let addresses = [0x8000, 0x8001, 0x8FFF]; // Various valid addresses
@@ -255,7 +285,7 @@ fn test_bus_device_write_fails_read_only() {
 
 #[test]
 fn test_bus_device_write_fails_even_invalid_addresses() {
-    let mut rom = Rom::new(RomSize::_4K, 0x8000);
+    let mut rom = Rom::new(RomSize::_4K, 0x8000).unwrap();
     
     // Even invalid addresses should return ReadOnly error, not AddressOutOfRange
     // This tests that ROM checks for write permission before address validation
@@ -270,7 +300,7 @@ fn test_bus_device_write_fails_even_invalid_addresses() {
 
 #[test]
 fn test_bus_device_write_does_not_modify_data() {
-    let mut rom = Rom::new(RomSize::_4K, 0x8000);
+    let mut rom = Rom::new(RomSize::_4K, 0x8000).unwrap();
     
     // Import initial data
     let initial_data = vec![0xAA, 0xBB, 0xCC, 0xDD];
@@ -290,10 +320,25 @@ fn test_bus_device_write_does_not_modify_data() {
     assert_eq!(result.unwrap(), 0xBB);
 }
 
+#[test]
+fn test_bus_device_load_bypasses_read_only() {
+    let mut rom = Rom::new(RomSize::_4K, 0x8000).unwrap();
+
+    rom.load(0x8000, 0x42).unwrap();
+    rom.load(0x8FFF, 0x24).unwrap();
+
+    assert_eq!(rom.read(0x8000).unwrap(), 0x42);
+    assert_eq!(rom.read(0x8FFF).unwrap(), 0x24);
+    assert!(matches!(
+        rom.load(0x9000, 0x00),
+        Err(BusError::AddressOutOfRange(0x9000))
+    ));
+}
+
 // Test BusDevice trait methods that don't do anything for ROM
 #[test]
 fn test_bus_device_tick() {
-    let mut rom = Rom::new(RomSize::_4K, 0x0000);
+    let mut rom = Rom::new(RomSize::_4K, 0x0000).unwrap();
     
     // Import some data
     let data = vec![0xFF, 0xEE, 0xDD];
@@ -310,7 +355,7 @@ fn test_bus_device_tick() {
 
 #[test]
 fn test_bus_device_interrupts() {
-    let rom = Rom::new(RomSize::_4K, 0x0000);
+    let rom = Rom::new(RomSize::_4K, 0x0000).unwrap();
     
     // ROM should never generate interrupts
     assert_eq!(rom.check_irq(), false);
@@ -359,7 +404,7 @@ fn test_rom_size_traits() {
 // Integration tests combining multiple features
 #[test]
 fn test_rom_integration_import_export_bus() {
-    let mut rom = Rom::new(RomSize::_4K, 0x2000);
+    let mut rom = Rom::new(RomSize::_4K, 0x2000).unwrap();
     
     // Import data using import method
     let original_data = vec![0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE, 0xF0];
@@ -392,7 +437,7 @@ fn test_rom_integration_import_export_bus() {
 
 #[test]
 fn test_rom_persistence_across_operations() {
-    let mut rom = Rom::new(RomSize::_2K, 0x0000);
+    let mut rom = Rom::new(RomSize::_2K, 0x0000).unwrap();
     
     // Fill ROM with pattern
     let mut pattern_data = Vec::new();
@@ -424,7 +469,7 @@ fn test_rom_persistence_across_operations() {
 
 #[test]
 fn test_rom_boundary_conditions() {
-    let mut rom = Rom::new(RomSize::_4K, 0x8000);
+    let mut rom = Rom::new(RomSize::_4K, 0x8000).unwrap();
     
     // Import data at boundaries
     rom.import(&[0xAA], 0).unwrap(); // First byte
@@ -454,7 +499,7 @@ fn test_rom_boundary_conditions() {
 // Performance-related tests
 #[test]
 fn test_rom_large_operations() {
-    let mut rom = Rom::new(RomSize::_64K, 0x0000);
+    let mut rom = Rom::new(RomSize::_64K, 0x0000).unwrap();
     
     // Import large amount of data
     let large_data = vec![0xFF; 32768]; // 32K of data
@@ -474,7 +519,7 @@ fn test_rom_large_operations() {
 
 #[test]
 fn test_rom_typical_bootloader_scenario() {
-    let mut rom = Rom::new(RomSize::_32K, 0x8000);
+    let mut rom = Rom::new(RomSize::_32K, 0x8000).unwrap();
     
     // Simulate a typical 6502 bootloader ROM setup
     // Reset vector at 0xFFFC-0xFFFF (last 4 bytes of ROM)
@@ -503,7 +548,7 @@ fn test_rom_typical_bootloader_scenario() {
 
 #[test]
 fn test_debug_trait() {
-    let rom = Rom::new(RomSize::_4K, 0x8000);
+    let rom = Rom::new(RomSize::_4K, 0x8000).unwrap();
     let debug_string = format!("{:?}", rom);
     
     // Should contain key information
@@ -515,7 +560,7 @@ fn test_debug_trait() {
 // Test edge cases and error conditions
 #[test]
 fn test_rom_zero_offset_import_export() {
-    let mut rom = Rom::new(RomSize::_4K, 0x0000);
+    let mut rom = Rom::new(RomSize::_4K, 0x0000).unwrap();
     let data = vec![0x01, 0x02, 0x03, 0x04];
     
     // Import at offset 0
@@ -533,7 +578,7 @@ fn test_rom_zero_offset_import_export() {
 
 #[test]
 fn test_rom_address_wrapping_behavior() {
-    let mut rom = Rom::new(RomSize::_4K, 0xF000);
+    let mut rom = Rom::new(RomSize::_4K, 0xF000).unwrap();
     
     // Import data near the high end of address space
     let data = vec![0xAA, 0xBB];
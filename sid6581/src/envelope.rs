@@ -0,0 +1,139 @@
+//! ADSR envelope generator of a SID voice.
+
+/// Cycles between envelope steps for each attack, decay and release rate
+const RATE_PERIODS: [u16; 16] = [
+    9, 32, 63, 95, 149, 220, 267, 313, 392, 977, 1954, 3126, 3907, 11720, 19532, 31251,
+];
+
+/// Phase of the envelope
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Attack,
+    DecaySustain,
+    Release,
+}
+
+/// 8-bit envelope counter clocked by a 15-bit rate counter
+///
+/// The rate counter is only compared for equality with the period of the current
+/// rate. Lowering the period below the count reached makes the counter run through
+/// all 32768 values before the next step: the ADSR delay bug.
+#[derive(Debug, Clone)]
+pub(crate) struct Envelope {
+    counter: u8,
+    rate_counter: u16,
+    rate_period: u16,
+    /// Steps skipped in decay and release, approximating an exponential curve
+    exponential_counter: u8,
+    exponential_period: u8,
+    state: State,
+    /// The counter reached zero and stays there until the next attack
+    hold_zero: bool,
+    gate: bool,
+    attack_decay: u8,
+    sustain_release: u8,
+}
+
+impl Envelope {
+    pub(crate) fn new() -> Self {
+        Self {
+            counter: 0,
+            rate_counter: 0,
+            rate_period: RATE_PERIODS[0],
+            exponential_counter: 0,
+            exponential_period: 1,
+            state: State::Release,
+            hold_zero: true,
+            gate: false,
+            attack_decay: 0,
+            sustain_release: 0,
+        }
+    }
+
+    /// Current 8-bit envelope level
+    pub(crate) fn level(&self) -> u8 {
+        self.counter
+    }
+
+    /// Follow the gate bit, starting the attack or the release on its edges
+    pub(crate) fn set_gate(&mut self, gate: bool) {
+        if gate && !self.gate {
+            self.state = State::Attack;
+            self.hold_zero = false;
+        } else if !gate && self.gate {
+            self.state = State::Release;
+        }
+        self.gate = gate;
+        self.update_rate_period();
+    }
+
+    pub(crate) fn set_attack_decay(&mut self, value: u8) {
+        self.attack_decay = value;
+        self.update_rate_period();
+    }
+
+    pub(crate) fn set_sustain_release(&mut self, value: u8) {
+        self.sustain_release = value;
+        self.update_rate_period();
+    }
+
+    fn update_rate_period(&mut self) {
+        let rate = match self.state {
+            State::Attack => self.attack_decay >> 4,
+            State::DecaySustain => self.attack_decay & 0x0F,
+            State::Release => self.sustain_release & 0x0F,
+        };
+        self.rate_period = RATE_PERIODS[rate as usize];
+    }
+
+    /// Advance the envelope by one cycle
+    pub(crate) fn clock(&mut self) {
+        self.rate_counter = (self.rate_counter + 1) & 0x7FFF;
+        if self.rate_counter != self.rate_period {
+            return;
+        }
+        self.rate_counter = 0;
+
+        // Attack is linear, decay and release only step every exponential period
+        if self.state != State::Attack {
+            self.exponential_counter += 1;
+            if self.exponential_counter < self.exponential_period {
+                return;
+            }
+        }
+        self.exponential_counter = 0;
+        if self.hold_zero {
+            return;
+        }
+
+        match self.state {
+            State::Attack => {
+                self.counter = self.counter.wrapping_add(1);
+                if self.counter == 0xFF {
+                    self.state = State::DecaySustain;
+                    self.update_rate_period();
+                }
+            }
+            State::DecaySustain => {
+                if self.counter != (self.sustain_release >> 4) * 0x11 {
+                    self.counter = self.counter.wrapping_sub(1);
+                }
+            }
+            State::Release => self.counter = self.counter.wrapping_sub(1),
+        }
+
+        self.exponential_period = match self.counter {
+            0xFF => 1,
+            0x5D => 2,
+            0x36 => 4,
+            0x1A => 8,
+            0x0E => 16,
+            0x06 => 30,
+            0x00 => {
+                self.hold_zero = true;
+                1
+            }
+            _ => self.exponential_period,
+        };
+    }
+}
@@ -0,0 +1,140 @@
+//! Multimode filter and output mixer of the SID.
+
+use std::f64::consts::PI;
+
+use crate::SidModel;
+
+/// Mode register: low-pass output
+const MODE_LOW_PASS: u8 = 0x10;
+/// Mode register: band-pass output
+const MODE_BAND_PASS: u8 = 0x20;
+/// Mode register: high-pass output
+const MODE_HIGH_PASS: u8 = 0x40;
+/// Mode register: voice 3 disconnected from the output unless filtered
+const MODE_VOICE_3_OFF: u8 = 0x80;
+
+/// DC level of the 6581 mixer, in voice output units, which makes volume writes audible
+const MIXER_DC_6581: f64 = -(0xFFF as f64 * 0xFF as f64 / 18.0);
+
+/// Two-integrator state variable filter with its voice routing and master volume
+#[derive(Debug, Clone)]
+pub(crate) struct Filter {
+    model: SidModel,
+    /// CPU clock in Hz, the filter is integrated once per cycle
+    clock: f64,
+    /// 11-bit cutoff frequency register
+    cutoff: u16,
+    /// Resonance in bits 4-7, voices routed through the filter in bits 0-3
+    resonance_routing: u8,
+    /// Filter mode in bits 4-7, master volume in bits 0-3
+    mode_volume: u8,
+    /// Integrator gain for the cutoff frequency
+    w0: f64,
+    /// Inverse of the resonance quality factor
+    q_inverse: f64,
+    low_pass: f64,
+    band_pass: f64,
+    high_pass: f64,
+}
+
+impl Filter {
+    pub(crate) fn new(model: SidModel, clock: u32) -> Self {
+        let mut filter = Self {
+            model,
+            clock: clock as f64,
+            cutoff: 0,
+            resonance_routing: 0,
+            mode_volume: 0,
+            w0: 0.0,
+            q_inverse: 0.0,
+            low_pass: 0.0,
+            band_pass: 0.0,
+            high_pass: 0.0,
+        };
+        filter.update_coefficients();
+        filter
+    }
+
+    pub(crate) fn set_clock(&mut self, clock: u32) {
+        self.clock = clock as f64;
+        self.update_coefficients();
+    }
+
+    pub(crate) fn set_cutoff_low(&mut self, value: u8) {
+        self.cutoff = (self.cutoff & 0x7F8) | (value as u16 & 0x07);
+        self.update_coefficients();
+    }
+
+    pub(crate) fn set_cutoff_high(&mut self, value: u8) {
+        self.cutoff = (self.cutoff & 0x007) | ((value as u16) << 3);
+        self.update_coefficients();
+    }
+
+    pub(crate) fn set_resonance_routing(&mut self, value: u8) {
+        self.resonance_routing = value;
+        self.update_coefficients();
+    }
+
+    pub(crate) fn set_mode_volume(&mut self, value: u8) {
+        self.mode_volume = value;
+    }
+
+    /// Cutoff frequency in Hz for the cutoff register
+    ///
+    /// The 8580 curve is close to linear. The 6581 curve varies a lot between chips,
+    /// the typical shape has a floor of about 220 Hz and rises steeply at the top.
+    fn cutoff_frequency(&self) -> f64 {
+        let position = self.cutoff as f64 / 2047.0;
+        match self.model {
+            SidModel::Mos6581 => 220.0 + 17_780.0 * position * position,
+            SidModel::Mos8580 => 30.0 + 12_000.0 * position,
+        }
+    }
+
+    fn update_coefficients(&mut self) {
+        let frequency = self.cutoff_frequency().min(self.clock / 8.0);
+        self.w0 = 2.0 * PI * frequency / self.clock;
+        let resonance = (self.resonance_routing >> 4) as f64 / 15.0;
+        self.q_inverse = 1.0 / (0.707 + resonance);
+    }
+
+    /// Filter and mix one cycle of voice outputs
+    ///
+    /// # Arguments
+    /// * `voices` - Signed outputs of the three voices
+    ///
+    /// # Returns
+    /// * The mixed output, scaled by the master volume
+    pub(crate) fn clock(&mut self, voices: [f64; 3]) -> f64 {
+        let mut filtered_input = 0.0;
+        let mut unfiltered = 0.0;
+        for (index, voice) in voices.iter().enumerate() {
+            if self.resonance_routing & (1 << index) != 0 {
+                filtered_input += voice;
+            } else if index != 2 || self.mode_volume & MODE_VOICE_3_OFF == 0 {
+                unfiltered += voice;
+            }
+        }
+
+        self.low_pass -= self.w0 * self.band_pass;
+        self.band_pass -= self.w0 * self.high_pass;
+        self.high_pass = self.band_pass * self.q_inverse - self.low_pass - filtered_input;
+
+        let mut filtered = 0.0;
+        if self.mode_volume & MODE_LOW_PASS != 0 {
+            filtered += self.low_pass;
+        }
+        if self.mode_volume & MODE_BAND_PASS != 0 {
+            filtered += self.band_pass;
+        }
+        if self.mode_volume & MODE_HIGH_PASS != 0 {
+            filtered += self.high_pass;
+        }
+
+        let dc = match self.model {
+            SidModel::Mos6581 => MIXER_DC_6581,
+            SidModel::Mos8580 => 0.0,
+        };
+        (unfiltered + filtered + dc) * (self.mode_volume & 0x0F) as f64 / 15.0
+    }
+}
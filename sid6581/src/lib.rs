@@ -0,0 +1,351 @@
+//! MOS 6581/8580 Sound Interface Device (SID).
+//!
+//! Register map, relative to the start address of the device and mirrored every
+//! 32 bytes:
+//! - `$00`-`$06`: voice 1 frequency low/high, pulse width low/high, control, attack/decay,
+//!   sustain/release
+//! - `$07`-`$0D`: voice 2, same layout as voice 1
+//! - `$0E`-`$14`: voice 3, same layout as voice 1
+//! - `$15`/`$16`: filter cutoff frequency, bits 0-2 and bits 3-10
+//! - `$17`: filter resonance (bits 4-7) and voices routed through the filter (bits 0-3)
+//! - `$18`: filter mode, voice 3 off (bits 4-7) and master volume (bits 0-3)
+//! - `$19`/`$1A`: POTX/POTY, paddle positions (read only)
+//! - `$1B`: OSC3, upper 8 bits of the voice 3 waveform (read only)
+//! - `$1C`: ENV3, voice 3 envelope level (read only)
+//!
+//! The other registers are write only and read back the last value written to the
+//! chip. The SID is clocked by Φ2, one `tick` per CPU cycle: every tick advances the
+//! oscillators, envelopes and filter by one cycle, and the output is averaged into
+//! 16-bit samples at the sample rate, see `Sid::with_sample_rate`. Samples are taken
+//! with `Sid::take_samples` and can be saved with the `wav` module.
+
+mod envelope;
+mod filter;
+mod oscillator;
+/// WAV files of rendered audio
+pub mod wav;
+
+use bus::errors::BusError;
+use bus::trait_bus_device::BusDevice;
+
+use crate::envelope::Envelope;
+use crate::filter::Filter;
+use crate::oscillator::{CONTROL_GATE, Oscillator};
+
+/// Number of registers exposed on the bus
+pub const SID_REGISTER_COUNT: u16 = 32;
+/// CPU clock of a PAL C64, the default clock of the SID, in Hz
+pub const DEFAULT_CPU_CLOCK: u32 = 985_248;
+/// Sample rate of the rendered audio unless configured otherwise, in Hz
+pub const DEFAULT_SAMPLE_RATE: u32 = 44_100;
+
+/// Registers per voice
+const VOICE_REGISTER_COUNT: u16 = 7;
+const VOICE_FREQUENCY_LOW: u16 = 0x0;
+const VOICE_FREQUENCY_HIGH: u16 = 0x1;
+const VOICE_PULSE_WIDTH_LOW: u16 = 0x2;
+const VOICE_PULSE_WIDTH_HIGH: u16 = 0x3;
+const VOICE_CONTROL: u16 = 0x4;
+const VOICE_ATTACK_DECAY: u16 = 0x5;
+
+const REGISTER_CUTOFF_LOW: u16 = 0x15;
+const REGISTER_CUTOFF_HIGH: u16 = 0x16;
+const REGISTER_RESONANCE_ROUTING: u16 = 0x17;
+const REGISTER_MODE_VOLUME: u16 = 0x18;
+const REGISTER_POT_X: u16 = 0x19;
+const REGISTER_POT_Y: u16 = 0x1A;
+const REGISTER_OSC3: u16 = 0x1B;
+const REGISTER_ENV3: u16 = 0x1C;
+
+/// Largest mixer output, three voices at full amplitude and envelope
+const FULL_SCALE: f64 = 3.0 * 2048.0 * 255.0;
+
+/// Chip revision, which changes the filter and the mixer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SidModel {
+    /// Original NMOS SID, with a non-linear filter and a DC offset in the mixer
+    Mos6581,
+    /// HMOS-II SID of later C64s, with a near-linear filter and no DC offset
+    Mos8580,
+}
+
+/// Oscillator and envelope of one of the three voices
+#[derive(Debug, Clone)]
+struct Voice {
+    oscillator: Oscillator,
+    envelope: Envelope,
+}
+
+impl Voice {
+    fn new() -> Self {
+        Self {
+            oscillator: Oscillator::new(),
+            envelope: Envelope::new(),
+        }
+    }
+}
+
+/// Represents a MOS 6581 or 8580 SID.
+#[derive(Debug)]
+pub struct Sid {
+    /// Start address of the register window
+    start_address: u16,
+    model: SidModel,
+    voices: [Voice; 3],
+    filter: Filter,
+    pot_x: u8,
+    pot_y: u8,
+    /// Last value written, returned by reads of write-only registers
+    bus_value: u8,
+    /// Frequency of Φ2 in Hz
+    cpu_clock: u32,
+    sample_rate: u32,
+    /// Fraction of the next sample, in units of 1 / cpu_clock samples
+    sample_phase: u64,
+    /// Sum of the mixer outputs since the last sample
+    sample_sum: f64,
+    /// Cycles summed into `sample_sum`
+    sample_cycles: u32,
+    samples: Vec<i16>,
+}
+
+impl Sid {
+    /// Create a new SID with its registers at the specified start address.
+    ///
+    /// # Arguments
+    /// * `start_address` - Start address of the register window
+    /// * `model` - Chip revision
+    ///
+    /// # Returns
+    /// * A new Sid instance, silent, rendering at the default clock and sample rate
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let sid = Sid::new(0xD400, SidModel::Mos6581);
+    /// bus.register_device(0xD400, 0xD7FF, Box::new(sid))?;
+    /// ```
+    pub fn new(start_address: u16, model: SidModel) -> Self {
+        Self {
+            start_address,
+            model,
+            voices: [Voice::new(), Voice::new(), Voice::new()],
+            filter: Filter::new(model, DEFAULT_CPU_CLOCK),
+            pot_x: 0xFF,
+            pot_y: 0xFF,
+            bus_value: 0,
+            cpu_clock: DEFAULT_CPU_CLOCK,
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            sample_phase: 0,
+            sample_sum: 0.0,
+            sample_cycles: 0,
+            samples: Vec::new(),
+        }
+    }
+
+    /// Set the CPU clock and the sample rate of the rendered audio.
+    ///
+    /// # Arguments
+    /// * `cpu_clock` - Frequency of Φ2 in Hz
+    /// * `sample_rate` - Samples per second of the rendered audio
+    ///
+    /// # Returns
+    /// * The Sid with the new clock and sample rate
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// // NTSC C64
+    /// let sid = Sid::new(0xD400, SidModel::Mos8580).with_sample_rate(1_022_727, 48_000);
+    /// ```
+    pub fn with_sample_rate(mut self, cpu_clock: u32, sample_rate: u32) -> Self {
+        self.cpu_clock = cpu_clock.max(1);
+        self.sample_rate = sample_rate.clamp(1, self.cpu_clock);
+        self.filter.set_clock(self.cpu_clock);
+        self
+    }
+
+    /// Pull the RES pin low.
+    ///
+    /// Clears all registers, silences the envelopes and discards unread samples.
+    pub fn reset(&mut self) {
+        self.voices = [Voice::new(), Voice::new(), Voice::new()];
+        self.filter = Filter::new(self.model, self.cpu_clock);
+        self.bus_value = 0;
+        self.sample_phase = 0;
+        self.sample_sum = 0.0;
+        self.sample_cycles = 0;
+        self.samples.clear();
+    }
+
+    /// Chip revision
+    pub fn model(&self) -> SidModel {
+        self.model
+    }
+
+    /// Samples per second of the rendered audio
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Samples rendered and not taken yet
+    pub fn samples(&self) -> &[i16] {
+        &self.samples
+    }
+
+    /// Take the samples rendered so far
+    ///
+    /// # Returns
+    /// * Signed 16-bit mono samples at the sample rate
+    pub fn take_samples(&mut self) -> Vec<i16> {
+        std::mem::take(&mut self.samples)
+    }
+
+    /// Set the position of the paddle read through POTX
+    pub fn set_pot_x(&mut self, value: u8) {
+        self.pot_x = value;
+    }
+
+    /// Set the position of the paddle read through POTY
+    pub fn set_pot_y(&mut self, value: u8) {
+        self.pot_y = value;
+    }
+
+    /// Envelope level of a voice
+    ///
+    /// # Arguments
+    /// * `voice` - Voice number, 0-2
+    ///
+    /// # Returns
+    /// * `Some(level)` with the 8-bit envelope level
+    /// * `None` if there is no such voice
+    pub fn envelope_level(&self, voice: usize) -> Option<u8> {
+        Some(self.voices.get(voice)?.envelope.level())
+    }
+
+    /// 12-bit waveform output of a voice, with ring modulation applied
+    fn waveform(&self, voice: usize) -> u16 {
+        let source = &self.voices[(voice + 2) % 3].oscillator;
+        self.voices[voice].oscillator.output(source.msb())
+    }
+
+    /// Write a voice register
+    fn write_voice(&mut self, voice: usize, register: u16, data: u8) {
+        let Voice {
+            oscillator,
+            envelope,
+        } = &mut self.voices[voice];
+        match register {
+            VOICE_FREQUENCY_LOW => {
+                oscillator.frequency = (oscillator.frequency & 0xFF00) | data as u16;
+            }
+            VOICE_FREQUENCY_HIGH => {
+                oscillator.frequency = (oscillator.frequency & 0x00FF) | (data as u16) << 8;
+            }
+            VOICE_PULSE_WIDTH_LOW => {
+                oscillator.pulse_width = (oscillator.pulse_width & 0xF00) | data as u16;
+            }
+            VOICE_PULSE_WIDTH_HIGH => {
+                oscillator.pulse_width =
+                    (oscillator.pulse_width & 0x0FF) | (data as u16 & 0x0F) << 8;
+            }
+            VOICE_CONTROL => {
+                oscillator.set_control(data);
+                envelope.set_gate(data & CONTROL_GATE != 0);
+            }
+            VOICE_ATTACK_DECAY => envelope.set_attack_decay(data),
+            // $6: Sustain/release
+            _ => envelope.set_sustain_release(data),
+        }
+    }
+
+    /// Average the mixer output into a sample once a sample period has passed
+    fn render(&mut self, output: f64) {
+        self.sample_sum += output;
+        self.sample_cycles += 1;
+        self.sample_phase += self.sample_rate as u64;
+        if self.sample_phase < self.cpu_clock as u64 {
+            return;
+        }
+        self.sample_phase -= self.cpu_clock as u64;
+        let average = self.sample_sum / self.sample_cycles as f64;
+        let sample = (average / FULL_SCALE * 32767.0).round();
+        self.samples.push(sample.clamp(-32768.0, 32767.0) as i16);
+        self.sample_sum = 0.0;
+        self.sample_cycles = 0;
+    }
+}
+
+impl BusDevice for Sid {
+    fn read(&mut self, address: u16) -> Result<u8, BusError> {
+        let value = match address.wrapping_sub(self.start_address) & 0x1F {
+            REGISTER_POT_X => self.pot_x,
+            REGISTER_POT_Y => self.pot_y,
+            REGISTER_OSC3 => (self.waveform(2) >> 4) as u8,
+            REGISTER_ENV3 => self.voices[2].envelope.level(),
+            // Write-only registers: the value left on the data bus by the last write
+            _ => self.bus_value,
+        };
+        Ok(value)
+    }
+
+    fn write(&mut self, address: u16, data: u8) -> Result<(), BusError> {
+        self.bus_value = data;
+        let register = address.wrapping_sub(self.start_address) & 0x1F;
+        match register {
+            0x00..=0x14 => self.write_voice(
+                (register / VOICE_REGISTER_COUNT) as usize,
+                register % VOICE_REGISTER_COUNT,
+                data,
+            ),
+            REGISTER_CUTOFF_LOW => self.filter.set_cutoff_low(data),
+            REGISTER_CUTOFF_HIGH => self.filter.set_cutoff_high(data),
+            REGISTER_RESONANCE_ROUTING => self.filter.set_resonance_routing(data),
+            REGISTER_MODE_VOLUME => self.filter.set_mode_volume(data),
+            // $19-$1F: Read-only and unused registers
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn tick(&mut self) {
+        for voice in &mut self.voices {
+            voice.oscillator.clock();
+        }
+        // Each voice is synchronized by the previous one, voice 1 by voice 3
+        let msb_rising = self
+            .voices
+            .each_ref()
+            .map(|voice| voice.oscillator.msb_rising());
+        for (index, voice) in self.voices.iter_mut().enumerate() {
+            voice.oscillator.synchronize(msb_rising[(index + 2) % 3]);
+            voice.envelope.clock();
+        }
+
+        let mut outputs = [0.0; 3];
+        for (index, output) in outputs.iter_mut().enumerate() {
+            let level = self.voices[index].envelope.level();
+            *output = (self.waveform(index) as f64 - 2048.0) * level as f64;
+        }
+        let mixed = self.filter.clock(outputs);
+        self.render(mixed);
+    }
+
+    fn check_irq(&self) -> bool {
+        // The SID has no interrupt output
+        false
+    }
+
+    fn check_nmi(&self) -> bool {
+        // The SID has no interrupt output
+        false
+    }
+
+    fn check_rdy(&self) -> bool {
+        // The SID does not insert wait states
+        false
+    }
+
+    fn check_so(&self) -> bool {
+        // The SID does not drive the SO line
+        false
+    }
+}
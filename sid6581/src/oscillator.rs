@@ -0,0 +1,146 @@
+//! Waveform generator of a SID voice.
+
+/// Control register: gate bit, starts the attack of the envelope
+pub(crate) const CONTROL_GATE: u8 = 0x01;
+/// Control register: hard sync to the previous voice
+const CONTROL_SYNC: u8 = 0x02;
+/// Control register: ring modulation of the triangle by the previous voice
+const CONTROL_RING: u8 = 0x04;
+/// Control register: test bit, holds the oscillator at zero
+const CONTROL_TEST: u8 = 0x08;
+const CONTROL_TRIANGLE: u8 = 0x10;
+const CONTROL_SAWTOOTH: u8 = 0x20;
+const CONTROL_PULSE: u8 = 0x40;
+const CONTROL_NOISE: u8 = 0x80;
+
+/// Most significant bit of the 24-bit phase accumulator
+const ACCUMULATOR_MSB: u32 = 0x80_0000;
+/// Accumulator bit clocking the noise shift register
+const NOISE_CLOCK_BIT: u32 = 0x08_0000;
+/// Noise shift register after a reset
+const NOISE_SEED: u32 = 0x7F_FFF8;
+
+/// 24-bit phase accumulator with its waveform selectors and noise shift register
+#[derive(Debug, Clone)]
+pub(crate) struct Oscillator {
+    accumulator: u32,
+    /// 23-bit noise linear feedback shift register
+    shift_register: u32,
+    pub(crate) frequency: u16,
+    /// 12-bit pulse width
+    pub(crate) pulse_width: u16,
+    pub(crate) control: u8,
+    /// The accumulator MSB went from 0 to 1 on the last clock, for hard sync
+    msb_rising: bool,
+}
+
+impl Oscillator {
+    pub(crate) fn new() -> Self {
+        Self {
+            accumulator: 0,
+            shift_register: NOISE_SEED,
+            frequency: 0,
+            pulse_width: 0,
+            control: 0,
+            msb_rising: false,
+        }
+    }
+
+    /// Write the control register, the test bit resets the accumulator and noise
+    pub(crate) fn set_control(&mut self, control: u8) {
+        if control & CONTROL_TEST != 0 {
+            self.accumulator = 0;
+            self.shift_register = NOISE_SEED;
+        }
+        self.control = control;
+    }
+
+    /// Advance the accumulator by one cycle
+    pub(crate) fn clock(&mut self) {
+        if self.control & CONTROL_TEST != 0 {
+            self.msb_rising = false;
+            return;
+        }
+        let previous = self.accumulator;
+        self.accumulator = (previous + self.frequency as u32) & 0xFF_FFFF;
+        self.msb_rising =
+            previous & ACCUMULATOR_MSB == 0 && self.accumulator & ACCUMULATOR_MSB != 0;
+        if previous & NOISE_CLOCK_BIT == 0 && self.accumulator & NOISE_CLOCK_BIT != 0 {
+            let feedback = ((self.shift_register >> 22) ^ (self.shift_register >> 17)) & 1;
+            self.shift_register = ((self.shift_register << 1) & 0x7F_FFFF) | feedback;
+        }
+    }
+
+    /// The accumulator MSB went from 0 to 1 on the last clock
+    pub(crate) fn msb_rising(&self) -> bool {
+        self.msb_rising
+    }
+
+    /// Reset the accumulator if hard sync is on and the source MSB rose
+    ///
+    /// # Arguments
+    /// * `source_msb_rising` - `msb_rising` of the previous voice
+    pub(crate) fn synchronize(&mut self, source_msb_rising: bool) {
+        if self.control & CONTROL_SYNC != 0 && source_msb_rising {
+            self.accumulator = 0;
+        }
+    }
+
+    /// The accumulator MSB, the ring modulation source of the next voice
+    pub(crate) fn msb(&self) -> bool {
+        self.accumulator & ACCUMULATOR_MSB != 0
+    }
+
+    /// 12-bit waveform output
+    ///
+    /// Combined waveforms are approximated by ANDing the selected waveforms, which
+    /// matches the dominant effect of the shared output lines on both chip models.
+    ///
+    /// # Arguments
+    /// * `ring_source_msb` - Accumulator MSB of the previous voice
+    pub(crate) fn output(&self, ring_source_msb: bool) -> u16 {
+        if self.control & 0xF0 == 0 {
+            return 0;
+        }
+        let mut output = 0xFFF;
+        if self.control & CONTROL_TRIANGLE != 0 {
+            let mut msb = self.msb();
+            if self.control & CONTROL_RING != 0 {
+                msb ^= ring_source_msb;
+            }
+            let folded = if msb {
+                !self.accumulator
+            } else {
+                self.accumulator
+            };
+            output &= (folded >> 11) as u16 & 0xFFF;
+        }
+        if self.control & CONTROL_SAWTOOTH != 0 {
+            output &= (self.accumulator >> 12) as u16;
+        }
+        if self.control & CONTROL_PULSE != 0 {
+            let high = self.control & CONTROL_TEST != 0
+                || (self.accumulator >> 12) as u16 >= self.pulse_width;
+            if !high {
+                output = 0;
+            }
+        }
+        if self.control & CONTROL_NOISE != 0 {
+            output &= self.noise();
+        }
+        output
+    }
+
+    /// Eight bits of the shift register, on the top bits of the 12-bit output
+    fn noise(&self) -> u16 {
+        let register = self.shift_register;
+        (((register & 0x40_0000) >> 11)
+            | ((register & 0x10_0000) >> 10)
+            | ((register & 0x01_0000) >> 7)
+            | ((register & 0x00_2000) >> 5)
+            | ((register & 0x00_0800) >> 4)
+            | ((register & 0x00_0080) >> 1)
+            | ((register & 0x00_0010) << 1)
+            | ((register & 0x00_0004) << 2)) as u16
+    }
+}
@@ -0,0 +1,90 @@
+//! WAV files of rendered audio.
+//!
+//! Samples are written as 16-bit signed mono PCM, the format of the SID sample
+//! buffer.
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+/// Size of the RIFF, fmt and data chunk headers
+const HEADER_SIZE: u32 = 36;
+
+/// Write samples as a mono 16-bit PCM WAV stream.
+///
+/// # Arguments
+/// * `writer` - Destination of the stream
+/// * `samples` - Signed 16-bit samples
+/// * `sample_rate` - Sample rate in Hz
+///
+/// # Returns
+/// * `Ok(())` if the stream was written
+/// * `Err(io::Error)` if writing failed
+///
+/// # Errors
+/// * If the writer fails
+/// * `io::ErrorKind::InvalidInput` if the samples or the sample rate do not fit in a
+///   WAV file
+///
+/// # Examples
+/// ``` ignore
+/// let mut bytes = Vec::new();
+/// write_wav(&mut bytes, &sid.take_samples(), sid.sample_rate())?;
+/// ```
+pub fn write_wav<W: Write>(writer: &mut W, samples: &[i16], sample_rate: u32) -> io::Result<()> {
+    let data_size = u32::try_from(samples.len() * 2)
+        .ok()
+        .filter(|size| size.checked_add(HEADER_SIZE).is_some())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Too many samples for WAV"))?;
+    let byte_rate = sample_rate.checked_mul(2).ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "Sample rate too high for WAV")
+    })?;
+
+    writer.write_all(b"RIFF")?;
+    writer.write_all(&(HEADER_SIZE + data_size).to_le_bytes())?;
+    writer.write_all(b"WAVE")?;
+
+    writer.write_all(b"fmt ")?;
+    writer.write_all(&16u32.to_le_bytes())?;
+    // PCM, one channel
+    writer.write_all(&1u16.to_le_bytes())?;
+    writer.write_all(&1u16.to_le_bytes())?;
+    writer.write_all(&sample_rate.to_le_bytes())?;
+    // Byte rate, block alignment and bits per sample
+    writer.write_all(&byte_rate.to_le_bytes())?;
+    writer.write_all(&2u16.to_le_bytes())?;
+    writer.write_all(&16u16.to_le_bytes())?;
+
+    writer.write_all(b"data")?;
+    writer.write_all(&data_size.to_le_bytes())?;
+    for sample in samples {
+        writer.write_all(&sample.to_le_bytes())?;
+    }
+    Ok(())
+}
+
+/// Save samples to a mono 16-bit PCM WAV file.
+///
+/// # Arguments
+/// * `path` - Path of the file, replaced if it exists
+/// * `samples` - Signed 16-bit samples
+/// * `sample_rate` - Sample rate in Hz
+///
+/// # Returns
+/// * `Ok(())` if the file was written
+/// * `Err(io::Error)` if the file could not be created or written
+///
+/// # Errors
+/// * If the file cannot be created or written
+/// * `io::ErrorKind::InvalidInput` if the samples or the sample rate do not fit in a
+///   WAV file
+///
+/// # Examples
+/// ``` ignore
+/// save_wav(Path::new("tune.wav"), &sid.take_samples(), sid.sample_rate())?;
+/// ```
+pub fn save_wav(path: &Path, samples: &[i16], sample_rate: u32) -> io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_wav(&mut writer, samples, sample_rate)?;
+    writer.flush()
+}
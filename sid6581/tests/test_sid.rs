@@ -0,0 +1,337 @@
+//! Unit tests for the 6581/8580 SID implementation
+//!
+//! This module tests the waveforms read through OSC3, sync and ring modulation, the
+//! ADSR envelopes and their delay bug, the filter and mixer of both models, and the
+//! rendered samples and WAV output.
+
+use bus::trait_bus_device::BusDevice;
+use sid6581::wav::{save_wav, write_wav};
+use sid6581::{Sid, SidModel};
+
+const BASE: u16 = 0xD400;
+/// Voice register offsets
+const FREQUENCY_LOW: u16 = 0x00;
+const FREQUENCY_HIGH: u16 = 0x01;
+const PULSE_WIDTH_LOW: u16 = 0x02;
+const PULSE_WIDTH_HIGH: u16 = 0x03;
+const CONTROL: u16 = 0x04;
+const ATTACK_DECAY: u16 = 0x05;
+const SUSTAIN_RELEASE: u16 = 0x06;
+/// Start of the voice 2 and voice 3 registers
+const VOICE_2: u16 = 0x07;
+const VOICE_3: u16 = 0x0E;
+const CUTOFF_HIGH: u16 = BASE + 0x16;
+const RESONANCE_ROUTING: u16 = BASE + 0x17;
+const MODE_VOLUME: u16 = BASE + 0x18;
+const POT_X: u16 = BASE + 0x19;
+const OSC3: u16 = BASE + 0x1B;
+const ENV3: u16 = BASE + 0x1C;
+
+fn run(sid: &mut Sid, ticks: u32) {
+    for _ in 0..ticks {
+        sid.tick();
+    }
+}
+
+/// Write a register of a voice, 0x00, VOICE_2 or VOICE_3
+fn write_voice(sid: &mut Sid, voice: u16, register: u16, data: u8) {
+    sid.write(BASE + voice + register, data).unwrap();
+}
+
+/// Set the frequency of a voice
+fn set_frequency(sid: &mut Sid, voice: u16, frequency: u16) {
+    write_voice(sid, voice, FREQUENCY_LOW, frequency as u8);
+    write_voice(sid, voice, FREQUENCY_HIGH, (frequency >> 8) as u8);
+}
+
+/// Root mean square of samples around their mean
+fn rms(samples: &[i16]) -> f64 {
+    let mean = samples.iter().map(|&s| s as f64).sum::<f64>() / samples.len() as f64;
+    let power = samples
+        .iter()
+        .map(|&s| (s as f64 - mean).powi(2))
+        .sum::<f64>();
+    (power / samples.len() as f64).sqrt()
+}
+
+/// 64-bit FNV-1a hash of samples, stable across platforms and toolchains
+fn hash(samples: &[i16]) -> u64 {
+    samples
+        .iter()
+        .flat_map(|sample| sample.to_le_bytes())
+        .fold(0xCBF2_9CE4_8422_2325, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01B3)
+        })
+}
+
+// Test registers
+#[test]
+fn test_read_only_registers() {
+    let mut sid = Sid::new(BASE, SidModel::Mos6581);
+    assert_eq!(sid.read(POT_X).unwrap(), 0xFF);
+    sid.set_pot_x(0x40);
+    assert_eq!(sid.read(POT_X).unwrap(), 0x40);
+    // Write-only registers read back the last value written
+    sid.write(MODE_VOLUME, 0x1F).unwrap();
+    assert_eq!(sid.read(BASE).unwrap(), 0x1F);
+    // Registers are mirrored every 32 bytes
+    assert_eq!(sid.read(POT_X + 0x20).unwrap(), 0x40);
+}
+
+// Test waveforms
+#[test]
+fn test_sawtooth_and_triangle() {
+    let mut sid = Sid::new(BASE, SidModel::Mos6581);
+    set_frequency(&mut sid, VOICE_3, 0x1000);
+    write_voice(&mut sid, VOICE_3, CONTROL, 0x20);
+    run(&mut sid, 0x100);
+    assert_eq!(sid.read(OSC3).unwrap(), 0x10);
+
+    // The triangle rises at twice the rate and folds at the accumulator MSB
+    write_voice(&mut sid, VOICE_3, CONTROL, 0x10);
+    assert_eq!(sid.read(OSC3).unwrap(), 0x20);
+    run(&mut sid, 0xB00);
+    assert_eq!(sid.read(OSC3).unwrap(), 0x7F);
+}
+
+#[test]
+fn test_pulse_width() {
+    let mut sid = Sid::new(BASE, SidModel::Mos6581);
+    set_frequency(&mut sid, VOICE_3, 0x1000);
+    write_voice(&mut sid, VOICE_3, PULSE_WIDTH_LOW, 0x00);
+    write_voice(&mut sid, VOICE_3, PULSE_WIDTH_HIGH, 0xF8);
+    write_voice(&mut sid, VOICE_3, CONTROL, 0x40);
+    run(&mut sid, 0x7FF);
+    assert_eq!(sid.read(OSC3).unwrap(), 0x00);
+    run(&mut sid, 1);
+    assert_eq!(sid.read(OSC3).unwrap(), 0xFF);
+}
+
+#[test]
+fn test_noise_and_test_bit() {
+    let mut sid = Sid::new(BASE, SidModel::Mos6581);
+    set_frequency(&mut sid, VOICE_3, 0xFFFF);
+    write_voice(&mut sid, VOICE_3, CONTROL, 0x88);
+    // The test bit reloads the shift register with its seed
+    assert_eq!(sid.read(OSC3).unwrap(), 0xFE);
+    write_voice(&mut sid, VOICE_3, CONTROL, 0x80);
+    let mut values = Vec::new();
+    for _ in 0..64 {
+        run(&mut sid, 16);
+        values.push(sid.read(OSC3).unwrap());
+    }
+    values.sort_unstable();
+    values.dedup();
+    assert!(values.len() > 32);
+}
+
+#[test]
+fn test_combined_waveforms() {
+    let mut sid = Sid::new(BASE, SidModel::Mos6581);
+    set_frequency(&mut sid, VOICE_3, 0x1000);
+    write_voice(&mut sid, VOICE_3, CONTROL, 0x30);
+    run(&mut sid, 0x300);
+    // Sawtooth $300 AND triangle $600
+    assert_eq!(sid.read(OSC3).unwrap(), 0x20);
+    // A pulse width of zero keeps the pulse high
+    write_voice(&mut sid, VOICE_3, CONTROL, 0x60);
+    assert_eq!(sid.read(OSC3).unwrap(), 0x30);
+}
+
+#[test]
+fn test_hard_sync() {
+    let mut sid = Sid::new(BASE, SidModel::Mos6581);
+    set_frequency(&mut sid, VOICE_2, 0x8000);
+    set_frequency(&mut sid, VOICE_3, 0x1000);
+    write_voice(&mut sid, VOICE_3, CONTROL, 0x22);
+    run(&mut sid, 255);
+    assert_eq!(sid.read(OSC3).unwrap(), 0x0F);
+    // The MSB of voice 2 rises and restarts voice 3
+    run(&mut sid, 1);
+    assert_eq!(sid.read(OSC3).unwrap(), 0x00);
+}
+
+#[test]
+fn test_ring_modulation() {
+    let mut sid = Sid::new(BASE, SidModel::Mos6581);
+    set_frequency(&mut sid, VOICE_2, 0x8000);
+    set_frequency(&mut sid, VOICE_3, 0x1000);
+    write_voice(&mut sid, VOICE_3, CONTROL, 0x14);
+    run(&mut sid, 0x100);
+    // The MSB of voice 2 inverts the triangle of voice 3
+    assert_eq!(sid.read(OSC3).unwrap(), 0xDF);
+    write_voice(&mut sid, VOICE_3, CONTROL, 0x10);
+    assert_eq!(sid.read(OSC3).unwrap(), 0x20);
+}
+
+// Test envelopes
+#[test]
+fn test_attack_decay_sustain_release() {
+    let mut sid = Sid::new(BASE, SidModel::Mos6581);
+    write_voice(&mut sid, VOICE_3, ATTACK_DECAY, 0x00);
+    write_voice(&mut sid, VOICE_3, SUSTAIN_RELEASE, 0x80);
+    write_voice(&mut sid, VOICE_3, CONTROL, 0x01);
+    // The fastest attack steps every 9 cycles
+    run(&mut sid, 255 * 9 - 1);
+    assert_eq!(sid.read(ENV3).unwrap(), 0xFE);
+    run(&mut sid, 1);
+    assert_eq!(sid.read(ENV3).unwrap(), 0xFF);
+
+    run(&mut sid, 0x77 * 9);
+    assert_eq!(sid.read(ENV3).unwrap(), 0x88);
+    run(&mut sid, 10_000);
+    assert_eq!(sid.read(ENV3).unwrap(), 0x88);
+
+    write_voice(&mut sid, VOICE_3, CONTROL, 0x00);
+    run(&mut sid, 20_000);
+    assert_eq!(sid.read(ENV3).unwrap(), 0x00);
+    assert_eq!(sid.envelope_level(2), Some(0x00));
+    assert_eq!(sid.envelope_level(3), None);
+}
+
+#[test]
+fn test_adsr_delay_bug() {
+    let mut sid = Sid::new(BASE, SidModel::Mos6581);
+    write_voice(&mut sid, VOICE_3, ATTACK_DECAY, 0xF0);
+    write_voice(&mut sid, VOICE_3, CONTROL, 0x01);
+    run(&mut sid, 1000);
+    // The rate counter is past the new period and has to wrap around first
+    write_voice(&mut sid, VOICE_3, ATTACK_DECAY, 0x00);
+    run(&mut sid, 0x8000 - 1000 + 9 - 1);
+    assert_eq!(sid.read(ENV3).unwrap(), 0x00);
+    run(&mut sid, 1);
+    assert_eq!(sid.read(ENV3).unwrap(), 0x01);
+}
+
+// Test filter and mixer
+/// Render a sustained voice 1 sawtooth at about 3.8 kHz
+fn render_sawtooth(model: SidModel, routing: u8, mode_volume: u8) -> Vec<i16> {
+    let mut sid = Sid::new(BASE, model);
+    set_frequency(&mut sid, 0x00, 0xFFFF);
+    write_voice(&mut sid, 0x00, SUSTAIN_RELEASE, 0xF0);
+    write_voice(&mut sid, 0x00, CONTROL, 0x21);
+    sid.write(CUTOFF_HIGH, 0x00).unwrap();
+    sid.write(RESONANCE_ROUTING, routing).unwrap();
+    sid.write(MODE_VOLUME, mode_volume).unwrap();
+    run(&mut sid, 20_000);
+    sid.take_samples();
+    run(&mut sid, 20_000);
+    sid.take_samples()
+}
+
+#[test]
+fn test_low_pass_filter() {
+    for model in [SidModel::Mos6581, SidModel::Mos8580] {
+        let direct = rms(&render_sawtooth(model, 0x00, 0x1F));
+        let filtered = rms(&render_sawtooth(model, 0x01, 0x1F));
+        assert!(direct > 4000.0, "{:?}: {}", model, direct);
+        assert!(filtered < direct / 4.0, "{:?}: {}", model, filtered);
+        // The high-pass output lets the sawtooth through
+        let high_pass = rms(&render_sawtooth(model, 0x01, 0x4F));
+        assert!(high_pass > direct / 2.0, "{:?}: {}", model, high_pass);
+    }
+}
+
+#[test]
+fn test_voice_3_off() {
+    let mut sid = Sid::new(BASE, SidModel::Mos8580);
+    set_frequency(&mut sid, VOICE_3, 0x4000);
+    write_voice(&mut sid, VOICE_3, SUSTAIN_RELEASE, 0xF0);
+    write_voice(&mut sid, VOICE_3, CONTROL, 0x21);
+    sid.write(MODE_VOLUME, 0x8F).unwrap();
+    run(&mut sid, 10_000);
+    assert!(rms(&sid.take_samples()) < 1.0);
+    // OSC3 and ENV3 keep running for modulation effects
+    assert_eq!(sid.read(ENV3).unwrap(), 0xFF);
+}
+
+#[test]
+fn test_volume_register_samples() {
+    // Only the DC offset of the 6581 mixer makes volume writes audible
+    for (model, audible) in [(SidModel::Mos6581, true), (SidModel::Mos8580, false)] {
+        let mut sid = Sid::new(BASE, model);
+        sid.write(MODE_VOLUME, 0x00).unwrap();
+        run(&mut sid, 1000);
+        sid.write(MODE_VOLUME, 0x0F).unwrap();
+        run(&mut sid, 1000);
+        let samples = sid.take_samples();
+        assert_eq!(samples[0] != samples[samples.len() - 1], audible);
+    }
+}
+
+// Test rendering
+#[test]
+fn test_sample_rate() {
+    let mut sid = Sid::new(BASE, SidModel::Mos6581).with_sample_rate(1_000_000, 50_000);
+    assert_eq!(sid.sample_rate(), 50_000);
+    run(&mut sid, 1000);
+    assert_eq!(sid.samples().len(), 50);
+    assert_eq!(sid.take_samples().len(), 50);
+    assert!(sid.samples().is_empty());
+}
+
+#[test]
+fn test_render_hash() {
+    let render = || {
+        let mut sid = Sid::new(BASE, SidModel::Mos6581);
+        // A pulse arpeggio through the band-pass filter
+        write_voice(&mut sid, 0x00, PULSE_WIDTH_HIGH, 0x08);
+        write_voice(&mut sid, 0x00, ATTACK_DECAY, 0x09);
+        write_voice(&mut sid, 0x00, SUSTAIN_RELEASE, 0xA4);
+        sid.write(CUTOFF_HIGH, 0x40).unwrap();
+        sid.write(RESONANCE_ROUTING, 0xF1).unwrap();
+        sid.write(MODE_VOLUME, 0x2F).unwrap();
+        for frequency in [0x1CD6u16, 0x2454, 0x2B0F] {
+            set_frequency(&mut sid, 0x00, frequency);
+            write_voice(&mut sid, 0x00, CONTROL, 0x41);
+            run(&mut sid, 15_000);
+            write_voice(&mut sid, 0x00, CONTROL, 0x40);
+            run(&mut sid, 5_000);
+        }
+        sid.take_samples()
+    };
+    let samples = render();
+    assert_eq!(samples, render());
+    assert!(rms(&samples) > 100.0);
+    // Reference render, any change to the synthesis has to update it deliberately
+    assert_eq!(hash(&samples), 18124748085456101581);
+}
+
+#[test]
+fn test_reset() {
+    let mut sid = Sid::new(BASE, SidModel::Mos6581);
+    write_voice(&mut sid, VOICE_3, CONTROL, 0x21);
+    run(&mut sid, 1000);
+    sid.reset();
+    assert_eq!(sid.read(ENV3).unwrap(), 0x00);
+    assert_eq!(sid.read(OSC3).unwrap(), 0x00);
+    assert!(sid.samples().is_empty());
+}
+
+#[test]
+fn test_wav_output() {
+    let samples = [0i16, 1000, -1000, i16::MAX];
+    let mut bytes = Vec::new();
+    write_wav(&mut bytes, &samples, 44_100).unwrap();
+    assert_eq!(bytes.len(), 44 + 8);
+    assert_eq!(&bytes[0..4], b"RIFF");
+    assert_eq!(bytes[4..8], 44u32.to_le_bytes());
+    assert_eq!(&bytes[8..16], b"WAVEfmt ");
+    assert_eq!(bytes[24..28], 44_100u32.to_le_bytes());
+    assert_eq!(&bytes[36..40], b"data");
+    assert_eq!(bytes[40..44], 8u32.to_le_bytes());
+    assert_eq!(bytes[46..48], 1000i16.to_le_bytes());
+
+    let path = std::env::temp_dir().join(format!("sid6581-{}.wav", std::process::id()));
+    save_wav(&path, &samples, 44_100).unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), bytes);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_wav_sample_rate_too_high() {
+    let mut bytes = Vec::new();
+    let error = write_wav(&mut bytes, &[0, 1], u32::MAX / 2 + 1).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(bytes.is_empty());
+}
@@ -0,0 +1,834 @@
+//! MOS 6522 Versatile Interface Adapter (VIA).
+//!
+//! Register map, selected by address lines RS0-RS3 and mirrored every 16 bytes:
+//! - `$0`: ORB/IRB, port B with CB1/CB2 handshake
+//! - `$1`: ORA/IRA, port A with CA1/CA2 handshake
+//! - `$2`/`$3`: DDRB/DDRA, data direction of port B/A (1 = output)
+//! - `$4`/`$5`: T1 counter low/high, writing `$5` starts T1
+//! - `$6`/`$7`: T1 latch low/high
+//! - `$8`/`$9`: T2 counter low/high, writing `$9` starts T2
+//! - `$A`: SR, shift register
+//! - `$B`: ACR, auxiliary control (latching, shift register mode, timer modes)
+//! - `$C`: PCR, peripheral control (CA1/CA2/CB1/CB2 edges and handshake)
+//! - `$D`: IFR, interrupt flags (write 1 to clear)
+//! - `$E`: IER, interrupt enable (bit 7 selects set or clear)
+//! - `$F`: ORA/IRA without handshake
+//!
+//! The VIA is clocked by Φ2, one `tick` per CPU cycle. External pins are driven and
+//! observed through the host pin methods, e.g. `Via::set_port_a_input` and `Via::cb2`.
+
+use bus::errors::BusError;
+use bus::trait_bus_device::BusDevice;
+
+/// Number of registers exposed on the bus
+pub const VIA_REGISTER_COUNT: u16 = 16;
+
+const REGISTER_ORB: u16 = 0x0;
+const REGISTER_ORA: u16 = 0x1;
+const REGISTER_DDRB: u16 = 0x2;
+const REGISTER_DDRA: u16 = 0x3;
+const REGISTER_T1C_L: u16 = 0x4;
+const REGISTER_T1C_H: u16 = 0x5;
+const REGISTER_T1L_L: u16 = 0x6;
+const REGISTER_T1L_H: u16 = 0x7;
+const REGISTER_T2C_L: u16 = 0x8;
+const REGISTER_T2C_H: u16 = 0x9;
+const REGISTER_SR: u16 = 0xA;
+const REGISTER_ACR: u16 = 0xB;
+const REGISTER_PCR: u16 = 0xC;
+const REGISTER_IFR: u16 = 0xD;
+const REGISTER_IER: u16 = 0xE;
+
+/// Interrupt flag: active edge on CA2
+pub const INTERRUPT_CA2: u8 = 0x01;
+/// Interrupt flag: active edge on CA1
+pub const INTERRUPT_CA1: u8 = 0x02;
+/// Interrupt flag: eight bits shifted
+pub const INTERRUPT_SR: u8 = 0x04;
+/// Interrupt flag: active edge on CB2
+pub const INTERRUPT_CB2: u8 = 0x08;
+/// Interrupt flag: active edge on CB1
+pub const INTERRUPT_CB1: u8 = 0x10;
+/// Interrupt flag: T2 timed out
+pub const INTERRUPT_T2: u8 = 0x20;
+/// Interrupt flag: T1 timed out
+pub const INTERRUPT_T1: u8 = 0x40;
+/// IFR bit 7: any enabled interrupt is active; IER bit 7: set rather than clear
+const INTERRUPT_ANY: u8 = 0x80;
+
+const ACR_LATCH_A: u8 = 0x01;
+const ACR_LATCH_B: u8 = 0x02;
+const ACR_T2_PULSE_COUNTING: u8 = 0x20;
+const ACR_T1_FREE_RUN: u8 = 0x40;
+const ACR_T1_PB7: u8 = 0x80;
+
+/// Operating mode of a CA2 or CB2 control line, from bits 1-3 or 5-7 of the PCR
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ControlMode {
+    /// Input, flag set on the given edge (`true` = rising) and cleared by port access
+    Input(bool),
+    /// Input, flag set on the given edge and only cleared through the IFR
+    IndependentInput(bool),
+    /// Output low after port access until the active edge of CA1/CB1
+    Handshake,
+    /// Output low for one cycle after port access
+    Pulse,
+    /// Output held at the given level
+    Manual(bool),
+}
+
+impl ControlMode {
+    /// Decode the three control bits of the PCR
+    fn from_bits(bits: u8) -> ControlMode {
+        match bits & 0x07 {
+            0 => ControlMode::Input(false),
+            1 => ControlMode::IndependentInput(false),
+            2 => ControlMode::Input(true),
+            3 => ControlMode::IndependentInput(true),
+            4 => ControlMode::Handshake,
+            5 => ControlMode::Pulse,
+            6 => ControlMode::Manual(false),
+            _ => ControlMode::Manual(true),
+        }
+    }
+
+    /// Edge that sets the interrupt flag, if the line is an input
+    fn input_edge(self) -> Option<bool> {
+        match self {
+            ControlMode::Input(rising) | ControlMode::IndependentInput(rising) => Some(rising),
+            _ => None,
+        }
+    }
+}
+
+/// Operating mode of the shift register, from bits 2-4 of the ACR
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ShiftMode {
+    /// Shift register disabled
+    Disabled,
+    /// Shift in at the rate of T2
+    InT2,
+    /// Shift in at half the rate of Φ2
+    InPhi2,
+    /// Shift in on the CB1 clock
+    InExternal,
+    /// Shift out at the rate of T2, repeating forever without interrupts
+    OutFreeRunning,
+    /// Shift out at the rate of T2
+    OutT2,
+    /// Shift out at half the rate of Φ2
+    OutPhi2,
+    /// Shift out on the CB1 clock
+    OutExternal,
+}
+
+impl ShiftMode {
+    /// Decode bits 2-4 of the ACR
+    fn from_acr(acr: u8) -> ShiftMode {
+        match (acr >> 2) & 0x07 {
+            0 => ShiftMode::Disabled,
+            1 => ShiftMode::InT2,
+            2 => ShiftMode::InPhi2,
+            3 => ShiftMode::InExternal,
+            4 => ShiftMode::OutFreeRunning,
+            5 => ShiftMode::OutT2,
+            6 => ShiftMode::OutPhi2,
+            _ => ShiftMode::OutExternal,
+        }
+    }
+
+    /// The shift register shifts data out on CB2 rather than in
+    fn is_output(self) -> bool {
+        matches!(
+            self,
+            ShiftMode::OutFreeRunning
+                | ShiftMode::OutT2
+                | ShiftMode::OutPhi2
+                | ShiftMode::OutExternal
+        )
+    }
+
+    /// The shift clock on CB1 is generated by the VIA
+    fn is_internal(self) -> bool {
+        !matches!(
+            self,
+            ShiftMode::Disabled | ShiftMode::InExternal | ShiftMode::OutExternal
+        )
+    }
+}
+
+/// Represents a MOS 6522 VIA.
+#[derive(Debug)]
+pub struct Via {
+    /// Start address of the register window
+    start_address: u16,
+    ora: u8,
+    orb: u8,
+    ddra: u8,
+    ddrb: u8,
+    /// Levels driven onto port A by external hardware
+    port_a_input: u8,
+    /// Levels driven onto port B by external hardware
+    port_b_input: u8,
+    /// Port A input latched by the last active CA1 edge
+    latch_a: u8,
+    /// Port B input latched by the last active CB1 edge
+    latch_b: u8,
+    t1_counter: u16,
+    t1_latch: u16,
+    /// T1 raises its interrupt on the next time-out
+    t1_armed: bool,
+    /// T1 reloads from the latch on the next tick, after a free-running time-out
+    t1_reload: bool,
+    /// Level of PB7 while it is driven by T1
+    pb7: bool,
+    t2_counter: u16,
+    /// T2 latch, only the low byte is kept by the chip
+    t2_latch_low: u8,
+    /// T2 raises its interrupt on the next time-out
+    t2_armed: bool,
+    sr: u8,
+    /// Bits shifted since the shift register was last accessed
+    sr_count: u8,
+    /// The shift register is shifting
+    sr_running: bool,
+    /// Ticks until the next edge of the internal shift clock
+    sr_timer: u16,
+    acr: u8,
+    pcr: u8,
+    ifr: u8,
+    ier: u8,
+    /// Levels of the control lines, as driven externally or by the VIA
+    ca1: bool,
+    ca2_input: bool,
+    cb1_input: bool,
+    cb2_input: bool,
+    /// CA2 output level in handshake and pulse modes
+    ca2_handshake: bool,
+    /// CB2 output level in handshake and pulse modes
+    cb2_handshake: bool,
+    /// CB1 shift clock output
+    cb1_clock: bool,
+    /// CB2 shift data output
+    cb2_shift: bool,
+}
+
+impl Via {
+    /// Create a new VIA with its registers at the specified start address.
+    ///
+    /// # Arguments
+    /// * `start_address` - Start address of the register window
+    ///
+    /// # Returns
+    /// * A new Via instance in its reset state
+    ///
+    /// # Examples
+    /// ``` ignore
+    /// let via = Via::new(0x6000);
+    /// bus.register_device(0x6000, 0x600F, Box::new(via))?;
+    /// ```
+    pub fn new(start_address: u16) -> Self {
+        Self {
+            start_address,
+            ora: 0,
+            orb: 0,
+            ddra: 0,
+            ddrb: 0,
+            // Undriven inputs are pulled high
+            port_a_input: 0xFF,
+            port_b_input: 0xFF,
+            latch_a: 0xFF,
+            latch_b: 0xFF,
+            t1_counter: 0xFFFF,
+            t1_latch: 0xFFFF,
+            t1_armed: false,
+            t1_reload: false,
+            pb7: true,
+            t2_counter: 0xFFFF,
+            t2_latch_low: 0xFF,
+            t2_armed: false,
+            sr: 0,
+            sr_count: 0,
+            sr_running: false,
+            sr_timer: 0,
+            acr: 0,
+            pcr: 0,
+            ifr: 0,
+            ier: 0,
+            ca1: true,
+            ca2_input: true,
+            cb1_input: true,
+            cb2_input: true,
+            ca2_handshake: true,
+            cb2_handshake: true,
+            cb1_clock: true,
+            cb2_shift: true,
+        }
+    }
+
+    /// Pull the RES pin low.
+    ///
+    /// Clears the port, data direction and control registers and disables interrupts.
+    /// The timers and the shift register keep their contents but stop interrupting.
+    pub fn reset(&mut self) {
+        self.ora = 0;
+        self.orb = 0;
+        self.ddra = 0;
+        self.ddrb = 0;
+        self.acr = 0;
+        self.pcr = 0;
+        self.ifr = 0;
+        self.ier = 0;
+        self.t1_armed = false;
+        self.t1_reload = false;
+        self.t2_armed = false;
+        self.sr_running = false;
+        self.ca2_handshake = true;
+        self.cb2_handshake = true;
+    }
+
+    /// Drive the port A pins from external hardware.
+    ///
+    /// Only pins configured as inputs in DDRA are affected.
+    ///
+    /// # Arguments
+    /// * `value` - Levels of PA0-PA7
+    pub fn set_port_a_input(&mut self, value: u8) {
+        self.port_a_input = value;
+    }
+
+    /// Drive the port B pins from external hardware.
+    ///
+    /// Only pins configured as inputs in DDRB are affected. Falling edges on PB6 are
+    /// counted by T2 in pulse counting mode.
+    ///
+    /// # Arguments
+    /// * `value` - Levels of PB0-PB7
+    pub fn set_port_b_input(&mut self, value: u8) {
+        let falling_pb6 = self.port_b_input & 0x40 != 0 && value & 0x40 == 0;
+        self.port_b_input = value;
+        if falling_pb6 && self.acr & ACR_T2_PULSE_COUNTING != 0 {
+            self.t2_counter = self.t2_counter.wrapping_sub(1);
+            if self.t2_counter == 0 && self.t2_armed {
+                self.t2_armed = false;
+                self.ifr |= INTERRUPT_T2;
+            }
+        }
+    }
+
+    /// Levels of the port A pins: outputs from ORA, inputs from external hardware
+    pub fn port_a_output(&self) -> u8 {
+        (self.ora & self.ddra) | (self.port_a_input & !self.ddra)
+    }
+
+    /// Levels of the port B pins: outputs from ORB or T1 on PB7, inputs from external hardware
+    pub fn port_b_output(&self) -> u8 {
+        let value = (self.orb & self.ddrb) | (self.port_b_input & !self.ddrb);
+        self.with_pb7(value)
+    }
+
+    /// Drive the CA1 input.
+    ///
+    /// # Arguments
+    /// * `level` - New level of the pin
+    pub fn set_ca1(&mut self, level: bool) {
+        if level == self.ca1 {
+            return;
+        }
+        self.ca1 = level;
+        if level == (self.pcr & 0x01 != 0) {
+            self.ifr |= INTERRUPT_CA1;
+            self.latch_a = self.port_a_output();
+            if self.ca2_mode() == ControlMode::Handshake {
+                self.ca2_handshake = true;
+            }
+        }
+    }
+
+    /// Drive the CA2 pin, when it is configured as an input.
+    ///
+    /// # Arguments
+    /// * `level` - New level of the pin
+    pub fn set_ca2(&mut self, level: bool) {
+        let rising = level && !self.ca2_input;
+        let changed = level != self.ca2_input;
+        self.ca2_input = level;
+        if changed && self.ca2_mode().input_edge() == Some(rising) {
+            self.ifr |= INTERRUPT_CA2;
+        }
+    }
+
+    /// Drive the CB1 pin, when it is not the shift clock output.
+    ///
+    /// In the external clock shift register modes, a falling edge shifts a bit out to
+    /// CB2 and a rising edge shifts a bit in from CB2.
+    ///
+    /// # Arguments
+    /// * `level` - New level of the pin
+    pub fn set_cb1(&mut self, level: bool) {
+        if level == self.cb1_input {
+            return;
+        }
+        self.cb1_input = level;
+        let mode = self.shift_mode();
+        if self.sr_running && matches!(mode, ShiftMode::InExternal | ShiftMode::OutExternal) {
+            self.shift_clock_edge(mode, level);
+        }
+        if level == (self.pcr & 0x10 != 0) {
+            self.ifr |= INTERRUPT_CB1;
+            self.latch_b = (self.orb & self.ddrb) | (self.port_b_input & !self.ddrb);
+            if self.cb2_mode() == ControlMode::Handshake {
+                self.cb2_handshake = true;
+            }
+        }
+    }
+
+    /// Drive the CB2 pin, when it is configured as an input or shift data input.
+    ///
+    /// # Arguments
+    /// * `level` - New level of the pin
+    pub fn set_cb2(&mut self, level: bool) {
+        let rising = level && !self.cb2_input;
+        let changed = level != self.cb2_input;
+        self.cb2_input = level;
+        if changed
+            && self.shift_mode() == ShiftMode::Disabled
+            && self.cb2_mode().input_edge() == Some(rising)
+        {
+            self.ifr |= INTERRUPT_CB2;
+        }
+    }
+
+    /// Level of the CA2 pin
+    pub fn ca2(&self) -> bool {
+        match self.ca2_mode() {
+            ControlMode::Handshake | ControlMode::Pulse => self.ca2_handshake,
+            ControlMode::Manual(level) => level,
+            _ => self.ca2_input,
+        }
+    }
+
+    /// Level of the CB1 pin, which carries the shift clock in the internal clock modes
+    pub fn cb1(&self) -> bool {
+        if self.shift_mode().is_internal() {
+            self.cb1_clock
+        } else {
+            self.cb1_input
+        }
+    }
+
+    /// Level of the CB2 pin, which carries the shift data in the output shift modes
+    pub fn cb2(&self) -> bool {
+        let shift_mode = self.shift_mode();
+        if shift_mode.is_output() {
+            return self.cb2_shift;
+        }
+        if shift_mode != ShiftMode::Disabled {
+            return self.cb2_input;
+        }
+        match self.cb2_mode() {
+            ControlMode::Handshake | ControlMode::Pulse => self.cb2_handshake,
+            ControlMode::Manual(level) => level,
+            _ => self.cb2_input,
+        }
+    }
+
+    /// Mode of the CA2 line
+    fn ca2_mode(&self) -> ControlMode {
+        ControlMode::from_bits(self.pcr >> 1)
+    }
+
+    /// Mode of the CB2 line
+    fn cb2_mode(&self) -> ControlMode {
+        ControlMode::from_bits(self.pcr >> 5)
+    }
+
+    /// Mode of the shift register
+    fn shift_mode(&self) -> ShiftMode {
+        ShiftMode::from_acr(self.acr)
+    }
+
+    /// Replace bit 7 of a port B value by the T1 output if enabled
+    fn with_pb7(&self, value: u8) -> u8 {
+        if self.acr & ACR_T1_PB7 == 0 {
+            value
+        } else if self.pb7 {
+            value | 0x80
+        } else {
+            value & 0x7F
+        }
+    }
+
+    /// Value read from port A, from the pins or the latch
+    fn read_port_a(&self) -> u8 {
+        if self.acr & ACR_LATCH_A != 0 {
+            self.latch_a
+        } else {
+            self.port_a_output()
+        }
+    }
+
+    /// Value of IFR including bit 7
+    fn interrupt_flags(&self) -> u8 {
+        if self.ifr & self.ier & 0x7F != 0 {
+            self.ifr | INTERRUPT_ANY
+        } else {
+            self.ifr
+        }
+    }
+
+    /// Clear the port A flags and start a CA2 handshake after an access to ORA
+    fn port_a_access(&mut self) {
+        self.ifr &= !INTERRUPT_CA1;
+        if !matches!(self.ca2_mode(), ControlMode::IndependentInput(_)) {
+            self.ifr &= !INTERRUPT_CA2;
+        }
+        if matches!(self.ca2_mode(), ControlMode::Handshake | ControlMode::Pulse) {
+            self.ca2_handshake = false;
+        }
+    }
+
+    /// Clear the port B flags after an access to ORB
+    fn port_b_access(&mut self) {
+        self.ifr &= !INTERRUPT_CB1;
+        if !matches!(self.cb2_mode(), ControlMode::IndependentInput(_)) {
+            self.ifr &= !INTERRUPT_CB2;
+        }
+    }
+
+    /// Restart the shift register after an access to SR
+    fn start_shift(&mut self) {
+        self.ifr &= !INTERRUPT_SR;
+        self.sr_count = 0;
+        self.sr_running = self.shift_mode() != ShiftMode::Disabled;
+        self.sr_timer = self.shift_half_period();
+    }
+
+    /// Ticks per half period of the internal shift clock
+    fn shift_half_period(&self) -> u16 {
+        match self.shift_mode() {
+            ShiftMode::InPhi2 | ShiftMode::OutPhi2 => 1,
+            _ => self.t2_latch_low as u16 + 2,
+        }
+    }
+
+    /// Handle an edge of the shift clock on CB1
+    ///
+    /// Data is shifted out on the falling edge. Data is shifted in, and the bit is
+    /// counted, on the rising edge.
+    fn shift_clock_edge(&mut self, mode: ShiftMode, rising: bool) {
+        if !rising {
+            if mode.is_output() {
+                self.cb2_shift = self.sr & 0x80 != 0;
+                self.sr = self.sr.rotate_left(1);
+            }
+            return;
+        }
+        if !mode.is_output() {
+            self.sr = (self.sr << 1) | self.cb2_input as u8;
+        }
+        self.sr_count += 1;
+        if self.sr_count == 8 {
+            self.sr_count = 0;
+            if mode != ShiftMode::OutFreeRunning {
+                self.sr_running = false;
+                self.ifr |= INTERRUPT_SR;
+            }
+        }
+    }
+
+    /// Advance T1 by one cycle
+    fn tick_t1(&mut self) {
+        if self.t1_reload {
+            self.t1_reload = false;
+            self.t1_counter = self.t1_latch;
+            return;
+        }
+        let (counter, timed_out) = self.t1_counter.overflowing_sub(1);
+        self.t1_counter = counter;
+        if !timed_out {
+            return;
+        }
+        if self.acr & ACR_T1_FREE_RUN != 0 {
+            self.t1_reload = true;
+            self.ifr |= INTERRUPT_T1;
+            self.pb7 = !self.pb7;
+        } else if self.t1_armed {
+            self.t1_armed = false;
+            self.ifr |= INTERRUPT_T1;
+            self.pb7 = true;
+        }
+    }
+
+    /// Advance T2 by one cycle, unless it counts PB6 pulses
+    fn tick_t2(&mut self) {
+        if self.acr & ACR_T2_PULSE_COUNTING != 0 {
+            return;
+        }
+        let (counter, timed_out) = self.t2_counter.overflowing_sub(1);
+        self.t2_counter = counter;
+        if timed_out && self.t2_armed {
+            self.t2_armed = false;
+            self.ifr |= INTERRUPT_T2;
+        }
+    }
+
+    /// Advance the internal shift clock by one cycle
+    fn tick_shift_register(&mut self) {
+        let mode = self.shift_mode();
+        if !self.sr_running || !mode.is_internal() {
+            return;
+        }
+        self.sr_timer = self.sr_timer.saturating_sub(1);
+        if self.sr_timer > 0 {
+            return;
+        }
+        self.sr_timer = self.shift_half_period();
+        self.cb1_clock = !self.cb1_clock;
+        self.shift_clock_edge(mode, self.cb1_clock);
+    }
+
+    /// Number of ticks during which the timers and the shift clock only count down
+    fn quiet_ticks(&self) -> u64 {
+        let mut quiet = if self.t1_reload {
+            0
+        } else {
+            self.t1_counter as u64
+        };
+        if self.acr & ACR_T2_PULSE_COUNTING == 0 {
+            quiet = quiet.min(self.t2_counter as u64);
+        }
+        if self.sr_running && self.shift_mode().is_internal() {
+            quiet = quiet.min(self.sr_timer.saturating_sub(1) as u64);
+        }
+        quiet
+    }
+
+    /// Count down by a number of ticks no larger than `quiet_ticks`
+    fn skip_quiet_ticks(&mut self, ticks: u64) {
+        if ticks == 0 {
+            return;
+        }
+        if self.ca2_mode() == ControlMode::Pulse {
+            self.ca2_handshake = true;
+        }
+        if self.cb2_mode() == ControlMode::Pulse {
+            self.cb2_handshake = true;
+        }
+        // Bounded by the 16-bit counters
+        let ticks = ticks as u16;
+        self.t1_counter -= ticks;
+        if self.acr & ACR_T2_PULSE_COUNTING == 0 {
+            self.t2_counter -= ticks;
+        }
+        if self.sr_running && self.shift_mode().is_internal() {
+            self.sr_timer -= ticks;
+        }
+    }
+
+    /// Ticks until T1 sets its interrupt flag
+    fn ticks_until_t1(&self) -> Option<u64> {
+        if self.acr & ACR_T1_FREE_RUN == 0 && !self.t1_armed {
+            return None;
+        }
+        if self.t1_reload {
+            Some(self.t1_latch as u64 + 2)
+        } else {
+            Some(self.t1_counter as u64 + 1)
+        }
+    }
+
+    /// Ticks until T2 sets its interrupt flag
+    fn ticks_until_t2(&self) -> Option<u64> {
+        if self.acr & ACR_T2_PULSE_COUNTING != 0 || !self.t2_armed {
+            return None;
+        }
+        Some(self.t2_counter as u64 + 1)
+    }
+
+    /// Ticks until the shift register, clocked internally, completes eight bits
+    fn ticks_until_shift_done(&self) -> Option<u64> {
+        let mode = self.shift_mode();
+        if !self.sr_running || !mode.is_internal() || mode == ShiftMode::OutFreeRunning {
+            return None;
+        }
+        // The shift clock alternates, and a bit is complete on each rising edge
+        let rising_edges = (8 - self.sr_count) as u64;
+        let edges = if self.cb1_clock {
+            2 * rising_edges
+        } else {
+            2 * rising_edges - 1
+        };
+        let first_edge = self.sr_timer.max(1) as u64;
+        Some(first_edge + (edges - 1) * self.shift_half_period() as u64)
+    }
+}
+
+impl BusDevice for Via {
+    fn read(&mut self, address: u16) -> Result<u8, BusError> {
+        let value = match address.wrapping_sub(self.start_address) & 0x0F {
+            REGISTER_ORB => {
+                self.port_b_access();
+                let input = if self.acr & ACR_LATCH_B != 0 {
+                    self.latch_b
+                } else {
+                    self.port_b_input
+                };
+                self.with_pb7((self.orb & self.ddrb) | (input & !self.ddrb))
+            }
+            REGISTER_ORA => {
+                self.port_a_access();
+                self.read_port_a()
+            }
+            REGISTER_DDRB => self.ddrb,
+            REGISTER_DDRA => self.ddra,
+            REGISTER_T1C_L => {
+                self.ifr &= !INTERRUPT_T1;
+                self.t1_counter as u8
+            }
+            REGISTER_T1C_H => (self.t1_counter >> 8) as u8,
+            REGISTER_T1L_L => self.t1_latch as u8,
+            REGISTER_T1L_H => (self.t1_latch >> 8) as u8,
+            REGISTER_T2C_L => {
+                self.ifr &= !INTERRUPT_T2;
+                self.t2_counter as u8
+            }
+            REGISTER_T2C_H => (self.t2_counter >> 8) as u8,
+            REGISTER_SR => {
+                let value = self.sr;
+                self.start_shift();
+                value
+            }
+            REGISTER_ACR => self.acr,
+            REGISTER_PCR => self.pcr,
+            REGISTER_IFR => self.interrupt_flags(),
+            REGISTER_IER => self.ier | INTERRUPT_ANY,
+            // $F: ORA without handshake
+            _ => self.read_port_a(),
+        };
+        Ok(value)
+    }
+
+    fn write(&mut self, address: u16, data: u8) -> Result<(), BusError> {
+        match address.wrapping_sub(self.start_address) & 0x0F {
+            REGISTER_ORB => {
+                self.orb = data;
+                self.port_b_access();
+                if matches!(self.cb2_mode(), ControlMode::Handshake | ControlMode::Pulse) {
+                    self.cb2_handshake = false;
+                }
+            }
+            REGISTER_ORA => {
+                self.ora = data;
+                self.port_a_access();
+            }
+            REGISTER_DDRB => self.ddrb = data,
+            REGISTER_DDRA => self.ddra = data,
+            REGISTER_T1C_L | REGISTER_T1L_L => {
+                self.t1_latch = (self.t1_latch & 0xFF00) | data as u16;
+            }
+            REGISTER_T1C_H => {
+                self.t1_latch = (self.t1_latch & 0x00FF) | (data as u16) << 8;
+                self.t1_counter = self.t1_latch;
+                self.t1_armed = true;
+                self.t1_reload = false;
+                self.ifr &= !INTERRUPT_T1;
+                if self.acr & ACR_T1_PB7 != 0 {
+                    self.pb7 = false;
+                }
+            }
+            REGISTER_T1L_H => {
+                self.t1_latch = (self.t1_latch & 0x00FF) | (data as u16) << 8;
+                self.ifr &= !INTERRUPT_T1;
+            }
+            REGISTER_T2C_L => self.t2_latch_low = data,
+            REGISTER_T2C_H => {
+                self.t2_counter = (data as u16) << 8 | self.t2_latch_low as u16;
+                self.t2_armed = true;
+                self.ifr &= !INTERRUPT_T2;
+            }
+            REGISTER_SR => {
+                self.sr = data;
+                self.start_shift();
+            }
+            REGISTER_ACR => {
+                self.acr = data;
+                if !self.shift_mode().is_internal() {
+                    self.cb1_clock = true;
+                }
+            }
+            REGISTER_PCR => {
+                self.pcr = data;
+                self.ca2_handshake = true;
+                self.cb2_handshake = true;
+            }
+            REGISTER_IFR => self.ifr &= !(data & 0x7F),
+            REGISTER_IER => {
+                if data & INTERRUPT_ANY != 0 {
+                    self.ier |= data & 0x7F;
+                } else {
+                    self.ier &= !data;
+                }
+            }
+            // $F: ORA without handshake
+            _ => self.ora = data,
+        }
+        Ok(())
+    }
+
+    fn tick(&mut self) {
+        // A pulse output returns high after one cycle
+        if self.ca2_mode() == ControlMode::Pulse {
+            self.ca2_handshake = true;
+        }
+        if self.cb2_mode() == ControlMode::Pulse {
+            self.cb2_handshake = true;
+        }
+        self.tick_t1();
+        self.tick_t2();
+        self.tick_shift_register();
+    }
+
+    fn advance(&mut self, ticks: u64) {
+        let mut remaining = ticks;
+        while remaining > 0 {
+            let quiet = self.quiet_ticks().min(remaining);
+            self.skip_quiet_ticks(quiet);
+            remaining -= quiet;
+            if remaining > 0 {
+                self.tick();
+                remaining -= 1;
+            }
+        }
+    }
+
+    fn ticks_until_event(&self) -> Option<u64> {
+        [
+            self.ticks_until_t1(),
+            self.ticks_until_t2(),
+            self.ticks_until_shift_done(),
+        ]
+        .into_iter()
+        .flatten()
+        .min()
+    }
+
+    fn check_irq(&self) -> bool {
+        self.ifr & self.ier & 0x7F != 0
+    }
+
+    fn check_nmi(&self) -> bool {
+        // The IRQ output may be wired to NMI, but the VIA itself only has one output
+        false
+    }
+
+    fn check_rdy(&self) -> bool {
+        // The VIA does not insert wait states
+        false
+    }
+
+    fn check_so(&self) -> bool {
+        // The VIA does not drive the SO line
+        false
+    }
+}
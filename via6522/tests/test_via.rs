@@ -0,0 +1,422 @@
+//! Unit tests for the 6522 VIA implementation
+//!
+//! This module tests the ports and data direction registers, both timers, the shift
+//! register, the control line handshakes and the interrupt logic.
+
+use bus::BusController;
+use bus::timing_mode::TimingMode;
+use bus::trait_bus_device::BusDevice;
+use via6522::{
+    INTERRUPT_CA1, INTERRUPT_CA2, INTERRUPT_CB1, INTERRUPT_SR, INTERRUPT_T1, INTERRUPT_T2, Via,
+};
+
+const BASE: u16 = 0x6000;
+const ORB: u16 = BASE;
+const ORA: u16 = BASE + 0x1;
+const DDRB: u16 = BASE + 0x2;
+const DDRA: u16 = BASE + 0x3;
+const T1C_L: u16 = BASE + 0x4;
+const T1C_H: u16 = BASE + 0x5;
+const T1L_H: u16 = BASE + 0x7;
+const T2C_L: u16 = BASE + 0x8;
+const T2C_H: u16 = BASE + 0x9;
+const SR: u16 = BASE + 0xA;
+const ACR: u16 = BASE + 0xB;
+const PCR: u16 = BASE + 0xC;
+const IFR: u16 = BASE + 0xD;
+const IER: u16 = BASE + 0xE;
+const ORA_NO_HANDSHAKE: u16 = BASE + 0xF;
+
+/// Tick the VIA a number of times
+fn run(via: &mut Via, ticks: u32) {
+    for _ in 0..ticks {
+        via.tick();
+    }
+}
+
+// Test ports
+#[test]
+fn test_port_outputs_follow_ddr() {
+    let mut via = Via::new(BASE);
+    via.write(DDRA, 0x0F).unwrap();
+    via.write(ORA, 0xA5).unwrap();
+    via.set_port_a_input(0x30);
+    assert_eq!(via.port_a_output(), 0x35);
+    assert_eq!(via.read(DDRA).unwrap(), 0x0F);
+
+    via.write(DDRB, 0xF0).unwrap();
+    via.write(ORB, 0xA5).unwrap();
+    via.set_port_b_input(0x0C);
+    assert_eq!(via.port_b_output(), 0xAC);
+}
+
+#[test]
+fn test_port_reads() {
+    let mut via = Via::new(BASE);
+    via.write(DDRB, 0xF0).unwrap();
+    via.write(ORB, 0x50).unwrap();
+    via.set_port_b_input(0x0A);
+    assert_eq!(via.read(ORB).unwrap(), 0x5A);
+
+    // Port A reads the pins, including the inputs
+    via.set_port_a_input(0x3C);
+    assert_eq!(via.read(ORA).unwrap(), 0x3C);
+    assert_eq!(via.read(ORA_NO_HANDSHAKE).unwrap(), 0x3C);
+}
+
+#[test]
+fn test_registers_are_mirrored() {
+    let mut via = Via::new(BASE);
+    via.write(BASE + 0x13, 0x12).unwrap();
+    assert_eq!(via.read(DDRA).unwrap(), 0x12);
+}
+
+#[test]
+fn test_input_latching() {
+    let mut via = Via::new(BASE);
+    via.write(ACR, 0x01).unwrap();
+    via.set_port_a_input(0x11);
+    via.set_ca1(false);
+    via.set_port_a_input(0x22);
+    assert_eq!(via.read(ORA).unwrap(), 0x11);
+}
+
+// Test timer 1
+#[test]
+fn test_t1_one_shot() {
+    let mut via = Via::new(BASE);
+    via.write(IER, 0x80 | INTERRUPT_T1).unwrap();
+    via.write(T1C_L, 10).unwrap();
+    via.write(T1C_H, 0).unwrap();
+
+    run(&mut via, 10);
+    assert_eq!(via.read(T1C_L).unwrap(), 0);
+    assert!(!via.check_irq());
+    via.tick();
+    assert!(via.check_irq());
+    assert_eq!(via.read(IFR).unwrap(), 0x80 | INTERRUPT_T1);
+
+    // Reading the low counter clears the flag, and one-shot mode does not fire again
+    via.read(T1C_L).unwrap();
+    assert!(!via.check_irq());
+    run(&mut via, 0x20000);
+    assert!(!via.check_irq());
+}
+
+#[test]
+fn test_t1_free_run_with_pb7() {
+    let mut via = Via::new(BASE);
+    via.write(ACR, 0xC0).unwrap();
+    via.write(T1C_L, 4).unwrap();
+    via.write(T1C_H, 0).unwrap();
+    assert_eq!(via.port_b_output() & 0x80, 0x00);
+
+    run(&mut via, 5);
+    assert_eq!(via.read(IFR).unwrap() & INTERRUPT_T1, INTERRUPT_T1);
+    assert_eq!(via.port_b_output() & 0x80, 0x80);
+
+    // Period of N + 2 cycles
+    via.write(IFR, INTERRUPT_T1).unwrap();
+    run(&mut via, 5);
+    assert_eq!(via.read(IFR).unwrap() & INTERRUPT_T1, 0);
+    via.tick();
+    assert_eq!(via.read(IFR).unwrap() & INTERRUPT_T1, INTERRUPT_T1);
+    assert_eq!(via.port_b_output() & 0x80, 0x00);
+}
+
+#[test]
+fn test_t1_latch_write_clears_flag() {
+    let mut via = Via::new(BASE);
+    via.write(T1C_L, 0).unwrap();
+    via.write(T1C_H, 0).unwrap();
+    via.tick();
+    assert_eq!(via.read(IFR).unwrap() & INTERRUPT_T1, INTERRUPT_T1);
+    via.write(T1L_H, 0x12).unwrap();
+    assert_eq!(via.read(IFR).unwrap() & INTERRUPT_T1, 0);
+}
+
+// Test timer 2
+#[test]
+fn test_t2_one_shot() {
+    let mut via = Via::new(BASE);
+    via.write(IER, 0x80 | INTERRUPT_T2).unwrap();
+    via.write(T2C_L, 0x00).unwrap();
+    via.write(T2C_H, 0x01).unwrap();
+    run(&mut via, 0x100);
+    assert!(!via.check_irq());
+    via.tick();
+    assert!(via.check_irq());
+
+    via.read(T2C_L).unwrap();
+    run(&mut via, 0x10000);
+    assert!(!via.check_irq());
+}
+
+#[test]
+fn test_t2_pulse_counting() {
+    let mut via = Via::new(BASE);
+    via.write(ACR, 0x20).unwrap();
+    via.write(T2C_L, 3).unwrap();
+    via.write(T2C_H, 0).unwrap();
+
+    // Cycles do not count, falling edges on PB6 do
+    run(&mut via, 100);
+    for pulse in 1..=3 {
+        via.set_port_b_input(0xBF);
+        via.set_port_b_input(0xFF);
+        let flags = via.read(IFR).unwrap() & INTERRUPT_T2;
+        assert_eq!(flags != 0, pulse == 3);
+    }
+}
+
+// Test shift register
+#[test]
+fn test_shift_out_phi2() {
+    let mut via = Via::new(BASE);
+    via.write(ACR, 0x18).unwrap();
+    via.write(SR, 0b1010_0110).unwrap();
+
+    let mut bits = Vec::new();
+    for _ in 0..8 {
+        via.tick();
+        assert!(!via.cb1());
+        bits.push(via.cb2() as u8);
+        via.tick();
+        assert!(via.cb1());
+    }
+    assert_eq!(bits, vec![1, 0, 1, 0, 0, 1, 1, 0]);
+    assert_eq!(via.read(IFR).unwrap() & INTERRUPT_SR, INTERRUPT_SR);
+    // The data recirculates
+    assert_eq!(via.read(SR).unwrap(), 0b1010_0110);
+    assert_eq!(via.read(IFR).unwrap() & INTERRUPT_SR, 0);
+}
+
+#[test]
+fn test_shift_out_t2_rate() {
+    let mut via = Via::new(BASE);
+    via.write(ACR, 0x14).unwrap();
+    via.write(T2C_L, 2).unwrap();
+    via.write(SR, 0xFF).unwrap();
+    // Each half period of the shift clock takes N + 2 cycles
+    run(&mut via, 8 * 2 * 4 - 1);
+    assert_eq!(via.read(IFR).unwrap() & INTERRUPT_SR, 0);
+    via.tick();
+    assert_eq!(via.read(IFR).unwrap() & INTERRUPT_SR, INTERRUPT_SR);
+}
+
+#[test]
+fn test_shift_in_external_clock() {
+    let mut via = Via::new(BASE);
+    via.write(ACR, 0x0C).unwrap();
+    via.read(SR).unwrap();
+    for bit in [0, 1, 1, 0, 1, 0, 0, 1] {
+        via.set_cb2(bit == 1);
+        via.set_cb1(false);
+        via.set_cb1(true);
+    }
+    assert_eq!(via.read(IFR).unwrap() & INTERRUPT_SR, INTERRUPT_SR);
+    assert_eq!(via.read(SR).unwrap(), 0b0110_1001);
+}
+
+// Test control lines
+#[test]
+fn test_ca1_edge_selection() {
+    let mut via = Via::new(BASE);
+    via.set_ca1(false);
+    assert_eq!(via.read(IFR).unwrap() & INTERRUPT_CA1, INTERRUPT_CA1);
+    via.read(ORA).unwrap();
+    assert_eq!(via.read(IFR).unwrap() & INTERRUPT_CA1, 0);
+
+    // Positive edge
+    via.write(PCR, 0x01).unwrap();
+    via.set_ca1(true);
+    assert_eq!(via.read(IFR).unwrap() & INTERRUPT_CA1, INTERRUPT_CA1);
+}
+
+#[test]
+fn test_ca2_independent_input() {
+    let mut via = Via::new(BASE);
+    via.write(PCR, 0x02).unwrap();
+    via.set_ca2(false);
+    via.read(ORA).unwrap();
+    assert_eq!(via.read(IFR).unwrap() & INTERRUPT_CA2, INTERRUPT_CA2);
+    via.write(IFR, INTERRUPT_CA2).unwrap();
+    assert_eq!(via.read(IFR).unwrap() & INTERRUPT_CA2, 0);
+
+    // Dependent input is cleared by the port access
+    via.write(PCR, 0x00).unwrap();
+    via.set_ca2(true);
+    via.set_ca2(false);
+    via.read(ORA).unwrap();
+    assert_eq!(via.read(IFR).unwrap() & INTERRUPT_CA2, 0);
+}
+
+#[test]
+fn test_ca2_handshake() {
+    let mut via = Via::new(BASE);
+    via.write(PCR, 0x08).unwrap();
+    assert!(via.ca2());
+    via.read(ORA).unwrap();
+    assert!(!via.ca2());
+    via.tick();
+    assert!(!via.ca2());
+    // Data ready from the peripheral
+    via.set_ca1(false);
+    assert!(via.ca2());
+}
+
+#[test]
+fn test_cb2_pulse_and_manual_output() {
+    let mut via = Via::new(BASE);
+    via.write(PCR, 0xA0).unwrap();
+    via.read(ORB).unwrap();
+    assert!(via.cb2());
+    via.write(ORB, 0x00).unwrap();
+    assert!(!via.cb2());
+    via.tick();
+    assert!(via.cb2());
+
+    via.write(PCR, 0xC0).unwrap();
+    assert!(!via.cb2());
+    via.write(PCR, 0xE0).unwrap();
+    assert!(via.cb2());
+}
+
+#[test]
+fn test_cb1_flag_cleared_by_port_b() {
+    let mut via = Via::new(BASE);
+    via.set_cb1(false);
+    assert_eq!(via.read(IFR).unwrap() & INTERRUPT_CB1, INTERRUPT_CB1);
+    via.write(ORB, 0x00).unwrap();
+    assert_eq!(via.read(IFR).unwrap() & INTERRUPT_CB1, 0);
+}
+
+// Test scheduled timing
+/// VIA with both timers and the shift register running at different rates
+fn busy_via() -> Via {
+    let mut via = Via::new(BASE);
+    via.write(ACR, 0xD4).unwrap();
+    via.write(PCR, 0x0A).unwrap();
+    via.write(T1C_L, 0x37).unwrap();
+    via.write(T1C_H, 0x01).unwrap();
+    via.write(T2C_L, 0x05).unwrap();
+    via.write(T2C_H, 0x02).unwrap();
+    via.write(SR, 0b1100_1010).unwrap();
+    via
+}
+
+#[test]
+fn test_advance_matches_ticks() {
+    for ticks in [0, 1, 7, 100, 0x13A, 0x20000] {
+        let mut ticked = busy_via();
+        let mut advanced = busy_via();
+        run(&mut ticked, ticks);
+        advanced.advance(ticks as u64);
+
+        assert_eq!(advanced.ticks_until_event(), ticked.ticks_until_event());
+        assert_eq!(advanced.port_b_output(), ticked.port_b_output());
+        assert_eq!(advanced.cb1(), ticked.cb1());
+        assert_eq!(advanced.cb2(), ticked.cb2());
+        assert_eq!(advanced.ca2(), ticked.ca2());
+        for register in [T1C_L, T1C_H, T2C_L, T2C_H, IFR, SR] {
+            assert_eq!(
+                advanced.read(register).unwrap(),
+                ticked.read(register).unwrap()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_ticks_until_event() {
+    let mut via = Via::new(BASE);
+    assert_eq!(via.ticks_until_event(), None);
+
+    via.write(T1C_L, 10).unwrap();
+    via.write(T1C_H, 0).unwrap();
+    assert_eq!(via.ticks_until_event(), Some(11));
+    via.write(T2C_L, 3).unwrap();
+    via.write(T2C_H, 0).unwrap();
+    assert_eq!(via.ticks_until_event(), Some(4));
+
+    // T2 rate shifting takes 16 half periods of N + 2 cycles
+    via.write(ACR, 0x14).unwrap();
+    via.write(T2C_L, 2).unwrap();
+    via.write(SR, 0xFF).unwrap();
+    run(&mut via, 11);
+    assert_eq!(via.ticks_until_event(), Some(64 - 11));
+}
+
+#[test]
+fn test_scheduled_timers_raise_irq() {
+    let mut bus = BusController::new();
+    let id = bus
+        .register_device(BASE, BASE + 0x0F, Box::new(Via::new(BASE)))
+        .unwrap();
+    bus.set_timing_mode(id, TimingMode::Scheduled).unwrap();
+    bus.write(IER, 0x80 | INTERRUPT_T1 | INTERRUPT_T2).unwrap();
+    bus.write(T2C_L, 0x00).unwrap();
+    bus.write(T2C_H, 0x02).unwrap();
+    bus.write(ACR, 0x40).unwrap();
+    bus.write(T1C_L, 0x50).unwrap();
+    bus.write(T1C_H, 0x00).unwrap();
+
+    // Free running T1 fires every N + 2 cycles without the CPU touching the VIA
+    for _ in 0..0x50 {
+        bus.tick();
+    }
+    assert!(!bus.check_irq());
+    bus.tick();
+    assert!(bus.check_irq());
+    bus.write(IFR, INTERRUPT_T1).unwrap();
+    for _ in 0..0x51 {
+        bus.tick();
+    }
+    assert!(!bus.check_irq());
+    bus.tick();
+    assert!(bus.check_irq());
+    bus.write(IER, INTERRUPT_T1).unwrap();
+
+    // T2 was loaded with $200 before T1 was started, $A3 cycles ago
+    assert!(!bus.check_irq());
+    for _ in 0..(0x200 - 0xA3) {
+        bus.tick();
+    }
+    assert!(!bus.check_irq());
+    bus.tick();
+    assert!(bus.check_irq());
+}
+
+// Test interrupt logic
+#[test]
+fn test_interrupt_enable_register() {
+    let mut via = Via::new(BASE);
+    assert_eq!(via.read(IER).unwrap(), 0x80);
+    via.write(IER, 0x80 | INTERRUPT_CA1 | INTERRUPT_T1).unwrap();
+    assert_eq!(via.read(IER).unwrap(), 0x80 | INTERRUPT_CA1 | INTERRUPT_T1);
+    via.write(IER, INTERRUPT_T1).unwrap();
+    assert_eq!(via.read(IER).unwrap(), 0x80 | INTERRUPT_CA1);
+
+    // Disabled flags are visible in IFR but do not raise IRQ
+    via.set_cb1(false);
+    assert_eq!(via.read(IFR).unwrap(), INTERRUPT_CB1);
+    assert!(!via.check_irq());
+    via.set_ca1(false);
+    assert_eq!(via.read(IFR).unwrap(), 0x80 | INTERRUPT_CA1 | INTERRUPT_CB1);
+    assert!(via.check_irq());
+}
+
+#[test]
+fn test_reset() {
+    let mut via = Via::new(BASE);
+    via.write(DDRA, 0xFF).unwrap();
+    via.write(IER, 0xFF).unwrap();
+    via.write(T1C_L, 0x34).unwrap();
+    via.set_ca1(false);
+    via.reset();
+    assert_eq!(via.read(DDRA).unwrap(), 0x00);
+    assert_eq!(via.read(IER).unwrap(), 0x80);
+    assert_eq!(via.read(IFR).unwrap(), 0x00);
+    assert_eq!(via.read(BASE + 0x6).unwrap(), 0x34);
+    assert!(!via.check_irq());
+}